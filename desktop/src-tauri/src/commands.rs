@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
-use serde::{Deserialize, Serialize};
+use common::{parse_verse_ref, Label, Lang, Surah, SurahInfo, SurahSummary, TagLabels, Verse};
+use serde::Serialize;
 use serde_json::Value;
 use std::{
     collections::{HashMap, HashSet},
@@ -8,62 +9,6 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-#[derive(Debug, Clone, Deserialize)]
-#[allow(dead_code)]
-struct SurahSummary {
-    number: i64,
-    name: String,
-    #[serde(default, rename = "ayah_count")]
-    ayah_count: Option<i64>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct SurahInfo {
-    number: i64,
-    #[serde(default)]
-    name: String,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct SurahData {
-    surah: SurahInfo,
-    verses: Vec<VerseSummary>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct VerseSummary {
-    ayah: i64,
-    #[serde(default)]
-    text: String,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct Segment {
-    #[serde(default)]
-    root: Option<String>,
-    #[serde(default)]
-    pos: Option<String>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct Token {
-    text: Option<String>,
-    #[serde(default)]
-    form: Option<String>,
-    #[serde(default)]
-    segments: Vec<Segment>,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-struct Verse {
-    surah: SurahInfo,
-    ayah: i64,
-    #[serde(default)]
-    text: String,
-    #[serde(default)]
-    tokens: Vec<Token>,
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Mode {
     Read,
@@ -160,122 +105,51 @@ struct CommandResponse {
     prefill: Option<String>,
 }
 
-const ARABIC_SURAH_NAMES: [&str; 114] = [
-    "الفاتحة",
-    "البقرة",
-    "آل عمران",
-    "النساء",
-    "المائدة",
-    "الأنعام",
-    "الأعراف",
-    "الأنفال",
-    "التوبة",
-    "يونس",
-    "هود",
-    "يوسف",
-    "الرعد",
-    "إبراهيم",
-    "الحجر",
-    "النحل",
-    "الإسراء",
-    "الكهف",
-    "مريم",
-    "طه",
-    "الأنبياء",
-    "الحج",
-    "المؤمنون",
-    "النور",
-    "الفرقان",
-    "الشعراء",
-    "النمل",
-    "القصص",
-    "العنكبوت",
-    "الروم",
-    "لقمان",
-    "السجدة",
-    "الأحزاب",
-    "سبإ",
-    "فاطر",
-    "يس",
-    "الصافات",
-    "ص",
-    "الزمر",
-    "غافر",
-    "فصلت",
-    "الشورى",
-    "الزخرف",
-    "الدخان",
-    "الجاثية",
-    "الأحقاف",
-    "محمد",
-    "الفتح",
-    "الحجرات",
-    "ق",
-    "الذاريات",
-    "الطور",
-    "النجم",
-    "القمر",
-    "الرحمن",
-    "الواقعة",
-    "الحديد",
-    "المجادلة",
-    "الحشر",
-    "الممتحنة",
-    "الصف",
-    "الجمعة",
-    "المنافقون",
-    "التغابن",
-    "الطلاق",
-    "التحريم",
-    "الملك",
-    "القلم",
-    "الحاقة",
-    "المعارج",
-    "نوح",
-    "الجن",
-    "المزمل",
-    "المدثر",
-    "القيامة",
-    "الإنسان",
-    "المرسلات",
-    "النبإ",
-    "النازعات",
-    "عبس",
-    "التكوير",
-    "الانفطار",
-    "المطففين",
-    "الانشقاق",
-    "البروج",
-    "الطارق",
-    "الأعلى",
-    "الغاشية",
-    "الفجر",
-    "البلد",
-    "الشمس",
-    "الليل",
-    "الضحى",
-    "الشرح",
-    "التين",
-    "العلق",
-    "القدر",
-    "البينة",
-    "الزلزلة",
-    "العاديات",
-    "القارعة",
-    "التكاثر",
-    "العصر",
-    "الهمزة",
-    "الفيل",
-    "قريش",
-    "الماعون",
-    "الكوثر",
-    "الكافرون",
-    "النصر",
-    "المسد",
-    "الإخلاص",
-    "الفلق",
-    "الناس",
-];
+/// HTTP client that identifies the local profile (see `configure_profile`)
+/// so research records are attributed to this researcher, and sends
+/// `KALIMA_TOKEN` when the server requires a bearer token.
+fn build_client() -> reqwest::blocking::Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(value) = std::env::var("KALIMA_USER")
+        .ok()
+        .and_then(|u| reqwest::header::HeaderValue::from_str(u.trim()).ok())
+    {
+        headers.insert("x-kalima-user", value);
+    }
+    if let Some(mut value) = std::env::var("KALIMA_TOKEN")
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .and_then(|t| reqwest::header::HeaderValue::from_str(&format!("Bearer {}", t.trim())).ok())
+    {
+        value.set_sensitive(true);
+        headers.insert(reqwest::header::AUTHORIZATION, value);
+    }
+    reqwest::blocking::Client::builder()
+        .default_headers(headers)
+        .build()
+        .expect("reqwest client")
+}
+
+/// Turn an error response into an error carrying the server's message and
+/// code from its `{"error": {...}}` envelope.
+trait ApiResponse: Sized {
+    fn api_ok(self) -> Result<Self>;
+}
+
+impl ApiResponse for reqwest::blocking::Response {
+    fn api_ok(self) -> Result<Self> {
+        let status = self.status();
+        if status.is_success() {
+            return Ok(self);
+        }
+        let body: Value = self.json().unwrap_or(Value::Null);
+        let message = body["error"]["message"].as_str().unwrap_or("request failed");
+        match body["error"]["code"].as_str() {
+            Some(code) => Err(anyhow!("{} ({}, {})", message, code, status.as_u16())),
+            None => Err(anyhow!("{} ({})", message, status)),
+        }
+    }
+}
 
 impl AppState {
     fn new() -> Self {
@@ -286,9 +160,7 @@ impl AppState {
             interpretations: HashMap::new(),
             mode: Mode::Read,
             editing: None,
-            client: reqwest::blocking::Client::builder()
-                .build()
-                .expect("reqwest client"),
+            client: build_client(),
         }
     }
 
@@ -324,9 +196,6 @@ fn surah_name_or_fallback(number: i64, name: &str) -> String {
     if !trimmed.is_empty() {
         return trimmed.to_string();
     }
-    if (1..=114).contains(&number) {
-        return ARABIC_SURAH_NAMES[(number - 1) as usize].to_string();
-    }
     format!("Surah {}", number)
 }
 
@@ -375,14 +244,10 @@ fn handle_command(state: &mut AppState, line: &str) -> Result<CommandResponse> {
         if idx >= list.len() {
             anyhow::bail!("editing target out of range; try 'write' again.");
         }
-        let old_id = &list[idx].0;
-
-        // Delete existing annotation, then save replacement.
-        let _ = state
-            .client
-            .delete(format!("{}/annotations/{}", state.base_url, old_id))
-            .send();
-        save_interpretation(state, s, a, line.trim())?;
+        let old_id = list[idx].0.clone();
+
+        // Replace in one request so a failure can't lose the old interpretation.
+        replace_interpretation(state, s, a, &old_id, line.trim())?;
         state.editing = None;
 
         // Refresh list
@@ -408,8 +273,7 @@ fn handle_command(state: &mut AppState, line: &str) -> Result<CommandResponse> {
         "inspect" => {
             if rest.is_empty() {
                 resp(inspect_current(state)?)
-            } else if rest.contains(':') {
-                let (s, a) = parse_verse_ref(&rest)?;
+            } else if let Ok((s, a)) = parse_verse_ref(&rest) {
                 let verse = fetch_verse(state, s, a)?;
                 state.current_verse = Some(verse.clone());
                 resp(inspect_specific(state, &verse)?)
@@ -432,16 +296,15 @@ fn handle_command(state: &mut AppState, line: &str) -> Result<CommandResponse> {
                 }
             }
 
-            // Shorthand: allow `read <surah:ayah>` without the `verse` keyword
-            if !trimmed.contains(' ') && trimmed.contains(':') {
-                let (s, a) = parse_verse_ref(trimmed)?;
-                return resp(read_specific_verse(state, s, a)?);
-            }
             // Shorthand: allow `read <ayah>` within current surah
             if !trimmed.contains(' ') && trimmed.chars().all(|c| c.is_ascii_digit()) {
                 let num = parse_number(trimmed)?;
                 return resp(read_verse(state, num)?);
             }
+            // Shorthand: allow `read <surah:ayah>` (or `read al-Baqarah 255`) without the `verse` keyword
+            if let Ok((s, a)) = parse_verse_ref(trimmed) {
+                return resp(read_specific_verse(state, s, a)?);
+            }
 
             let mut args = trimmed.split_whitespace();
             let subtype = args.next().ok_or_else(|| {
@@ -456,8 +319,7 @@ fn handle_command(state: &mut AppState, line: &str) -> Result<CommandResponse> {
                 }
                 "verse" => {
                     // Allow either a simple ayah number (if a surah is in scope) or a fully-qualified surah:ayah
-                    if tail.contains(':') {
-                        let (s, a) = parse_verse_ref(&tail)?;
+                    if let Ok((s, a)) = parse_verse_ref(&tail) {
                         resp(read_specific_verse(state, s, a)?)
                     } else {
                         let num = parse_number(&tail)?;
@@ -640,9 +502,7 @@ fn read_chapter(state: &mut AppState, number: i64) -> Result<CommandOutput> {
                     number: surah.surah.number,
                     name: surah.surah.name.clone(),
                 },
-                ayah: verse.ayah,
-                text: verse.text.clone(),
-                tokens: vec![],
+                ..verse.clone()
             }
         };
         let interp = match state.mode {
@@ -675,16 +535,41 @@ fn parse_write_target(state: &AppState, rest: &str) -> Result<((i64, i64), Strin
 
     if first.contains(':') {
         let (s, a) = parse_verse_ref(first)?;
-        Ok(((s, a), remaining))
-    } else {
-        let ayah = parse_number(first)?;
-        let surah = state
-            .current_verse
-            .as_ref()
-            .map(|v| v.surah.number)
-            .ok_or_else(|| anyhow!("No surah in context. Use 'write <surah:ayah>' first."))?;
-        Ok(((surah, ayah), remaining))
+        return Ok(((s, a), remaining));
     }
+    if !first.chars().all(|c| c.is_ascii_digit()) {
+        if let Some(named) = named_write_target(trimmed) {
+            return Ok(named);
+        }
+    }
+    let ayah = parse_number(first)?;
+    let surah = state
+        .current_verse
+        .as_ref()
+        .map(|v| v.surah.number)
+        .ok_or_else(|| anyhow!("No surah in context. Use 'write <surah:ayah>' first."))?;
+    Ok(((surah, ayah), remaining))
+}
+
+/// `write al-Baqarah 255 <text>` / `write البقرة ٢٥٥ <text>`: the reference is
+/// the longest run of (up to four) leading words that names a single verse.
+fn named_write_target(input: &str) -> Option<((i64, i64), String)> {
+    let mut word_ends = Vec::new();
+    let mut in_word = false;
+    for (i, c) in input.char_indices() {
+        if c.is_whitespace() && in_word {
+            word_ends.push(i);
+        }
+        in_word = !c.is_whitespace();
+    }
+    if in_word {
+        word_ends.push(input.len());
+    }
+    word_ends.truncate(4);
+    word_ends.iter().rev().find_map(|&end| {
+        let target = parse_verse_ref(&input[..end]).ok()?;
+        Some((target, input[end..].trim_start().to_string()))
+    })
 }
 
 fn handle_write(state: &mut AppState, rest: &str) -> Result<CommandOutput> {
@@ -753,7 +638,10 @@ fn read_chapters(state: &mut AppState) -> Result<CommandOutput> {
     let mut content = String::from("=== Chapters ===\n\n");
     for s in &state.surahs {
         let name = surah_name_or_fallback(s.number, &s.name);
-        content.push_str(&format!("{}. {}\n", s.number, name));
+        match s.name_translit.as_deref().filter(|t| !t.is_empty()) {
+            Some(translit) => content.push_str(&format!("{}. {} ({})\n", s.number, name, translit)),
+            None => content.push_str(&format!("{}. {}\n", s.number, name)),
+        }
     }
 
     Ok(CommandOutput::Pager { content })
@@ -801,12 +689,12 @@ fn read_word(state: &AppState, word_num: i64) -> Result<CommandOutput> {
         .ok_or_else(|| anyhow!("Word {} not found in current verse", word_num))?;
 
     let mut details = Vec::new();
-    let token_text = token.text.clone().unwrap_or_default();
+    let token_text = token.text.clone();
     details.push(AnalysisToken {
         text: token_text.clone(),
         root: token.segments.get(0).and_then(|s| s.root.clone()),
         pos: token.segments.get(0).and_then(|s| s.pos.clone()),
-        form: token.form.clone(),
+        form: None,
         lemma: None,
         features: None,
         role: None,
@@ -974,11 +862,7 @@ fn build_analysis_tokens(
             .tokens
             .iter()
             .flat_map(|token| {
-                let base_text = token
-                    .form
-                    .clone()
-                    .or_else(|| token.text.clone())
-                    .unwrap_or_default();
+                let base_text = token.text.clone();
                 let key = base_text.to_lowercase();
                 if !key.is_empty() && seen_keys.contains(&key) {
                     return Vec::new();
@@ -1016,7 +900,7 @@ fn build_analysis_tokens(
                         text: base_text.clone(),
                         root: None,
                         pos: None,
-                        form: token.form.clone(),
+                        form: None,
                         lemma: None,
                         features: None,
                         role: None,
@@ -1040,7 +924,7 @@ fn build_analysis_tokens(
                             text: label,
                             root: seg.root.clone(),
                             pos: seg.pos.clone(),
-                            form: token.form.clone(),
+                            form: Some(seg.form.clone()),
                             lemma: None,
                             features: None,
                             role: None,
@@ -1158,38 +1042,21 @@ fn consolidate_tokens(tokens: Vec<AnalysisToken>) -> Vec<AnalysisToken> {
     map.into_values().collect()
 }
 
-fn pos_long_form(tag: &str) -> String {
-    let upper = tag.trim().to_uppercase();
-    let long = match upper.as_str() {
-        "DET" => "Determiner",
-        "PREP" => "Preposition",
-        "NOUN" => "Noun",
-        "PROP-NOUN" | "PROPN" => "Proper Noun",
-        "ADJ" => "Adjective",
-        "VERB" | "V" => "Verb",
-        "ADV" => "Adverb",
-        "PRON" => "Pronoun",
-        "CONJ" => "Conjunction",
-        "PART" => "Particle",
-        "NUM" => "Numeral",
-        "INTERJ" => "Interjection",
-        _ => {
-            let cleaned = upper.replace('_', " ").replace('-', " ");
-            let titled = cleaned
-                .split_whitespace()
-                .map(|w| {
-                    let mut chars = w.chars();
-                    match chars.next() {
-                        Some(first) => format!("{}{}", first.to_uppercase(), chars.as_str().to_lowercase()),
-                        None => String::new(),
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join(" ");
-            return if titled.is_empty() { tag.to_string() } else { titled };
-        }
-    };
-    format!("{} ({})", long, tag)
+/// Language grammatical labels are shown in (`KALIMA_LANG`, default English).
+fn label_lang() -> Lang {
+    std::env::var("KALIMA_LANG")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default()
+}
+
+/// A code with its name from the bundled tag tables, e.g. `Noun (N)`;
+/// codes the tables do not know are shown as they are.
+fn code_label(label: Option<&Label>, code: &str) -> String {
+    match label {
+        Some(label) => format!("{} ({})", label.get(label_lang()), code.trim()),
+        None => code.to_string(),
+    }
 }
 
 fn interp_key(surah: i64, ayah: i64) -> String {
@@ -1222,7 +1089,7 @@ fn fetch_interpretations_with_ids(
         .get(url)
         .query(&[("target_id", key.as_str())])
         .send()?
-        .error_for_status()?
+        .api_ok()?
         .json()?;
 
     let mut out = Vec::new();
@@ -1268,7 +1135,7 @@ fn save_interpretation(state: &mut AppState, surah: i64, ayah: i64, text: &str)
         .post(format!("{}/annotations", state.base_url))
         .json(&body)
         .send()?
-        .error_for_status()?;
+        .api_ok()?;
 
     state
         .interpretations
@@ -1278,6 +1145,37 @@ fn save_interpretation(state: &mut AppState, surah: i64, ayah: i64, text: &str)
     Ok(())
 }
 
+fn replace_interpretation(
+    state: &mut AppState,
+    surah: i64,
+    ayah: i64,
+    old_id: &str,
+    text: &str,
+) -> Result<()> {
+    let key = interp_key(surah, ayah);
+    let body = serde_json::json!({
+        "target_id": key,
+        "layer": "interpretation",
+        "payload": { "text": text },
+    });
+
+    state
+        .client
+        .put(format!("{}/annotations/{}", state.base_url, old_id))
+        .json(&body)
+        .send()?
+        .api_ok()?;
+
+    if let Some(entry) = state
+        .interpretations
+        .get_mut(&key)
+        .and_then(|list| list.iter_mut().find(|(id, _)| id == old_id))
+    {
+        entry.1 = text.to_string();
+    }
+    Ok(())
+}
+
 fn build_tree_display(verse: &Verse, segments: &[Value]) -> String {
     #[derive(Default)]
     struct Node {
@@ -1302,7 +1200,7 @@ fn build_tree_display(verse: &Verse, segments: &[Value]) -> String {
         word_surfaces = verse
             .tokens
             .iter()
-            .map(|t| t.form.clone().or_else(|| t.text.clone()).unwrap_or_default())
+            .map(|t| t.text.clone())
             .collect();
     }
 
@@ -1377,13 +1275,13 @@ fn build_tree_display(verse: &Verse, segments: &[Value]) -> String {
             }
         }
         if !pos.is_empty() {
-            parts.push(format!("POS: {}", pos_long_form(pos)));
+            parts.push(format!("POS: {}", code_label(TagLabels::bundled().pos(pos), pos)));
         }
         if !role.is_empty() {
-            parts.push(format!("Role: {}", role));
+            parts.push(format!("Role: {}", code_label(TagLabels::bundled().relation(role), role)));
         }
         if !case_.is_empty() {
-            parts.push(format!("Case: {}", case_));
+            parts.push(format!("Case: {}", code_label(TagLabels::bundled().feature("case", case_), case_)));
         }
         if let Some(inf) = inflection.clone() {
             parts.push(format!("Inflection: {}", inf));
@@ -1471,13 +1369,13 @@ fn build_tree_display(verse: &Verse, segments: &[Value]) -> String {
     out
 }
 
-fn fetch_surah(state: &AppState, number: i64) -> Result<SurahData> {
+fn fetch_surah(state: &AppState, number: i64) -> Result<Surah> {
     let surah = state
         .client
         .get(format!("{}/api/surah/{}", state.base_url, number))
         .send()?
-        .error_for_status()?
-        .json::<SurahData>()?;
+        .api_ok()?
+        .json::<Surah>()?;
     Ok(surah)
 }
 
@@ -1486,7 +1384,7 @@ fn fetch_surah_list(state: &AppState) -> Result<Vec<SurahSummary>> {
         .client
         .get(format!("{}/api/surahs", state.base_url))
         .send()?
-        .error_for_status()?
+        .api_ok()?
         .json::<Vec<SurahSummary>>()?;
     Ok(surahs
         .into_iter()
@@ -1504,7 +1402,7 @@ fn fetch_verse(state: &AppState, surah: i64, ayah: i64) -> Result<Verse> {
         .client
         .get(format!("{}/api/verse/{}/{}", state.base_url, surah, ayah))
         .send()?
-        .error_for_status()?
+        .api_ok()?
         .json::<Verse>()?;
     validate_verse(&verse)?;
     Ok(verse)
@@ -1518,7 +1416,7 @@ fn fetch_morphology(state: &AppState, surah: i64, ayah: i64) -> Result<Vec<Value
             state.base_url, surah, ayah
         ))
         .send()?
-        .error_for_status()?
+        .api_ok()?
         .json()?;
     let s = res.get("surah").and_then(Value::as_i64).unwrap_or(0);
     let a = res.get("ayah").and_then(Value::as_i64).unwrap_or(0);
@@ -1546,7 +1444,7 @@ fn fetch_dependency(state: &AppState, surah: i64, ayah: i64) -> Result<Vec<Value
             state.base_url, surah, ayah
         ))
         .send()?
-        .error_for_status()?
+        .api_ok()?
         .json()?;
     let s = res.get("surah").and_then(Value::as_i64).unwrap_or(0);
     let a = res.get("ayah").and_then(Value::as_i64).unwrap_or(0);
@@ -1814,19 +1712,6 @@ fn load_masaq_morphology(surah: i64, ayah: i64) -> Vec<Value> {
     result
 }
 
-fn parse_verse_ref(s: &str) -> Result<(i64, i64)> {
-    let parts: Vec<_> = s.split(':').collect();
-    let surah = parts
-        .get(0)
-        .ok_or_else(|| anyhow!("missing surah"))?
-        .parse()?;
-    let ayah = parts
-        .get(1)
-        .ok_or_else(|| anyhow!("missing ayah"))?
-        .parse()?;
-    Ok((surah, ayah))
-}
-
 fn parse_number(s: &str) -> Result<i64> {
     if let Ok(n) = s.trim().parse::<i64>() {
         return Ok(n);
@@ -1946,11 +1831,14 @@ fn validate_verse(v: &Verse) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::{Segment, Token};
     use serde_json::json;
 
     #[test]
     fn parse_verse_ref_accepts_surah_ayah() {
         assert_eq!(parse_verse_ref("2:5").unwrap(), (2, 5));
+        assert_eq!(parse_verse_ref("al-Baqarah 255").unwrap(), (2, 255));
+        assert_eq!(named_write_target("البقرة ٢٥٥ a note").unwrap(), ((2, 255), "a note".to_string()));
     }
 
     #[test]
@@ -2001,10 +1889,10 @@ mod tests {
             ayah: 1,
             text: "بِسْمِ ٱللَّهِ".into(),
             tokens: vec![Token {
-                text: Some("بِسْمِ".into()),
-                form: Some("بِسْمِ".into()),
-                segments: vec![],
+                text: "بِسْمِ".into(),
+                ..Default::default()
             }],
+            ..Default::default()
         });
 
         let output = inspect_current(&state).expect("inspect should succeed");
@@ -2033,6 +1921,7 @@ mod tests {
             ayah: 1,
             text: "text".into(),
             tokens: vec![],
+            ..Default::default()
         };
         let morph = vec![json!({
             "text": "word",
@@ -2059,13 +1948,15 @@ mod tests {
             ayah: 1,
             text: "text".into(),
             tokens: vec![Token {
-                text: Some("base".into()),
-                form: Some("form".into()),
+                text: "form".into(),
                 segments: vec![Segment {
                     root: Some("root".into()),
                     pos: Some("POS".into()),
+                    ..Default::default()
                 }],
+                ..Default::default()
             }],
+            ..Default::default()
         };
         let tokens = build_analysis_tokens(&verse, &[], &[], true);
         assert_eq!(tokens.len(), 2);
@@ -2083,6 +1974,7 @@ mod tests {
             ayah: 1,
             text: "text".into(),
             tokens: vec![],
+            ..Default::default()
         };
         let morph: Vec<Value> = vec![];
         let deps: Vec<Value> = vec![json!({
@@ -2107,19 +1999,20 @@ mod tests {
             text: "text".into(),
             tokens: vec![
                 Token {
-                    text: Some("first".into()),
-                    form: Some("first".into()),
-                    segments: vec![],
+                    text: "first".into(),
+                    ..Default::default()
                 },
                 Token {
-                    text: Some("second".into()),
-                    form: Some("second".into()),
+                    text: "second".into(),
                     segments: vec![Segment {
                         root: Some("r2".into()),
                         pos: Some("POS2".into()),
+                        ..Default::default()
                     }],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
         // Morphology only covers the first token
         let morph = vec![json!({
@@ -2147,10 +2040,10 @@ mod tests {
             ayah: 1,
             text: "foo bar baz".into(),
             tokens: vec![Token {
-                text: Some("foo bar baz".into()),
-                form: Some("foo bar baz".into()),
-                segments: vec![],
+                text: "foo bar baz".into(),
+                ..Default::default()
             }],
+            ..Default::default()
         };
         let tokens = build_analysis_tokens(&verse, &[], &[], true);
         assert_eq!(tokens.len(), 3);
@@ -2167,6 +2060,7 @@ mod tests {
             ayah: 1,
             text: "بِسْمِ ٱللَّهِ ٱلرَحْمَٰنِ ٱلرَّحِيمِ".into(),
             tokens: vec![],
+            ..Default::default()
         };
         // Simulated segments with explicit word indices.
         let segments = vec![
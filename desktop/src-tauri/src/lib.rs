@@ -13,6 +13,7 @@ pub fn run() {
             }
 
             configure_backend_paths();
+            configure_profile();
 
             // Start the API server in background and wait for it to be ready
             tauri::async_runtime::spawn(async {
@@ -68,3 +69,34 @@ fn configure_backend_paths() {
     std::env::set_var("KALIMA_DB", db_path.to_string_lossy().to_string());
     std::env::set_var("KALIMA_INDEX", index_path.to_string_lossy().to_string());
 }
+
+/// Resolve the researcher name sent with every request (`x-kalima-user`).
+///
+/// `KALIMA_USER` wins if set; otherwise `data/profile.json` (`{"user": "..."}`)
+/// next to the executable or working directory, then the OS login name.
+fn configure_profile() {
+    if std::env::var("KALIMA_USER").map(|u| !u.trim().is_empty()).unwrap_or(false) {
+        return;
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()));
+    let candidates = exe_dir
+        .into_iter()
+        .chain(std::env::current_dir().ok())
+        .map(|dir| dir.join("data").join("profile.json"));
+
+    let from_profile = candidates
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .filter_map(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .find_map(|v| v.get("user").and_then(|u| u.as_str()).map(|u| u.trim().to_string()))
+        .filter(|u| !u.is_empty());
+
+    let user = from_profile
+        .or_else(|| std::env::var("USERNAME").ok())
+        .or_else(|| std::env::var("USER").ok());
+    if let Some(user) = user {
+        std::env::set_var("KALIMA_USER", user);
+    }
+}
@@ -0,0 +1,179 @@
+//! Optional bearer-token access control, and read-only mode.
+//!
+//! With no tokens configured every request is let through, as suits the
+//! desktop app talking to its own server on localhost. Once any token is
+//! configured, every route but `/health` and the API description needs an
+//! `Authorization: Bearer <token>` header whose scope covers the request:
+//!
+//! - `read`: `GET` routes, and searches sent as `POST`
+//! - `write`: every other change, such as annotations, tags or notes
+//! - `admin`: backups, restores and purging the trash
+//!
+//! Each scope includes the ones before it. In read-only mode anything needing
+//! more than `read` is refused, whatever the token.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{fmt, str::FromStr, sync::Arc};
+
+use crate::{ApiError, ServerConfig};
+
+/// What a token may do. Each scope includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    Read,
+    Write,
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read" => Ok(Scope::Read),
+            "write" => Ok(Scope::Write),
+            "admin" => Ok(Scope::Admin),
+            other => Err(format!("unknown scope `{}` (expected read, write or admin)", other)),
+        }
+    }
+}
+
+/// A bearer token and the scope it grants.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiToken {
+    secret: String,
+    pub scope: Scope,
+}
+
+impl ApiToken {
+    pub fn new(secret: impl Into<String>, scope: Scope) -> Self {
+        Self { secret: secret.into(), scope }
+    }
+
+    /// Compare without stopping at the first differing byte.
+    fn matches(&self, presented: &str) -> bool {
+        let (a, b) = (self.secret.as_bytes(), presented.as_bytes());
+        a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+    }
+}
+
+/// Keeps the secret out of logs.
+impl fmt::Debug for ApiToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiToken").field("scope", &self.scope).finish_non_exhaustive()
+    }
+}
+
+/// Tokens written `scope:token` and separated by commas, as in `KALIMA_API_TOKENS`.
+pub(crate) fn parse_token_list(list: &str) -> Result<Vec<ApiToken>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((scope, secret)) => token(scope, secret),
+            None => Err(format!("`{}…` is not `scope:token`", entry.chars().take(4).collect::<String>())),
+        })
+        .collect()
+}
+
+/// A tokens file: one `scope token` pair per line. Blank lines and lines
+/// starting with `#` are skipped.
+pub(crate) fn parse_token_file(text: &str) -> Result<Vec<ApiToken>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(scope), Some(secret), None) => token(scope, secret),
+                _ => Err("expected `scope token`".to_string()),
+            }
+            .map_err(|e| format!("line {}: {}", number, e))
+        })
+        .collect()
+}
+
+fn token(scope: &str, secret: &str) -> Result<ApiToken, String> {
+    let secret = secret.trim();
+    if secret.is_empty() || secret.contains(char::is_whitespace) {
+        return Err("a token must be non-empty and contain no spaces".to_string());
+    }
+    Ok(ApiToken::new(secret, scope.parse()?))
+}
+
+/// `POST` routes that only read: searches take their query as a body.
+const READING_POSTS: &[&str] = &["/v1/search", "/v1/search/pattern-word", "/search", "/api/search/pattern_word"];
+
+/// The scope a request needs, or `None` for routes open to everyone.
+fn required_scope(method: &Method, path: &str) -> Option<Scope> {
+    if path == "/health" || path == "/openapi.json" || path == "/docs" || path.starts_with("/docs/") {
+        return None;
+    }
+    if path.starts_with("/v1/admin/") || path.starts_with("/api/admin/") || path.ends_with("/trash/purge") {
+        return Some(Scope::Admin);
+    }
+    let reads = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || (*method == Method::POST && READING_POSTS.contains(&path));
+    Some(if reads { Scope::Read } else { Scope::Write })
+}
+
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+/// Refuse requests the configuration does not allow.
+pub(crate) async fn guard(State(config): State<Arc<ServerConfig>>, request: Request, next: Next) -> Response {
+    let Some(required) = required_scope(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    if config.read_only && required > Scope::Read {
+        return ApiError::new(StatusCode::FORBIDDEN, "read_only", "This server is read-only").into_response();
+    }
+    if config.tokens.is_empty() {
+        return next.run(request).await;
+    }
+    let presented = bearer(request.headers());
+    let Some(token) = presented.and_then(|p| config.tokens.iter().find(|t| t.matches(p))) else {
+        let message = match presented {
+            Some(_) => "Unknown bearer token",
+            None => "A bearer token is required",
+        };
+        let mut response = ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", message).into_response();
+        response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return response;
+    };
+    if token.scope < required {
+        return ApiError::new(
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            format!("This token's scope is `{}`; the request needs `{}`", token.scope, required),
+        )
+        .with_details(serde_json::json!({ "scope": token.scope.as_str(), "required": required.as_str() }))
+        .into_response();
+    }
+    next.run(request).await
+}
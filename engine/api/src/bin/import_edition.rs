@@ -0,0 +1,87 @@
+use store::{Edition, QuranMetadata, SqliteStorage};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Import a text edition (e.g. Warsh, Hafs simple script) and align it to the
+/// analysed edition already in the database.
+#[derive(StructOpt)]
+struct Args {
+    /// Edition text: either `surah|ayah|text` lines (Tanzil format) or one
+    /// verse per line in mushaf order
+    #[structopt(long, parse(from_os_str))]
+    input: PathBuf,
+    /// SQLite database path
+    #[structopt(long, default_value = "kalima.db")]
+    db: String,
+    /// Edition id used by the `edition` API parameter, e.g. `warsh`
+    #[structopt(long)]
+    id: String,
+    /// Display name
+    #[structopt(long)]
+    name: String,
+    /// Riwayah, e.g. `hafs` or `warsh`
+    #[structopt(long)]
+    riwayah: Option<String>,
+    /// Script, e.g. `uthmani` or `imlaei`
+    #[structopt(long)]
+    script: Option<String>,
+    /// Where the text came from
+    #[structopt(long)]
+    source: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::from_args();
+    let storage = SqliteStorage::connect(&args.db).await?;
+
+    // Ayah counts for assigning verse numbers to plain one-verse-per-line files.
+    let metadata = QuranMetadata::bundled()?;
+    let mut sequence = metadata
+        .surahs
+        .iter()
+        .flat_map(|s| (1..=s.ayah_count).map(move |a| (s.number, a)));
+
+    let reader = BufReader::new(File::open(&args.input)?);
+    let mut verses = Vec::new();
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_start_matches('\u{feff}').trim();
+        // Tanzil appends license comments starting with '#'.
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.splitn(3, '|').collect();
+        let verse = match parts.as_slice() {
+            [s, a, text] => {
+                let surah: i64 = s.trim().parse().map_err(|_| {
+                    anyhow::anyhow!("line {}: invalid surah number '{}'", line_no + 1, s)
+                })?;
+                let ayah: i64 = a.trim().parse().map_err(|_| {
+                    anyhow::anyhow!("line {}: invalid ayah number '{}'", line_no + 1, a)
+                })?;
+                (surah, ayah, text.trim().to_string())
+            }
+            _ => {
+                let (surah, ayah) = sequence
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("line {}: more verses than the mushaf has", line_no + 1))?;
+                (surah, ayah, line.to_string())
+            }
+        };
+        verses.push(verse);
+    }
+
+    let edition = Edition {
+        id: args.id,
+        name: args.name,
+        riwayah: args.riwayah,
+        script: args.script,
+        source: args.source,
+    };
+    let count = storage.import_edition(&edition, &verses).await?;
+    println!("Imported {} verses into edition '{}'", count, edition.id);
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use store::{align_translation, decode_text, parse_alignments, parse_glosses, GlossFormat, SqliteStorage};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Import word-by-word glosses and verse translations aligned to tokens, in
+/// the formats described in `store::glosses`. Translations listed without
+/// links are aligned from the verse's glosses.
+#[derive(StructOpt)]
+struct Args {
+    /// Gloss file (TSV or JSON, UTF-8 or UTF-16)
+    #[structopt(long, parse(from_os_str), required_unless = "translations")]
+    input: Option<PathBuf>,
+    /// Verse translations to align (JSON list or JSON lines)
+    #[structopt(long, parse(from_os_str))]
+    translations: Option<PathBuf>,
+    /// Source name the glosses and translations are filed under, e.g. `corpus-wbw`
+    #[structopt(long)]
+    source: String,
+    /// Language of the glosses, e.g. `en`
+    #[structopt(long)]
+    lang: Option<String>,
+    /// `tsv` or `json`; taken from the file extension when omitted
+    #[structopt(long)]
+    format: Option<GlossFormat>,
+    /// SQLite database path
+    #[structopt(long, default_value = "kalima.db")]
+    db: String,
+    /// Check the files without writing anything
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::from_args();
+
+    let glosses = match &args.input {
+        Some(input) => {
+            let format = match args.format {
+                Some(format) => format,
+                None => input.extension().and_then(|e| e.to_str()).unwrap_or("tsv").parse()?,
+            };
+            parse_glosses(&decode_text(&std::fs::read(input)?)?, format, &args.source, args.lang.as_deref())?
+        }
+        None => Vec::new(),
+    };
+    let mut alignments = match &args.translations {
+        Some(path) => parse_alignments(&decode_text(&std::fs::read(path)?)?, &args.source)?,
+        None => Vec::new(),
+    };
+
+    if args.dry_run {
+        for alignment in alignments.iter_mut().filter(|a| a.links.is_empty()) {
+            let verse: Vec<_> =
+                glosses.iter().filter(|g| (g.surah, g.ayah) == (alignment.surah, alignment.ayah)).cloned().collect();
+            alignment.links = align_translation(&alignment.text, &verse);
+        }
+        let links: usize = alignments.iter().map(|a| a.links.len()).sum();
+        println!("(dry run) {} glosses, {} translations, {} links", glosses.len(), alignments.len(), links);
+        return Ok(());
+    }
+
+    let storage = SqliteStorage::connect(&args.db).await?;
+    let written = storage.import_glosses(&glosses).await?;
+    for alignment in alignments.iter_mut().filter(|a| a.links.is_empty()) {
+        let verse = storage.get_glosses(alignment.surah, alignment.ayah, None).await?;
+        alignment.links = align_translation(&alignment.text, &verse);
+    }
+    let links = storage.import_alignments(&alignments).await?;
+    println!("Imported {} glosses, {} translations ({} links)", written, alignments.len(), links);
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use common::Note;
+use store::{decode_text, note_title, parse_note_links, SqliteStorage};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Import markdown and plain-text notes (e.g. the repository's `notebooks/`)
+/// into the database. Each file becomes one note whose id is the file name
+/// without its extension; re-importing a file updates that note.
+#[derive(StructOpt)]
+struct Args {
+    /// Directory of `.md` / `.txt` notes; subdirectories are not read
+    #[structopt(long, parse(from_os_str))]
+    dir: PathBuf,
+    /// SQLite database path
+    #[structopt(long, default_value = "kalima.db")]
+    db: String,
+    /// Author recorded for new notes
+    #[structopt(long)]
+    author: Option<String>,
+    /// List what would be imported without writing anything
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::from_args();
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&args.dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("md" | "markdown" | "txt")))
+        .collect();
+    paths.sort();
+
+    let mut notes = Vec::new();
+    for path in paths {
+        let body = decode_text(&std::fs::read(&path)?)?;
+        let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let title = match note_title(&body) {
+            // A first line that runs on is prose, not a title.
+            title if !title.is_empty() && title.chars().count() <= 80 && body.trim_start().starts_with('#') => title,
+            _ => stem.clone(),
+        };
+        notes.push(Note { id: stem, title, body, author: args.author.clone(), ..Default::default() });
+    }
+
+    if args.dry_run {
+        for note in &notes {
+            println!("{}\t{}\t{} links", note.id, note.title, parse_note_links(&note.body).len());
+        }
+        println!("(dry run) {} notes", notes.len());
+        return Ok(());
+    }
+
+    let storage = SqliteStorage::connect(&args.db).await?;
+    let mut links = 0;
+    for note in &notes {
+        storage.upsert_note(note, args.author.as_deref()).await?;
+        links += storage.note_links(&note.id).await?.len();
+        for reference in storage.unresolved_note_links(&note.body).await? {
+            eprintln!("{}: [[{}]] does not name a verse in the corpus", note.id, reference);
+        }
+    }
+    println!("Imported {} notes ({} verse links)", notes.len(), links);
+    Ok(())
+}
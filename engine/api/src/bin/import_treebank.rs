@@ -0,0 +1,57 @@
+use common::{TagLabels, BUNDLED_POS};
+use store::{decode_text, parse_treebank, SqliteStorage};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Import a dependency treebank (heads, relations, elided words and phrase
+/// nodes) in the Quranic Arabic Corpus layout described in `store::treebank`.
+/// Verses in the file replace their previous analysis; others are untouched.
+#[derive(StructOpt)]
+struct Args {
+    /// Treebank file, tab separated, UTF-8 or UTF-16
+    #[structopt(long, parse(from_os_str))]
+    input: PathBuf,
+    /// SQLite database path
+    #[structopt(long, default_value = "kalima.db")]
+    db: String,
+    /// Relation labels to use instead of the bundled datasets/Quranic/RelLabels.csv
+    #[structopt(long, parse(from_os_str), requires = "constituents")]
+    relations: Option<PathBuf>,
+    /// Constituent tags to use instead of the bundled datasets/Quranic/ConstituentsTags.csv
+    #[structopt(long, parse(from_os_str), requires = "relations")]
+    constituents: Option<PathBuf>,
+    /// Check the file without writing anything
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::from_args();
+
+    let custom;
+    let tags = match (&args.relations, &args.constituents) {
+        (Some(relations), Some(constituents)) => {
+            custom = TagLabels::from_tables(
+                &decode_text(BUNDLED_POS)?,
+                &decode_text(&std::fs::read(relations)?)?,
+                &decode_text(&std::fs::read(constituents)?)?,
+            );
+            &custom
+        }
+        _ => TagLabels::bundled(),
+    };
+
+    let trees = parse_treebank(&decode_text(&std::fs::read(&args.input)?)?, tags)?;
+    let nodes: usize = trees.iter().map(|t| t.nodes.len()).sum();
+    if args.dry_run {
+        let edges: usize = trees.iter().map(|t| t.edges.len()).sum();
+        println!("(dry run) {} verses, {} nodes, {} edges", trees.len(), nodes, edges);
+        return Ok(());
+    }
+
+    let storage = SqliteStorage::connect(&args.db).await?;
+    let edges = storage.import_treebank(&trees).await?;
+    println!("Imported {} verses ({} nodes, {} edges)", trees.len(), nodes, edges);
+    Ok(())
+}
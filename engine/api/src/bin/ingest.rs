@@ -1,13 +1,13 @@
 use common::{SearchBackend, Segment, SegmentView};
 use search::TantivyIndex;
-use store::SqliteStorage;
+use store::{Lexicon, QuranMetadata, SqliteStorage};
 use serde::Deserialize;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
 use std::sync::Arc;
 use structopt::StructOpt;
-use sqlx::{query, Executor};
+use sqlx::Executor;
 
 #[derive(StructOpt)]
 struct Args {
@@ -23,6 +23,16 @@ struct Args {
     /// Skip creating the search index (useful for debugging permissions)
     #[structopt(long)]
     skip_index: bool,
+    /// Number of verses written per database transaction
+    #[structopt(long, default_value = "500")]
+    commit_every: usize,
+    /// Surah metadata and division boundaries (defaults to the bundled datasets/quran-metadata.json)
+    #[structopt(long, parse(from_os_str))]
+    metadata: Option<PathBuf>,
+    /// Directory with CARootLexicon.csv, CALemmaLexicon.csv and CAPoS.csv
+    /// (defaults to the bundled datasets/Quranic tables)
+    #[structopt(long, parse(from_os_str))]
+    lexicon: Option<PathBuf>,
 }
 
 #[derive(Deserialize)]
@@ -95,8 +105,33 @@ async fn main() -> anyhow::Result<()> {
         None
     };
 
+    let metadata = match &args.metadata {
+        Some(path) => QuranMetadata::from_json(&std::fs::read_to_string(path)?)?,
+        None => QuranMetadata::bundled()?,
+    };
+    storage.load_metadata(&metadata).await?;
+    println!(
+        "Loaded metadata for {} surahs ({} division kinds)",
+        metadata.surahs.len(),
+        metadata.divisions.len()
+    );
+
+    let lexicon = match &args.lexicon {
+        Some(dir) => Lexicon::from_dir(dir)?,
+        None => Lexicon::bundled()?,
+    };
+    storage.load_lexicon(&lexicon).await?;
+    println!(
+        "Loaded lexicon: {} roots, {} lemmas, {} POS tags",
+        lexicon.roots.len(),
+        lexicon.lemmas.len(),
+        lexicon.pos.len()
+    );
+
     let file = File::open(&args.input)?;
     let reader = BufReader::new(file);
+    let commit_every = args.commit_every.max(1);
+    let mut batch = storage.begin_batch().await?;
 
     let mut verse_count: usize = 0;
     let mut token_count: usize = 0;
@@ -123,6 +158,7 @@ async fn main() -> anyhow::Result<()> {
                 .filter(|t| !t.is_empty())
                 .unwrap_or_else(|| joined_tokens.clone());
 
+            let mut docs = Vec::with_capacity(tokens.len());
             for (i, tok) in tokens.into_iter().enumerate() {
                 token_count += 1;
                 let segs = tok
@@ -160,36 +196,34 @@ async fn main() -> anyhow::Result<()> {
                     .id
                     .map(|v| v.to_string().trim_matches('"').to_string())
                     .unwrap_or_else(|| format!("{}:{}:{}", verse.surah.number, verse.ayah, i));
-                let doc = SegmentView {
+                docs.push(SegmentView {
                     id: token_id.clone(),
                     verse_ref: format!("{}:{}", verse.surah.number, verse.ayah),
                     token_index: i,
                     text: tok.form.clone(),
                     segments: segs,
                     annotations: vec![],
-                };
-                storage.upsert_segment(&doc).await?;
-                if let Some(idx) = &index {
-                    idx.index_document(&doc).await?;
-                }
+                });
             }
 
+            batch.upsert_many(&docs).await?;
             // Override verse text to the clean Quran text once per verse.
-            query(
-                r#"
-                INSERT INTO verse_texts (surah_number, ayah_number, text)
-                VALUES (?1, ?2, ?3)
-                ON CONFLICT(surah_number, ayah_number) DO UPDATE SET text=excluded.text;
-                "#,
-            )
-            .bind(verse.surah.number)
-            .bind(verse.ayah)
-            .bind(&verse_text)
-            .execute(storage.pool())
-            .await?;
+            batch
+                .set_verse_text(verse.surah.number, verse.ayah, &verse_text)
+                .await?;
+            if let Some(idx) = &index {
+                for doc in &docs {
+                    idx.index_document(doc).await?;
+                }
+            }
         }
 
-        if verse_count % LOG_EVERY == 0 {
+        if verse_count.is_multiple_of(commit_every) {
+            batch.commit().await?;
+            batch = storage.begin_batch().await?;
+        }
+
+        if verse_count.is_multiple_of(LOG_EVERY) {
             println!(
                 "Ingested {} verses ({} tokens, {} segments)...",
                 verse_count, token_count, segment_count
@@ -197,6 +231,8 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
+    batch.commit().await?;
+
     if let Some(idx) = &index {
         println!("Committing index...");
         idx.commit()?;
@@ -6,6 +6,8 @@
 
 use std::env;
 
+use crate::auth::{self, ApiToken};
+
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     /// Database file path or connection string
@@ -14,11 +16,38 @@ pub struct ServerConfig {
     /// Search index directory path
     pub index_path: String,
 
-    /// Server bind address
+    /// Server bind address; localhost unless the server is meant to be shared
     pub bind_address: String,
 
     /// Log level (trace, debug, info, warn, error)
     pub log_level: String,
+
+    /// Days a soft-deleted annotation or connection stays in the trash
+    pub trash_retention_days: i64,
+
+    /// Directory database snapshots are written to
+    pub backup_dir: String,
+
+    /// Hours between automatic snapshots (0 disables them)
+    pub backup_interval_hours: u64,
+
+    /// Snapshots kept when rotating automatic backups
+    pub backup_keep: usize,
+
+    /// Language of grammatical labels when a request does not pass `?lang=`
+    pub lang: common::Lang,
+
+    /// Whether the deprecated pre-`/v1` routes are still served
+    pub legacy_routes: bool,
+
+    /// Bearer tokens accepted by the server; when empty no token is needed
+    pub tokens: Vec<ApiToken>,
+
+    /// Refuse every request that would change data
+    pub read_only: bool,
+
+    /// Why the configured tokens could not be read, reported by `validate`
+    token_error: Option<String>,
 }
 
 impl ServerConfig {
@@ -27,18 +56,58 @@ impl ServerConfig {
     /// Environment variables:
     /// - `KALIMA_DB`: Database path (default: "data/database/kalima.db")
     /// - `KALIMA_INDEX`: Search index path (default: "data/search-index")
-    /// - `KALIMA_BIND_ADDR`: Server bind address (default: "0.0.0.0:8080")
+    /// - `KALIMA_BIND_ADDR`: Server bind address (default: "127.0.0.1:8080")
     /// - `RUST_LOG`: Log level (default: "info")
+    /// - `KALIMA_TRASH_RETENTION_DAYS`: Days before trashed records are purged (default: 30)
+    /// - `KALIMA_BACKUP_DIR`: Snapshot directory (default: "backups" next to the database)
+    /// - `KALIMA_BACKUP_INTERVAL_HOURS`: Hours between automatic snapshots (default: 0, off)
+    /// - `KALIMA_BACKUP_KEEP`: Snapshots kept by rotation (default: 7)
+    /// - `KALIMA_LANG`: Label language, `en` or `ar` (default: "en")
+    /// - `KALIMA_LEGACY_ROUTES`: `false` stops serving the pre-`/v1` routes (default: true)
+    /// - `KALIMA_API_TOKENS`: Comma-separated `scope:token` pairs, scope `read`, `write` or `admin`
+    /// - `KALIMA_API_TOKENS_FILE`: File of `scope token` lines, added to the above
+    /// - `KALIMA_READ_ONLY`: `true` refuses every change (default: false)
     pub fn from_env() -> Self {
+        let database_path = env::var("KALIMA_DB")
+            .unwrap_or_else(|_| "data/database/kalima.db".to_string());
+        let (tokens, token_error) = match tokens_from_env() {
+            Ok(tokens) => (tokens, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
         Self {
-            database_path: env::var("KALIMA_DB")
-                .unwrap_or_else(|_| "data/database/kalima.db".to_string()),
+            backup_dir: env::var("KALIMA_BACKUP_DIR")
+                .unwrap_or_else(|_| default_backup_dir(&database_path)),
+            backup_interval_hours: env::var("KALIMA_BACKUP_INTERVAL_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            backup_keep: env::var("KALIMA_BACKUP_KEEP")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(store::DEFAULT_BACKUP_KEEP),
+            database_path,
             index_path: env::var("KALIMA_INDEX")
                 .unwrap_or_else(|_| "data/search-index".to_string()),
             bind_address: env::var("KALIMA_BIND_ADDR")
-                .unwrap_or_else(|_| "0.0.0.0:8080".to_string()),
+                .unwrap_or_else(|_| "127.0.0.1:8080".to_string()),
             log_level: env::var("RUST_LOG")
                 .unwrap_or_else(|_| "info".to_string()),
+            trash_retention_days: env::var("KALIMA_TRASH_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(store::DEFAULT_TRASH_RETENTION_DAYS),
+            lang: env::var("KALIMA_LANG")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
+            legacy_routes: env::var("KALIMA_LEGACY_ROUTES")
+                .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "false" | "0" | "off" | "no"))
+                .unwrap_or(true),
+            tokens,
+            read_only: env::var("KALIMA_READ_ONLY")
+                .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "true" | "1" | "on" | "yes"))
+                .unwrap_or(false),
+            token_error,
         }
     }
 
@@ -47,10 +116,19 @@ impl ServerConfig {
     /// Useful for testing or programmatic configuration.
     pub fn new(database_path: String, index_path: String) -> Self {
         Self {
+            backup_dir: default_backup_dir(&database_path),
+            backup_interval_hours: 0,
+            backup_keep: store::DEFAULT_BACKUP_KEEP,
             database_path,
             index_path,
-            bind_address: "0.0.0.0:8080".to_string(),
+            bind_address: "127.0.0.1:8080".to_string(),
             log_level: "info".to_string(),
+            trash_retention_days: store::DEFAULT_TRASH_RETENTION_DAYS,
+            lang: common::Lang::default(),
+            legacy_routes: true,
+            tokens: Vec::new(),
+            read_only: false,
+            token_error: None,
         }
     }
 
@@ -58,6 +136,10 @@ impl ServerConfig {
     ///
     /// Returns an error if paths are invalid or inaccessible.
     pub fn validate(&self) -> Result<(), String> {
+        if let Some(e) = &self.token_error {
+            return Err(format!("API tokens could not be read: {}", e));
+        }
+
         // Check if database directory exists
         if let Some(parent) = std::path::Path::new(&self.database_path).parent() {
             if !parent.exists() {
@@ -83,6 +165,32 @@ impl ServerConfig {
     }
 }
 
+/// Tokens from `KALIMA_API_TOKENS` and the file named by `KALIMA_API_TOKENS_FILE`.
+fn tokens_from_env() -> Result<Vec<ApiToken>, String> {
+    let mut tokens = match env::var("KALIMA_API_TOKENS") {
+        Ok(list) => auth::parse_token_list(&list).map_err(|e| format!("KALIMA_API_TOKENS: {}", e))?,
+        Err(_) => Vec::new(),
+    };
+    if let Ok(path) = env::var("KALIMA_API_TOKENS_FILE") {
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+        tokens.extend(auth::parse_token_file(&text).map_err(|e| format!("{}: {}", path, e))?);
+    }
+    Ok(tokens)
+}
+
+/// `backups/` beside the database file; connection strings fall back to `data/backups`.
+fn default_backup_dir(database_path: &str) -> String {
+    if database_path.starts_with("sqlite:") {
+        return "data/backups".to_string();
+    }
+    std::path::Path::new(database_path)
+        .parent()
+        .map(|p| p.join("backups"))
+        .unwrap_or_else(|| "backups".into())
+        .to_string_lossy()
+        .to_string()
+}
+
 impl Default for ServerConfig {
     fn default() -> Self {
         Self::from_env()
@@ -98,7 +206,7 @@ mod tests {
         let config = ServerConfig::default();
         assert!(!config.database_path.is_empty());
         assert!(!config.index_path.is_empty());
-        assert_eq!(config.bind_address, "0.0.0.0:8080");
+        assert_eq!(config.bind_address, "127.0.0.1:8080");
     }
 
     #[test]
@@ -109,5 +217,29 @@ mod tests {
         );
         assert_eq!(config.database_path, "test.db");
         assert_eq!(config.index_path, "test-index");
+        assert_eq!(config.trash_retention_days, store::DEFAULT_TRASH_RETENTION_DAYS);
+        assert_eq!(config.backup_interval_hours, 0);
+        assert!(config.legacy_routes);
+        assert!(config.tokens.is_empty());
+        assert!(!config.read_only);
+    }
+
+    #[test]
+    fn test_backup_dir_defaults_next_to_database() {
+        let config = ServerConfig::new("data/database/kalima.db".to_string(), "idx".to_string());
+        assert_eq!(std::path::Path::new(&config.backup_dir), std::path::Path::new("data/database/backups"));
+    }
+
+    #[test]
+    fn test_token_list_and_file() {
+        let tokens = auth::parse_token_list("read:student-pass, admin:s3cret").unwrap();
+        assert_eq!(tokens, vec![ApiToken::new("student-pass", auth::Scope::Read), ApiToken::new("s3cret", auth::Scope::Admin)]);
+        assert!(auth::parse_token_list("owner:abc").unwrap_err().contains("unknown scope"));
+        assert!(auth::parse_token_list("abc").is_err());
+
+        let file = "# shared instance\nread student-pass\n\nwrite ta-pass\n";
+        assert_eq!(auth::parse_token_file(file).unwrap().len(), 2);
+        assert!(auth::parse_token_file("read\n").unwrap_err().starts_with("line 1"));
+        assert!(!format!("{:?}", tokens[0]).contains("student-pass"));
     }
 }
@@ -0,0 +1,130 @@
+//! The JSON body every failing route returns:
+//!
+//! ```json
+//! { "error": { "code": "validation_failed", "message": "...", "details": { "field": "filters[0].field" } } }
+//! ```
+//!
+//! `code` is stable and meant for programs; `message` is for people and may
+//! change. `details` is `null` unless the error can say more, such as which
+//! field was wrong.
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::EngineError;
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorBody {
+    #[schema(example = "validation_failed")]
+    pub code: String,
+    pub message: String,
+    /// What the error points at, e.g. `{"field": "limit", "value": "ten"}`; null when nothing.
+    pub details: Value,
+}
+
+/// The body of every error response.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+/// An error response: a status and the [`ErrorBody`] sent with it.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub body: ErrorBody,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: ErrorBody { code: code.to_string(), message: message.into(), details: Value::Null },
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.body.details = details;
+        self
+    }
+
+    pub fn code(&self) -> &str {
+        &self.body.code
+    }
+}
+
+/// The code sent with a bare status, for errors raised outside a handler.
+fn status_code_name(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "invalid_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+        StatusCode::UNPROCESSABLE_ENTITY => "validation_failed",
+        s if s.is_server_error() => "internal_error",
+        _ => "error",
+    }
+}
+
+impl From<EngineError> for ApiError {
+    fn from(err: EngineError) -> Self {
+        match err {
+            EngineError::NotFound => ApiError::new(StatusCode::NOT_FOUND, "not_found", "Not found"),
+            EngineError::Invalid(msg) => ApiError::new(StatusCode::BAD_REQUEST, "invalid_request", msg),
+            EngineError::Validation { message, details } => {
+                ApiError::new(StatusCode::BAD_REQUEST, "validation_failed", message).with_details(details)
+            }
+            EngineError::Conflict(msg) => ApiError::new(StatusCode::CONFLICT, "conflict", msg),
+            EngineError::Unsupported(msg) => ApiError::new(StatusCode::BAD_REQUEST, "unsupported", msg),
+            EngineError::Storage(msg) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "storage_error", msg),
+            EngineError::Search(msg) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "search_error", msg),
+            EngineError::Other(e) => ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", e.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ErrorEnvelope { error: self.body })).into_response()
+    }
+}
+
+/// Wrap error responses that no handler produced — extractor rejections such
+/// as a malformed JSON body or a non-numeric path segment, unknown routes and
+/// wrong methods — in the same envelope.
+pub async fn wrap_plain_errors(response: Response) -> Response {
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) {
+        return response;
+    }
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if is_json {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = axum::body::to_bytes(body, usize::MAX).await.unwrap_or_default();
+    let message = match String::from_utf8_lossy(&body).trim() {
+        "" => status.canonical_reason().unwrap_or("Error").to_string(),
+        text => text.to_string(),
+    };
+    let mut wrapped = ApiError::new(status, status_code_name(status), message).into_response();
+    // Keep what the rejection said besides its body, such as `Allow` on a 405.
+    let mut headers = parts.headers;
+    headers.remove(header::CONTENT_TYPE);
+    headers.remove(header::CONTENT_LENGTH);
+    wrapped.headers_mut().extend(headers);
+    wrapped
+}
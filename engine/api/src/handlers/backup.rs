@@ -0,0 +1,67 @@
+use axum::{extract::{Query, State}, Json};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use store::SnapshotInfo;
+
+use crate::{ApiError, AppState, map_err};
+
+pub async fn list_backups(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SnapshotInfo>>, ApiError> {
+    let snapshots = store::list_snapshots(Path::new(&state.config.backup_dir)).map_err(map_err)?;
+    Ok(Json(snapshots))
+}
+
+/// Snapshot the live database into the backup directory (`?label=` is appended to the name).
+#[utoipa::path(
+    post, path = "/v1/admin/backups", tag = "admin",
+    params(("label" = Option<String>, Query, description = "Appended to the snapshot name")),
+    responses((status = 200, body = SnapshotInfo))
+)]
+pub async fn create_backup(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<SnapshotInfo>, ApiError> {
+    let snapshot = state
+        .storage
+        .snapshot_into(Path::new(&state.config.backup_dir), params.get("label").map(String::as_str))
+        .await
+        .map_err(map_err)?;
+    tracing::info!("Wrote database snapshot {}", snapshot.path);
+    Ok(Json(snapshot))
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct RestoreRequest {
+    /// File name of a snapshot in the backup directory
+    pub name: String,
+}
+
+/// Restore a snapshot from the backup directory, first snapshotting the
+/// current state so the restore itself can be undone.
+pub async fn restore_backup(
+    State(state): State<AppState>,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let safety = restore_snapshot(&state, &req.name).await?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "restored": req.name,
+        "pre_restore_snapshot": safety
+    })))
+}
+
+/// Restore the named snapshot, returning the one taken of the state it replaced.
+pub(crate) async fn restore_snapshot(state: &AppState, name: &str) -> Result<SnapshotInfo, ApiError> {
+    let dir = Path::new(&state.config.backup_dir);
+    let source = store::find_snapshot(dir, name).map_err(map_err)?;
+    let safety = state
+        .storage
+        .snapshot_into(dir, Some("pre-restore"))
+        .await
+        .map_err(map_err)?;
+    state.storage.restore_from(&source).await.map_err(map_err)?;
+    tracing::info!("Restored database from {} (previous state in {})", name, safety.name);
+    Ok(safety)
+}
@@ -0,0 +1,77 @@
+use axum::{extract::{Path, State}, Json};
+use common::{EngineError, Verse};
+use serde::Serialize;
+use store::Division;
+use utoipa::ToSchema;
+
+use crate::{ApiError, AppState, map_err};
+
+#[derive(Serialize, ToSchema)]
+pub struct DivisionKind {
+    pub kind: String,
+    /// Divisions of this kind loaded.
+    pub count: i64,
+}
+
+/// A division and the verses it spans.
+#[derive(Serialize, ToSchema)]
+pub struct DivisionDetail {
+    pub division: Division,
+    pub verses: Vec<Verse>,
+}
+
+/// Division kinds with the number of divisions loaded for each.
+pub async fn list_division_kinds(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    Ok(Json(serde_json::json!({ "kinds": division_kinds(&state).await? })))
+}
+
+pub(crate) async fn division_kinds(state: &AppState) -> Result<Vec<DivisionKind>, ApiError> {
+    let counts = state.corpus.count_divisions().await.map_err(map_err)?;
+    Ok(counts.into_iter().map(|(kind, count)| DivisionKind { kind, count }).collect())
+}
+
+pub async fn list_divisions(
+    State(state): State<AppState>,
+    Path(kind): Path<String>,
+) -> Result<Json<Vec<Division>>, ApiError> {
+    let divisions = state.corpus.list_divisions(&kind).await.map_err(map_err)?;
+    Ok(Json(divisions))
+}
+
+/// One division with the verses it spans.
+#[utoipa::path(
+    get, path = "/v1/divisions/{kind}/{number}", tag = "divisions",
+    params(("kind" = String, Path), ("number" = i64, Path)),
+    responses((status = 200, body = DivisionDetail))
+)]
+pub async fn get_division(
+    State(state): State<AppState>,
+    Path((kind, number)): Path<(String, i64)>,
+) -> Result<Json<DivisionDetail>, ApiError> {
+    let division = state
+        .corpus
+        .get_division(&kind, number)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    let verses = state
+        .corpus
+        .list_verses_between(
+            (division.start_surah, division.start_ayah),
+            (division.end_surah, division.end_ayah),
+        )
+        .await
+        .map_err(map_err)?;
+    Ok(Json(DivisionDetail { division, verses }))
+}
+
+/// Which juz, hizb, rub' and manzil a verse falls in.
+pub async fn locate_verse(
+    State(state): State<AppState>,
+    Path((surah, ayah)): Path<(i64, i64)>,
+) -> Result<Json<Vec<Division>>, ApiError> {
+    let divisions = state.corpus.locate_verse(surah, ayah).await.map_err(map_err)?;
+    Ok(Json(divisions))
+}
@@ -0,0 +1,93 @@
+use axum::{extract::{Query, State}, Json};
+use common::EngineError;
+use std::collections::HashMap;
+use store::{Edition, PRIMARY_EDITION};
+
+use crate::{ApiError, AppState, map_err};
+
+pub async fn list_editions(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Edition>>, ApiError> {
+    let editions = state.corpus.list_editions().await.map_err(map_err)?;
+    Ok(Json(editions))
+}
+
+/// The non-primary edition requested via `?edition=`, if any.
+///
+/// Unknown editions are rejected; the primary edition yields `None` so
+/// callers keep serving the analysed text unchanged.
+pub async fn requested_edition(
+    state: &AppState,
+    params: &HashMap<String, String>,
+) -> Result<Option<String>, ApiError> {
+    let Some(id) = params.get("edition").map(|s| s.trim()).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    if id == PRIMARY_EDITION {
+        return Ok(None);
+    }
+    match state.corpus.get_edition(id).await.map_err(map_err)? {
+        Some(edition) => Ok(Some(edition.id)),
+        None => Err(map_err(EngineError::Invalid(format!("Unknown edition: {}", id)))),
+    }
+}
+
+/// Word-level differences between two editions.
+///
+/// `GET /api/diff?from=hafs-uthmani&to=warsh&surah=2[&ayah=255][&normalize=true]`.
+/// Without `ayah` the whole surah is compared and only differing verses are returned.
+#[utoipa::path(
+    get, path = "/v1/editions/diff", tag = "editions",
+    params(
+        ("from" = Option<String>, Query, description = "Edition compared from, default the primary edition"),
+        ("to" = String, Query, description = "Edition compared to"),
+        ("surah" = i64, Query),
+        ("ayah" = Option<i64>, Query, description = "One verse; without it the whole surah"),
+        ("normalize" = Option<bool>, Query, description = "Ignore orthographic differences"),
+    ),
+    responses((status = 200, body = Object))
+)]
+pub async fn diff_editions(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let from = params.get("from").map(String::as_str).unwrap_or(PRIMARY_EDITION);
+    let to = params
+        .get("to")
+        .ok_or_else(|| map_err(EngineError::Invalid("Missing 'to' edition".into())))?;
+    let surah: i64 = params
+        .get("surah")
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| map_err(EngineError::Invalid("Missing or invalid 'surah'".into())))?;
+    let ayah: Option<i64> = match params.get("ayah") {
+        Some(a) => Some(
+            a.parse()
+                .map_err(|_| map_err(EngineError::Invalid(format!("Invalid ayah: {}", a))))?,
+        ),
+        None => None,
+    };
+    let normalize = params.get("normalize").map(|v| v == "true" || v == "1").unwrap_or(false);
+
+    let mut diffs = state
+        .corpus
+        .diff_editions(from, to, surah, ayah, normalize)
+        .await
+        .map_err(map_err)?;
+    if ayah.is_some() && diffs.is_empty() {
+        return Err(map_err(EngineError::NotFound));
+    }
+    let compared = diffs.len();
+    if ayah.is_none() {
+        diffs.retain(|d| !d.is_identical());
+    }
+
+    Ok(Json(serde_json::json!({
+        "from": from,
+        "to": to,
+        "surah": surah,
+        "normalized": normalize,
+        "verses_compared": compared,
+        "verses_differing": diffs.iter().filter(|d| !d.is_identical()).count(),
+        "verses": diffs
+    })))
+}
@@ -0,0 +1,129 @@
+use axum::{extract::{Path, Query, State}, Json};
+use common::{EngineError, Gloss, GlossSource, TranslationAlignment};
+use std::collections::HashMap;
+
+use crate::{ApiError, AppState, map_err};
+use crate::openapi::VersePath;
+
+fn param<'a>(params: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    params.get(key).map(|s| s.trim()).filter(|s| !s.is_empty())
+}
+
+fn alignment_json(alignment: &TranslationAlignment) -> serde_json::Value {
+    serde_json::json!({
+        "source": alignment.source,
+        "text": alignment.text,
+        "words": alignment.words(),
+        "links": alignment.links
+    })
+}
+
+fn gloss_json(gloss: &Gloss) -> serde_json::Value {
+    serde_json::json!({ "source": gloss.source, "lang": gloss.lang, "text": gloss.text })
+}
+
+pub async fn list_sources(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<GlossSource>>, ApiError> {
+    Ok(Json(state.corpus.list_gloss_sources().await.map_err(map_err)?))
+}
+
+/// Glosses of a verse, optionally from one `?source=`.
+pub async fn get_glosses(
+    State(state): State<AppState>,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<Gloss>>, ApiError> {
+    let glosses = state.corpus.get_glosses(surah, ayah, param(&params, "source")).await.map_err(map_err)?;
+    Ok(Json(glosses))
+}
+
+/// Verse translations with their token alignments, optionally from one `?source=`.
+pub async fn get_alignments(
+    State(state): State<AppState>,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, ApiError> {
+    let alignments = state.corpus.get_alignments(surah, ayah, param(&params, "source")).await.map_err(map_err)?;
+    Ok(Json(alignments.iter().map(alignment_json).collect()))
+}
+
+/// Arabic tokens of a verse with their glosses and the translation words
+/// aligned to them, for interlinear display.
+///
+/// `?source=` limits glosses and `?translation=` aligned translations to one
+/// source each.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/interlinear", tag = "glosses",
+    params(
+        VersePath,
+        ("source" = Option<String>, Query, description = "Gloss source"),
+        ("translation" = Option<String>, Query, description = "Aligned translation source"),
+    ),
+    responses((status = 200, body = Object))
+)]
+pub async fn interlinear(
+    State(state): State<AppState>,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse = state
+        .corpus
+        .get_verse(surah, ayah)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    let glosses = state.corpus.get_glosses(surah, ayah, param(&params, "source")).await.map_err(map_err)?;
+    let alignments = state
+        .corpus
+        .get_alignments(surah, ayah, param(&params, "translation"))
+        .await
+        .map_err(map_err)?;
+
+    let tokens: Vec<serde_json::Value> = verse
+        .tokens
+        .iter()
+        .map(|token| {
+            let of_token = |segment: Option<i64>| -> Vec<serde_json::Value> {
+                glosses
+                    .iter()
+                    .filter(|g| g.token_index == token.index && g.segment_index == segment)
+                    .map(gloss_json)
+                    .collect()
+            };
+            let segments: Vec<serde_json::Value> = token
+                .segments
+                .iter()
+                .enumerate()
+                .map(|(i, seg)| {
+                    serde_json::json!({
+                        "index": i,
+                        "form": seg.form,
+                        "pos": seg.pos,
+                        "glosses": of_token(Some(i as i64))
+                    })
+                })
+                .collect();
+            let translation: HashMap<&str, String> = alignments
+                .iter()
+                .map(|a| (a.source.as_str(), a.words_for_token(token.index).join(" ")))
+                .filter(|(_, words)| !words.is_empty())
+                .collect();
+            serde_json::json!({
+                "index": token.index,
+                "text": token.text,
+                "glosses": of_token(None),
+                "segments": segments,
+                "translation": translation
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "surah": surah,
+        "ayah": ayah,
+        "text": verse.text,
+        "tokens": tokens,
+        "translations": alignments.iter().map(alignment_json).collect::<Vec<_>>()
+    })))
+}
@@ -0,0 +1,185 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::{Anchor, EngineError};
+use serde::Serialize;
+use std::collections::HashMap;
+use store::{ConnectionGraph, DegreeStats, GraphFormat, GraphPath, Subgraph};
+use utoipa::ToSchema;
+
+use crate::{ApiError, AppState, map_err};
+use crate::openapi::GraphQuery;
+use super::util::{author_filter, number_param};
+
+const DEFAULT_DEPTH: usize = 1;
+
+fn flag(params: &HashMap<String, String>, name: &str) -> bool {
+    params.get(name).map(|v| v == "true" || v == "1").unwrap_or(false)
+}
+
+/// Graph over live connections, narrowed by `?layer=` and `?author=`/`?mine=true`.
+async fn load_graph(
+    state: &AppState,
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> Result<ConnectionGraph, ApiError> {
+    let author = author_filter(params, headers)?;
+    let layer = params.get("layer").map(|s| s.as_str()).filter(|s| !s.is_empty());
+    let edges = state
+        .research
+        .list_connections(layer, author.as_deref())
+        .await
+        .map_err(map_err)?;
+    Ok(ConnectionGraph::new(edges))
+}
+
+/// Fill in node text from the corpus; nodes that do not resolve keep none.
+async fn resolve_text(state: &AppState, subgraph: &mut Subgraph) {
+    for node in subgraph.nodes.iter_mut() {
+        if let Ok(Some(anchor)) = Anchor::parse_target(&node.id) {
+            if let Ok(resolved) = state.corpus.resolve_anchor(&anchor).await {
+                node.text = Some(resolved.text);
+            }
+        }
+    }
+}
+
+/// Nodes within `?depth=` steps (default 1) of a node, with the connections
+/// among them. Edges are followed both ways unless `?directed=true`.
+#[utoipa::path(
+    get, path = "/v1/graph/neighbours/{node}", tag = "graph",
+    params(("node" = String, Path), ("depth" = Option<usize>, Query, description = "Default 1"), GraphQuery),
+    responses((status = 200, body = Subgraph))
+)]
+pub async fn neighbours(
+    State(state): State<AppState>,
+    Path(node): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Subgraph>, ApiError> {
+    let graph = load_graph(&state, &params, &headers).await?;
+    let mut subgraph = graph
+        .neighbourhood(&node, number_param(&params, "depth")?.unwrap_or(DEFAULT_DEPTH), flag(&params, "directed"))
+        .map_err(map_err)?;
+    resolve_text(&state, &mut subgraph).await;
+    Ok(Json(subgraph))
+}
+
+/// Shortest path between `?from=` and `?to=`; 404 when they are not connected.
+#[utoipa::path(
+    get, path = "/v1/graph/path", tag = "graph",
+    params(("from" = String, Query), ("to" = String, Query), GraphQuery),
+    responses((status = 200, body = GraphPath))
+)]
+pub async fn shortest_path(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<GraphPath>, ApiError> {
+    let (from, to) = match (params.get("from"), params.get("to")) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return Err(map_err(EngineError::Invalid("Both from and to are required".into()))),
+    };
+    let graph = load_graph(&state, &params, &headers).await?;
+    let path = graph
+        .shortest_path(from, to, flag(&params, "directed"))
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    Ok(Json(path))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Components {
+    pub count: usize,
+    /// Node ids of each component, largest component first.
+    pub components: Vec<Vec<String>>,
+}
+
+/// Connected components, largest first.
+#[utoipa::path(
+    get, path = "/v1/graph/components", tag = "graph",
+    params(GraphQuery),
+    responses((status = 200, body = Components))
+)]
+pub async fn components(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Components>, ApiError> {
+    let graph = load_graph(&state, &params, &headers).await?;
+    let components = graph.components();
+    Ok(Json(Components { count: components.len(), components }))
+}
+
+/// Degree statistics; `?limit=` caps the per-node list.
+#[utoipa::path(
+    get, path = "/v1/graph/degrees", tag = "graph",
+    params(("limit" = Option<usize>, Query, description = "Cap on the per-node list"), GraphQuery),
+    responses((status = 200, body = DegreeStats))
+)]
+pub async fn degrees(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<DegreeStats>, ApiError> {
+    let graph = load_graph(&state, &params, &headers).await?;
+    let mut stats = graph.degree_stats();
+    if let Some(limit) = number_param(&params, "limit")? {
+        stats.degrees.truncate(limit);
+    }
+    Ok(Json(stats))
+}
+
+/// Export the graph, or the neighbourhood of `?node=` to `?depth=`, as
+/// `?format=graphml|dot|json` (JSON Graph Format, the default).
+#[utoipa::path(
+    get, path = "/v1/graph/export", tag = "graph",
+    params(
+        ("format" = Option<String>, Query, description = "`json` (JSON Graph Format, default), `graphml` or `dot`"),
+        ("node" = Option<String>, Query, description = "Export only this node's neighbourhood"),
+        ("depth" = Option<usize>, Query),
+        GraphQuery,
+    ),
+    responses((status = 200, description = "The graph in the requested format", content(
+        (String = "application/json"),
+        (String = "application/graphml+xml"),
+        (String = "text/vnd.graphviz"),
+    )))
+)]
+pub async fn export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response, ApiError> {
+    let format: GraphFormat = params
+        .get("format")
+        .map(|f| f.parse())
+        .transpose()
+        .map_err(map_err)?
+        .unwrap_or(GraphFormat::Json);
+    let graph = load_graph(&state, &params, &headers).await?;
+    let mut subgraph = match params.get("node") {
+        Some(node) => graph
+            .neighbourhood(node, number_param(&params, "depth")?.unwrap_or(DEFAULT_DEPTH), flag(&params, "directed"))
+            .map_err(map_err)?,
+        None => graph.to_subgraph(),
+    };
+    resolve_text(&state, &mut subgraph).await;
+    let body = subgraph.export(format).map_err(map_err)?;
+
+    let disposition = format!("attachment; filename=\"connections.{}\"", format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(format.content_type())),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition).map_err(|e| map_err(EngineError::Other(e.into())))?,
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
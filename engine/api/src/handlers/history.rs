@@ -0,0 +1,53 @@
+use axum::{extract::{Path, State}, http::HeaderMap, Json};
+use store::ChangeRecord;
+
+use crate::{ApiError, AppState, map_err};
+use super::util::request_actor;
+
+pub async fn get_record_history(
+    State(state): State<AppState>,
+    Path((record_type, record_id)): Path<(String, String)>,
+) -> Result<Json<Vec<ChangeRecord>>, ApiError> {
+    let changes = state
+        .research
+        .list_record_history(&record_type, &record_id)
+        .await
+        .map_err(map_err)?;
+    Ok(Json(changes))
+}
+
+pub async fn get_verse_history(
+    State(state): State<AppState>,
+    Path((surah, ayah)): Path<(i64, i64)>,
+) -> Result<Json<Vec<ChangeRecord>>, ApiError> {
+    let verse_ref = format!("{}:{}", surah, ayah);
+    let changes = state
+        .research
+        .list_verse_history(&verse_ref)
+        .await
+        .map_err(map_err)?;
+    Ok(Json(changes))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct RevertRequest {
+    /// Restore the record to the version recorded by this change.
+    pub change_id: i64,
+}
+
+pub async fn revert_record(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((record_type, record_id)): Path<(String, String)>,
+    Json(req): Json<RevertRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let restored = state
+        .research
+        .revert_record(&record_type, &record_id, req.change_id, request_actor(&headers).as_deref())
+        .await
+        .map_err(map_err)?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "record": restored
+    })))
+}
@@ -0,0 +1,75 @@
+use axum::{extract::{Path, State}, Json};
+use common::{AnnotationLayer, EngineError};
+
+use crate::{ApiError, AppState, map_err};
+
+/// Registered annotation layers with their target and payload schema, so
+/// clients can render annotation forms generically.
+pub async fn list_layers(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<AnnotationLayer>>, ApiError> {
+    let layers = state.research.list_layers().await.map_err(map_err)?;
+    Ok(Json(layers))
+}
+
+#[utoipa::path(
+    get, path = "/v1/layers/{name}", tag = "annotations",
+    params(("name" = String, Path)),
+    responses((status = 200, body = AnnotationLayer))
+)]
+pub async fn get_layer(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<AnnotationLayer>, ApiError> {
+    let layer = state
+        .research
+        .get_layer(&name)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    Ok(Json(layer))
+}
+
+/// Register a layer, or redefine it if the name is taken.
+#[utoipa::path(
+    post, path = "/v1/layers", tag = "annotations",
+    request_body = AnnotationLayer,
+    responses((status = 200, body = AnnotationLayer))
+)]
+pub async fn create_layer(
+    State(state): State<AppState>,
+    Json(layer): Json<AnnotationLayer>,
+) -> Result<Json<AnnotationLayer>, ApiError> {
+    save_layer(&state, layer).await
+}
+
+#[utoipa::path(
+    put, path = "/v1/layers/{name}", tag = "annotations",
+    params(("name" = String, Path)),
+    request_body = AnnotationLayer,
+    responses((status = 200, body = AnnotationLayer))
+)]
+pub async fn update_layer(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(layer): Json<AnnotationLayer>,
+) -> Result<Json<AnnotationLayer>, ApiError> {
+    save_layer(&state, AnnotationLayer { name, ..layer }).await
+}
+
+pub async fn delete_layer(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.research.delete_layer(&name).await.map_err(map_err)?;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+async fn save_layer(
+    state: &AppState,
+    layer: AnnotationLayer,
+) -> Result<Json<AnnotationLayer>, ApiError> {
+    state.research.upsert_layer(&layer).await.map_err(map_err)?;
+    let stored = state.research.get_layer(&layer.name).await.map_err(map_err)?;
+    Ok(Json(stored.unwrap_or(layer)))
+}
@@ -1,6 +1,16 @@
+pub mod backup;
+pub mod divisions;
+pub mod editions;
+pub mod glosses;
+pub mod graph;
+pub mod history;
+pub mod layers;
 pub mod morphology;
+pub mod notes;
 pub mod pattern;
 pub mod research;
 pub mod search;
+pub mod tags;
+pub mod trash;
 pub mod util;
 pub mod verse;
@@ -1,13 +1,42 @@
-use axum::{extract::{Path, State}, http::StatusCode, Json};
-use common::StorageBackend;
-use std::collections::HashMap;
+use axum::{extract::{Path, Query, State}, Json};
+use common::{Lang, SyntaxNode, TagLabels, VerseSegment};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
 
-use crate::{AppState, map_err};
+use super::util::request_lang;
+use crate::{ApiError, AppState, map_err};
+use crate::openapi::{VersePath, LangQuery};
+
+/// A verse segment with the names of its codes under `labels`.
+fn labelled(seg: &VerseSegment, lang: Lang) -> serde_json::Value {
+    let mut value = serde_json::json!(seg);
+    value["labels"] = serde_json::json!(TagLabels::bundled().segment(&seg.segment, lang));
+    value
+}
+
+/// Name of a syntax node's tag: the phrase type for phrases, else the POS.
+fn node_label(node: &SyntaxNode, lang: Lang) -> String {
+    let labels = TagLabels::bundled();
+    let label = if node.kind == store::NODE_PHRASE { labels.constituent(&node.tag) } else { labels.pos(&node.tag) };
+    label.map(|l| l.get(lang).to_string()).unwrap_or_else(|| node.tag.clone())
+}
+
+fn relation_label(relation: &str, lang: Lang) -> String {
+    TagLabels::bundled()
+        .relation(relation)
+        .map(|l| l.get(lang).to_string())
+        .unwrap_or_else(|| relation.to_string())
+}
+
+fn pos_label(pos: &str, lang: Lang) -> String {
+    TagLabels::bundled().pos(pos).map(|l| l.get(lang).to_string()).unwrap_or_else(|| pos.to_string())
+}
 
 pub async fn search_morphology(
     State(state): State<AppState>,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<Vec<common::SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<common::SegmentView>>, ApiError> {
     let q = params.get("q").cloned().unwrap_or_default();
     // If query contains "pattern:" or "root:" prefix, map to filters
     let mut filters = Vec::new();
@@ -22,16 +51,24 @@ pub async fn search_morphology(
         .search_with_filters(&q, filters, 50)
         .await
         .map_err(map_err)?;
-    let docs = state.storage.hydrate_segments(&hits).await.map_err(map_err)?;
+    let docs = state.corpus.hydrate_segments(&hits).await.map_err(map_err)?;
     Ok(Json(docs))
 }
 
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/segments", tag = "morphology",
+    params(VersePath, LangQuery),
+    responses((status = 200, body = Object))
+)]
 pub async fn get_morphology(
     State(state): State<AppState>,
     Path((surah, ayah)): Path<(i64, i64)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let lang = request_lang(&params, &state)?;
     // Return segments for this verse as morphology data
-    let segments = state.storage.get_verse_segments(surah, ayah).await.map_err(map_err)?;
+    let segments = state.corpus.get_verse_segments(surah, ayah).await.map_err(map_err)?;
+    let segments: Vec<serde_json::Value> = segments.iter().map(|seg| labelled(seg, lang)).collect();
 
     Ok(Json(serde_json::json!({
         "surah": surah,
@@ -40,26 +77,32 @@ pub async fn get_morphology(
     })))
 }
 
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/morphology", tag = "morphology",
+    params(VersePath, LangQuery),
+    responses((status = 200, body = Object))
+)]
 pub async fn get_parsed_morphology(
     State(state): State<AppState>,
     Path((surah, ayah)): Path<(i64, i64)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let segments = state.storage.get_verse_segments(surah, ayah).await.map_err(map_err)?;
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let lang = request_lang(&params, &state)?;
+    let segments = state.corpus.get_verse_segments(surah, ayah).await.map_err(map_err)?;
 
     // Group segments by token
-    let mut tokens_map: HashMap<usize, Vec<serde_json::Value>> = HashMap::new();
+    let mut tokens_map: BTreeMap<i64, Vec<VerseSegment>> = BTreeMap::new();
     for seg in segments {
-        let token_idx = seg.get("token_index").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-        tokens_map.entry(token_idx).or_insert_with(Vec::new).push(seg);
+        tokens_map.entry(seg.token_index).or_default().push(seg);
     }
 
-    let mut tokens = Vec::new();
-    for (idx, segs) in tokens_map.into_iter() {
-        tokens.push(serde_json::json!({
+    let tokens: Vec<serde_json::Value> = tokens_map
+        .into_iter()
+        .map(|(idx, segs)| serde_json::json!({
             "id": idx,
-            "segments": segs
-        }));
-    }
+            "segments": segs.iter().map(|seg| labelled(seg, lang)).collect::<Vec<_>>()
+        }))
+        .collect();
 
     Ok(Json(serde_json::json!({
         "surah": surah,
@@ -68,56 +111,160 @@ pub async fn get_parsed_morphology(
     })))
 }
 
+/// Treebank analysis of a verse: syntax `nodes` (segments, elided words,
+/// phrases), labelled head -> dependent `edges`, and the `roots` no edge
+/// points to. `dependency_tree` lists the edges flat, one per dependent word.
+/// Verses without treebank data fall back to the segments' relation labels.
+/// Tags and relations are named in the `?lang=` language.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/dependency", tag = "morphology",
+    params(VersePath, LangQuery),
+    responses((status = 200, body = Object))
+)]
 pub async fn get_dependency(
     State(state): State<AppState>,
     Path((surah, ayah)): Path<(i64, i64)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    // Return dependency data for verse (placeholder - would need treebank data)
-    let segments = state.storage.get_verse_segments(surah, ayah).await.map_err(map_err)?;
-
-    let dependency_tree: Vec<serde_json::Value> = segments.into_iter()
-        .filter_map(|seg| {
-            seg.get("dependency_rel").and_then(|rel| {
-                if !rel.is_null() {
-                    Some(serde_json::json!({
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let lang = request_lang(&params, &state)?;
+    let segments = state.corpus.get_verse_segments(surah, ayah).await.map_err(map_err)?;
+    let mut tree = state.corpus.get_dependency_tree(surah, ayah).await.map_err(map_err)?;
+
+    if tree.nodes.is_empty() {
+        let dependency_tree: Vec<serde_json::Value> = segments
+            .into_iter()
+            .filter_map(|seg| {
+                seg.segment.dependency_rel.as_ref().map(|rel| {
+                    serde_json::json!({
                         "rel_label": rel,
-                        "word": seg.get("text").cloned().unwrap_or(serde_json::Value::Null),
-                        "pos": seg.get("pos").cloned().unwrap_or(serde_json::Value::Null)
-                    }))
-                } else {
-                    None
-                }
+                        "relation_label": relation_label(rel, lang),
+                        "word": seg.text,
+                        "pos": seg.segment.pos,
+                        "pos_label": seg.segment.pos.as_deref().map(|pos| pos_label(pos, lang))
+                    })
+                })
             })
+            .collect();
+        return Ok(Json(serde_json::json!({
+            "surah": surah,
+            "ayah": ayah,
+            "nodes": [],
+            "edges": [],
+            "roots": [],
+            "dependency_tree": dependency_tree
+        })));
+    }
+
+    // Show segments in the corpus script where the treebank gives no form.
+    let mut forms: HashMap<(i64, i64), String> = HashMap::new();
+    let mut counts: HashMap<i64, i64> = HashMap::new();
+    for seg in &segments {
+        let index = counts.entry(seg.token_index).or_insert(0);
+        forms.insert((seg.token_index, *index), seg.text.clone());
+        *index += 1;
+    }
+    for node in tree.nodes.iter_mut() {
+        if let (None, Some(token), Some(segment)) = (&node.form, node.token_index, node.segment_index) {
+            node.form = forms.get(&(token, segment)).cloned();
+        }
+    }
+
+    let word = |id: &str| {
+        tree.node(id)
+            .map(|n| n.form.clone().unwrap_or_else(|| n.tag.clone()))
+            .unwrap_or_default()
+    };
+    let dependency_tree: Vec<serde_json::Value> = tree
+        .edges
+        .iter()
+        .map(|edge| {
+            serde_json::json!({
+                "rel_label": edge.relation,
+                "rel_ar": edge.relation_ar,
+                "word": word(&edge.dependent),
+                "relation_label": relation_label(&edge.relation, lang),
+                "pos": tree.node(&edge.dependent).map(|n| n.tag.clone()),
+                "pos_label": tree.node(&edge.dependent).map(|n| node_label(n, lang)),
+                "dependent": edge.dependent,
+                "head": edge.head,
+                "head_word": word(&edge.head)
+            })
+        })
+        .collect();
+
+    let nodes: Vec<serde_json::Value> = tree
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut value = serde_json::json!(node);
+            value["tag_label"] = serde_json::json!(node_label(node, lang));
+            value
+        })
+        .collect();
+    let edges: Vec<serde_json::Value> = tree
+        .edges
+        .iter()
+        .map(|edge| {
+            let mut value = serde_json::json!(edge);
+            value["relation_label"] = serde_json::json!(relation_label(&edge.relation, lang));
+            value
         })
         .collect();
 
     Ok(Json(serde_json::json!({
         "surah": surah,
         "ayah": ayah,
+        "lang": lang,
+        "roots": tree.roots(),
+        "nodes": nodes,
+        "edges": edges,
         "dependency_tree": dependency_tree
     })))
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct MorphPattern {
+    pub pattern: String,
+}
+
+/// A POS tag in the corpus with its label.
+#[derive(Serialize, ToSchema)]
+pub struct PosName {
+    pub pos: String,
+    pub label: String,
+}
+
 pub async fn list_morph_patterns(
     State(state): State<AppState>,
-) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
+) -> Result<Json<Vec<MorphPattern>>, ApiError> {
     // Query unique patterns from storage
-    let patterns = state.storage.list_unique_patterns().await.map_err(map_err)?;
-    let pattern_list: Vec<serde_json::Value> = patterns
-        .into_iter()
-        .map(|p| serde_json::json!({ "pattern": p }))
-        .collect();
-    Ok(Json(pattern_list))
+    let patterns = state.lexicon.list_unique_patterns().await.map_err(map_err)?;
+    Ok(Json(patterns.into_iter().map(|pattern| MorphPattern { pattern }).collect()))
 }
 
 pub async fn list_syntax_patterns(
     State(state): State<AppState>,
-) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<PosName>>, ApiError> {
+    let lang = request_lang(&params, &state)?;
     // Query unique POS tags from storage
-    let pos_tags = state.storage.list_unique_pos().await.map_err(map_err)?;
-    let syntax_patterns: Vec<serde_json::Value> = pos_tags
-        .into_iter()
-        .map(|pos| serde_json::json!({ "pos": pos }))
-        .collect();
-    Ok(Json(syntax_patterns))
+    let pos_tags = state.lexicon.list_unique_pos().await.map_err(map_err)?;
+    Ok(Json(pos_tags.into_iter().map(|pos| PosName { label: pos_label(&pos, lang), pos }).collect()))
+}
+
+/// Names of every POS tag, feature value, relation and phrase tag, in the
+/// `?lang=` language or, without one, in both.
+#[utoipa::path(
+    get, path = "/v1/labels", tag = "lexicon",
+    params(LangQuery),
+    responses((status = 200, body = Object))
+)]
+pub async fn list_labels(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let lang = match params.get("lang").map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        Some(lang) => Some(lang.parse::<Lang>().map_err(map_err)?),
+        None => None,
+    };
+    Ok(Json(TagLabels::bundled().catalogue(lang)))
 }
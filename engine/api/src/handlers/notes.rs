@@ -0,0 +1,194 @@
+use axum::{extract::{Path, Query, State}, http::HeaderMap, Json};
+use common::{Anchor, EngineError, Note, NoteBacklink, NoteLink};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{ApiError, AppState, map_err};
+use super::util::{author_filter, request_actor};
+
+/// Characters of context shown on each side of a search match.
+const SNIPPET_CHARS: usize = 100;
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct NoteRequest {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// A note without its body, as listed.
+#[derive(Serialize, ToSchema)]
+pub struct NoteSummary {
+    pub id: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// A note with the anchors its body cites.
+#[derive(Serialize, ToSchema)]
+pub struct NoteDetail {
+    #[serde(flatten)]
+    pub note: Note,
+    pub links: Vec<NoteLink>,
+    /// `[[...]]` references that name nothing in the corpus, as written.
+    pub unresolved: Vec<String>,
+}
+
+/// A note matching a library search.
+#[derive(Serialize, ToSchema)]
+pub struct NoteMatch {
+    pub id: String,
+    pub title: String,
+    /// Text around the first match.
+    pub snippet: String,
+}
+
+pub(crate) fn note_summary(note: &Note) -> NoteSummary {
+    NoteSummary {
+        id: note.id.clone(),
+        title: note.title.clone(),
+        author: note.author.clone(),
+        created_at: note.created_at.clone(),
+        updated_at: note.updated_at.clone(),
+    }
+}
+
+/// Text around the first match of `needle` (already lowercased), or the start
+/// of the note when only the title matched.
+fn snippet(body: &str, needle: &str) -> String {
+    let chars: Vec<char> = body.chars().collect();
+    let lower: Vec<char> = body.chars().flat_map(char::to_lowercase).collect();
+    let needle: Vec<char> = needle.chars().collect();
+    // Lowercasing can change the length of a few characters; fall back to the
+    // start of the note rather than point at the wrong place.
+    let at = if lower.len() == chars.len() && !needle.is_empty() {
+        lower.windows(needle.len()).position(|w| w == needle.as_slice()).unwrap_or(0)
+    } else {
+        0
+    };
+    let start = at.saturating_sub(SNIPPET_CHARS);
+    let end = (at + needle.len() + SNIPPET_CHARS).min(chars.len());
+    chars[start..end].iter().collect::<String>().trim().to_string()
+}
+
+/// Notes by title, without their bodies. Accepts `?author=` / `?mine=true`.
+pub async fn list_notes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let author = author_filter(&params, &headers)?;
+    let notes = state.research.list_notes(author.as_deref()).await.map_err(map_err)?;
+    Ok(Json(serde_json::json!({ "notes": notes.iter().map(note_summary).collect::<Vec<_>>() })))
+}
+
+/// A note with the anchors it cites.
+#[utoipa::path(
+    get, path = "/v1/notes/{id}", tag = "notes",
+    params(("id" = String, Path)),
+    responses((status = 200, body = NoteDetail))
+)]
+pub async fn get_note(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<NoteDetail>, ApiError> {
+    let note = state
+        .research
+        .get_note(&id)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    note_with_links(&state, note).await
+}
+
+#[utoipa::path(
+    post, path = "/v1/notes", tag = "notes",
+    request_body = NoteRequest,
+    responses((status = 200, body = NoteDetail))
+)]
+pub async fn create_note(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<NoteRequest>,
+) -> Result<Json<NoteDetail>, ApiError> {
+    let id = req.id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+    save_note(&state, &headers, id, req).await
+}
+
+#[utoipa::path(
+    put, path = "/v1/notes/{id}", tag = "notes",
+    params(("id" = String, Path)),
+    request_body = NoteRequest,
+    responses((status = 200, body = NoteDetail))
+)]
+pub async fn update_note(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<NoteRequest>,
+) -> Result<Json<NoteDetail>, ApiError> {
+    save_note(&state, &headers, id, req).await
+}
+
+pub async fn delete_note(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.research.delete_note(&id, request_actor(&headers).as_deref()).await.map_err(map_err)?;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+async fn save_note(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: String,
+    req: NoteRequest,
+) -> Result<Json<NoteDetail>, ApiError> {
+    let note = Note { id, title: req.title, body: req.body, ..Default::default() };
+    state.research.upsert_note(&note, request_actor(headers).as_deref()).await.map_err(map_err)?;
+    let stored = state.research.get_note(&note.id).await.map_err(map_err)?;
+    note_with_links(state, stored.unwrap_or(note)).await
+}
+
+async fn note_with_links(state: &AppState, note: Note) -> Result<Json<NoteDetail>, ApiError> {
+    let links = state.research.note_links(&note.id).await.map_err(map_err)?;
+    let unresolved = state.research.unresolved_note_links(&note.body).await.map_err(map_err)?;
+    Ok(Json(NoteDetail { note, links, unresolved }))
+}
+
+/// Notes whose title or text contains `?q=`, with a snippet around the match.
+pub async fn search_library(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<NoteMatch>>, ApiError> {
+    let q = params.get("q").map(|s| s.trim().to_lowercase()).unwrap_or_default();
+    let notes = state.research.search_notes(&q).await.map_err(map_err)?;
+    Ok(Json(
+        notes
+            .iter()
+            .map(|note| NoteMatch {
+                id: note.id.clone(),
+                title: note.title.clone(),
+                snippet: snippet(&note.body, &q),
+            })
+            .collect(),
+    ))
+}
+
+/// Every note citing the verse or a word or span inside it.
+pub async fn verse_notes(
+    State(state): State<AppState>,
+    Path((surah, ayah)): Path<(i64, i64)>,
+) -> Result<Json<Vec<NoteBacklink>>, ApiError> {
+    let anchor: Anchor = format!("{}:{}", surah, ayah).parse().map_err(map_err)?;
+    let backlinks = state.research.note_backlinks(&anchor).await.map_err(map_err)?;
+    Ok(Json(backlinks))
+}
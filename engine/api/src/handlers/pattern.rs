@@ -1,9 +1,9 @@
-use axum::{extract::State, http::StatusCode, Json};
-use common::{EngineError, StorageBackend};
+use axum::{extract::State, Json};
+use common::EngineError;
 
-use crate::{AppState, map_err};
+use crate::{ApiError, AppState, map_err};
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, utoipa::ToSchema)]
 pub struct PatternWordRequest {
     #[serde(default)]
     pub word: Option<String>,
@@ -64,7 +64,7 @@ pub fn pattern_segments_to_regex(
             let specific: String = diacs
                 .iter()
                 .filter_map(|d| d.as_str())
-                .map(|d| regex::escape(d))
+                .map(regex::escape)
                 .collect();
             format!("{}{}*", specific, DIACRITIC_CLASS)
         } else {
@@ -87,10 +87,15 @@ pub fn pattern_segments_to_regex(
     Some(format!("{}{}{}", left, body, right))
 }
 
+#[utoipa::path(
+    post, path = "/v1/search/pattern-word", tag = "search",
+    request_body = PatternWordRequest,
+    responses((status = 200, body = Object))
+)]
 pub async fn search_pattern_word(
     State(state): State<AppState>,
     Json(body): Json<PatternWordRequest>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let limit = body.limit.unwrap_or(50);
 
     // If we have segments, do regex-based pattern matching
@@ -106,7 +111,7 @@ pub async fn search_pattern_word(
                 .map_err(|e| map_err(EngineError::Invalid(format!("Invalid regex: {}", e))))?;
 
             // Fetch verse texts and match against pattern
-            let verse_texts = state.storage.get_all_verse_texts(6236).await.map_err(map_err)?;
+            let verse_texts = state.corpus.get_all_verse_texts(6236).await.map_err(map_err)?;
             let mut results = Vec::new();
             let mut total_count = 0;
 
@@ -118,7 +123,7 @@ pub async fn search_pattern_word(
                         // Parse verse_ref to get verse data
                         if let Some((surah_str, ayah_str)) = verse_ref.split_once(':') {
                             if let (Ok(surah), Ok(ayah)) = (surah_str.parse::<i64>(), ayah_str.parse::<i64>()) {
-                                if let Ok(Some(verse_data)) = state.storage.get_verse(surah, ayah).await {
+                                if let Ok(Some(verse_data)) = state.corpus.get_verse(surah, ayah).await {
                                     results.push(serde_json::json!({
                                         "verse": verse_data,
                                         "match": "Pattern match",
@@ -159,7 +164,7 @@ pub async fn search_pattern_word(
         .search_with_filters(word, filters, limit)
         .await
         .map_err(map_err)?;
-    let docs = state.storage.hydrate_segments(&hits).await.map_err(map_err)?;
+    let docs = state.corpus.hydrate_segments(&hits).await.map_err(map_err)?;
 
     Ok(Json(serde_json::json!({
         "results": docs,
@@ -1,14 +1,25 @@
-use axum::{extract::{Path, State}, http::StatusCode, Json};
-use common::{Annotation, EngineError};
+use axum::{extract::{Path, State}, http::{HeaderMap, StatusCode}, Json};
+use common::{Anchor, Annotation, EngineError};
 use store::ConnectionRecord;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::{AppState, map_err};
+use crate::{ApiError, AppState, map_err};
+use super::util::{anchor_param, author_filter, request_actor, retain_author, stamp_author, verse_key, verse_param};
+
+/// Targets written as anchors must exist in the corpus; other ids are opaque.
+pub(crate) async fn check_target(state: &AppState, target: &str) -> Result<(), ApiError> {
+    if let Some(anchor) = Anchor::parse_target(target).map_err(map_err)? {
+        state.corpus.resolve_anchor(&anchor).await.map_err(map_err)?;
+    }
+    Ok(())
+}
 
 // Annotations
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, utoipa::ToSchema)]
 pub struct AnnotationRequest {
     #[serde(default)]
     pub id: Option<String>,
@@ -17,62 +28,133 @@ pub struct AnnotationRequest {
     pub payload: serde_json::Value,
 }
 
+#[utoipa::path(
+    post, path = "/v1/annotations", tag = "annotations",
+    request_body = AnnotationRequest,
+    responses((status = 200, body = Annotation))
+)]
 pub async fn create_annotation(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<AnnotationRequest>,
-) -> Result<Json<Annotation>, (StatusCode, String)> {
+) -> Result<Json<Annotation>, ApiError> {
+    check_target(&state, &req.target_id).await?;
     let id = req.id.unwrap_or_else(|| Uuid::new_v4().to_string());
     let ann = Annotation {
         id: id.clone(),
         target_id: req.target_id,
         layer: req.layer,
         payload: req.payload,
+        author: None,
+        created_at: None,
+        updated_at: None,
     };
-    state.storage.upsert_annotation(&ann).await.map_err(map_err)?;
-    Ok(Json(ann))
+    state.research.upsert_annotation(&ann, request_actor(&headers).as_deref()).await.map_err(map_err)?;
+    let stored = state.research.get_annotation(&id).await.map_err(map_err)?;
+    Ok(Json(stored.unwrap_or(ann)))
+}
+
+/// Replace an annotation in one step; the previous version goes to the trash
+/// if the replacement carries a new id.
+#[utoipa::path(
+    put, path = "/v1/annotations/{id}", tag = "annotations",
+    params(("id" = String, Path)),
+    request_body = AnnotationRequest,
+    responses((status = 200, body = Annotation))
+)]
+pub async fn replace_annotation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(old_id): Path<String>,
+    Json(req): Json<AnnotationRequest>,
+) -> Result<Json<Annotation>, ApiError> {
+    check_target(&state, &req.target_id).await?;
+    let ann = Annotation {
+        id: req.id.unwrap_or_else(|| old_id.clone()),
+        target_id: req.target_id,
+        layer: req.layer,
+        payload: req.payload,
+        author: None,
+        created_at: None,
+        updated_at: None,
+    };
+    state
+        .research
+        .replace_annotation(&old_id, &ann, request_actor(&headers).as_deref())
+        .await
+        .map_err(map_err)?;
+    let stored = state.research.get_annotation(&ann.id).await.map_err(map_err)?;
+    Ok(Json(stored.unwrap_or(ann)))
 }
 
+/// `?target_id=` restricts to one target and `?overlaps=<anchor>` to targets
+/// overlapping an anchor; `?author=` or `?mine=true` to one researcher.
 pub async fn list_annotations(
     State(state): State<AppState>,
+    headers: HeaderMap,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<Vec<Annotation>>, (StatusCode, String)> {
+) -> Result<Json<Vec<Annotation>>, ApiError> {
     let target = params.get("target_id").map(|s| s.as_str());
+    let author = author_filter(&params, &headers)?;
+    if let Some(overlaps) = params.get("overlaps") {
+        let anchor = anchor_param(overlaps)?;
+        let anns = state
+            .research
+            .list_annotations_overlapping(&anchor, author.as_deref())
+            .await
+            .map_err(map_err)?;
+        return Ok(Json(anns));
+    }
     let anns = state
-        .storage
-        .list_annotations(target)
+        .research
+        .list_annotations(target, author.as_deref())
         .await
         .map_err(map_err)?;
     Ok(Json(anns))
 }
 
+#[utoipa::path(
+    delete, path = "/v1/annotations/{id}", tag = "annotations",
+    params(("id" = String, Path)),
+    responses((status = 204))
+)]
 pub async fn delete_annotation(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<StatusCode, (StatusCode, String)> {
+) -> Result<StatusCode, ApiError> {
     state
-        .storage
-        .delete_annotation(&id)
+        .research
+        .delete_annotation(&id, request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Annotations on a verse or anything inside it.
 pub async fn get_annotations(
-    State(_state): State<AppState>,
-    Path((_surah, _ayah)): Path<(i64, i64)>,
-) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
-    // Annotations are handled via the general annotation endpoints
-    // This endpoint exists for frontend compatibility
-    Ok(Json(vec![]))
+    State(state): State<AppState>,
+    Path((surah, ayah)): Path<(i64, i64)>,
+) -> Result<Json<Vec<Annotation>>, ApiError> {
+    let anchor = Anchor::Verse { surah, ayah };
+    state.corpus.resolve_anchor(&anchor).await.map_err(map_err)?;
+    let anns = state
+        .research
+        .list_annotations_overlapping(&anchor, None)
+        .await
+        .map_err(map_err)?;
+    Ok(Json(anns))
 }
 
 pub async fn create_annotation_verse(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path((surah, ayah)): Path<(i64, i64)>,
     Json(annotation): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     // Convert to annotation format and create
     let target_id = format!("{}:{}", surah, ayah);
+    check_target(&state, &target_id).await?;
     let layer = annotation.get("layer")
         .and_then(|v| v.as_str())
         .unwrap_or("default")
@@ -86,16 +168,19 @@ pub async fn create_annotation_verse(
         target_id,
         layer,
         payload: annotation.get("payload").cloned().unwrap_or(annotation.clone()),
+        author: None,
+        created_at: None,
+        updated_at: None,
     };
 
-    state.storage.upsert_annotation(&ann).await.map_err(map_err)?;
+    state.research.upsert_annotation(&ann, request_actor(&headers).as_deref()).await.map_err(map_err)?;
 
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
 // Connections
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, utoipa::ToSchema)]
 pub struct ConnectionRequest {
     #[serde(default)]
     pub id: Option<String>,
@@ -106,10 +191,18 @@ pub struct ConnectionRequest {
     pub meta: serde_json::Value,
 }
 
+#[utoipa::path(
+    post, path = "/v1/connections", tag = "connections",
+    request_body = ConnectionRequest,
+    responses((status = 200, body = ConnectionRecord))
+)]
 pub async fn create_connection(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<ConnectionRequest>,
-) -> Result<Json<ConnectionRecord>, (StatusCode, String)> {
+) -> Result<Json<ConnectionRecord>, ApiError> {
+    check_target(&state, &req.from_token).await?;
+    check_target(&state, &req.to_token).await?;
     let id = req.id.unwrap_or_else(|| Uuid::new_v4().to_string());
     let conn = ConnectionRecord {
         id: id.clone(),
@@ -117,38 +210,47 @@ pub async fn create_connection(
         to_token: req.to_token,
         layer: req.layer,
         meta: req.meta,
+        author: None,
+        created_at: None,
+        updated_at: None,
     };
     state
-        .storage
-        .upsert_connection(&conn)
+        .research
+        .upsert_connection(&conn, request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
-    Ok(Json(conn))
-}
-
-#[derive(serde::Deserialize)]
-pub struct VerseQuery {
-    pub verse: String,
+    let stored = state.research.get_connection(&id).await.map_err(map_err)?;
+    Ok(Json(stored.unwrap_or(conn)))
 }
 
+/// `?verse=S:A` lists connections starting in a verse; `?overlaps=<anchor>`
+/// those with either end overlapping an anchor.
 pub async fn list_connections(
     State(state): State<AppState>,
-    axum::extract::Query(q): axum::extract::Query<VerseQuery>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let (surah, ayah) = q
-        .verse
-        .split_once(':')
-        .ok_or_else(|| map_err(EngineError::Invalid("Invalid verse param".into())))?;
-    let surah_num: i64 = surah
-        .parse()
-        .map_err(|_| map_err(EngineError::Invalid("Invalid surah".into())))?;
-    let ayah_num: i64 = ayah
-        .parse()
-        .map_err(|_| map_err(EngineError::Invalid("Invalid ayah".into())))?;
+    headers: HeaderMap,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let author = author_filter(&params, &headers)?;
+    if let Some(overlaps) = params.get("overlaps") {
+        let anchor = anchor_param(overlaps)?;
+        let conns = state
+            .research
+            .list_connections_overlapping(&anchor, author.as_deref())
+            .await
+            .map_err(map_err)?;
+        return Ok(Json(serde_json::json!({
+            "internal": conns,
+            "external": []
+        })));
+    }
+    let verse = params
+        .get("verse")
+        .ok_or_else(|| map_err(EngineError::Invalid("Either verse or overlaps is required".into())))?;
+    let (surah_num, ayah_num) = verse_param(verse)?;
 
     let conns = state
-        .storage
-        .list_connections_for_verse(surah_num, ayah_num)
+        .research
+        .list_connections_for_verse(surah_num, ayah_num, author.as_deref())
         .await
         .map_err(map_err)?;
     Ok(Json(serde_json::json!({
@@ -157,13 +259,19 @@ pub async fn list_connections(
     })))
 }
 
+#[utoipa::path(
+    delete, path = "/v1/connections/{id}", tag = "connections",
+    params(("id" = String, Path)),
+    responses((status = 204))
+)]
 pub async fn delete_connection(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<StatusCode, (StatusCode, String)> {
+) -> Result<StatusCode, ApiError> {
     state
-        .storage
-        .delete_connection(&id)
+        .research
+        .delete_connection(&id, request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
     Ok(StatusCode::NO_CONTENT)
@@ -171,21 +279,16 @@ pub async fn delete_connection(
 
 pub async fn get_connections(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let (surah, ayah) = verse_ref
-        .split_once(':')
-        .ok_or_else(|| map_err(EngineError::Invalid("Invalid verse reference".into())))?;
-    let surah_num: i64 = surah
-        .parse()
-        .map_err(|_| map_err(EngineError::Invalid("Invalid surah".into())))?;
-    let ayah_num: i64 = ayah
-        .parse()
-        .map_err(|_| map_err(EngineError::Invalid("Invalid ayah".into())))?;
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let author = author_filter(&params, &headers)?;
+    let (surah_num, ayah_num) = verse_param(&verse_ref)?;
 
     let conns = state
-        .storage
-        .list_connections_for_verse(surah_num, ayah_num)
+        .research
+        .list_connections_for_verse(surah_num, ayah_num, author.as_deref())
         .await
         .map_err(map_err)?;
 
@@ -197,32 +300,47 @@ pub async fn get_connections(
 
 pub async fn save_connections(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(_verse_ref): Path<String>,
     Json(data): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    // Extract connections from the payload
-    let empty_vec = vec![];
-    let internal = data.get("internal").and_then(|v| v.as_array()).unwrap_or(&empty_vec);
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let internal = data.get("internal").and_then(|v| v.as_array()).ok_or_else(|| {
+        map_err(EngineError::validation(
+            "Expected an 'internal' array of connections",
+            serde_json::json!({ "field": "internal" }),
+        ))
+    })?;
 
-    for conn in internal {
+    // Check every entry before writing any, so a bad one saves nothing.
+    let mut records = Vec::with_capacity(internal.len());
+    for (i, conn) in internal.iter().enumerate() {
+        let endpoint = |name: &str| {
+            conn.get(name)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    map_err(EngineError::validation(
+                        format!("Connection {} is missing '{}'", i, name),
+                        serde_json::json!({ "field": format!("internal[{}].{}", i, name) }),
+                    ))
+                })
+        };
+        let from_token = endpoint("from_token")?;
+        let to_token = endpoint("to_token")?;
         let conn_id = conn.get("id")
             .and_then(|v| v.as_str())
-            .unwrap_or_else(|| "");
-        let from_token = conn.get("from_token")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let to_token = conn.get("to_token")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+            .unwrap_or("");
         let layer = conn.get("layer")
             .and_then(|v| v.as_str())
             .unwrap_or("default")
             .to_string();
         let meta = conn.get("meta").cloned().unwrap_or(serde_json::json!({}));
+        check_target(&state, &from_token).await?;
+        check_target(&state, &to_token).await?;
 
-        let connection_record = ConnectionRecord {
+        records.push(ConnectionRecord {
             id: if conn_id.is_empty() {
                 Uuid::new_v4().to_string()
             } else {
@@ -232,9 +350,13 @@ pub async fn save_connections(
             to_token,
             layer,
             meta,
-        };
-
-        state.storage.upsert_connection(&connection_record).await.map_err(map_err)?;
+            author: None,
+            created_at: None,
+            updated_at: None,
+        });
+    }
+    for record in &records {
+        state.research.upsert_connection(record, request_actor(&headers).as_deref()).await.map_err(map_err)?;
     }
 
     Ok(Json(serde_json::json!({ "success": true })))
@@ -244,30 +366,37 @@ pub async fn save_connections(
 
 pub async fn get_pronouns(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
-) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
-    let pronouns = state
-        .storage
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
+    let author = author_filter(&params, &headers)?;
+    let mut pronouns = state
+        .research
         .get_verse_metadata(&verse_ref, "pronouns")
         .await
         .map_err(map_err)?;
+    retain_author(&mut pronouns, author.as_deref());
     Ok(Json(pronouns))
 }
 
 pub async fn create_pronoun(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
     Json(data): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     let mut pronouns = state
-        .storage
+        .research
         .get_verse_metadata(&verse_ref, "pronouns")
         .await
         .map_err(map_err)?;
 
     // Add ID if not present
     let mut new_entry = data;
-    if !new_entry.get("id").is_some() {
+    if new_entry.get("id").is_none() {
         let id = format!("pr-{}", chrono::Utc::now().timestamp());
         new_entry["id"] = serde_json::json!(id);
     }
@@ -276,12 +405,13 @@ pub async fn create_pronoun(
     let now = chrono::Utc::now().to_rfc3339();
     new_entry["created_at"] = serde_json::json!(now.clone());
     new_entry["updated_at"] = serde_json::json!(now);
+    stamp_author(&mut new_entry, request_actor(&headers).as_deref());
 
     pronouns.push(new_entry.clone());
 
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "pronouns", &serde_json::json!(pronouns))
+        .research
+        .set_verse_metadata(&verse_ref, "pronouns", &serde_json::json!(pronouns), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -293,11 +423,13 @@ pub async fn create_pronoun(
 
 pub async fn update_pronoun(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path((verse_ref, ref_id)): Path<(String, String)>,
     Json(updates): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     let mut pronouns = state
-        .storage
+        .research
         .get_verse_metadata(&verse_ref, "pronouns")
         .await
         .map_err(map_err)?;
@@ -326,8 +458,8 @@ pub async fn update_pronoun(
     }
 
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "pronouns", &serde_json::json!(pronouns))
+        .research
+        .set_verse_metadata(&verse_ref, "pronouns", &serde_json::json!(pronouns), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -336,10 +468,12 @@ pub async fn update_pronoun(
 
 pub async fn delete_pronoun(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path((verse_ref, ref_id)): Path<(String, String)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     let mut pronouns = state
-        .storage
+        .research
         .get_verse_metadata(&verse_ref, "pronouns")
         .await
         .map_err(map_err)?;
@@ -352,8 +486,8 @@ pub async fn delete_pronoun(
     }
 
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "pronouns", &serde_json::json!(pronouns))
+        .research
+        .set_verse_metadata(&verse_ref, "pronouns", &serde_json::json!(pronouns), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -364,29 +498,36 @@ pub async fn delete_pronoun(
 
 pub async fn get_hypotheses(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
-) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
-    let hypotheses = state
-        .storage
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
+    let author = author_filter(&params, &headers)?;
+    let mut hypotheses = state
+        .research
         .get_verse_metadata(&verse_ref, "hypotheses")
         .await
         .map_err(map_err)?;
+    retain_author(&mut hypotheses, author.as_deref());
     Ok(Json(hypotheses))
 }
 
 pub async fn create_hypothesis(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
     Json(data): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     let mut hypotheses = state
-        .storage
+        .research
         .get_verse_metadata(&verse_ref, "hypotheses")
         .await
         .map_err(map_err)?;
 
     let mut new_entry = data;
-    if !new_entry.get("id").is_some() {
+    if new_entry.get("id").is_none() {
         let id = format!("hyp-{}", chrono::Utc::now().timestamp());
         new_entry["id"] = serde_json::json!(id);
     }
@@ -394,12 +535,13 @@ pub async fn create_hypothesis(
     let now = chrono::Utc::now().to_rfc3339();
     new_entry["created_at"] = serde_json::json!(now.clone());
     new_entry["updated_at"] = serde_json::json!(now);
+    stamp_author(&mut new_entry, request_actor(&headers).as_deref());
 
     hypotheses.push(new_entry.clone());
 
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "hypotheses", &serde_json::json!(hypotheses))
+        .research
+        .set_verse_metadata(&verse_ref, "hypotheses", &serde_json::json!(hypotheses), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -411,11 +553,13 @@ pub async fn create_hypothesis(
 
 pub async fn update_hypothesis(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path((verse_ref, hyp_id)): Path<(String, String)>,
     Json(updates): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     let mut hypotheses = state
-        .storage
+        .research
         .get_verse_metadata(&verse_ref, "hypotheses")
         .await
         .map_err(map_err)?;
@@ -443,8 +587,8 @@ pub async fn update_hypothesis(
     }
 
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "hypotheses", &serde_json::json!(hypotheses))
+        .research
+        .set_verse_metadata(&verse_ref, "hypotheses", &serde_json::json!(hypotheses), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -453,10 +597,12 @@ pub async fn update_hypothesis(
 
 pub async fn delete_hypothesis(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path((verse_ref, hyp_id)): Path<(String, String)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     let mut hypotheses = state
-        .storage
+        .research
         .get_verse_metadata(&verse_ref, "hypotheses")
         .await
         .map_err(map_err)?;
@@ -469,8 +615,8 @@ pub async fn delete_hypothesis(
     }
 
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "hypotheses", &serde_json::json!(hypotheses))
+        .research
+        .set_verse_metadata(&verse_ref, "hypotheses", &serde_json::json!(hypotheses), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -481,41 +627,49 @@ pub async fn delete_hypothesis(
 
 pub async fn get_translations(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
-) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
-    let translations = state
-        .storage
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
+    let author = author_filter(&params, &headers)?;
+    let mut translations = state
+        .research
         .get_verse_metadata(&verse_ref, "translations")
         .await
         .map_err(map_err)?;
+    retain_author(&mut translations, author.as_deref());
     Ok(Json(translations))
 }
 
 pub async fn create_translation(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
     Json(data): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     let mut translations = state
-        .storage
+        .research
         .get_verse_metadata(&verse_ref, "translations")
         .await
         .map_err(map_err)?;
 
     let mut new_entry = data;
-    if !new_entry.get("id").is_some() {
+    if new_entry.get("id").is_none() {
         let id = format!("tr-{}", chrono::Utc::now().timestamp());
         new_entry["id"] = serde_json::json!(id);
     }
 
     let now = chrono::Utc::now().to_rfc3339();
     new_entry["created_at"] = serde_json::json!(now);
+    stamp_author(&mut new_entry, request_actor(&headers).as_deref());
 
     translations.push(new_entry.clone());
 
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "translations", &serde_json::json!(translations))
+        .research
+        .set_verse_metadata(&verse_ref, "translations", &serde_json::json!(translations), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -527,12 +681,14 @@ pub async fn create_translation(
 
 pub async fn update_translations(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(verse_ref): Path<String>,
     Json(data): Json<Vec<serde_json::Value>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let verse_ref = verse_key(&verse_ref)?;
     state
-        .storage
-        .set_verse_metadata(&verse_ref, "translations", &serde_json::json!(data))
+        .research
+        .set_verse_metadata(&verse_ref, "translations", &serde_json::json!(data), request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -543,22 +699,35 @@ pub async fn update_translations(
 
 pub async fn get_patterns(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let patterns = state
-        .storage
+    headers: HeaderMap,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let author = author_filter(&params, &headers)?;
+    let mut patterns = state
+        .research
         .get_research_data("patterns")
         .await
         .map_err(map_err)?
         .unwrap_or(serde_json::json!({}));
+    if let (Some(author), Some(obj)) = (author.as_deref(), patterns.as_object_mut()) {
+        obj.retain(|_, p| p.get("author").and_then(|a| a.as_str()) == Some(author));
+    }
     Ok(Json(patterns))
 }
 
 pub async fn create_pattern(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(mut pattern): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
+    if !pattern.is_object() {
+        return Err(map_err(EngineError::validation(
+            "A pattern must be a JSON object",
+            serde_json::json!({ "field": "body" }),
+        )));
+    }
     let mut patterns = state
-        .storage
+        .research
         .get_research_data("patterns")
         .await
         .map_err(map_err)?
@@ -574,11 +743,12 @@ pub async fn create_pattern(
         .unwrap_or_else(|| format!("pattern-{}", patterns_obj.len() + 1));
 
     pattern["id"] = serde_json::json!(pattern_id.clone());
+    stamp_author(&mut pattern, request_actor(&headers).as_deref());
     patterns_obj.insert(pattern_id.clone(), pattern);
 
     state
-        .storage
-        .set_research_data("patterns", &patterns)
+        .research
+        .set_research_data("patterns", &patterns, request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -588,12 +758,17 @@ pub async fn create_pattern(
     })))
 }
 
+#[utoipa::path(
+    get, path = "/v1/patterns/{pattern_id}", tag = "research",
+    params(("pattern_id" = String, Path)),
+    responses((status = 200, body = Object))
+)]
 pub async fn get_pattern(
     State(state): State<AppState>,
     Path(pattern_id): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let patterns = state
-        .storage
+        .research
         .get_research_data("patterns")
         .await
         .map_err(map_err)?
@@ -607,10 +782,11 @@ pub async fn get_pattern(
 
 pub async fn delete_pattern(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(pattern_id): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let mut patterns = state
-        .storage
+        .research
         .get_research_data("patterns")
         .await
         .map_err(map_err)?
@@ -625,69 +801,8 @@ pub async fn delete_pattern(
     }
 
     state
-        .storage
-        .set_research_data("patterns", &patterns)
-        .await
-        .map_err(map_err)?;
-
-    Ok(Json(serde_json::json!({ "success": true })))
-}
-
-// Tags
-
-pub async fn get_tags(
-    State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let tags = state
-        .storage
-        .get_research_data("tags")
-        .await
-        .map_err(map_err)?
-        .unwrap_or(serde_json::json!({}));
-    Ok(Json(tags))
-}
-
-pub async fn get_tag(
-    State(state): State<AppState>,
-    Path(tag_name): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let tags = state
-        .storage
-        .get_research_data("tags")
-        .await
-        .map_err(map_err)?
-        .unwrap_or(serde_json::json!({}));
-
-    let tag = tags.get("tags")
-        .and_then(|t| t.get(&tag_name))
-        .ok_or_else(|| map_err(EngineError::NotFound))?;
-
-    Ok(Json(tag.clone()))
-}
-
-pub async fn update_tag(
-    State(state): State<AppState>,
-    Path(tag_name): Path<String>,
-    Json(tag_data): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let mut tags = state
-        .storage
-        .get_research_data("tags")
-        .await
-        .map_err(map_err)?
-        .unwrap_or(serde_json::json!({}));
-
-    if !tags.get("tags").is_some() {
-        tags["tags"] = serde_json::json!({});
-    }
-
-    if let Some(tags_obj) = tags.get_mut("tags").and_then(|t| t.as_object_mut()) {
-        tags_obj.insert(tag_name, tag_data);
-    }
-
-    state
-        .storage
-        .set_research_data("tags", &tags)
+        .research
+        .set_research_data("patterns", &patterns, request_actor(&headers).as_deref())
         .await
         .map_err(map_err)?;
 
@@ -696,28 +811,42 @@ pub async fn update_tag(
 
 // Stats
 
+/// Corpus and research totals.
+#[derive(Serialize, ToSchema)]
+pub struct Stats {
+    pub total_verses: i64,
+    pub verses_with_tokens: i64,
+    pub total_annotations: i64,
+    pub total_hypothesis_tags: usize,
+    /// Verse counts keyed by `meccan` / `medinan`.
+    pub verses_by_revelation_place: BTreeMap<String, i64>,
+}
+
+#[utoipa::path(
+    get, path = "/v1/stats", tag = "research",
+    responses((status = 200, body = Stats))
+)]
 pub async fn get_stats(
     State(state): State<AppState>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let total_verses = state.storage.count_verses().await.map_err(map_err)?;
-    let verses_with_tokens = state.storage.count_verses_with_tokens().await.map_err(map_err)?;
-    let total_annotations = state.storage.count_annotations().await.map_err(map_err)?;
-
-    let tags = state
-        .storage
-        .get_research_data("tags")
+) -> Result<Json<Stats>, ApiError> {
+    let total_verses = state.corpus.count_verses().await.map_err(map_err)?;
+    let verses_with_tokens = state.corpus.count_verses_with_tokens().await.map_err(map_err)?;
+    let total_annotations = state.research.count_annotations().await.map_err(map_err)?;
+    let verses_by_revelation_place = state
+        .corpus
+        .count_verses_by_revelation_place()
         .await
         .map_err(map_err)?
-        .unwrap_or(serde_json::json!({}));
-    let total_tags = tags.get("tags")
-        .and_then(|t| t.as_object())
-        .map(|o| o.len())
-        .unwrap_or(0);
-
-    Ok(Json(serde_json::json!({
-        "total_verses": total_verses,
-        "verses_with_tokens": verses_with_tokens,
-        "total_annotations": total_annotations,
-        "total_hypothesis_tags": total_tags
-    })))
+        .into_iter()
+        .collect();
+
+    let total_hypothesis_tags = state.research.list_tags().await.map_err(map_err)?.len();
+
+    Ok(Json(Stats {
+        total_verses,
+        verses_with_tokens,
+        total_annotations,
+        total_hypothesis_tags,
+        verses_by_revelation_place,
+    }))
 }
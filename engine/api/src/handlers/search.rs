@@ -1,16 +1,83 @@
-use axum::{extract::{Path, State}, http::StatusCode, Json};
-use common::{QuerySpec, SearchBackend, SegmentView, StorageBackend};
-use std::collections::HashMap;
+use axum::{extract::{Path, State}, Json};
+use common::{
+    EngineError, LemmaDetail, QueryFilter, QuerySpec, RootDetail, RootSummary, SearchBackend, SearchHit, SegmentView,
+};
+use std::collections::{HashMap, HashSet};
 
-use crate::{AppState, map_err};
+use crate::{ApiError, AppState, map_err};
+use super::util::number_param;
 
+/// Verses a search is restricted to by its `tag` filters. A verse has to
+/// carry every filtered tag (with `op: "in"`, any one of the listed tags),
+/// directly or through a tag below it. `None` when there is no tag filter.
+async fn tag_scope(
+    state: &AppState,
+    filters: &[QueryFilter],
+) -> Result<Option<HashSet<(i64, i64)>>, ApiError> {
+    let mut scope: Option<HashSet<(i64, i64)>> = None;
+    for filter in filters.iter().filter(|f| f.field == "tag") {
+        let names: Vec<&str> = match &filter.value {
+            serde_json::Value::Array(values) => values.iter().filter_map(|v| v.as_str()).collect(),
+            value => value.as_str().into_iter().collect(),
+        };
+        if names.is_empty() {
+            return Err(map_err(EngineError::Invalid("A tag filter needs a tag name".into())));
+        }
+        let mut verses = HashSet::new();
+        for name in names {
+            verses.extend(state.research.tagged_verses(name, true).await.map_err(map_err)?);
+        }
+        scope = Some(match scope {
+            Some(scope) => scope.intersection(&verses).copied().collect(),
+            None => verses,
+        });
+    }
+    Ok(scope)
+}
+
+fn hit_verse(hit: &SearchHit) -> Option<(i64, i64)> {
+    let mut parts = hit.id.split(':');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+/// Run a query, honouring `tag` filters.
+///
+/// The index knows nothing about tags, so tag-scoped queries fetch a larger
+/// window and keep the hits inside tagged verses, widening the window until
+/// `limit` hits are found or the index runs out.
+pub(crate) async fn scoped_search(state: &AppState, spec: &QuerySpec) -> Result<Vec<SearchHit>, ApiError> {
+    let Some(scope) = tag_scope(state, &spec.filters).await? else {
+        return state.search.search(spec).await.map_err(map_err);
+    };
+    if scope.is_empty() || spec.limit == 0 {
+        return Ok(Vec::new());
+    }
+    let mut probe = QuerySpec {
+        filters: spec.filters.iter().filter(|f| f.field != "tag").cloned().collect(),
+        limit: spec.limit.saturating_mul(4),
+        ..spec.clone()
+    };
+    loop {
+        let hits = state.search.search(&probe).await.map_err(map_err)?;
+        let exhausted = hits.len() < probe.limit;
+        let scoped: Vec<SearchHit> =
+            hits.into_iter().filter(|h| hit_verse(h).map(|v| scope.contains(&v)).unwrap_or(false)).collect();
+        if exhausted || scoped.len() >= spec.limit {
+            return Ok(scoped.into_iter().take(spec.limit).collect());
+        }
+        probe.limit = probe.limit.saturating_mul(4);
+    }
+}
+
+/// Run a `QuerySpec`. Besides the index fields, filters may name a `tag` to
+/// restrict hits to verses carrying it.
 pub async fn search_handler(
     State(state): State<AppState>,
     Json(spec): Json<QuerySpec>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
-    let hits = state.search.search(&spec).await.map_err(map_err)?;
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
+    let hits = scoped_search(&state, &spec).await?;
     let docs = state
-        .storage
+        .corpus
         .hydrate_segments(&hits)
         .await
         .map_err(map_err)?;
@@ -20,14 +87,14 @@ pub async fn search_handler(
 pub async fn search_root(
     State(state): State<AppState>,
     Path(root): Path<String>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
     let hits = state
         .search
         .search_with_filters("", vec![("root".into(), vec![root])], 50)
         .await
         .map_err(map_err)?;
     let docs = state
-        .storage
+        .corpus
         .hydrate_segments(&hits)
         .await
         .map_err(map_err)?;
@@ -37,7 +104,7 @@ pub async fn search_root(
 pub async fn search_roots_query(
     State(state): State<AppState>,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
     let root = params.get("root").cloned().unwrap_or_default();
     search_root(State(state), Path(root)).await
 }
@@ -45,14 +112,14 @@ pub async fn search_roots_query(
 pub async fn search_pos(
     State(state): State<AppState>,
     Path(pos): Path<String>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
     let hits = state
         .search
         .search_with_filters("", vec![("pos".into(), vec![pos])], 50)
         .await
         .map_err(map_err)?;
     let docs = state
-        .storage
+        .corpus
         .hydrate_segments(&hits)
         .await
         .map_err(map_err)?;
@@ -62,14 +129,14 @@ pub async fn search_pos(
 pub async fn search_pattern(
     State(state): State<AppState>,
     Path(pattern): Path<String>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
     let hits = state
         .search
         .search_with_filters("", vec![("pattern".into(), vec![pattern])], 50)
         .await
         .map_err(map_err)?;
     let docs = state
-        .storage
+        .corpus
         .hydrate_segments(&hits)
         .await
         .map_err(map_err)?;
@@ -79,14 +146,14 @@ pub async fn search_pattern(
 pub async fn search_verb_form(
     State(state): State<AppState>,
     Path(form): Path<String>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
     let hits = state
         .search
         .search_with_filters("", vec![("verb_form".into(), vec![form])], 50)
         .await
         .map_err(map_err)?;
     let docs = state
-        .storage
+        .corpus
         .hydrate_segments(&hits)
         .await
         .map_err(map_err)?;
@@ -96,14 +163,14 @@ pub async fn search_verb_form(
 pub async fn search_dependency(
     State(state): State<AppState>,
     Path(rel): Path<String>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
     let hits = state
         .search
         .search_with_filters("", vec![("dependency_rel".into(), vec![rel])], 50)
         .await
         .map_err(map_err)?;
     let docs = state
-        .storage
+        .corpus
         .hydrate_segments(&hits)
         .await
         .map_err(map_err)?;
@@ -113,34 +180,45 @@ pub async fn search_dependency(
 pub async fn search_syntax(
     State(state): State<AppState>,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<Vec<SegmentView>>, (StatusCode, String)> {
+) -> Result<Json<Vec<SegmentView>>, ApiError> {
     let q = params.get("q").cloned().unwrap_or_default();
     let mut filters = Vec::new();
     if let Some(pos) = params.get("pos") {
         filters.push(("pos".into(), vec![pos.clone()]));
     }
     let hits = state.search.search_with_filters(&q, filters, 50).await.map_err(map_err)?;
-    let docs = state.storage.hydrate_segments(&hits).await.map_err(map_err)?;
+    let docs = state.corpus.hydrate_segments(&hits).await.map_err(map_err)?;
     Ok(Json(docs))
 }
 
 pub async fn legacy_search(
     State(state): State<AppState>,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let query = params.get("q").cloned().unwrap_or_default();
     let search_type = params.get("type").map(|s| s.as_str()).unwrap_or("text");
-    let limit: usize = params.get("limit").and_then(|s| s.parse().ok()).unwrap_or(100);
+    let limit: usize = number_param(&params, "limit")?.unwrap_or(100);
 
-    let results = match search_type {
-        "root" => {
+    let results = match (search_type, params.get("tag").filter(|t| !t.trim().is_empty())) {
+        (_, Some(tag)) => {
+            let mut filters = vec![QueryFilter { field: "tag".into(), op: "eq".into(), value: serde_json::json!(tag) }];
+            let mut text = query.clone();
+            if search_type == "root" {
+                filters.push(QueryFilter { field: "root".into(), op: "in".into(), value: serde_json::json!([query]) });
+                text.clear();
+            }
+            let spec = QuerySpec { query: serde_json::json!(text), filters, limit, offset: 0, sort: None };
+            let hits = scoped_search(&state, &spec).await?;
+            state.corpus.hydrate_segments(&hits).await.map_err(map_err)?
+        }
+        ("root", None) => {
             let hits = state.search.search_with_filters("", vec![("root".into(), vec![query.clone()])], limit).await.map_err(map_err)?;
-            state.storage.hydrate_segments(&hits).await.map_err(map_err)?
+            state.corpus.hydrate_segments(&hits).await.map_err(map_err)?
         },
-        _ => {
+        (_, None) => {
             // Text search
             let hits = state.search.search_with_filters(&query, vec![], limit).await.map_err(map_err)?;
-            state.storage.hydrate_segments(&hits).await.map_err(map_err)?
+            state.corpus.hydrate_segments(&hits).await.map_err(map_err)?
         }
     };
 
@@ -155,7 +233,7 @@ pub async fn legacy_search(
 pub async fn search_verb_forms_query(
     State(state): State<AppState>,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let mut filters = Vec::new();
 
     // Map query params to filters
@@ -185,7 +263,7 @@ pub async fn search_verb_forms_query(
     }
 
     let hits = state.search.search_with_filters("", filters, 100).await.map_err(map_err)?;
-    let docs = state.storage.hydrate_segments(&hits).await.map_err(map_err)?;
+    let docs = state.corpus.hydrate_segments(&hits).await.map_err(map_err)?;
 
     Ok(Json(serde_json::json!({
         "results": docs,
@@ -196,7 +274,7 @@ pub async fn search_verb_forms_query(
 pub async fn search_dependency_query(
     State(state): State<AppState>,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let relation = params.get("relation").cloned().unwrap_or_default();
 
     let hits = state
@@ -204,7 +282,7 @@ pub async fn search_dependency_query(
         .search_with_filters("", vec![("dependency_rel".into(), vec![relation.clone()])], 100)
         .await
         .map_err(map_err)?;
-    let docs = state.storage.hydrate_segments(&hits).await.map_err(map_err)?;
+    let docs = state.corpus.hydrate_segments(&hits).await.map_err(map_err)?;
 
     Ok(Json(serde_json::json!({
         "results": docs,
@@ -212,9 +290,48 @@ pub async fn search_dependency_query(
     })))
 }
 
+/// Every root with its Arabic form and corpus frequency.
 pub async fn list_roots(
     State(state): State<AppState>,
-) -> Result<Json<Vec<String>>, (StatusCode, String)> {
-    let roots = state.storage.list_unique_roots().await.map_err(map_err)?;
+) -> Result<Json<Vec<RootSummary>>, ApiError> {
+    let roots = state.lexicon.list_roots().await.map_err(map_err)?;
     Ok(Json(roots))
 }
+
+/// A root (Buckwalter or Arabic) with the lemmas derived from it.
+#[utoipa::path(
+    get, path = "/v1/roots/{root}", tag = "lexicon",
+    params(("root" = String, Path, description = "Buckwalter or Arabic")),
+    responses((status = 200, body = RootDetail))
+)]
+pub async fn get_root(
+    State(state): State<AppState>,
+    Path(root): Path<String>,
+) -> Result<Json<RootDetail>, ApiError> {
+    let root = state
+        .lexicon
+        .get_root(&root)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    Ok(Json(root))
+}
+
+/// A lemma (Buckwalter or Arabic) with its roots and parts of speech.
+#[utoipa::path(
+    get, path = "/v1/lemmas/{lemma}", tag = "lexicon",
+    params(("lemma" = String, Path)),
+    responses((status = 200, body = LemmaDetail))
+)]
+pub async fn get_lemma(
+    State(state): State<AppState>,
+    Path(lemma): Path<String>,
+) -> Result<Json<LemmaDetail>, ApiError> {
+    let lemma = state
+        .lexicon
+        .get_lemma(&lemma)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    Ok(Json(lemma))
+}
@@ -0,0 +1,284 @@
+use axum::{extract::{Path, Query, State}, http::HeaderMap, Json};
+use common::{Anchor, EngineError, Tag, TagAssignment, TagCount};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{ApiError, AppState, map_err};
+use super::research::check_target;
+use super::util::{anchor_param, author_filter, request_actor};
+
+/// `?descendants=false` limits a tag query to the tag itself; by default the
+/// tags below it count too.
+fn descendants(params: &HashMap<String, String>) -> bool {
+    !matches!(params.get("descendants").map(|s| s.trim()), Some("false" | "0"))
+}
+
+fn tag_json(tag: &Tag, count: Option<&TagCount>) -> serde_json::Value {
+    let mut value = serde_json::json!(tag);
+    if let (Some(obj), Some(count)) = (value.as_object_mut(), count) {
+        obj.insert("assignments".into(), serde_json::json!(count.assignments));
+        obj.insert("total".into(), serde_json::json!(count.total));
+        obj.insert("verses".into(), serde_json::json!(count.verses));
+    }
+    value
+}
+
+fn subtree_json(
+    name: &str,
+    tags: &HashMap<&str, (&Tag, Option<&TagCount>)>,
+    children: &HashMap<&str, Vec<&str>>,
+) -> serde_json::Value {
+    let (tag, count) = tags[name];
+    let mut value = tag_json(tag, count);
+    let nested: Vec<serde_json::Value> = children
+        .get(name)
+        .into_iter()
+        .flatten()
+        .map(|child| subtree_json(child, tags, children))
+        .collect();
+    value["children"] = serde_json::json!(nested);
+    value
+}
+
+/// All tags with their usage counts, as a flat list or, with `?tree=true`,
+/// nested under their parents.
+pub async fn list_tags(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<serde_json::Value>>, ApiError> {
+    let tags = state.research.list_tags().await.map_err(map_err)?;
+    let counts = state.research.tag_counts().await.map_err(map_err)?;
+    let by_name: HashMap<&str, (&Tag, Option<&TagCount>)> = tags
+        .iter()
+        .map(|t| (t.name.as_str(), (t, counts.iter().find(|c| c.tag == t.name))))
+        .collect();
+
+    if !matches!(params.get("tree").map(|s| s.as_str()), Some("true" | "1")) {
+        return Ok(Json(tags.iter().map(|t| tag_json(t, by_name[t.name.as_str()].1)).collect()));
+    }
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for tag in &tags {
+        if let Some(parent) = tag.parent.as_deref() {
+            children.entry(parent).or_default().push(&tag.name);
+        }
+    }
+    Ok(Json(
+        tags.iter()
+            .filter(|t| t.parent.is_none())
+            .map(|t| subtree_json(&t.name, &by_name, &children))
+            .collect(),
+    ))
+}
+
+pub async fn tag_counts(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TagCount>>, ApiError> {
+    Ok(Json(state.research.tag_counts().await.map_err(map_err)?))
+}
+
+/// A tag with its counts, its ancestors (nearest first) and its direct children.
+#[utoipa::path(
+    get, path = "/v1/tags/{tag_name}", tag = "tags",
+    params(("tag_name" = String, Path)),
+    responses((status = 200, body = Object))
+)]
+pub async fn get_tag(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let tags = state.research.list_tags().await.map_err(map_err)?;
+    let tag = tags.iter().find(|t| t.name == name).ok_or_else(|| map_err(EngineError::NotFound))?;
+    let counts = state.research.tag_counts().await.map_err(map_err)?;
+
+    let mut ancestors = Vec::new();
+    let mut parent = tag.parent.as_deref();
+    while let Some(p) = parent.filter(|p| !ancestors.contains(p) && ancestors.len() < tags.len()) {
+        ancestors.push(p);
+        parent = tags.iter().find(|t| t.name == p).and_then(|t| t.parent.as_deref());
+    }
+    let children: Vec<&str> =
+        tags.iter().filter(|t| t.parent.as_deref() == Some(&name)).map(|t| t.name.as_str()).collect();
+
+    let mut value = tag_json(tag, counts.iter().find(|c| c.tag == name));
+    value["ancestors"] = serde_json::json!(ancestors);
+    value["children"] = serde_json::json!(children);
+    Ok(Json(value))
+}
+
+/// Create a tag; the body names it.
+#[utoipa::path(
+    post, path = "/v1/tags", tag = "tags",
+    request_body(content = Object, description = "`{ \"name\", \"parent\"?, \"description\"? }`; other fields go to `meta`"),
+    responses((status = 200, body = Tag))
+)]
+pub async fn create_tag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Json<Tag>, ApiError> {
+    let name = body
+        .get("name")
+        .and_then(|n| n.as_str())
+        .ok_or_else(|| map_err(EngineError::Invalid("Tag name is required".into())))?
+        .to_string();
+    save_tag(&state, &headers, &name, &body).await
+}
+
+/// Create or update a tag. `parent` and `description` are tag fields; any
+/// other field is kept in the tag's `meta`. A tag keeps the author who
+/// created it across updates.
+#[utoipa::path(
+    put, path = "/v1/tags/{tag_name}", tag = "tags",
+    params(("tag_name" = String, Path)),
+    request_body = Object,
+    responses((status = 200, body = Tag))
+)]
+pub async fn update_tag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<Json<Tag>, ApiError> {
+    save_tag(&state, &headers, &name, &body).await
+}
+
+async fn save_tag(
+    state: &AppState,
+    headers: &HeaderMap,
+    name: &str,
+    body: &serde_json::Value,
+) -> Result<Json<Tag>, ApiError> {
+    let tag = store::tag_from_value(name, body);
+    state.research.upsert_tag(&tag, request_actor(headers).as_deref()).await.map_err(map_err)?;
+    let stored = state.research.get_tag(&tag.name).await.map_err(map_err)?;
+    Ok(Json(stored.unwrap_or(tag)))
+}
+
+pub async fn delete_tag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.research.delete_tag(&name, request_actor(&headers).as_deref()).await.map_err(map_err)?;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Verses carrying a tag, directly or through a token or span inside them.
+pub async fn tagged_verses(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.research.get_tag(&name).await.map_err(map_err)?.ok_or_else(|| map_err(EngineError::NotFound))?;
+    let verses = state.research.tagged_verses(&name, descendants(&params)).await.map_err(map_err)?;
+    let verses: Vec<String> = verses.iter().map(|(s, a)| format!("{}:{}", s, a)).collect();
+    Ok(Json(serde_json::json!({
+        "tag": name,
+        "count": verses.len(),
+        "verses": verses
+    })))
+}
+
+// Assignments
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct AssignmentRequest {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub target: String,
+}
+
+/// Assignments of a tag and, unless `?descendants=false`, of the tags below
+/// it. `?overlaps=<anchor>` and `?author=` / `?mine=true` narrow the list.
+pub async fn list_tag_assignments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<TagAssignment>>, ApiError> {
+    state.research.get_tag(&name).await.map_err(map_err)?.ok_or_else(|| map_err(EngineError::NotFound))?;
+    assignments(&state, &headers, Some(&name), &params).await
+}
+
+/// Assignments of every tag, usually narrowed with `?overlaps=<anchor>` to
+/// see how a verse or word is tagged.
+pub async fn list_all_assignments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<TagAssignment>>, ApiError> {
+    assignments(&state, &headers, None, &params).await
+}
+
+async fn assignments(
+    state: &AppState,
+    headers: &HeaderMap,
+    tag: Option<&str>,
+    params: &HashMap<String, String>,
+) -> Result<Json<Vec<TagAssignment>>, ApiError> {
+    let author = author_filter(params, headers)?;
+    let anchor: Option<Anchor> = match params.get("overlaps") {
+        Some(overlaps) => Some(anchor_param(overlaps)?),
+        None => None,
+    };
+    let assignments = state
+        .research
+        .list_tag_assignments(tag, descendants(params), anchor.as_ref(), author.as_deref())
+        .await
+        .map_err(map_err)?;
+    Ok(Json(assignments))
+}
+
+/// Apply a tag to a verse, verse range, token, token range or letter span.
+#[utoipa::path(
+    post, path = "/v1/tags/{tag_name}/assignments", tag = "tags",
+    params(("tag_name" = String, Path)),
+    request_body = AssignmentRequest,
+    responses((status = 200, body = TagAssignment))
+)]
+pub async fn assign_tag(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(req): Json<AssignmentRequest>,
+) -> Result<Json<TagAssignment>, ApiError> {
+    state.research.get_tag(&name).await.map_err(map_err)?.ok_or_else(|| map_err(EngineError::NotFound))?;
+    check_target(&state, &req.target).await?;
+    let assignment = TagAssignment {
+        id: req.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        tag: name,
+        target: req.target,
+        ..Default::default()
+    };
+    state.research.assign_tag(&assignment, request_actor(&headers).as_deref()).await.map_err(map_err)?;
+    let stored = state.research.get_tag_assignment(&assignment.id).await.map_err(map_err)?;
+    Ok(Json(stored.unwrap_or(assignment)))
+}
+
+#[utoipa::path(
+    get, path = "/v1/tag-assignments/{id}", tag = "tags",
+    params(("id" = String, Path)),
+    responses((status = 200, body = TagAssignment))
+)]
+pub async fn get_assignment(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<TagAssignment>, ApiError> {
+    let assignment = state
+        .research
+        .get_tag_assignment(&id)
+        .await
+        .map_err(map_err)?
+        .ok_or_else(|| map_err(EngineError::NotFound))?;
+    Ok(Json(assignment))
+}
+
+pub async fn delete_assignment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state.research.get_tag_assignment(&id).await.map_err(map_err)?.ok_or_else(|| map_err(EngineError::NotFound))?;
+    state.research.unassign_tag(&id, request_actor(&headers).as_deref()).await.map_err(map_err)?;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
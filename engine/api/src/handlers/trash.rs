@@ -0,0 +1,39 @@
+use axum::{extract::{Path, State}, http::HeaderMap, Json};
+use std::collections::HashMap;
+use store::TrashItem;
+
+use crate::{ApiError, AppState, map_err};
+use super::util::{number_param, request_actor};
+
+pub async fn list_trash(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TrashItem>>, ApiError> {
+    let items = state.research.list_trash().await.map_err(map_err)?;
+    Ok(Json(items))
+}
+
+pub async fn restore(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((record_type, id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    state
+        .research
+        .restore_from_trash(&record_type, &id, request_actor(&headers).as_deref())
+        .await
+        .map_err(map_err)?;
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+/// Purge trashed records older than `older_than_days` (defaults to the configured retention).
+pub async fn purge_trash(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let days = number_param::<u32>(&params, "older_than_days")?.map_or(state.config.trash_retention_days, i64::from);
+    let purged = state.research.purge_trash(days).await.map_err(map_err)?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "purged": purged
+    })))
+}
@@ -1,70 +1,161 @@
-use axum::{extract::State, http::StatusCode, Json};
-use common::{EngineError, SegmentView, StorageBackend};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::{parse_verse_ref, Anchor, EngineError, Lang, Segment, SegmentView, TagLabels, VerseRef};
+use serde::Serialize;
 use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
 
-use crate::{AppState, map_err};
+use crate::{ApiError, AppState, map_err};
+use crate::openapi::LangQuery;
 
-pub async fn health() -> &'static str {
-    "ok"
+/// Header carrying the name of the researcher making a change.
+pub const ACTOR_HEADER: &str = "x-kalima-user";
+
+/// Extract the acting user from request headers, if one was supplied.
+pub fn request_actor(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ACTOR_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
 }
 
-pub async fn segment_handler(
-    State(state): State<AppState>,
-    axum::extract::Path(id): axum::extract::Path<String>,
-) -> Result<Json<SegmentView>, (StatusCode, String)> {
-    match state.storage.get_segment(&id).await.map_err(map_err)? {
-        Some(doc) => Ok(Json(doc)),
-        None => Err(map_err(EngineError::NotFound)),
+/// Author filter for list endpoints: `?author=<name>`, or `?mine=true` for
+/// the user named in the request header.
+pub fn author_filter(
+    params: &HashMap<String, String>,
+    headers: &HeaderMap,
+) -> Result<Option<String>, ApiError> {
+    if let Some(author) = params.get("author").map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        return Ok(Some(author.to_string()));
+    }
+    if params.get("mine").map(|v| v == "true" || v == "1").unwrap_or(false) {
+        return request_actor(headers).map(Some).ok_or_else(|| {
+            map_err(EngineError::Invalid(format!("mine=true requires the {} header", ACTOR_HEADER)))
+        });
     }
+    Ok(None)
 }
 
-pub async fn search_library(
-    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
-    let q = params.get("q").cloned().unwrap_or_default().to_lowercase();
-    let notes_dir = PathBuf::from("notes");
-    let mut results = Vec::new();
-    if notes_dir.exists() {
-        for entry in fs::read_dir(notes_dir).map_err(|e| map_err(EngineError::Storage(e.to_string())))? {
-            let entry = entry.map_err(|e| map_err(EngineError::Storage(e.to_string())))?;
-            let path = entry.path();
-            if path.is_file() {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if content.to_lowercase().contains(&q) {
-                        results.push(serde_json::json!({
-                            "path": path.to_string_lossy(),
-                            "snippet": &content[..content.len().min(200)]
-                        }));
+/// A single-verse path or query parameter in any form [`common::VerseRef`]
+/// accepts (`2:255`, `al-Baqarah:255`, `البقرة ٢٥٥`), as `(surah, ayah)`.
+pub fn verse_param(s: &str) -> Result<(i64, i64), ApiError> {
+    parse_verse_ref(s).map_err(map_err)
+}
+
+/// The `surah:ayah` key research entries for a verse are stored under.
+pub fn verse_key(s: &str) -> Result<String, ApiError> {
+    let (surah, ayah) = verse_param(s)?;
+    Ok(format!("{}:{}", surah, ayah))
+}
+
+/// An anchor parameter. Verse-level anchors may also be written as a
+/// reference (`al-Baqarah:255-257`, `البقرة`).
+pub fn anchor_param(s: &str) -> Result<Anchor, ApiError> {
+    s.parse::<Anchor>()
+        .or_else(|err| match s.parse::<VerseRef>() {
+            Ok(reference) if reference.ranges().len() == 1 => Ok(reference.ranges()[0].anchor()),
+            _ => Err(err),
+        })
+        .map_err(map_err)
+}
+
+/// Optional numeric query parameter; present but unparsable is an error
+/// naming the parameter rather than a silent fallback.
+pub fn number_param<T: std::str::FromStr>(params: &HashMap<String, String>, name: &str) -> Result<Option<T>, ApiError> {
+    match params.get(name) {
+        Some(raw) => raw.trim().parse().map(Some).map_err(|_| {
+            map_err(EngineError::validation(
+                format!("{} must be a non-negative whole number, got '{}'", name, raw),
+                serde_json::json!({ "field": name, "value": raw }),
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Label language: `?lang=ar|en`, else the server default (`KALIMA_LANG`).
+pub fn request_lang(params: &HashMap<String, String>, state: &AppState) -> Result<Lang, ApiError> {
+    match params.get("lang").map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        Some(lang) => lang.parse().map_err(map_err),
+        None => Ok(state.config.lang),
+    }
+}
+
+/// A response whose segments carry the names of their codes under `labels`,
+/// as `/v1/verses/{surah}/{ayah}/segments` does.
+pub struct Labelled<T>(pub T, pub Lang);
+
+impl<T: Serialize> IntoResponse for Labelled<T> {
+    fn into_response(self) -> Response {
+        let mut value = serde_json::json!(self.0);
+        add_labels(&mut value, self.1);
+        Json(value).into_response()
+    }
+}
+
+/// Label every object listed under a `segments` key. Annotation payloads are
+/// the researcher's own and left alone.
+fn add_labels(value: &mut serde_json::Value, lang: Lang) {
+    match value {
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| add_labels(item, lang)),
+        serde_json::Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                match (key.as_str(), item) {
+                    ("annotations", _) => {}
+                    ("segments", serde_json::Value::Array(segments)) => {
+                        for seg in segments.iter_mut() {
+                            if let Ok(segment) = serde_json::from_value::<Segment>(seg.clone()) {
+                                seg["labels"] = serde_json::json!(TagLabels::bundled().segment(&segment, lang));
+                            }
+                        }
                     }
+                    (_, item) => add_labels(item, lang),
                 }
             }
         }
+        _ => {}
     }
-    Ok(Json(results))
 }
 
-pub async fn list_notes() -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let notes_dir = PathBuf::from("notes");
-    let mut notes = Vec::new();
-    if notes_dir.exists() {
-        for entry in fs::read_dir(notes_dir).map_err(|e| map_err(EngineError::Storage(e.to_string())))? {
-            let entry = entry.map_err(|e| map_err(EngineError::Storage(e.to_string())))?;
-            let path = entry.path();
-            if path.is_file() {
-                let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
-                notes.push(serde_json::json!({ "path": path.to_string_lossy(), "title": name }));
-            }
-        }
+/// Keep only JSON research entries written by `author` (all entries when `None`).
+pub fn retain_author(entries: &mut Vec<serde_json::Value>, author: Option<&str>) {
+    if let Some(author) = author {
+        entries.retain(|e| e.get("author").and_then(|a| a.as_str()) == Some(author));
+    }
+}
+
+/// Stamp a new JSON research entry with its author unless it already names one.
+pub fn stamp_author(entry: &mut serde_json::Value, actor: Option<&str>) {
+    if let (Some(obj), Some(actor)) = (entry.as_object_mut(), actor) {
+        obj.entry("author").or_insert_with(|| serde_json::json!(actor));
     }
-    Ok(Json(serde_json::json!({ "notes": notes })))
 }
 
-pub async fn get_note_content(
-    axum::extract::Query(q): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let path = q.get("path").cloned().unwrap_or_default();
-    let content = fs::read_to_string(&path).unwrap_or_default();
-    Ok(Json(serde_json::json!({ "content": content })))
+#[utoipa::path(
+    get, path = "/health", tag = "meta",
+    responses((status = 200, body = String, content_type = "text/plain"))
+)]
+pub async fn health() -> &'static str {
+    "ok"
+}
+
+#[utoipa::path(
+    get, path = "/v1/segments/{id}", tag = "morphology",
+    params(("id" = String, Path), LangQuery),
+    responses((status = 200, body = SegmentView))
+)]
+pub async fn segment_handler(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Labelled<SegmentView>, ApiError> {
+    let lang = request_lang(&params, &state)?;
+    match state.corpus.get_segment(&id).await.map_err(map_err)? {
+        Some(doc) => Ok(Labelled(doc, lang)),
+        None => Err(map_err(EngineError::NotFound)),
+    }
 }
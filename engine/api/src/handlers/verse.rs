@@ -1,136 +1,233 @@
-use axum::{extract::{Path, State}, http::StatusCode, Json};
-use common::EngineError;
+use axum::{extract::{Path, State}, Json};
+use common::{EngineError, Page, ResolvedAnchor, Surah, SurahSummary, Verse, VerseRef};
+use serde::Serialize;
 use std::collections::HashMap;
-
-use crate::{AppState, map_err};
-
-#[derive(serde::Serialize)]
-pub struct SurahResponse {
-    pub surah: SurahInfo,
-    pub verses: Vec<VerseResponse>,
-}
-
-#[derive(serde::Serialize)]
-pub struct SurahInfo {
-    pub number: i64,
-    pub name: String,
-}
-
-#[derive(serde::Serialize)]
-pub struct VerseResponse {
-    pub ayah: i64,
-    pub text: String,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub tokens: Vec<serde_json::Value>,
+use utoipa::ToSchema;
+
+use crate::{ApiError, AppState, map_err};
+use crate::openapi::{VersePath, EditionQuery, LangQuery};
+use super::editions::requested_edition;
+use super::util::{anchor_param, number_param, request_lang, Labelled};
+
+/// Most verses one `/v1/verses/range` request returns.
+const MAX_RANGE_VERSES: usize = 2000;
+
+/// The verses a reference covers, in the order it names them.
+#[derive(Serialize, ToSchema)]
+pub struct VerseRange {
+    /// The reference in canonical form, e.g. `2:1-20`.
+    #[serde(rename = "ref")]
+    pub reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    pub verses: Vec<Verse>,
 }
 
+/// A verse with its analysed tokens; `?edition=` swaps in the text of another
+/// edition and adds its word-to-token alignment.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}", tag = "verses",
+    params(VersePath, EditionQuery, LangQuery),
+    responses((status = 200, body = Verse))
+)]
 pub async fn get_verse(
     State(state): State<AppState>,
     Path((surah, ayah)): Path<(i64, i64)>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let verse_data = state.storage.get_verse(surah, ayah).await.map_err(map_err)?;
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Labelled<Verse>, ApiError> {
+    let lang = request_lang(&params, &state)?;
+    let edition = requested_edition(&state, &params).await?;
+    let verse_data = state.corpus.get_verse(surah, ayah).await.map_err(map_err)?;
 
-    if let Some(verse) = verse_data {
-        Ok(Json(verse))
-    } else {
-        Err(map_err(EngineError::NotFound))
+    let Some(mut verse) = verse_data else {
+        return Err(map_err(EngineError::NotFound));
+    };
+    if let Some(edition) = edition {
+        verse.text = state
+            .corpus
+            .get_edition_text(&edition, surah, ayah)
+            .await
+            .map_err(map_err)?
+            .ok_or_else(|| map_err(EngineError::NotFound))?;
+        verse.alignment = Some(
+            state
+                .corpus
+                .get_edition_alignment(&edition, surah, ayah)
+                .await
+                .map_err(map_err)?,
+        );
+        verse.edition = Some(edition);
     }
+    Ok(Labelled(verse, lang))
 }
 
+#[utoipa::path(
+    get, path = "/v1/verses/by-index/{index}", tag = "verses",
+    params(("index" = i64, Path, description = "Position of the verse in the mushaf, from 0"), LangQuery),
+    responses((status = 200, body = Verse))
+)]
 pub async fn get_verse_by_index(
     State(state): State<AppState>,
     Path(index): Path<i64>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let verse_data = state.storage.get_verse_by_index(index).await.map_err(map_err)?;
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Labelled<Verse>, ApiError> {
+    let lang = request_lang(&params, &state)?;
+    let verse_data = state.corpus.get_verse_by_index(index).await.map_err(map_err)?;
 
     if let Some(verse) = verse_data {
-        Ok(Json(verse))
+        Ok(Labelled(verse, lang))
     } else {
         Err(map_err(EngineError::NotFound))
     }
 }
 
+#[utoipa::path(
+    get, path = "/v1/verses", tag = "verses",
+    params(
+        ("start" = Option<u32>, Query, description = "Offset into the mushaf, default 0"),
+        ("limit" = Option<u32>, Query, description = "Page size, default 50"),
+    ),
+    responses((status = 200, body = Page<Verse>, description = "Verses without tokens"))
+)]
 pub async fn list_verses(
     State(state): State<AppState>,
     axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let start: i64 = params.get("start").and_then(|s| s.parse().ok()).unwrap_or(0);
-    let limit: i64 = params.get("limit").and_then(|s| s.parse().ok()).unwrap_or(50);
-
-    let verses = state.storage.list_verses(start, limit).await.map_err(map_err)?;
-    let total = state.storage.count_verses().await.map_err(map_err)?;
-
-    Ok(Json(serde_json::json!({
-        "verses": verses,
-        "total": total,
-        "start": start,
-        "limit": limit
-    })))
+) -> Result<Json<Page<Verse>>, ApiError> {
+    let start: i64 = number_param::<u32>(&params, "start")?.map_or(0, i64::from);
+    let limit: i64 = number_param::<u32>(&params, "limit")?.map_or(50, i64::from);
+
+    let verses = state.corpus.list_verses(start, limit).await.map_err(map_err)?;
+    let total = state.corpus.count_verses().await.map_err(map_err)?;
+
+    Ok(Json(Page {
+        items: verses,
+        total,
+        start,
+        limit,
+    }))
 }
 
+#[utoipa::path(
+    get, path = "/v1/surahs/{surah}", tag = "verses",
+    params(("surah" = i64, Path, description = "Surah number, 1-114"), EditionQuery, LangQuery),
+    responses((status = 200, body = Surah))
+)]
 pub async fn get_surah(
     State(state): State<AppState>,
     Path(number): Path<i64>,
-) -> Result<Json<SurahResponse>, (StatusCode, String)> {
-    let verses = state.storage.get_surah_verses(number).await.map_err(map_err)?;
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Labelled<Surah>, ApiError> {
+    let lang = request_lang(&params, &state)?;
+    let edition = requested_edition(&state, &params).await?;
+    let mut verses = state
+        .corpus
+        .get_verse_range((number, 1), (number, i64::MAX))
+        .await
+        .map_err(map_err)?;
+
+    let Some(first) = verses.first() else {
+        return Err(map_err(EngineError::NotFound));
+    };
+    let mut surah_info = first.surah.clone();
+    if surah_info.name.is_empty() {
+        surah_info.name = "Unknown".to_string();
+    }
+    if let Some(edition) = &edition {
+        use_edition_text(&state, edition, &mut verses).await?;
+    }
 
+    Ok(Labelled(
+        Surah {
+            surah: surah_info,
+            edition,
+            verses,
+        },
+        lang,
+    ))
+}
+
+/// Verses with their tokens for `?ref=`: a range (`2:1-20`, `2:286-3:5`), whole
+/// surahs or a list (`2:255, 3:1-5`) in any form [`VerseRef`] accepts, in the
+/// order written. `?edition=` swaps in another edition's text.
+#[utoipa::path(
+    get, path = "/v1/verses/range", tag = "verses",
+    params(("ref" = String, Query, description = "Verse reference: `2:1-20`, `2:286-3:5`, `al-Kahf`, `2:255, 3:1-5`"), EditionQuery, LangQuery),
+    responses((status = 200, body = VerseRange))
+)]
+pub async fn get_verse_range(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Labelled<VerseRange>, ApiError> {
+    let lang = request_lang(&params, &state)?;
+    let reference: VerseRef = params
+        .get("ref")
+        .filter(|r| !r.trim().is_empty())
+        .ok_or_else(|| map_err(EngineError::Invalid("ref is required".into())))?
+        .parse()
+        .map_err(map_err)?;
+    if reference.verses().count() > MAX_RANGE_VERSES {
+        return Err(map_err(EngineError::Invalid(format!(
+            "{} covers more than {} verses; request it in parts",
+            reference, MAX_RANGE_VERSES
+        ))));
+    }
+    let edition = requested_edition(&state, &params).await?;
+
+    let mut verses = Vec::new();
+    for range in reference.ranges() {
+        verses.extend(state.corpus.get_verse_range(range.start, range.end).await.map_err(map_err)?);
+    }
     if verses.is_empty() {
         return Err(map_err(EngineError::NotFound));
     }
+    if let Some(edition) = &edition {
+        use_edition_text(&state, edition, &mut verses).await?;
+    }
 
-    // Extract surah info from first verse
-    let surah_info = SurahInfo {
-        number,
-        name: verses.first()
-            .and_then(|v| v.get("surah_name"))
-            .and_then(|n| n.as_str())
-            .unwrap_or("Unknown")
-            .to_string(),
-    };
+    Ok(Labelled(VerseRange { reference: reference.to_string(), edition, verses }, lang))
+}
 
-    // Populate tokens from segments for each verse
-    let mut verse_responses: Vec<VerseResponse> = Vec::new();
-    for v in verses {
-        let ayah = v.get("ayah").and_then(|a| a.as_i64()).unwrap_or(0);
-        let text = v.get("text").and_then(|t| t.as_str()).unwrap_or("").to_string();
-
-        // Fetch tokens/segments for this verse
-        let segments = state.storage.get_verse_segments(number, ayah).await.map_err(map_err)?;
-
-        // Group segments by token_index
-        let mut tokens_map: HashMap<usize, Vec<serde_json::Value>> = HashMap::new();
-        for seg in segments {
-            let token_idx = seg.get("token_index").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-            tokens_map.entry(token_idx).or_insert_with(Vec::new).push(seg);
-        }
-
-        // Convert to sorted token array
-        let mut token_indices: Vec<_> = tokens_map.keys().copied().collect();
-        token_indices.sort();
-        let tokens: Vec<serde_json::Value> = token_indices
-            .into_iter()
-            .map(|idx| serde_json::json!({
-                "index": idx,
-                "segments": tokens_map.get(&idx).cloned().unwrap_or_default()
-            }))
-            .collect();
-
-        verse_responses.push(VerseResponse {
-            ayah,
-            text,
-            tokens,
-        });
+/// Replace each verse's text with the text of `edition`, one query per surah.
+async fn use_edition_text(state: &AppState, edition: &str, verses: &mut [Verse]) -> Result<(), ApiError> {
+    let mut surahs: Vec<i64> = verses.iter().map(|v| v.surah.number).collect();
+    surahs.sort_unstable();
+    surahs.dedup();
+    let mut texts: HashMap<(i64, i64), String> = HashMap::new();
+    for surah in surahs {
+        let surah_texts = state.corpus.get_edition_surah_texts(edition, surah).await.map_err(map_err)?;
+        texts.extend(surah_texts.into_iter().map(|(ayah, text)| ((surah, ayah), text)));
     }
-
-    Ok(Json(SurahResponse {
-        surah: surah_info,
-        verses: verse_responses,
-    }))
+    for verse in verses {
+        verse.text = texts.get(&(verse.surah.number, verse.ayah)).cloned().unwrap_or_default();
+    }
+    Ok(())
 }
 
+/// All surahs with their metadata; `?revelation_place=meccan|medinan` filters the list.
 pub async fn list_surahs(
     State(state): State<AppState>,
-) -> Result<Json<Vec<store::SurahSummary>>, (StatusCode, String)> {
-    let surahs = state.storage.list_surahs().await.map_err(map_err)?;
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Result<Json<Vec<SurahSummary>>, ApiError> {
+    let mut surahs = state.corpus.list_surahs().await.map_err(map_err)?;
+    if let Some(place) = params.get("revelation_place") {
+        surahs.retain(|s| s.revelation_place.as_deref() == Some(place.as_str()));
+    }
     Ok(Json(surahs))
 }
+
+/// Resolve an anchor such as `2:255`, `2:255-257`, `2:255:3-5` or
+/// `2:255:3@0-2` against the corpus and return the text it covers. Verses
+/// and ranges may also be named (`al-Baqarah:255`).
+#[utoipa::path(
+    get, path = "/v1/anchors/{anchor}", tag = "verses",
+    params(("anchor" = String, Path, description = "`2:255`, `2:255-257`, `2:255:3-5`, `2:255:3@0-2`, ...")),
+    responses((status = 200, body = ResolvedAnchor))
+)]
+pub async fn resolve_anchor(
+    State(state): State<AppState>,
+    Path(anchor): Path<String>,
+) -> Result<Json<ResolvedAnchor>, ApiError> {
+    let anchor = anchor_param(&anchor)?;
+    let resolved = state.corpus.resolve_anchor(&anchor).await.map_err(map_err)?;
+    Ok(Json(resolved))
+}
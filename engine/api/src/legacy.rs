@@ -0,0 +1,183 @@
+//! Routes from before `/v1`, kept for existing clients.
+//!
+//! Each answers as it always has, plus a `Deprecation` header and, when the
+//! request's path parameters fill it in, a `Link` to the `/v1` route that
+//! replaces it. Set `KALIMA_LEGACY_ROUTES=false` to stop serving them.
+
+use axum::{
+    extract::{RawPathParams, Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::{self, Next},
+    response::Response,
+    routing::{delete, get, post, put, MethodRouter},
+    Router,
+};
+
+use crate::handlers::{
+    backup, divisions, editions, glosses, graph, history, layers, morphology, notes, pattern, research, search,
+    tags, trash, util, verse,
+};
+use crate::AppState;
+
+/// When the legacy routes were deprecated (2026-10-18), as an RFC 9745 date.
+const DEPRECATED_SINCE: &str = "@1792281600";
+
+/// The legacy routes, each with the `/v1` route that replaces it.
+pub(crate) fn routes() -> Router<AppState> {
+    LegacyRoutes(Router::new())
+        // Search
+        .route("/search", "/v1/search", post(search::search_handler))
+        .route("/search/root/:root", "/v1/search?root=:root", get(search::search_root))
+        .route("/search/pos/:pos", "/v1/search?pos=:pos", get(search::search_pos))
+        .route("/search/pattern/:pattern", "/v1/search?pattern=:pattern", get(search::search_pattern))
+        .route("/search/verb_forms/:verb_form", "/v1/search?verb_form=:verb_form", get(search::search_verb_form))
+        .route("/search/dependency/:rel", "/v1/search", get(search::search_dependency))
+        .route("/api/search/syntax", "/v1/search", get(search::search_syntax))
+        .route("/api/search/pattern_word", "/v1/search/pattern-word", post(pattern::search_pattern_word))
+        .route("/api/search/morphology", "/v1/search", get(morphology::search_morphology))
+        .route("/api/search/verb_forms", "/v1/search", get(search::search_verb_forms_query))
+        .route("/api/search/dependency", "/v1/search", get(search::search_dependency_query))
+        .route("/api/search", "/v1/search", get(search::legacy_search))
+        .route("/api/search/roots", "/v1/search", get(search::search_roots_query))
+
+        // Notes
+        .route("/api/library_search", "/v1/notes/search", get(notes::search_library))
+        .route("/api/notes", "/v1/notes", get(notes::list_notes).post(notes::create_note))
+        .route("/api/notes/:id", "/v1/notes/:id", get(notes::get_note).put(notes::update_note).delete(notes::delete_note))
+
+        // Lexicon
+        .route("/api/roots", "/v1/roots", get(search::list_roots))
+        .route("/api/roots/:root", "/v1/roots/:root", get(search::get_root))
+        .route("/api/lemmas/:lemma", "/v1/lemmas/:lemma", get(search::get_lemma))
+        .route("/api/morph_patterns", "/v1/morph-patterns", get(morphology::list_morph_patterns))
+        .route("/api/syntax_patterns", "/v1/syntax-patterns", get(morphology::list_syntax_patterns))
+        .route("/api/labels", "/v1/labels", get(morphology::list_labels))
+
+        // Verses, editions and divisions
+        .route("/api/surahs", "/v1/surahs", get(verse::list_surahs))
+        .route("/api/surah/:number", "/v1/surahs/:number", get(verse::get_surah))
+        .route("/api/verse/:surah/:ayah", "/v1/verses/:surah/:ayah", get(verse::get_verse))
+        .route("/api/verse/index/:index", "/v1/verses/by-index/:index", get(verse::get_verse_by_index))
+        .route("/api/verses", "/v1/verses", get(verse::list_verses))
+        .route("/api/verses/range", "/v1/verses/range", get(verse::get_verse_range))
+        .route("/api/anchors/:anchor", "/v1/anchors/:anchor", get(verse::resolve_anchor))
+        .route("/api/editions", "/v1/editions", get(editions::list_editions))
+        .route("/api/diff", "/v1/editions/diff", get(editions::diff_editions))
+        .route("/api/divisions", "/v1/divisions", get(divisions::list_division_kinds))
+        .route("/api/divisions/locate/:surah/:ayah", "/v1/verses/:surah/:ayah/divisions", get(divisions::locate_verse))
+        .route("/api/divisions/:kind", "/v1/divisions/:kind", get(divisions::list_divisions))
+        .route("/api/divisions/:kind/:number", "/v1/divisions/:kind/:number", get(divisions::get_division))
+
+        // Morphology and glosses
+        .route("/api/morphology/:surah/:ayah", "/v1/verses/:surah/:ayah/segments", get(morphology::get_morphology))
+        .route("/api/morphology/parsed/:surah/:ayah", "/v1/verses/:surah/:ayah/morphology", get(morphology::get_parsed_morphology))
+        .route("/api/dependency/:surah/:ayah", "/v1/verses/:surah/:ayah/dependency", get(morphology::get_dependency))
+        .route("/api/glosses/sources", "/v1/gloss-sources", get(glosses::list_sources))
+        .route("/api/glosses/:surah/:ayah", "/v1/verses/:surah/:ayah/glosses", get(glosses::get_glosses))
+        .route("/api/alignments/:surah/:ayah", "/v1/verses/:surah/:ayah/alignments", get(glosses::get_alignments))
+        .route("/api/interlinear/:surah/:ayah", "/v1/verses/:surah/:ayah/interlinear", get(glosses::interlinear))
+        .route("/segment/:id", "/v1/segments/:id", get(util::segment_handler))
+
+        // Annotations and connections
+        .route("/annotations", "/v1/annotations", post(research::create_annotation).get(research::list_annotations))
+        .route("/annotations/:id", "/v1/annotations/:id", delete(research::delete_annotation).put(research::replace_annotation))
+        .route("/api/layers", "/v1/layers", get(layers::list_layers).post(layers::create_layer))
+        .route("/api/layers/:name", "/v1/layers/:name", get(layers::get_layer).put(layers::update_layer).delete(layers::delete_layer))
+        .route("/api/annotations/:surah/:ayah", "/v1/verses/:surah/:ayah/annotations", get(research::get_annotations).post(research::create_annotation_verse))
+        .route("/connections", "/v1/connections", post(research::create_connection).get(research::list_connections))
+        .route("/connections/:id", "/v1/connections/:id", delete(research::delete_connection))
+        .route("/api/connections/:verse_ref", "/v1/verses/:verse_ref/connections", get(research::get_connections).post(research::save_connections))
+        .route("/api/graph/neighbours/:node", "/v1/graph/neighbours/:node", get(graph::neighbours))
+        .route("/api/graph/path", "/v1/graph/path", get(graph::shortest_path))
+        .route("/api/graph/components", "/v1/graph/components", get(graph::components))
+        .route("/api/graph/degrees", "/v1/graph/degrees", get(graph::degrees))
+        .route("/api/graph/export", "/v1/graph/export", get(graph::export))
+
+        // Verse research and tags
+        .route("/api/pronouns/:verse_ref", "/v1/verses/:verse_ref/pronouns", get(research::get_pronouns).post(research::create_pronoun))
+        .route("/api/pronouns/:verse_ref/:ref_id", "/v1/verses/:verse_ref/pronouns/:ref_id", put(research::update_pronoun).delete(research::delete_pronoun))
+        .route("/api/hypotheses/:verse_ref", "/v1/verses/:verse_ref/hypotheses", get(research::get_hypotheses).post(research::create_hypothesis))
+        .route("/api/hypotheses/:verse_ref/:hyp_id", "/v1/verses/:verse_ref/hypotheses/:hyp_id", put(research::update_hypothesis).delete(research::delete_hypothesis))
+        .route("/api/translations/:verse_ref", "/v1/verses/:verse_ref/translations", get(research::get_translations).post(research::create_translation).put(research::update_translations))
+        .route("/api/patterns", "/v1/patterns", get(research::get_patterns).post(research::create_pattern))
+        .route("/api/patterns/:pattern_id", "/v1/patterns/:pattern_id", get(research::get_pattern).delete(research::delete_pattern))
+        .route("/api/tags", "/v1/tags", get(tags::list_tags).post(tags::create_tag))
+        .route("/api/tags/counts", "/v1/tags/counts", get(tags::tag_counts))
+        .route("/api/tags/:tag_name", "/v1/tags/:tag_name", get(tags::get_tag).put(tags::update_tag).delete(tags::delete_tag))
+        .route("/api/tags/:tag_name/verses", "/v1/tags/:tag_name/verses", get(tags::tagged_verses))
+        .route("/api/tags/:tag_name/assignments", "/v1/tags/:tag_name/assignments", get(tags::list_tag_assignments).post(tags::assign_tag))
+        .route("/api/tag_assignments", "/v1/tag-assignments", get(tags::list_all_assignments))
+        .route("/api/tag_assignments/:id", "/v1/tag-assignments/:id", get(tags::get_assignment).delete(tags::delete_assignment))
+        .route("/api/stats", "/v1/stats", get(research::get_stats))
+
+        // Trash, history and backups
+        .route("/api/trash", "/v1/trash", get(trash::list_trash))
+        .route("/api/trash/purge", "/v1/trash/purge", post(trash::purge_trash))
+        .route("/api/trash/:record_type/:id/restore", "/v1/trash/:record_type/:id/restore", post(trash::restore))
+        .route("/api/history/:record_type/:record_id", "/v1/history/:record_type/:record_id", get(history::get_record_history))
+        .route("/api/history/:record_type/:record_id/revert", "/v1/history/:record_type/:record_id/revert", post(history::revert_record))
+        .route("/api/verse/:surah/:ayah/history", "/v1/verses/:surah/:ayah/history", get(history::get_verse_history))
+        .route("/api/verse/:surah/:ayah/notes", "/v1/verses/:surah/:ayah/notes", get(notes::verse_notes))
+        .route("/api/admin/backups", "/v1/admin/backups", get(backup::list_backups).post(backup::create_backup))
+        .route("/api/admin/restore", "/v1/admin/restore", post(backup::restore_backup))
+        .0
+}
+
+struct LegacyRoutes(Router<AppState>);
+
+impl LegacyRoutes {
+    /// Serve `path` as before, marked deprecated in favour of `successor`.
+    fn route(self, path: &str, successor: &'static str, methods: MethodRouter<AppState>) -> Self {
+        Self(self.0.route(path, methods.layer(middleware::from_fn_with_state(successor, deprecated))))
+    }
+}
+
+async fn deprecated(
+    State(successor): State<&'static str>,
+    params: Option<RawPathParams>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let link = successor_link(successor, params.as_ref());
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static(DEPRECATED_SINCE));
+    if let Some(link) = link.and_then(|l| HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", l)).ok()) {
+        headers.insert(axum::http::header::LINK, link);
+    }
+    response
+}
+
+/// `successor` with its `:name` parameters filled from the request's, or
+/// `None` if one is missing. A `:verse_ref` becomes `{surah}/{ayah}`.
+fn successor_link(successor: &str, params: Option<&RawPathParams>) -> Option<String> {
+    let lookup = |name: &str| params?.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string());
+    let mut link = String::with_capacity(successor.len());
+    let mut rest = successor;
+    while let Some(colon) = rest.find(':') {
+        link.push_str(&rest[..colon]);
+        let tail = &rest[colon + 1..];
+        let end = tail.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(tail.len());
+        let value = lookup(&tail[..end])?;
+        if &tail[..end] == "verse_ref" {
+            let (surah, ayah) = util::verse_param(&value).ok()?;
+            link.push_str(&format!("{}/{}", surah, ayah));
+        } else {
+            link.push_str(&encode(&value));
+        }
+        rest = &tail[end..];
+    }
+    link.push_str(rest);
+    Some(link)
+}
+
+/// Percent-encode everything but unreserved characters, `:` and `@`.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'@' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
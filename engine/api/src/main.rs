@@ -1,4 +1,189 @@
+use api::ServerConfig;
+use std::path::{Path, PathBuf};
+use common::Anchor;
+use store::{ConflictStrategy, ConnectionGraph, GraphFormat, ResearchBundle, SqliteStorage};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "kalima", about = "Kalima API server and research tools")]
+struct Cli {
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Run the API server (the default when no command is given)
+    Serve,
+    /// Snapshot the database (safe while the server is running)
+    Backup {
+        /// SQLite database path (defaults to KALIMA_DB)
+        #[structopt(long)]
+        db: Option<String>,
+        /// Snapshot file to write; defaults to a timestamped file in KALIMA_BACKUP_DIR
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+        /// Delete all but this many unlabelled snapshots in the backup directory afterwards
+        #[structopt(long)]
+        keep: Option<usize>,
+    },
+    /// Replace the database contents with a snapshot
+    Restore {
+        /// Snapshot file to restore
+        #[structopt(long, parse(from_os_str))]
+        snapshot: PathBuf,
+        /// SQLite database path (defaults to KALIMA_DB)
+        #[structopt(long)]
+        db: Option<String>,
+    },
+    /// Write all research records to a portable bundle
+    ExportResearch {
+        /// Bundle file to write
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+        /// SQLite database path (defaults to KALIMA_DB)
+        #[structopt(long)]
+        db: Option<String>,
+        /// Name recorded as the bundle's exporter (defaults to KALIMA_USER)
+        #[structopt(long, env = "KALIMA_USER")]
+        user: Option<String>,
+    },
+    /// Three-way merge a research bundle into the database
+    ImportResearch {
+        /// Bundle file to read
+        #[structopt(long, parse(from_os_str))]
+        bundle: PathBuf,
+        /// SQLite database path (defaults to KALIMA_DB)
+        #[structopt(long)]
+        db: Option<String>,
+        /// Name the merged changes are recorded under (defaults to KALIMA_USER)
+        #[structopt(long, env = "KALIMA_USER")]
+        user: Option<String>,
+        /// How to settle records edited on both sides: report, ours or theirs
+        #[structopt(long, default_value = "report")]
+        strategy: String,
+        /// Show what would change without writing anything
+        #[structopt(long)]
+        dry_run: bool,
+        /// Also write the merge report (including conflicts) to this file
+        #[structopt(long, parse(from_os_str))]
+        report: Option<PathBuf>,
+    },
+    /// Export the connections graph for Gephi, Graphviz or other graph tools
+    ExportGraph {
+        /// File to write
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+        /// graphml, dot or json (JSON Graph Format)
+        #[structopt(long, default_value = "graphml")]
+        format: String,
+        /// Only connections in this layer
+        #[structopt(long)]
+        layer: Option<String>,
+        /// Only the neighbourhood of this node
+        #[structopt(long)]
+        node: Option<String>,
+        /// Neighbourhood depth when --node is given
+        #[structopt(long, default_value = "1")]
+        depth: usize,
+        /// SQLite database path (defaults to KALIMA_DB)
+        #[structopt(long)]
+        db: Option<String>,
+    },
+}
+
+async fn open_storage(db: Option<String>) -> anyhow::Result<SqliteStorage> {
+    let path = db.unwrap_or_else(|| ServerConfig::from_env().database_path);
+    Ok(SqliteStorage::connect(&path).await?)
+}
+
 #[tokio::main]
-async fn main() {
-    api::start_server().await;
+async fn main() -> anyhow::Result<()> {
+    match Cli::from_args().command.unwrap_or(Command::Serve) {
+        Command::Serve => api::start_server().await,
+        Command::Backup { db, out, keep } => {
+            let config = ServerConfig::from_env();
+            let storage = open_storage(db).await?;
+            let dir = PathBuf::from(&config.backup_dir);
+            let snapshot = match out {
+                Some(path) => storage.snapshot_to(&path).await?,
+                None => storage.snapshot_into(&dir, None).await?,
+            };
+            println!("Wrote {} ({} bytes)", snapshot.path, snapshot.size_bytes);
+            if let Some(keep) = keep {
+                for name in store::prune_snapshots(&dir, keep)? {
+                    println!("  removed {}", name);
+                }
+            }
+        }
+        Command::Restore { snapshot, db } => {
+            let config = ServerConfig::from_env();
+            let storage = open_storage(db).await?;
+            let safety = storage
+                .snapshot_into(Path::new(&config.backup_dir), Some("pre-restore"))
+                .await?;
+            storage.restore_from(&snapshot).await?;
+            println!("Restored {} (previous contents saved to {})", snapshot.display(), safety.path);
+        }
+        Command::ExportResearch { out, db, user } => {
+            let storage = open_storage(db).await?;
+            let bundle = storage.export_research(user.as_deref()).await?;
+            std::fs::write(&out, serde_json::to_string_pretty(&bundle)?)?;
+            println!("Exported {} research records to {}", bundle.records.len(), out.display());
+        }
+        Command::ImportResearch { bundle, db, user, strategy, dry_run, report } => {
+            let strategy: ConflictStrategy = strategy.parse()?;
+            let storage = open_storage(db).await?;
+            let bundle: ResearchBundle = serde_json::from_str(&std::fs::read_to_string(&bundle)?)?;
+            let merge = storage
+                .import_research(&bundle, strategy, user.as_deref(), dry_run)
+                .await?;
+            if let Some(path) = report {
+                std::fs::write(path, serde_json::to_string_pretty(&merge)?)?;
+            }
+            println!(
+                "{}{} created, {} updated, {} deleted, {} unchanged, {} kept local, {} conflicts",
+                if dry_run { "(dry run) " } else { "" },
+                merge.created,
+                merge.updated,
+                merge.deleted,
+                merge.unchanged,
+                merge.kept_local,
+                merge.conflicts.len()
+            );
+            for c in &merge.conflicts {
+                println!(
+                    "  conflict: {} {}{}",
+                    c.record_type,
+                    c.id,
+                    c.verse_ref.as_deref().map(|v| format!(" ({})", v)).unwrap_or_default()
+                );
+            }
+            if !merge.conflicts.is_empty() && strategy == ConflictStrategy::Report {
+                std::process::exit(2);
+            }
+        }
+        Command::ExportGraph { out, format, layer, node, depth, db } => {
+            let format: GraphFormat = format.parse()?;
+            let storage = open_storage(db).await?;
+            let graph = ConnectionGraph::new(storage.list_connections(layer.as_deref(), None).await?);
+            let mut subgraph = match node {
+                Some(node) => graph.neighbourhood(&node, depth, false)?,
+                None => graph.to_subgraph(),
+            };
+            for n in subgraph.nodes.iter_mut() {
+                if let Ok(Some(anchor)) = Anchor::parse_target(&n.id) {
+                    n.text = storage.resolve_anchor(&anchor).await.ok().map(|r| r.text);
+                }
+            }
+            std::fs::write(&out, subgraph.export(format)?)?;
+            println!(
+                "Exported {} nodes and {} connections to {}",
+                subgraph.nodes.len(),
+                subgraph.edges.len(),
+                out.display()
+            );
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,252 @@
+//! OpenAPI 3 description of the `/v1` routes and `/health`, generated from
+//! the `#[utoipa::path]` attribute on each handler. Served at `/openapi.json`
+//! with a browsable page at `/docs` that works offline. The deprecated legacy
+//! routes are left out.
+//!
+//! `tests/openapi.rs` fails when a route is added to [`crate::v1`] without a
+//! matching operation here, or the other way round.
+
+use utoipa::openapi::{
+    path::Operation,
+    response::ResponseBuilder,
+    schema::Ref,
+    security::{HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme},
+    ContentBuilder, RefOr,
+};
+use utoipa::{IntoParams, Modify, OpenApi};
+
+use crate::error::ErrorEnvelope;
+use crate::{handlers, v1};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Kalima API",
+        description = "Quranic corpus, morphology and research data. Errors on every route share the \
+                       `{\"error\": {\"code\", \"message\", \"details\"}}` envelope."
+    ),
+    paths(
+        handlers::util::health,
+        v1::list_surahs,
+        handlers::verse::get_surah,
+        handlers::verse::list_verses,
+        handlers::verse::get_verse_range,
+        handlers::verse::get_verse_by_index,
+        handlers::verse::get_verse,
+        handlers::morphology::get_morphology,
+        handlers::morphology::get_parsed_morphology,
+        handlers::morphology::get_dependency,
+        v1::verse_glosses,
+        v1::verse_alignments,
+        handlers::glosses::interlinear,
+        v1::verse_divisions,
+        handlers::verse::resolve_anchor,
+        handlers::util::segment_handler,
+        v1::list_editions,
+        handlers::editions::diff_editions,
+        v1::list_division_kinds,
+        v1::list_divisions,
+        handlers::divisions::get_division,
+        v1::list_gloss_sources,
+        v1::list_roots,
+        handlers::search::get_root,
+        handlers::search::get_lemma,
+        v1::list_morph_patterns,
+        v1::list_syntax_patterns,
+        handlers::morphology::list_labels,
+        v1::search_segments,
+        v1::run_query,
+        handlers::pattern::search_pattern_word,
+        v1::list_annotations,
+        handlers::research::create_annotation,
+        handlers::research::replace_annotation,
+        handlers::research::delete_annotation,
+        v1::verse_annotations,
+        v1::list_layers,
+        handlers::layers::create_layer,
+        handlers::layers::get_layer,
+        handlers::layers::update_layer,
+        v1::delete_layer,
+        v1::list_connections,
+        handlers::research::create_connection,
+        handlers::research::delete_connection,
+        v1::verse_connections,
+        v1::save_verse_connections,
+        handlers::graph::neighbours,
+        handlers::graph::shortest_path,
+        handlers::graph::components,
+        handlers::graph::degrees,
+        handlers::graph::export,
+        v1::list_pronouns,
+        v1::create_pronoun,
+        v1::update_pronoun,
+        v1::delete_pronoun,
+        v1::list_hypotheses,
+        v1::create_hypothesis,
+        v1::update_hypothesis,
+        v1::delete_hypothesis,
+        v1::list_translations,
+        v1::create_translation,
+        v1::replace_translations,
+        v1::list_patterns,
+        v1::create_pattern,
+        handlers::research::get_pattern,
+        v1::delete_pattern,
+        handlers::research::get_stats,
+        v1::list_tags,
+        handlers::tags::create_tag,
+        v1::tag_counts,
+        handlers::tags::get_tag,
+        handlers::tags::update_tag,
+        v1::delete_tag,
+        v1::tagged_verses,
+        v1::list_tag_assignments,
+        handlers::tags::assign_tag,
+        v1::list_all_assignments,
+        handlers::tags::get_assignment,
+        v1::delete_assignment,
+        v1::list_notes,
+        handlers::notes::create_note,
+        v1::search_notes,
+        handlers::notes::get_note,
+        handlers::notes::update_note,
+        v1::delete_note,
+        v1::verse_notes,
+        v1::list_trash,
+        v1::purge_trash,
+        v1::restore_from_trash,
+        v1::record_history,
+        v1::revert_record,
+        v1::verse_history,
+        v1::list_backups,
+        handlers::backup::create_backup,
+        v1::restore_backup,
+    ),
+    components(schemas(ErrorEnvelope)),
+    modifiers(&ErrorResponses, &BearerAuth),
+)]
+pub struct ApiDoc;
+
+/// The generated document, as served at `/openapi.json`.
+pub fn spec() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+/// Give every operation a `default` response pointing at the shared error
+/// envelope, rather than repeating it on each handler.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let error = ResponseBuilder::new()
+            .description("Error envelope; see `code` for what went wrong")
+            .content(
+                "application/json",
+                ContentBuilder::new().schema(Some(Ref::from_schema_name("ErrorEnvelope"))).build(),
+            )
+            .build();
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .responses
+            .insert("Error".into(), RefOr::T(error));
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations: [&mut Option<Operation>; 4] =
+                [&mut item.get, &mut item.post, &mut item.put, &mut item.delete];
+            for operation in operations.into_iter().flatten() {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".into())
+                    .or_insert_with(|| RefOr::Ref(Ref::from_response_name("Error")));
+            }
+        }
+    }
+}
+
+/// Declare the optional bearer token (see [`crate::auth`]), so the docs page
+/// can send one.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.components.get_or_insert_with(Default::default).add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("Needed once the server has API tokens configured; scopes are read, write and admin"))
+                    .build(),
+            ),
+        );
+        openapi.security = Some(vec![SecurityRequirement::default(), SecurityRequirement::new("bearer", Vec::<String>::new())]);
+    }
+}
+
+// Parameter groups shared by many routes. They only describe parameters;
+// handlers still read them from the path or query map themselves.
+
+/// `{surah}/{ayah}` path segments.
+#[derive(IntoParams)]
+#[into_params(parameter_in = Path)]
+#[allow(dead_code)]
+pub(crate) struct VersePath {
+    /// Surah number, 1-114.
+    surah: i64,
+    /// Ayah number within the surah.
+    ayah: i64,
+}
+
+/// Narrowing of research listings to one researcher.
+#[derive(IntoParams)]
+#[into_params(parameter_in = Query)]
+#[allow(dead_code)]
+pub(crate) struct AuthorQuery {
+    /// Only entries written by this researcher.
+    author: Option<String>,
+    /// Only entries by the researcher named in the `x-kalima-user` header.
+    mine: Option<bool>,
+}
+
+#[derive(IntoParams)]
+#[into_params(parameter_in = Query)]
+#[allow(dead_code)]
+pub(crate) struct LangQuery {
+    /// Label language, `ar` or `en`; defaults to the server's `KALIMA_LANG`.
+    lang: Option<String>,
+}
+
+#[derive(IntoParams)]
+#[into_params(parameter_in = Query)]
+#[allow(dead_code)]
+pub(crate) struct EditionQuery {
+    /// Show this edition's text (see `/v1/editions`) in place of the analysed one.
+    edition: Option<String>,
+}
+
+/// Connection graph filters.
+#[derive(IntoParams)]
+#[into_params(parameter_in = Query)]
+#[allow(dead_code)]
+pub(crate) struct GraphQuery {
+    /// Only connections on this layer.
+    layer: Option<String>,
+    /// Only connections written by this researcher.
+    author: Option<String>,
+    /// Only connections by the researcher named in the `x-kalima-user` header.
+    mine: Option<bool>,
+    /// Follow edges from source to target only.
+    directed: Option<bool>,
+}
+
+/// The window of a list; see [`crate::v1`].
+#[derive(IntoParams)]
+#[into_params(parameter_in = Query)]
+#[allow(dead_code)]
+pub(crate) struct PageQuery {
+    /// Items to skip, default 0.
+    start: Option<usize>,
+    /// Items to return, default 50.
+    limit: Option<usize>,
+}
@@ -0,0 +1,1089 @@
+//! Version 1 of the HTTP API, served under `/v1`. Integrations should build
+//! on these routes; the older ones in [`crate::legacy`] are deprecated.
+//!
+//! The rules every route here keeps:
+//!
+//! - Resources are plural, kebab-case nouns. Anything about one verse lives
+//!   under `/v1/verses/{surah}/{ayah}/...`.
+//! - Every list is a [`Page`]. `?start=` and `?limit=` (default
+//!   [`DEFAULT_PAGE_SIZE`]) pick the window and `total` counts the whole list.
+//! - Creating or updating a record returns it as stored. A write with nothing
+//!   to return answers `204 No Content`.
+//! - Failures use the error envelope from [`crate::error`].
+//!
+//! Where an older route already had the right shape, its handler serves both
+//! paths. The handlers in this file adapt the older ones to the rules above.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use common::{
+    Annotation, AnnotationLayer, EngineError, Gloss, GlossSource, NoteBacklink, Page, QueryFilter, QuerySpec,
+    RootSummary, SearchHit, SegmentView, SurahSummary, TagAssignment, TagCount,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use store::{ChangeRecord, ConnectionRecord, Division, Edition, SnapshotInfo, TrashItem};
+use utoipa::ToSchema;
+
+use crate::handlers::{
+    backup, divisions, editions, glosses, graph, history, layers, morphology, notes, pattern, research, search,
+    tags, trash, util, verse,
+};
+use crate::handlers::divisions::DivisionKind;
+use crate::handlers::history::RevertRequest;
+use crate::handlers::morphology::{MorphPattern, PosName};
+use crate::handlers::notes::{NoteMatch, NoteSummary};
+use crate::handlers::backup::RestoreRequest;
+use crate::handlers::util::{number_param, Labelled};
+use crate::openapi::{AuthorQuery, LangQuery, PageQuery, VersePath};
+use crate::{ApiError, AppState, map_err};
+
+/// Page size when a list request has no `?limit=`.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Hits a search collects before paging; `total` stops counting here.
+pub const SEARCH_WINDOW: usize = 1000;
+
+/// The `/v1` routes.
+pub(crate) fn routes() -> Router<AppState> {
+    Router::new()
+        // Corpus
+        .route("/v1/surahs", get(list_surahs))
+        .route("/v1/surahs/:surah", get(verse::get_surah))
+        .route("/v1/verses", get(verse::list_verses))
+        .route("/v1/verses/range", get(verse::get_verse_range))
+        .route("/v1/verses/by-index/:index", get(verse::get_verse_by_index))
+        .route("/v1/verses/:surah/:ayah", get(verse::get_verse))
+        .route("/v1/verses/:surah/:ayah/segments", get(morphology::get_morphology))
+        .route("/v1/verses/:surah/:ayah/morphology", get(morphology::get_parsed_morphology))
+        .route("/v1/verses/:surah/:ayah/dependency", get(morphology::get_dependency))
+        .route("/v1/verses/:surah/:ayah/glosses", get(verse_glosses))
+        .route("/v1/verses/:surah/:ayah/alignments", get(verse_alignments))
+        .route("/v1/verses/:surah/:ayah/interlinear", get(glosses::interlinear))
+        .route("/v1/verses/:surah/:ayah/divisions", get(verse_divisions))
+        .route("/v1/anchors/:anchor", get(verse::resolve_anchor))
+        .route("/v1/segments/:id", get(util::segment_handler))
+        .route("/v1/editions", get(list_editions))
+        .route("/v1/editions/diff", get(editions::diff_editions))
+        .route("/v1/divisions", get(list_division_kinds))
+        .route("/v1/divisions/:kind", get(list_divisions))
+        .route("/v1/divisions/:kind/:number", get(divisions::get_division))
+        .route("/v1/gloss-sources", get(list_gloss_sources))
+
+        // Lexicon
+        .route("/v1/roots", get(list_roots))
+        .route("/v1/roots/:root", get(search::get_root))
+        .route("/v1/lemmas/:lemma", get(search::get_lemma))
+        .route("/v1/morph-patterns", get(list_morph_patterns))
+        .route("/v1/syntax-patterns", get(list_syntax_patterns))
+        .route("/v1/labels", get(morphology::list_labels))
+
+        // Search
+        .route("/v1/search", get(search_segments).post(run_query))
+        .route("/v1/search/pattern-word", post(pattern::search_pattern_word))
+
+        // Annotations and connections
+        .route("/v1/annotations", get(list_annotations).post(research::create_annotation))
+        .route("/v1/annotations/:id", put(research::replace_annotation).delete(research::delete_annotation))
+        .route("/v1/verses/:surah/:ayah/annotations", get(verse_annotations))
+        .route("/v1/layers", get(list_layers).post(layers::create_layer))
+        .route("/v1/layers/:name", get(layers::get_layer).put(layers::update_layer).delete(delete_layer))
+        .route("/v1/connections", get(list_connections).post(research::create_connection))
+        .route("/v1/connections/:id", delete(research::delete_connection))
+        .route("/v1/verses/:surah/:ayah/connections", get(verse_connections).post(save_verse_connections))
+        .route("/v1/graph/neighbours/:node", get(graph::neighbours))
+        .route("/v1/graph/path", get(graph::shortest_path))
+        .route("/v1/graph/components", get(graph::components))
+        .route("/v1/graph/degrees", get(graph::degrees))
+        .route("/v1/graph/export", get(graph::export))
+
+        // Verse research
+        .route("/v1/verses/:surah/:ayah/pronouns", get(list_pronouns).post(create_pronoun))
+        .route("/v1/verses/:surah/:ayah/pronouns/:id", put(update_pronoun).delete(delete_pronoun))
+        .route("/v1/verses/:surah/:ayah/hypotheses", get(list_hypotheses).post(create_hypothesis))
+        .route("/v1/verses/:surah/:ayah/hypotheses/:id", put(update_hypothesis).delete(delete_hypothesis))
+        .route("/v1/verses/:surah/:ayah/translations", get(list_translations).post(create_translation).put(replace_translations))
+        .route("/v1/patterns", get(list_patterns).post(create_pattern))
+        .route("/v1/patterns/:pattern_id", get(research::get_pattern).delete(delete_pattern))
+        .route("/v1/stats", get(research::get_stats))
+
+        // Tags
+        .route("/v1/tags", get(list_tags).post(tags::create_tag))
+        .route("/v1/tags/counts", get(tag_counts))
+        .route("/v1/tags/:tag_name", get(tags::get_tag).put(tags::update_tag).delete(delete_tag))
+        .route("/v1/tags/:tag_name/verses", get(tagged_verses))
+        .route("/v1/tags/:tag_name/assignments", get(list_tag_assignments).post(tags::assign_tag))
+        .route("/v1/tag-assignments", get(list_all_assignments))
+        .route("/v1/tag-assignments/:id", get(tags::get_assignment).delete(delete_assignment))
+
+        // Notes
+        .route("/v1/notes", get(list_notes).post(notes::create_note))
+        .route("/v1/notes/search", get(search_notes))
+        .route("/v1/notes/:id", get(notes::get_note).put(notes::update_note).delete(delete_note))
+        .route("/v1/verses/:surah/:ayah/notes", get(verse_notes))
+
+        // Trash, history and backups
+        .route("/v1/trash", get(list_trash))
+        .route("/v1/trash/purge", post(purge_trash))
+        .route("/v1/trash/:record_type/:id/restore", post(restore_from_trash))
+        .route("/v1/history/:record_type/:record_id", get(record_history))
+        .route("/v1/history/:record_type/:record_id/revert", post(revert_record))
+        .route("/v1/verses/:surah/:ayah/history", get(verse_history))
+        .route("/v1/admin/backups", get(list_backups).post(backup::create_backup))
+        .route("/v1/admin/restore", post(restore_backup))
+}
+
+/// The `?start=` / `?limit=` window of a list request.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Paging {
+    start: usize,
+    limit: usize,
+}
+
+impl Paging {
+    pub(crate) fn from_params(params: &HashMap<String, String>) -> Result<Self, ApiError> {
+        Ok(Self {
+            start: number_param(params, "start")?.unwrap_or(0),
+            limit: number_param(params, "limit")?.unwrap_or(DEFAULT_PAGE_SIZE),
+        })
+    }
+
+    /// This window of a complete list.
+    pub(crate) fn page<T>(self, items: Vec<T>) -> Page<T> {
+        let total = items.len() as i64;
+        self.wrap(items.into_iter().skip(self.start).take(self.limit).collect(), total)
+    }
+
+    fn wrap<T>(self, items: Vec<T>, total: i64) -> Page<T> {
+        Page { items, total, start: self.start as i64, limit: self.limit as i64 }
+    }
+}
+
+/// The legacy key of a verse's research entries.
+fn verse_ref(surah: i64, ayah: i64) -> Path<String> {
+    Path(format!("{}:{}", surah, ayah))
+}
+
+// Corpus
+
+/// All surahs with their metadata; `?revelation_place=meccan|medinan` filters the list.
+#[utoipa::path(
+    get, path = "/v1/surahs", tag = "verses",
+    params(("revelation_place" = Option<String>, Query, description = "`meccan` or `medinan`"), PageQuery),
+    responses((status = 200, body = Page<SurahSummary>))
+)]
+pub async fn list_surahs(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<SurahSummary>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(surahs) = verse::list_surahs(state, Query(params)).await?;
+    Ok(Json(paging.page(surahs)))
+}
+
+/// Glosses of a verse, optionally from one `?source=`.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/glosses", tag = "glosses",
+    params(VersePath, ("source" = Option<String>, Query), PageQuery),
+    responses((status = 200, body = Page<Gloss>))
+)]
+pub async fn verse_glosses(
+    state: State<AppState>,
+    verse: Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<Gloss>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = glosses::get_glosses(state, verse, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Verse translations with their token alignments, optionally from one `?source=`.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/alignments", tag = "glosses",
+    params(VersePath, ("source" = Option<String>, Query), PageQuery),
+    responses((status = 200, body = Page<Object>, description = "Items are `{ \"source\", \"text\", \"words\", \"links\" }`"))
+)]
+pub async fn verse_alignments(
+    state: State<AppState>,
+    verse: Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = glosses::get_alignments(state, verse, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Which juz, hizb, rub' and manzil a verse falls in.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/divisions", tag = "divisions",
+    params(VersePath, PageQuery),
+    responses((status = 200, body = Page<Division>))
+)]
+pub async fn verse_divisions(
+    state: State<AppState>,
+    verse: Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<Division>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = divisions::locate_verse(state, verse).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    get, path = "/v1/editions", tag = "editions",
+    params(PageQuery),
+    responses((status = 200, body = Page<Edition>))
+)]
+pub async fn list_editions(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<Edition>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = editions::list_editions(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Division kinds with the number of divisions loaded for each.
+#[utoipa::path(
+    get, path = "/v1/divisions", tag = "divisions",
+    params(PageQuery),
+    responses((status = 200, body = Page<DivisionKind>))
+)]
+pub async fn list_division_kinds(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<DivisionKind>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    Ok(Json(paging.page(divisions::division_kinds(&state).await?)))
+}
+
+#[utoipa::path(
+    get, path = "/v1/divisions/{kind}", tag = "divisions",
+    params(("kind" = String, Path, description = "`juz`, `hizb`, `rub` or `manzil`"), PageQuery),
+    responses((status = 200, body = Page<Division>))
+)]
+pub async fn list_divisions(
+    state: State<AppState>,
+    kind: Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<Division>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = divisions::list_divisions(state, kind).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    get, path = "/v1/gloss-sources", tag = "glosses",
+    params(PageQuery),
+    responses((status = 200, body = Page<GlossSource>))
+)]
+pub async fn list_gloss_sources(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<GlossSource>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = glosses::list_sources(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+// Lexicon
+
+/// Every root with its Arabic form and corpus frequency.
+#[utoipa::path(
+    get, path = "/v1/roots", tag = "lexicon",
+    params(PageQuery),
+    responses((status = 200, body = Page<RootSummary>))
+)]
+pub async fn list_roots(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<RootSummary>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = search::list_roots(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    get, path = "/v1/morph-patterns", tag = "lexicon",
+    params(PageQuery),
+    responses((status = 200, body = Page<MorphPattern>))
+)]
+pub async fn list_morph_patterns(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<MorphPattern>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = morphology::list_morph_patterns(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// POS tags found in the corpus, labelled in the `?lang=` language.
+#[utoipa::path(
+    get, path = "/v1/syntax-patterns", tag = "lexicon",
+    params(LangQuery, PageQuery),
+    responses((status = 200, body = Page<PosName>))
+)]
+pub async fn list_syntax_patterns(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<PosName>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = morphology::list_syntax_patterns(state, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+// Search
+
+/// Segments matching `?q=` text and field filters given as query parameters,
+/// e.g. `?root=ktb&pos=V`. `?tag=` keeps hits inside verses carrying the tag.
+#[utoipa::path(
+    get, path = "/v1/search", tag = "search",
+    params(
+        ("q" = Option<String>, Query, description = "Free text"),
+        ("tag" = Option<String>, Query, description = "Only verses carrying this tag or one below it"),
+        ("root" = Option<String>, Query),
+        ("lemma" = Option<String>, Query),
+        ("pos" = Option<String>, Query),
+        ("pattern" = Option<String>, Query),
+        ("verb_form" = Option<String>, Query),
+        ("gender" = Option<String>, Query),
+        ("number" = Option<String>, Query),
+        ("case" = Option<String>, Query),
+        ("voice" = Option<String>, Query),
+        ("mood" = Option<String>, Query),
+        ("aspect" = Option<String>, Query),
+        PageQuery,
+        LangQuery,
+    ),
+    responses((status = 200, body = Page<SegmentView>, description = "`total` stops counting at 1000"))
+)]
+pub async fn search_segments(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Labelled<Page<SegmentView>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let lang = util::request_lang(&params, &state)?;
+    let fields: BTreeMap<&str, &str> = params
+        .iter()
+        .filter(|(name, _)| !matches!(name.as_str(), "q" | "start" | "limit" | "lang"))
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    let mut filters = Vec::with_capacity(fields.len());
+    for (field, value) in fields {
+        if field != "tag" && !::search::FILTER_FIELDS.contains(&field) {
+            return Err(map_err(EngineError::validation(
+                format!("Unknown search parameter: {}", field),
+                serde_json::json!({ "field": field, "value": value, "allowed": ::search::FILTER_FIELDS }),
+            )));
+        }
+        filters.push(QueryFilter { field: field.to_string(), op: "eq".into(), value: serde_json::json!(value) });
+    }
+    let query = serde_json::json!(params.get("q").map(|q| q.trim()).unwrap_or(""));
+    let spec = QuerySpec { query, filters, limit: SEARCH_WINDOW, offset: 0, sort: None };
+    Ok(Labelled(search_page(&state, spec, paging).await?, lang))
+}
+
+/// Run a `QuerySpec`; its `offset` and `limit` select the page. Besides the
+/// index fields, filters may name a `tag`.
+#[utoipa::path(
+    post, path = "/v1/search", tag = "search",
+    request_body = QuerySpec,
+    params(LangQuery),
+    responses((status = 200, body = Page<SegmentView>, description = "`total` stops counting at 1000"))
+)]
+pub async fn run_query(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    Json(spec): Json<QuerySpec>,
+) -> Result<Labelled<Page<SegmentView>>, ApiError> {
+    let lang = util::request_lang(&params, &state)?;
+    let paging = Paging { start: spec.offset, limit: spec.limit };
+    let spec = QuerySpec { limit: SEARCH_WINDOW, offset: 0, ..spec };
+    Ok(Labelled(search_page(&state, spec, paging).await?, lang))
+}
+
+/// Collect up to [`SEARCH_WINDOW`] hits and hydrate the requested page of them.
+async fn search_page(state: &AppState, spec: QuerySpec, paging: Paging) -> Result<Page<SegmentView>, ApiError> {
+    let hits = search::scoped_search(state, &spec).await?;
+    let total = hits.len() as i64;
+    let window: Vec<SearchHit> = hits.into_iter().skip(paging.start).take(paging.limit).collect();
+    let items = state.corpus.hydrate_segments(&window).await.map_err(map_err)?;
+    Ok(paging.wrap(items, total))
+}
+
+// Annotations and connections
+
+/// `?target_id=` restricts to one target and `?overlaps=<anchor>` to targets
+/// overlapping an anchor; `?author=` or `?mine=true` to one researcher.
+#[utoipa::path(
+    get, path = "/v1/annotations", tag = "annotations",
+    params(
+        ("target_id" = Option<String>, Query),
+        ("overlaps" = Option<String>, Query, description = "Anchor the targets must overlap"),
+        AuthorQuery,
+        PageQuery,
+    ),
+    responses((status = 200, body = Page<Annotation>))
+)]
+pub async fn list_annotations(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<Annotation>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = research::list_annotations(state, headers, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Annotations on a verse or anything inside it.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/annotations", tag = "annotations",
+    params(VersePath, PageQuery),
+    responses((status = 200, body = Page<Annotation>))
+)]
+pub async fn verse_annotations(
+    state: State<AppState>,
+    verse: Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<Annotation>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = research::get_annotations(state, verse).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Registered annotation layers with their target and payload schema.
+#[utoipa::path(
+    get, path = "/v1/layers", tag = "annotations",
+    params(PageQuery),
+    responses((status = 200, body = Page<AnnotationLayer>))
+)]
+pub async fn list_layers(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<AnnotationLayer>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = layers::list_layers(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    delete, path = "/v1/layers/{name}", tag = "annotations",
+    params(("name" = String, Path)),
+    responses((status = 204), (status = 409, description = "Live annotations still use the layer"))
+)]
+pub async fn delete_layer(state: State<AppState>, name: Path<String>) -> Result<StatusCode, ApiError> {
+    let _ = layers::delete_layer(state, name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `?verse=S:A` lists connections starting in a verse; `?overlaps=<anchor>`
+/// those with either end overlapping an anchor. One of the two is required.
+#[utoipa::path(
+    get, path = "/v1/connections", tag = "connections",
+    params(
+        ("verse" = Option<String>, Query, description = "Connections starting in this verse"),
+        ("overlaps" = Option<String>, Query, description = "Connections with either end overlapping this anchor"),
+        AuthorQuery,
+        PageQuery,
+    ),
+    responses((status = 200, body = Page<ConnectionRecord>))
+)]
+pub async fn list_connections(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<ConnectionRecord>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(body) = research::list_connections(state, headers, Query(params)).await?;
+    Ok(Json(paging.page(internal_connections(body)?)))
+}
+
+/// Connections starting in a verse.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/connections", tag = "connections",
+    params(VersePath, AuthorQuery, PageQuery),
+    responses((status = 200, body = Page<ConnectionRecord>))
+)]
+pub async fn verse_connections(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<ConnectionRecord>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(body) = research::get_connections(state, headers, verse_ref(surah, ayah), Query(params)).await?;
+    Ok(Json(paging.page(internal_connections(body)?)))
+}
+
+/// The records of a legacy `{ "internal": [...], "external": [] }` body.
+fn internal_connections(mut body: serde_json::Value) -> Result<Vec<ConnectionRecord>, ApiError> {
+    serde_json::from_value(body["internal"].take()).map_err(|e| map_err(EngineError::Other(e.into())))
+}
+
+/// Save several connections at once. Nothing is written unless every entry is valid.
+#[utoipa::path(
+    post, path = "/v1/verses/{surah}/{ayah}/connections", tag = "connections",
+    params(VersePath),
+    request_body(content = Object, description = "`{ \"internal\": [{ \"from_token\", \"to_token\", \"layer\"?, \"meta\"?, \"id\"? }] }`"),
+    responses((status = 204))
+)]
+pub async fn save_verse_connections(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    body: Json<serde_json::Value>,
+) -> Result<StatusCode, ApiError> {
+    let _ = research::save_connections(state, headers, verse_ref(surah, ayah), body).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Verse research
+
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/pronouns", tag = "research",
+    params(VersePath, AuthorQuery, PageQuery),
+    responses((status = 200, body = Page<Object>))
+)]
+pub async fn list_pronouns(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = research::get_pronouns(state, headers, verse_ref(surah, ayah), Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Record a pronoun reference; returns it with its id and timestamps.
+#[utoipa::path(
+    post, path = "/v1/verses/{surah}/{ayah}/pronouns", tag = "research",
+    params(VersePath),
+    request_body = Object,
+    responses((status = 200, body = Object))
+)]
+pub async fn create_pronoun(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    body: Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(mut created) = research::create_pronoun(state, headers, verse_ref(surah, ayah), body).await?;
+    Ok(Json(created["reference"].take()))
+}
+
+/// Merge the given fields into a pronoun reference.
+#[utoipa::path(
+    put, path = "/v1/verses/{surah}/{ayah}/pronouns/{id}", tag = "research",
+    params(VersePath, ("id" = String, Path)),
+    request_body = Object,
+    responses((status = 204))
+)]
+pub async fn update_pronoun(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah, id)): Path<(i64, i64, String)>,
+    body: Json<serde_json::Value>,
+) -> Result<StatusCode, ApiError> {
+    let _ = research::update_pronoun(state, headers, Path((format!("{}:{}", surah, ayah), id)), body).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete, path = "/v1/verses/{surah}/{ayah}/pronouns/{id}", tag = "research",
+    params(VersePath, ("id" = String, Path)),
+    responses((status = 204))
+)]
+pub async fn delete_pronoun(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah, id)): Path<(i64, i64, String)>,
+) -> Result<StatusCode, ApiError> {
+    let _ = research::delete_pronoun(state, headers, Path((format!("{}:{}", surah, ayah), id))).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/hypotheses", tag = "research",
+    params(VersePath, AuthorQuery, PageQuery),
+    responses((status = 200, body = Page<Object>))
+)]
+pub async fn list_hypotheses(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = research::get_hypotheses(state, headers, verse_ref(surah, ayah), Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Record a hypothesis; returns it with its id and timestamps.
+#[utoipa::path(
+    post, path = "/v1/verses/{surah}/{ayah}/hypotheses", tag = "research",
+    params(VersePath),
+    request_body = Object,
+    responses((status = 200, body = Object))
+)]
+pub async fn create_hypothesis(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    body: Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(mut created) = research::create_hypothesis(state, headers, verse_ref(surah, ayah), body).await?;
+    Ok(Json(created["hypothesis"].take()))
+}
+
+/// Merge the given fields into a hypothesis.
+#[utoipa::path(
+    put, path = "/v1/verses/{surah}/{ayah}/hypotheses/{id}", tag = "research",
+    params(VersePath, ("id" = String, Path)),
+    request_body = Object,
+    responses((status = 204))
+)]
+pub async fn update_hypothesis(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah, id)): Path<(i64, i64, String)>,
+    body: Json<serde_json::Value>,
+) -> Result<StatusCode, ApiError> {
+    let _ = research::update_hypothesis(state, headers, Path((format!("{}:{}", surah, ayah), id)), body).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete, path = "/v1/verses/{surah}/{ayah}/hypotheses/{id}", tag = "research",
+    params(VersePath, ("id" = String, Path)),
+    responses((status = 204))
+)]
+pub async fn delete_hypothesis(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah, id)): Path<(i64, i64, String)>,
+) -> Result<StatusCode, ApiError> {
+    let _ = research::delete_hypothesis(state, headers, Path((format!("{}:{}", surah, ayah), id))).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/translations", tag = "research",
+    params(VersePath, AuthorQuery, PageQuery),
+    responses((status = 200, body = Page<Object>))
+)]
+pub async fn list_translations(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = research::get_translations(state, headers, verse_ref(surah, ayah), Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Add a translation; returns it with its id and timestamp.
+#[utoipa::path(
+    post, path = "/v1/verses/{surah}/{ayah}/translations", tag = "research",
+    params(VersePath),
+    request_body = Object,
+    responses((status = 200, body = Object))
+)]
+pub async fn create_translation(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    body: Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(mut created) = research::create_translation(state, headers, verse_ref(surah, ayah), body).await?;
+    Ok(Json(created["translation"].take()))
+}
+
+/// Replace every translation of the verse.
+#[utoipa::path(
+    put, path = "/v1/verses/{surah}/{ayah}/translations", tag = "research",
+    params(VersePath),
+    request_body = Vec<Object>,
+    responses((status = 204))
+)]
+pub async fn replace_translations(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Path((surah, ayah)): Path<(i64, i64)>,
+    body: Json<Vec<serde_json::Value>>,
+) -> Result<StatusCode, ApiError> {
+    let _ = research::update_translations(state, headers, verse_ref(surah, ayah), body).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get, path = "/v1/patterns", tag = "research",
+    params(AuthorQuery, PageQuery),
+    responses((status = 200, body = Page<Object>))
+)]
+pub async fn list_patterns(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(patterns) = research::get_patterns(state, headers, Query(params)).await?;
+    let items = match patterns {
+        serde_json::Value::Object(patterns) => patterns.into_iter().map(|(_, p)| p).collect(),
+        _ => Vec::new(),
+    };
+    Ok(Json(paging.page(items)))
+}
+
+/// Save a pattern; returns it with its id.
+#[utoipa::path(
+    post, path = "/v1/patterns", tag = "research",
+    request_body = Object,
+    responses((status = 200, body = Object))
+)]
+pub async fn create_pattern(
+    state: State<AppState>,
+    headers: HeaderMap,
+    body: Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(created) = research::create_pattern(state.clone(), headers, body).await?;
+    let id = created["id"].as_str().unwrap_or_default().to_string();
+    research::get_pattern(state, Path(id)).await
+}
+
+#[utoipa::path(
+    delete, path = "/v1/patterns/{pattern_id}", tag = "research",
+    params(("pattern_id" = String, Path)),
+    responses((status = 204))
+)]
+pub async fn delete_pattern(
+    state: State<AppState>,
+    headers: HeaderMap,
+    id: Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let _ = research::delete_pattern(state, headers, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Tags
+
+/// All tags with their usage counts, as a flat list or, with `?tree=true`,
+/// nested under their parents (the page then counts top-level tags).
+#[utoipa::path(
+    get, path = "/v1/tags", tag = "tags",
+    params(("tree" = Option<bool>, Query, description = "Nest tags under their parents"), PageQuery),
+    responses((status = 200, body = Page<Object>))
+)]
+pub async fn list_tags(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<serde_json::Value>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = tags::list_tags(state, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    get, path = "/v1/tags/counts", tag = "tags",
+    params(PageQuery),
+    responses((status = 200, body = Page<TagCount>))
+)]
+pub async fn tag_counts(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<TagCount>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = tags::tag_counts(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    delete, path = "/v1/tags/{tag_name}", tag = "tags",
+    params(("tag_name" = String, Path)),
+    responses((status = 204), (status = 409, description = "The tag still has children"))
+)]
+pub async fn delete_tag(
+    state: State<AppState>,
+    headers: HeaderMap,
+    name: Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let _ = tags::delete_tag(state, headers, name).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Verses (`surah:ayah`) carrying a tag, directly or through a token or span inside them.
+#[utoipa::path(
+    get, path = "/v1/tags/{tag_name}/verses", tag = "tags",
+    params(("tag_name" = String, Path), ("descendants" = Option<bool>, Query, description = "Include tags below this one, default true"), PageQuery),
+    responses((status = 200, body = Page<String>))
+)]
+pub async fn tagged_verses(
+    state: State<AppState>,
+    name: Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<String>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(mut body) = tags::tagged_verses(state, name, Query(params)).await?;
+    let verses = serde_json::from_value(body["verses"].take()).map_err(|e| map_err(EngineError::Other(e.into())))?;
+    Ok(Json(paging.page(verses)))
+}
+
+/// Assignments of a tag and, unless `?descendants=false`, of the tags below
+/// it. `?overlaps=<anchor>` and `?author=` / `?mine=true` narrow the list.
+#[utoipa::path(
+    get, path = "/v1/tags/{tag_name}/assignments", tag = "tags",
+    params(
+        ("tag_name" = String, Path),
+        ("descendants" = Option<bool>, Query, description = "Include tags below this one, default true"),
+        ("overlaps" = Option<String>, Query, description = "Anchor the targets must overlap"),
+        AuthorQuery,
+        PageQuery,
+    ),
+    responses((status = 200, body = Page<TagAssignment>))
+)]
+pub async fn list_tag_assignments(
+    state: State<AppState>,
+    headers: HeaderMap,
+    name: Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<TagAssignment>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = tags::list_tag_assignments(state, headers, name, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Assignments of every tag, usually narrowed with `?overlaps=<anchor>` to
+/// see how a verse or word is tagged.
+#[utoipa::path(
+    get, path = "/v1/tag-assignments", tag = "tags",
+    params(("overlaps" = Option<String>, Query, description = "Anchor the targets must overlap"), AuthorQuery, PageQuery),
+    responses((status = 200, body = Page<TagAssignment>))
+)]
+pub async fn list_all_assignments(
+    state: State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<TagAssignment>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = tags::list_all_assignments(state, headers, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    delete, path = "/v1/tag-assignments/{id}", tag = "tags",
+    params(("id" = String, Path)),
+    responses((status = 204))
+)]
+pub async fn delete_assignment(
+    state: State<AppState>,
+    headers: HeaderMap,
+    id: Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let _ = tags::delete_assignment(state, headers, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Notes
+
+/// Notes by title, without their bodies.
+#[utoipa::path(
+    get, path = "/v1/notes", tag = "notes",
+    params(AuthorQuery, PageQuery),
+    responses((status = 200, body = Page<NoteSummary>))
+)]
+pub async fn list_notes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<NoteSummary>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let author = util::author_filter(&params, &headers)?;
+    let found = state.research.list_notes(author.as_deref()).await.map_err(map_err)?;
+    Ok(Json(paging.page(found.iter().map(notes::note_summary).collect())))
+}
+
+/// Notes whose title or text contains `?q=`, with a snippet around the match.
+#[utoipa::path(
+    get, path = "/v1/notes/search", tag = "notes",
+    params(("q" = String, Query), PageQuery),
+    responses((status = 200, body = Page<NoteMatch>))
+)]
+pub async fn search_notes(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<NoteMatch>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = notes::search_library(state, Query(params)).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    delete, path = "/v1/notes/{id}", tag = "notes",
+    params(("id" = String, Path)),
+    responses((status = 204))
+)]
+pub async fn delete_note(
+    state: State<AppState>,
+    headers: HeaderMap,
+    id: Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let _ = notes::delete_note(state, headers, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Every note citing the verse or a word or span inside it.
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/notes", tag = "notes",
+    params(VersePath, PageQuery),
+    responses((status = 200, body = Page<NoteBacklink>))
+)]
+pub async fn verse_notes(
+    state: State<AppState>,
+    verse: Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<NoteBacklink>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = notes::verse_notes(state, verse).await?;
+    Ok(Json(paging.page(items)))
+}
+
+// Trash, history and backups
+
+#[utoipa::path(
+    get, path = "/v1/trash", tag = "trash",
+    params(PageQuery),
+    responses((status = 200, body = Page<TrashItem>))
+)]
+pub async fn list_trash(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<TrashItem>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = trash::list_trash(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PurgeResult {
+    /// Records removed for good.
+    pub purged: u64,
+}
+
+/// Purge trashed records older than `older_than_days` (defaults to the configured retention).
+#[utoipa::path(
+    post, path = "/v1/trash/purge", tag = "trash",
+    params(("older_than_days" = Option<u32>, Query, description = "Default: the configured retention")),
+    responses((status = 200, body = PurgeResult))
+)]
+pub async fn purge_trash(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<PurgeResult>, ApiError> {
+    let days = number_param::<u32>(&params, "older_than_days")?.map_or(state.config.trash_retention_days, i64::from);
+    let purged = state.research.purge_trash(days).await.map_err(map_err)?;
+    Ok(Json(PurgeResult { purged }))
+}
+
+#[utoipa::path(
+    post, path = "/v1/trash/{record_type}/{id}/restore", tag = "trash",
+    params(("record_type" = String, Path), ("id" = String, Path)),
+    responses((status = 204))
+)]
+pub async fn restore_from_trash(
+    state: State<AppState>,
+    headers: HeaderMap,
+    record: Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let _ = trash::restore(state, headers, record).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get, path = "/v1/history/{record_type}/{record_id}", tag = "history",
+    params(("record_type" = String, Path), ("record_id" = String, Path), PageQuery),
+    responses((status = 200, body = Page<ChangeRecord>))
+)]
+pub async fn record_history(
+    state: State<AppState>,
+    record: Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<ChangeRecord>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = history::get_record_history(state, record).await?;
+    Ok(Json(paging.page(items)))
+}
+
+/// Restore a record to the version a change recorded; returns the restored
+/// record, or `null` when that version was a deletion.
+#[utoipa::path(
+    post, path = "/v1/history/{record_type}/{record_id}/revert", tag = "history",
+    params(("record_type" = String, Path), ("record_id" = String, Path)),
+    request_body = RevertRequest,
+    responses((status = 200, body = Object))
+)]
+pub async fn revert_record(
+    state: State<AppState>,
+    headers: HeaderMap,
+    record: Path<(String, String)>,
+    body: Json<RevertRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(mut reverted) = history::revert_record(state, headers, record, body).await?;
+    Ok(Json(reverted["record"].take()))
+}
+
+#[utoipa::path(
+    get, path = "/v1/verses/{surah}/{ayah}/history", tag = "history",
+    params(VersePath, PageQuery),
+    responses((status = 200, body = Page<ChangeRecord>))
+)]
+pub async fn verse_history(
+    state: State<AppState>,
+    verse: Path<(i64, i64)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<ChangeRecord>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = history::get_verse_history(state, verse).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[utoipa::path(
+    get, path = "/v1/admin/backups", tag = "admin",
+    params(PageQuery),
+    responses((status = 200, body = Page<SnapshotInfo>))
+)]
+pub async fn list_backups(
+    state: State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Page<SnapshotInfo>>, ApiError> {
+    let paging = Paging::from_params(&params)?;
+    let Json(items) = backup::list_backups(state).await?;
+    Ok(Json(paging.page(items)))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RestoredBackup {
+    /// The snapshot now live.
+    pub restored: String,
+    /// The state replaced by the restore, kept so it can be undone.
+    pub pre_restore_snapshot: SnapshotInfo,
+}
+
+/// Restore a snapshot from the backup directory, first snapshotting the
+/// current state so the restore itself can be undone.
+#[utoipa::path(
+    post, path = "/v1/admin/restore", tag = "admin",
+    request_body = RestoreRequest,
+    responses((status = 200, body = RestoredBackup))
+)]
+pub async fn restore_backup(
+    State(state): State<AppState>,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<RestoredBackup>, ApiError> {
+    let pre_restore_snapshot = backup::restore_snapshot(&state, &req.name).await?;
+    Ok(Json(RestoredBackup { restored: req.name, pre_restore_snapshot }))
+}
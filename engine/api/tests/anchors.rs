@@ -0,0 +1,154 @@
+mod common;
+
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use ::common::{Anchor, Annotation};
+use common::{annotation, seed_verse};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::{ConnectionRecord, SqliteStorage};
+use tower::ServiceExt;
+
+fn rhetoric(id: &str, target: &str) -> Annotation {
+    annotation(id, target).layer("rhetoric").payload(serde_json::json!({ "device": "qasr" })).build()
+}
+
+async fn seed(storage: &SqliteStorage) {
+    seed_verse(storage, 2, 255, "ٱللَّهُ لَآ إِلَٰهَ إِلَّا هُوَ").await;
+    seed_verse(storage, 2, 256, "لَآ إِكْرَاهَ فِى ٱلدِّينِ").await;
+}
+
+#[test]
+fn anchors_round_trip_through_their_string_form() {
+    for s in ["2", "2:255", "2:255-257", "2:286-3:2", "2:255:3", "2:255:3-5", "2:255:3:1", "2:255:3@0-2", "2:255:3@4"] {
+        let anchor: Anchor = s.parse().unwrap();
+        assert_eq!(anchor.to_string(), s);
+    }
+    assert_eq!("2:255:3-3".parse::<Anchor>().unwrap(), Anchor::Token { surah: 2, ayah: 255, token: 3 });
+    for bad in ["0:1", "115", "2:0", "2:257-255", "2:255:5-3", "2:255:x", "2:255:3:1:0", "2:255@1"] {
+        assert!(bad.parse::<Anchor>().is_err(), "{} should not parse", bad);
+    }
+    assert_eq!(Anchor::parse_target("note-7").unwrap(), None);
+
+    let token: Anchor = "2:255:3".parse().unwrap();
+    assert!(token.overlaps(&"2:254-256".parse().unwrap()));
+    assert!(token.overlaps(&"2".parse().unwrap()));
+    assert!(token.overlaps(&"2:255:3@1".parse().unwrap()));
+    assert!(!token.overlaps(&"2:255:4".parse().unwrap()));
+    assert!(!token.overlaps(&"2:256".parse().unwrap()));
+}
+
+#[tokio::test]
+async fn anchors_resolve_against_the_corpus() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    seed(&storage).await;
+
+    let range = storage.resolve_anchor(&"2:255:1-2".parse().unwrap()).await.unwrap();
+    assert_eq!(range.kind, "token_range");
+    assert_eq!(range.text, "لَآ إِلَٰهَ");
+    let verses = storage.resolve_anchor(&"2:255-256".parse().unwrap()).await.unwrap();
+    assert_eq!(verses.verses, vec![(2, 255), (2, 256)]);
+    let letters = storage.resolve_anchor(&"2:255:1@0-1".parse().unwrap()).await.unwrap();
+    assert_eq!(letters.text, "لَ");
+    let segment = storage.resolve_anchor(&"2:256:3:0".parse().unwrap()).await.unwrap();
+    assert_eq!(segment.text, "ٱلدِّينِ");
+
+    for missing in ["2:257", "2:255-257", "2:255:9", "2:255:4-5", "2:255:1@0-40", "2:255:1:3", "3"] {
+        assert!(storage.resolve_anchor(&missing.parse().unwrap()).await.is_err(), "{}", missing);
+    }
+}
+
+#[tokio::test]
+async fn overlap_queries_cover_every_granularity() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    for (id, target) in [
+        ("surah", "2"),
+        ("range", "2:254-256"),
+        ("verse", "2:255"),
+        ("tokens", "2:255:2-4"),
+        ("letters", "2:255:3@0-1"),
+        ("other-token", "2:255:0"),
+        ("other-verse", "2:256"),
+        ("opaque", "note-7"),
+    ] {
+        storage.upsert_annotation(&rhetoric(id, target), None).await.unwrap();
+    }
+
+    let hits = storage.list_annotations_overlapping(&"2:255:3".parse().unwrap(), None).await.unwrap();
+    let mut ids: Vec<String> = hits.into_iter().map(|a| a.id).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["letters", "range", "surah", "tokens", "verse"]);
+
+    // Moving an annotation moves its span.
+    storage.upsert_annotation(&rhetoric("tokens", "2:256:0-1"), None).await.unwrap();
+    let hits = storage.list_annotations_overlapping(&"2:255:3".parse().unwrap(), None).await.unwrap();
+    assert!(hits.iter().all(|a| a.id != "tokens"));
+
+    let link = ConnectionRecord {
+        id: "c1".into(),
+        from_token: "2:255:0-1".into(),
+        to_token: "2:256-257".into(),
+        layer: "rhetoric".into(),
+        meta: serde_json::json!({}),
+        author: None,
+        created_at: None,
+        updated_at: None,
+    };
+    storage.upsert_connection(&link, None).await.unwrap();
+    assert_eq!(storage.list_connections_overlapping(&"2:257".parse().unwrap(), None).await.unwrap().len(), 1);
+    assert_eq!(storage.list_connections_for_verse(2, 255, None).await.unwrap().len(), 1);
+    assert!(storage.list_connections_for_verse(2, 256, None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn connection_spans_are_indexed() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let indexes: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_index_list('connections')")
+        .fetch_all(storage.pool())
+        .await
+        .unwrap();
+    for name in ["idx_connections_from_span", "idx_connections_to_span"] {
+        assert!(indexes.iter().any(|i| i == name), "{} missing from {:?}", name, indexes);
+    }
+}
+
+#[tokio::test]
+async fn api_rejects_targets_missing_from_the_corpus() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    seed(&storage).await;
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let post = |target: &str| {
+        let body = serde_json::json!({ "target_id": target, "layer": "rhetoric", "payload": { "device": "qasr" } });
+        Request::post("/annotations")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    assert_eq!(app.clone().oneshot(post("2:255:1-3")).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(app.clone().oneshot(post("2:255:1-30")).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(app.clone().oneshot(post("2:2x5")).await.unwrap().status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/annotations?overlaps=2:255:2").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let hits: Vec<Annotation> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(hits.len(), 1);
+
+    let response = app
+        .oneshot(Request::get("/api/anchors/2:256:2@0-1").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let resolved: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(resolved["kind"], "letters");
+    assert_eq!(resolved["anchor"], "2:256:2@0-1");
+}
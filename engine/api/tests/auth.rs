@@ -0,0 +1,83 @@
+mod common;
+
+use api::{ApiToken, Scope};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{app_with, json};
+use tower::ServiceExt;
+
+async fn app(dir: &tempfile::TempDir, tokens: Vec<ApiToken>, read_only: bool) -> axum::Router {
+    app_with(dir, |config| {
+        config.tokens = tokens;
+        config.read_only = read_only;
+    })
+    .await
+}
+
+fn tokens() -> Vec<ApiToken> {
+    vec![
+        ApiToken::new("student", Scope::Read),
+        ApiToken::new("researcher", Scope::Write),
+        ApiToken::new("maintainer", Scope::Admin),
+    ]
+}
+
+/// [`common::send`] with a bearer token when one is given.
+fn send(method: &str, uri: &str, token: Option<&str>, body: &str) -> Request<Body> {
+    let mut request = common::send(method, uri, body);
+    if let Some(token) = token {
+        request.headers_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+    }
+    request
+}
+
+#[tokio::test]
+async fn tokens_gate_requests_by_scope() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir, tokens(), false).await;
+
+    let response = app.clone().oneshot(send("GET", "/v1/tags", None, "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+    let (code, body) = json(&app, send("GET", "/v1/tags", Some("guess"), "")).await;
+    assert_eq!(code, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "unauthorized");
+
+    assert_eq!(json(&app, send("GET", "/v1/tags", Some("student"), "")).await.0, StatusCode::OK);
+    assert_eq!(json(&app, send("POST", "/v1/search", Some("student"), r#"{"query":""}"#)).await.0, StatusCode::OK);
+    let (code, body) = json(&app, send("POST", "/v1/tags", Some("student"), r#"{"name":"mercy"}"#)).await;
+    assert_eq!(code, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "forbidden");
+    assert_eq!(body["error"]["details"]["required"], "write");
+    assert_eq!(json(&app, send("POST", "/api/tags", Some("student"), r#"{"name":"mercy"}"#)).await.0, StatusCode::FORBIDDEN);
+
+    assert_eq!(json(&app, send("POST", "/v1/tags", Some("researcher"), r#"{"name":"mercy"}"#)).await.0, StatusCode::OK);
+    let (code, body) = json(&app, send("GET", "/v1/admin/backups", Some("researcher"), "")).await;
+    assert_eq!(code, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["details"]["required"], "admin");
+    assert_eq!(json(&app, send("GET", "/v1/admin/backups", Some("maintainer"), "")).await.0, StatusCode::OK);
+    assert_eq!(json(&app, send("DELETE", "/v1/tags/mercy", Some("maintainer"), "")).await.0, StatusCode::NO_CONTENT);
+
+    assert_eq!(json(&app, send("GET", "/health", None, "")).await.0, StatusCode::OK);
+    assert_eq!(json(&app, send("GET", "/openapi.json", None, "")).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn read_only_mode_refuses_every_change() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir, Vec::new(), true).await;
+
+    assert_eq!(json(&app, send("GET", "/v1/tags", None, "")).await.0, StatusCode::OK);
+    assert_eq!(json(&app, send("POST", "/v1/search", None, r#"{"query":""}"#)).await.0, StatusCode::OK);
+    for (method, uri) in [("POST", "/v1/tags"), ("POST", "/api/hypotheses/2:255"), ("DELETE", "/annotations/x"), ("POST", "/v1/admin/backups")] {
+        let (code, body) = json(&app, send(method, uri, None, r#"{"name":"mercy"}"#)).await;
+        assert_eq!(code, StatusCode::FORBIDDEN, "{} {}", method, uri);
+        assert_eq!(body["error"]["code"], "read_only");
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let app = self::app(&dir, tokens(), true).await;
+    let (code, body) = json(&app, send("POST", "/v1/tags", Some("maintainer"), r#"{"name":"mercy"}"#)).await;
+    assert_eq!(code, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "read_only");
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::annotation;
+use store::{SqliteStorage, RECORD_ANNOTATION};
+
+#[tokio::test]
+async fn annotations_keep_their_author_and_filter_by_it() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    storage.upsert_annotation(&annotation("a1", "2:255").text("first").build(), Some("amina")).await.unwrap();
+    storage.upsert_annotation(&annotation("a2", "2:255").text("second").build(), Some("yusuf")).await.unwrap();
+
+    // Editing someone else's annotation does not take over its authorship.
+    storage.upsert_annotation(&annotation("a1", "2:255").text("edited").build(), Some("yusuf")).await.unwrap();
+    let a1 = storage.get_annotation("a1").await.unwrap().unwrap();
+    assert_eq!(a1.author.as_deref(), Some("amina"));
+    assert!(a1.created_at.is_some() && a1.updated_at.is_some());
+
+    let mine = storage.list_annotations(None, Some("yusuf")).await.unwrap();
+    assert_eq!(mine.len(), 1);
+    assert_eq!(mine[0].id, "a2");
+    assert_eq!(storage.list_annotations(Some("2:255"), None).await.unwrap().len(), 2);
+
+    // Rewriting identical content is not logged as a change.
+    storage.upsert_annotation(&annotation("a1", "2:255").text("edited").build(), Some("amina")).await.unwrap();
+    let history = storage.list_record_history(RECORD_ANNOTATION, "a1").await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].after.as_ref().unwrap()["author"], "amina");
+}
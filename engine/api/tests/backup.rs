@@ -0,0 +1,87 @@
+mod common;
+
+use common::annotation;
+use store::SqliteStorage;
+
+#[tokio::test]
+async fn snapshot_and_restore_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("kalima.db");
+    let backups = dir.path().join("backups");
+    let storage = SqliteStorage::connect(db.to_str().unwrap()).await.unwrap();
+
+    storage.upsert_annotation(&annotation("n1", "1:1").text("kept").build(), None).await.unwrap();
+    let snapshot = storage.snapshot_into(&backups, None).await.unwrap();
+    assert!(snapshot.size_bytes > 0);
+    assert!(snapshot.name.starts_with(store::SNAPSHOT_PREFIX));
+
+    storage.upsert_annotation(&annotation("n1", "1:1").text("changed").build(), None).await.unwrap();
+    storage.upsert_annotation(&annotation("n2", "1:1").text("added later").build(), None).await.unwrap();
+
+    storage.restore_from(std::path::Path::new(&snapshot.path)).await.unwrap();
+    let n1 = storage.get_annotation("n1").await.unwrap().unwrap();
+    assert_eq!(n1.payload["text"], "kept");
+    assert!(storage.get_annotation("n2").await.unwrap().is_none());
+
+    // The restored database keeps working normally.
+    storage.upsert_annotation(&annotation("n3", "1:1").text("after restore").build(), None).await.unwrap();
+    assert_eq!(storage.list_annotations(None, None).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn rotation_keeps_newest_snapshots() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = SqliteStorage::connect(dir.path().join("kalima.db").to_str().unwrap()).await.unwrap();
+    let backups = dir.path().join("backups");
+    let mut newest = None;
+    for _ in 0..3 {
+        newest = Some(storage.snapshot_into(&backups, None).await.unwrap().name);
+    }
+    std::fs::write(backups.join("notes.txt"), "not a snapshot").unwrap();
+
+    let all = store::list_snapshots(&backups).unwrap();
+    assert_eq!(all.len(), 3);
+    let removed = store::prune_snapshots(&backups, 1).unwrap();
+    assert_eq!(removed.len(), 2);
+    let left = store::list_snapshots(&backups).unwrap();
+    assert_eq!(left.len(), 1);
+    assert_eq!(Some(&left[0].name), newest.as_ref());
+    assert!(backups.join("notes.txt").exists());
+}
+
+#[tokio::test]
+async fn rotation_keeps_labelled_snapshots() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = SqliteStorage::connect(dir.path().join("kalima.db").to_str().unwrap()).await.unwrap();
+    let backups = dir.path().join("backups");
+    let manual = storage.snapshot_into(&backups, Some("before-import")).await.unwrap().name;
+    let safety = storage.snapshot_into(&backups, Some("pre-restore")).await.unwrap().name;
+    let mut newest = None;
+    for _ in 0..3 {
+        newest = Some(storage.snapshot_into(&backups, None).await.unwrap().name);
+    }
+
+    let removed = store::prune_snapshots(&backups, 1).unwrap();
+    assert_eq!(removed.len(), 2);
+    assert!(!removed.contains(&manual) && !removed.contains(&safety));
+    let mut left: Vec<String> = store::list_snapshots(&backups).unwrap().into_iter().map(|s| s.name).collect();
+    left.sort();
+    let mut expected = vec![manual, safety, newest.unwrap()];
+    expected.sort();
+    assert_eq!(left, expected);
+}
+
+#[tokio::test]
+async fn rejects_bad_snapshots() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+
+    assert!(store::find_snapshot(dir.path(), "../kalima.db").is_err());
+    assert!(store::find_snapshot(dir.path(), "kalima-missing.db").is_err());
+
+    let bogus = dir.path().join("kalima-bogus.db");
+    std::fs::write(&bogus, "definitely not sqlite").unwrap();
+    assert!(storage.restore_from(&bogus).await.is_err());
+    // A failed restore leaves the connection usable.
+    storage.upsert_annotation(&annotation("n1", "1:1").text("still fine").build(), None).await.unwrap();
+}
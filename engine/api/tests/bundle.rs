@@ -0,0 +1,121 @@
+mod common;
+
+use common::annotation;
+use store::{ConflictStrategy, ResearchBundle, SqliteStorage};
+
+async fn text_of(storage: &SqliteStorage, id: &str) -> Option<String> {
+    storage
+        .get_annotation(id)
+        .await
+        .unwrap()
+        .map(|a| a.payload["text"].as_str().unwrap().to_string())
+}
+
+/// Export from one database and decode it the way a file round trip would.
+async fn export(storage: &SqliteStorage) -> ResearchBundle {
+    let bundle = storage.export_research(Some("tester")).await.unwrap();
+    serde_json::from_str(&serde_json::to_string(&bundle).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn one_sided_edits_and_deletions_merge_cleanly() {
+    let a = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let b = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    for id in ["n1", "n2", "n3"] {
+        a.upsert_annotation(&annotation(id, "1:1").text("original").author("amina").build(), None).await.unwrap();
+    }
+
+    let first = export(&a).await;
+    let dry = b.import_research(&first, ConflictStrategy::Report, None, true).await.unwrap();
+    assert_eq!(dry.created, 3);
+    assert!(b.get_annotation("n1").await.unwrap().is_none());
+
+    let report = b.import_research(&first, ConflictStrategy::Report, None, false).await.unwrap();
+    assert_eq!(report.created, 3);
+    assert_eq!(text_of(&b, "n1").await.as_deref(), Some("original"));
+    assert_eq!(b.get_annotation("n1").await.unwrap().unwrap().author.as_deref(), Some("amina"));
+
+    // Each side edits a different record; B also deletes one.
+    b.upsert_annotation(&annotation("n1", "1:1").text("edited by b").author("amina").build(), None).await.unwrap();
+    b.delete_annotation("n3", None).await.unwrap();
+    a.upsert_annotation(&annotation("n2", "1:1").text("edited by a").author("amina").build(), None).await.unwrap();
+
+    let report = a.import_research(&export(&b).await, ConflictStrategy::Report, None, false).await.unwrap();
+    assert!(report.conflicts.is_empty());
+    assert_eq!((report.updated, report.deleted, report.kept_local), (1, 1, 1));
+    assert_eq!(text_of(&a, "n1").await.as_deref(), Some("edited by b"));
+    assert_eq!(text_of(&a, "n2").await.as_deref(), Some("edited by a"));
+    assert!(a.get_annotation("n3").await.unwrap().is_none());
+
+    // Importing the same bundle again is a no-op.
+    let again = a.import_research(&export(&b).await, ConflictStrategy::Report, None, false).await.unwrap();
+    assert_eq!(again.created + again.updated + again.deleted, 0);
+    assert!(again.conflicts.is_empty());
+}
+
+#[tokio::test]
+async fn concurrent_edits_are_reported_until_resolved() {
+    let a = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let b = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    a.upsert_annotation(&annotation("n1", "1:1").text("original").author("amina").build(), None).await.unwrap();
+    b.import_research(&export(&a).await, ConflictStrategy::Report, None, false).await.unwrap();
+
+    a.upsert_annotation(&annotation("n1", "1:1").text("a's reading").author("amina").build(), None).await.unwrap();
+    b.upsert_annotation(&annotation("n1", "1:1").text("b's reading").author("amina").build(), None).await.unwrap();
+
+    let bundle = export(&b).await;
+    let report = a.import_research(&bundle, ConflictStrategy::Report, None, false).await.unwrap();
+    assert_eq!(report.conflicts.len(), 1);
+    let conflict = &report.conflicts[0];
+    assert_eq!(conflict.id, "n1");
+    assert_eq!(conflict.base.as_ref().unwrap()["payload"]["text"], "original");
+    assert_eq!(conflict.ours.as_ref().unwrap()["payload"]["text"], "a's reading");
+    assert_eq!(conflict.theirs.as_ref().unwrap()["payload"]["text"], "b's reading");
+    assert_eq!(text_of(&a, "n1").await.as_deref(), Some("a's reading"));
+
+    // Still a conflict the second time round; "theirs" settles it.
+    let report = a.import_research(&bundle, ConflictStrategy::Report, None, false).await.unwrap();
+    assert_eq!(report.conflicts.len(), 1);
+    let report = a.import_research(&bundle, ConflictStrategy::Theirs, None, false).await.unwrap();
+    assert_eq!(report.updated, 1);
+    assert_eq!(text_of(&a, "n1").await.as_deref(), Some("b's reading"));
+
+    let history = a.list_record_history(store::RECORD_ANNOTATION, "n1").await.unwrap();
+    assert_eq!(history.last().unwrap().after.as_ref().unwrap()["payload"]["text"], "b's reading");
+}
+
+#[tokio::test]
+async fn failed_import_leaves_the_database_unchanged() {
+    let a = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let b = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    for id in ["n1", "n2"] {
+        a.upsert_annotation(&annotation(id, "1:1").text("original").build(), None).await.unwrap();
+    }
+    let good = export(&a).await;
+
+    // The broken record comes after the good ones, so the import fails partway.
+    let mut bad = good.clone();
+    let mut broken = bad.records[0].clone();
+    broken.id = "n3".into();
+    broken.value = Some(serde_json::json!({ "id": "n3" }));
+    bad.records.push(broken);
+    assert!(b.import_research(&bad, ConflictStrategy::Report, None, false).await.is_err());
+    assert!(b.list_annotations(None, None).await.unwrap().is_empty());
+    assert!(b.list_record_history(store::RECORD_ANNOTATION, "n1").await.unwrap().is_empty());
+
+    // No sync base was recorded, so the good bundle still applies in full.
+    let report = b.import_research(&good, ConflictStrategy::Report, None, false).await.unwrap();
+    assert_eq!(report.created, 2);
+    assert_eq!(text_of(&b, "n2").await.as_deref(), Some("original"));
+}
+
+#[tokio::test]
+async fn rejects_foreign_files() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let mut bundle = storage.export_research(None).await.unwrap();
+    bundle.format = "something-else".into();
+    assert!(storage
+        .import_research(&bundle, ConflictStrategy::Report, None, false)
+        .await
+        .is_err());
+}
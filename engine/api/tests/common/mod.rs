@@ -0,0 +1,127 @@
+//! Helpers shared by the integration tests. Each test binary uses a subset.
+#![allow(dead_code)]
+
+use ::common::{Annotation, Segment, SegmentView};
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::SqliteStorage;
+use tower::ServiceExt;
+
+/// A router over an empty in-memory store, indexing and backing up into `dir`.
+pub async fn app(dir: &tempfile::TempDir) -> axum::Router {
+    app_with(dir, |_| {}).await
+}
+
+/// [`app`] with the server config adjusted by `configure` first.
+pub async fn app_with(dir: &tempfile::TempDir, configure: impl FnOnce(&mut ServerConfig)) -> axum::Router {
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let mut config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    config.backup_dir = dir.path().join("backups").to_string_lossy().to_string();
+    configure(&mut config);
+    router(AppState::new(storage, search, config))
+}
+
+/// One token per word of `text`, each with a single stem segment.
+pub fn verse_docs(surah: i64, ayah: i64, text: &str) -> Vec<SegmentView> {
+    text.split_whitespace()
+        .enumerate()
+        .map(|(i, word)| SegmentView {
+            id: format!("{}:{}:{}", surah, ayah, i),
+            verse_ref: format!("{}:{}", surah, ayah),
+            token_index: i,
+            text: word.into(),
+            segments: vec![Segment {
+                id: format!("{}:{}:{}:0", surah, ayah, i),
+                r#type: "stem".into(),
+                form: word.into(),
+                ..Default::default()
+            }],
+            annotations: vec![],
+        })
+        .collect()
+}
+
+/// Store `text` as verse `surah:ayah`, tokenised by [`verse_docs`].
+pub async fn seed_verse(storage: &SqliteStorage, surah: i64, ayah: i64, text: &str) {
+    let mut batch = storage.begin_batch().await.unwrap();
+    batch.upsert_many(&verse_docs(surah, ayah, text)).await.unwrap();
+    batch.set_verse_text(surah, ayah, text).await.unwrap();
+    batch.commit().await.unwrap();
+}
+
+/// A request with a JSON body.
+pub fn send(method: &str, uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// Status and body of a response; the body is `null` unless it is JSON.
+pub async fn json(app: &axum::Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+}
+
+/// Status and the `error` object of the envelope, checking its shape.
+pub async fn error(app: &axum::Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let err = v["error"].clone();
+    assert!(err["code"].is_string() && err["message"].is_string(), "{}", v);
+    assert!(err.as_object().unwrap().contains_key("details"), "{}", v);
+    (status, err)
+}
+
+/// Builder for test annotations: an `interpretation` with no payload or
+/// author unless told otherwise.
+pub struct AnnotationBuilder(Annotation);
+
+pub fn annotation(id: &str, target: &str) -> AnnotationBuilder {
+    AnnotationBuilder(Annotation {
+        id: id.into(),
+        target_id: target.into(),
+        layer: "interpretation".into(),
+        payload: serde_json::json!({}),
+        author: None,
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+impl AnnotationBuilder {
+    pub fn layer(mut self, layer: &str) -> Self {
+        self.0.layer = layer.into();
+        self
+    }
+
+    pub fn payload(mut self, payload: serde_json::Value) -> Self {
+        self.0.payload = payload;
+        self
+    }
+
+    /// Shorthand for a `{ "text": ... }` payload.
+    pub fn text(self, text: &str) -> Self {
+        self.payload(serde_json::json!({ "text": text }))
+    }
+
+    pub fn author(mut self, author: &str) -> Self {
+        self.0.author = Some(author.into());
+        self
+    }
+
+    pub fn build(self) -> Annotation {
+        self.0
+    }
+}
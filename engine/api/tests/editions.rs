@@ -0,0 +1,50 @@
+mod common;
+
+use common::seed_verse;
+use store::{align_words, diff_words, Edition, SqliteStorage, PRIMARY_EDITION};
+
+#[tokio::test]
+async fn edition_import_alignment_and_diff() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    seed_verse(&storage, 1, 4, "مَـٰلِكِ يَوْمِ ٱلدِّينِ").await;
+
+    let warsh = Edition {
+        id: "warsh".into(),
+        name: "Warsh".into(),
+        riwayah: Some("warsh".into()),
+        script: Some("uthmani".into()),
+        source: None,
+    };
+    let verses = vec![(1, 4, "مَلِكِ يَوْمِ ٱلدِّينِ".to_string())];
+    assert_eq!(storage.import_edition(&warsh, &verses).await.unwrap(), 1);
+
+    let editions = storage.list_editions().await.unwrap();
+    assert_eq!(editions[0].id, PRIMARY_EDITION);
+    assert!(editions.iter().any(|e| e.id == "warsh"));
+
+    let alignment = storage.get_edition_alignment("warsh", 1, 4).await.unwrap();
+    let tokens: Vec<_> = alignment.iter().map(|w| w.token_index).collect();
+    assert_eq!(tokens, [Some(0), Some(1), Some(2)]);
+
+    let diff = storage
+        .diff_editions(PRIMARY_EDITION, "warsh", 1, Some(4), false)
+        .await
+        .unwrap();
+    let ops: Vec<_> = diff[0].changes.iter().map(|c| c.op.as_str()).collect();
+    assert_eq!(ops, ["replace", "equal", "equal"]);
+    assert_eq!(diff[0].changes[0].to.as_deref(), Some("مَلِكِ"));
+
+    assert!(storage.import_edition(&Edition { id: PRIMARY_EDITION.into(), ..warsh }, &verses).await.is_err());
+}
+
+#[test]
+fn alignment_handles_split_words() {
+    // An edition writing one token as two words keeps both on that token.
+    let tokens = ["يَـٰٓأَيُّهَا", "ٱلنَّاسُ"];
+    let words = ["يا", "أيها", "الناس"];
+    assert_eq!(align_words(&words, &tokens), [Some(0), Some(0), Some(1)]);
+
+    let changes = diff_words("قال رب احكم", "قل رب احكم", false);
+    assert_eq!(changes[0].op, "replace");
+    assert!(diff_words("ٱلْحَمْدُ لِلَّهِ", "الحمد لله", true).iter().all(|c| c.op == "equal"));
+}
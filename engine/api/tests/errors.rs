@@ -0,0 +1,95 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{app, error, send};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn engine_errors_share_one_envelope() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir).await;
+
+    let (status, err) = error(&app, Request::get("/api/hypotheses/2:287").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["code"], "invalid_request");
+    assert!(err["details"].is_null());
+
+    let (status, err) = error(&app, Request::get("/api/patterns/nope").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(err["code"], "not_found");
+
+    app.clone().oneshot(send("POST", "/api/tags", r#"{"name":"theology"}"#)).await.unwrap();
+    app.clone().oneshot(send("PUT", "/api/tags/tawhid", r#"{"parent":"theology"}"#)).await.unwrap();
+    let (status, err) = error(&app, send("DELETE", "/api/tags/theology", "{}")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(err["code"], "conflict");
+
+    let (status, err) = error(&app, send("POST", "/api/layers", r#"{"name":"x","target":"page","schema":{}}"#)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["code"], "validation_failed");
+    assert_eq!(err["details"]["field"], "target");
+}
+
+#[tokio::test]
+async fn bad_input_is_reported_instead_of_ignored() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir).await;
+
+    let spec = r#"{"query":"","filters":[{"field":"root","op":"eq","value":"ktb"},{"field":"colour","op":"eq","value":"red"}]}"#;
+    let (status, err) = error(&app, send("POST", "/search", spec)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["code"], "validation_failed");
+    assert_eq!(err["details"]["field"], "filters[1].field");
+    assert_eq!(err["details"]["value"], "colour");
+    assert!(err["details"]["allowed"].as_array().unwrap().contains(&serde_json::json!("lemma")));
+
+    let spec = r#"{"query":"","filters":[{"field":"pos","op":"like","value":"N"}]}"#;
+    let (_, err) = error(&app, send("POST", "/search", spec)).await;
+    assert_eq!(err["details"]["field"], "filters[0].op");
+    let spec = r#"{"query":"","filters":[{"field":"pos","op":"in","value":"N"}]}"#;
+    let (_, err) = error(&app, send("POST", "/search", spec)).await;
+    assert_eq!(err["details"]["field"], "filters[0].value");
+
+    let (status, err) = error(&app, Request::get("/search/dependency/subj").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["code"], "unsupported");
+
+    let (_, err) = error(&app, Request::get("/api/verses?limit=ten").body(Body::empty()).unwrap()).await;
+    assert_eq!(err["code"], "validation_failed");
+    assert_eq!(err["details"], serde_json::json!({ "field": "limit", "value": "ten" }));
+
+    let (_, err) = error(&app, send("POST", "/api/connections/1:1", r#"{"internal":[{"to_token":"1:1:1"}]}"#)).await;
+    assert_eq!(err["details"]["field"], "internal[0].from_token");
+    let (_, err) = error(&app, send("POST", "/api/connections/1:1", r#"{"external":[]}"#)).await;
+    assert_eq!(err["details"]["field"], "internal");
+
+    let (_, err) = error(&app, send("POST", "/api/patterns", "[1, 2]")).await;
+    assert_eq!(err["code"], "validation_failed");
+}
+
+#[tokio::test]
+async fn framework_rejections_use_the_envelope() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir).await;
+
+    let (status, err) = error(&app, Request::get("/api/nowhere").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(err["code"], "not_found");
+
+    let (status, err) = error(&app, send("DELETE", "/api/roots", "")).await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(err["code"], "method_not_allowed");
+    // Headers of the rejection survive the rewrite.
+    let response = app.clone().oneshot(send("DELETE", "/api/roots", "")).await.unwrap();
+    assert_eq!(response.headers()["allow"], "GET,HEAD");
+
+    let (status, err) = error(&app, send("POST", "/api/tags", "{not json")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["code"], "invalid_request");
+    assert!(!err["message"].as_str().unwrap().is_empty());
+
+    let (status, err) = error(&app, Request::get("/api/annotations/one/1").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["code"], "invalid_request");
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use ::common::EngineError;
+use common::seed_verse;
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::{align_translation, parse_alignments, parse_glosses, GlossFormat, SqliteStorage};
+use tower::ServiceExt;
+
+const BASMALA: &str = "بِسْمِ ٱللَّهِ ٱلرَّحْمَٰنِ ٱلرَّحِيمِ";
+const SAHIH: &str = "In the name of Allah, the Entirely Merciful, the Especially Merciful.";
+
+const WBW: &str = "\
+LOCATION\tGLOSS
+(1:1:1)\tIn (the) name
+(1:1:1:1)\tIn
+1:1:2\t(of) Allah
+1\t1\t3\tthe Most Gracious
+1\t1\t4\tthe Most Merciful
+";
+
+#[test]
+fn gloss_files_parse_in_common_layouts() {
+    let tsv = parse_glosses(WBW, GlossFormat::Tsv, "wbw", Some("en")).unwrap();
+    assert_eq!(tsv.len(), 5);
+    assert_eq!((tsv[0].token_index, tsv[0].segment_index), (0, None));
+    assert_eq!(tsv[1].segment_index, Some(0));
+    assert_eq!(tsv[3].text, "the Most Gracious");
+    assert!(parse_glosses("(1:1:1)\tIn\nnot a location\n", GlossFormat::Tsv, "wbw", None)
+        .unwrap_err()
+        .to_string()
+        .contains("line 2"));
+
+    let records = r#"[{"surah": 1, "ayah": 1, "word": 2, "gloss": "Allah"}, {"location": "1:1:1:2", "text": "name", "lang": "en"}]"#;
+    let json = parse_glosses(records, GlossFormat::Json, "wbw", None).unwrap();
+    assert_eq!(json[0].token_index, 1);
+    assert_eq!(json[1].segment_index, Some(1));
+    assert_eq!(json[1].lang.as_deref(), Some("en"));
+
+    let quran_com = r#"{"verses": [{"verse_key": "1:1", "words": [
+        {"position": 1, "char_type_name": "word", "translation": {"text": "In (the) name"}},
+        {"position": 5, "char_type_name": "end", "translation": {"text": "(1)"}}]}]}"#;
+    let json = parse_glosses(quran_com, GlossFormat::Json, "qdc", None).unwrap();
+    assert_eq!(json.len(), 1);
+    assert_eq!(json[0].text, "In (the) name");
+
+    let map = parse_glosses(r#"{"1:1:3": "the Most Gracious"}"#, GlossFormat::Json, "wbw", None).unwrap();
+    assert_eq!(map[0].token_index, 2);
+    assert!(parse_glosses("[{\"gloss\": \"x\"}]", GlossFormat::Json, "wbw", None).is_err());
+}
+
+#[test]
+fn translations_align_to_tokens_through_their_glosses() {
+    let glosses = parse_glosses(WBW, GlossFormat::Tsv, "wbw", None).unwrap();
+    let links = align_translation(SAHIH, &glosses);
+    // In(0) the(1) name(2) of(3) Allah,(4) the(5) Entirely(6) Merciful,(7) the(8) Especially(9) Merciful.(10)
+    assert_eq!(links[0].tokens, vec![0]);
+    assert_eq!(links[0].words, vec![0, 1, 2]);
+    assert_eq!(links[1].tokens, vec![1]);
+    assert_eq!(links[1].words, vec![3, 4]);
+    // "Merciful" renders the last token twice over; token 3 takes both.
+    assert_eq!(links[2].tokens, vec![3]);
+    assert!(links[2].words.contains(&7) && links[2].words.contains(&10));
+
+    let entries = parse_alignments(
+        r#"{"verse": "1:1", "text": "In the name", "links": [{"tokens": [0], "words": [0, 1, 2]}]}
+{"surah": 1, "ayah": 1, "source": "other", "text": "By the name"}"#,
+        "sahih",
+    )
+    .unwrap();
+    assert_eq!(entries[0].source, "sahih");
+    assert_eq!(entries[1].source, "other");
+    assert!(entries[1].links.is_empty());
+    assert!(parse_alignments(r#"[{"verse": "1:1", "text": "In", "links": [{"tokens": [0], "words": [3]}]}]"#, "s")
+        .unwrap_err()
+        .to_string()
+        .contains("past the end"));
+}
+
+#[tokio::test]
+async fn interlinear_joins_tokens_glosses_and_translations() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    seed_verse(&storage, 1, 1, BASMALA).await;
+
+    let glosses = parse_glosses(WBW, GlossFormat::Tsv, "wbw", Some("en")).unwrap();
+    assert_eq!(storage.import_glosses(&glosses).await.unwrap(), 5);
+    // Re-importing replaces rather than duplicates.
+    storage.import_glosses(&glosses).await.unwrap();
+    // Locations outside the corpus are rejected and nothing is written.
+    for bad in ["(1:1:5)\tfifth", "(1:1:1:2)\tsecond segment", "(1:2:1)\tnext verse"] {
+        let extra = parse_glosses(&format!("(1:1:1)\tName\n{}\n", bad), GlossFormat::Tsv, "other", None).unwrap();
+        let err = storage.import_glosses(&extra).await.unwrap_err();
+        assert!(matches!(err, EngineError::Invalid(_)), "{}", bad);
+    }
+    assert!(storage.get_glosses(1, 1, Some("other")).await.unwrap().is_empty());
+    let mut alignments = parse_alignments(&format!(r#"[{{"verse": "1:1", "text": "{}"}}]"#, SAHIH), "sahih").unwrap();
+    alignments[0].links = align_translation(SAHIH, &storage.get_glosses(1, 1, None).await.unwrap());
+    storage.import_alignments(&alignments).await.unwrap();
+
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    let json = |body: &[u8]| serde_json::from_slice::<serde_json::Value>(body).unwrap();
+
+    let response = app.clone().oneshot(get("/api/glosses/sources")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body), serde_json::json!([{ "source": "wbw", "lang": "en", "glosses": 5 }]));
+
+    let response = app.clone().oneshot(get("/api/glosses/1/1?source=none")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body), serde_json::json!([]));
+
+    let response = app.clone().oneshot(get("/api/interlinear/1/1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let v = json(&body);
+    let first = &v["tokens"][0];
+    assert_eq!(first["text"], "بِسْمِ");
+    assert_eq!(first["glosses"][0]["text"], "In (the) name");
+    assert_eq!(first["segments"][0]["glosses"][0]["text"], "In");
+    assert_eq!(first["translation"]["sahih"], "In the name");
+    assert_eq!(v["tokens"][1]["translation"]["sahih"], "of Allah,");
+    assert_eq!(v["translations"][0]["words"].as_array().unwrap().len(), 11);
+
+    let response = app.clone().oneshot(get("/api/alignments/1/1?source=sahih")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)[0]["links"][0]["tokens"], serde_json::json!([0]));
+
+    let response = app.oneshot(get("/api/interlinear/1/2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -32,6 +32,9 @@ async fn golden_search_annotation_connection() {
             gender: None,
             case_: Some("gen".into()),
             dependency_rel: Some("nmod".into()),
+            role: None,
+            derived_noun_type: None,
+            state: None,
         }],
         annotations: vec![],
     };
@@ -57,11 +60,14 @@ async fn golden_search_annotation_connection() {
         target_id: doc.id.clone(),
         layer: "note".into(),
         payload: serde_json::json!({"text":"hello"}),
+        author: None,
+        created_at: None,
+        updated_at: None,
     };
-    storage.upsert_annotation(&ann).await.unwrap();
-    let anns = storage.list_annotations(Some(&doc.id)).await.unwrap();
+    storage.upsert_annotation(&ann, None).await.unwrap();
+    let anns = storage.list_annotations(Some(&doc.id), None).await.unwrap();
     assert_eq!(anns.len(), 1);
-    storage.delete_annotation("a1").await.unwrap();
+    storage.delete_annotation("a1", None).await.unwrap();
 
     // Connection CRUD
     let conn = ConnectionRecord {
@@ -70,12 +76,42 @@ async fn golden_search_annotation_connection() {
         to_token: doc.id.clone(),
         layer: "internal".into(),
         meta: serde_json::json!({}),
+        author: None,
+        created_at: None,
+        updated_at: None,
     };
-    storage.upsert_connection(&conn).await.unwrap();
+    storage.upsert_connection(&conn, None).await.unwrap();
     let conns = storage
-        .list_connections_for_verse(1, 1)
+        .list_connections_for_verse(1, 1, None)
         .await
         .unwrap();
     assert_eq!(conns.len(), 1);
-    storage.delete_connection("c1").await.unwrap();
+    storage.delete_connection("c1", None).await.unwrap();
+}
+
+#[tokio::test]
+async fn batch_commit_and_rollback() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let token = |ayah: usize, idx: usize| SegmentView {
+        id: format!("1:{}:{}", ayah, idx),
+        verse_ref: format!("1:{}", ayah),
+        token_index: idx,
+        text: "كلمة".into(),
+        segments: vec![],
+        annotations: vec![],
+    };
+
+    let mut batch = storage.begin_batch().await.unwrap();
+    batch.upsert_many(&[token(1, 0), token(1, 1)]).await.unwrap();
+    batch.set_verse_text(1, 1, "كلمة كلمة").await.unwrap();
+    assert_eq!(batch.pending(), 2);
+    batch.commit().await.unwrap();
+
+    let mut batch = storage.begin_batch().await.unwrap();
+    batch.upsert_segment(&token(2, 0)).await.unwrap();
+    batch.rollback().await.unwrap();
+
+    assert_eq!(storage.count_verses().await.unwrap(), 1);
+    assert!(storage.get_segment("1:1:1").await.unwrap().is_some());
+    assert!(storage.get_segment("1:2:0").await.unwrap().is_none());
 }
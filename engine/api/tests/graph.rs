@@ -0,0 +1,144 @@
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::{ConnectionGraph, ConnectionRecord, GraphFormat, SqliteStorage};
+use tower::ServiceExt;
+
+fn link(id: &str, from: &str, to: &str, layer: &str) -> ConnectionRecord {
+    ConnectionRecord {
+        id: id.into(),
+        from_token: from.into(),
+        to_token: to.into(),
+        layer: layer.into(),
+        meta: serde_json::json!({}),
+        author: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+/// Two components: a chain 1:1:0 -> 1:1:1 -> 1:2:0 -> 1:3:0 plus a shortcut
+/// on another layer, and a separate pair on 2:255.
+fn edges() -> Vec<ConnectionRecord> {
+    vec![
+        link("a", "1:1:0", "1:1:1", "syntax"),
+        link("b", "1:1:1", "1:2:0", "syntax"),
+        link("c", "1:2:0", "1:3:0", "syntax"),
+        link("d", "1:3:0", "1:1:0", "rhetoric"),
+        link("e", "2:255:0", "2:255:3-3", "syntax"),
+    ]
+}
+
+#[test]
+fn traversals_follow_connections() {
+    let graph = ConnectionGraph::new(edges());
+    assert_eq!(graph.node_count(), 6);
+
+    let near = graph.neighbourhood("1:2:0", 1, false).unwrap();
+    let ids: Vec<&str> = near.nodes.iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(ids, vec!["1:1:1", "1:2:0", "1:3:0"]);
+    assert_eq!(near.edges.len(), 2);
+    let forward = graph.neighbourhood("1:2:0", 5, true).unwrap();
+    assert_eq!(forward.nodes.len(), 4);
+    assert!(graph.neighbourhood("9:9", 1, false).is_err());
+
+    // The undirected shortcut beats the chain; directed it cannot be used backwards.
+    let path = graph.shortest_path("1:1:0", "1:3:0", false).unwrap().unwrap();
+    assert_eq!(path.nodes, vec!["1:1:0", "1:3:0"]);
+    assert_eq!(path.edges[0].id, "d");
+    let path = graph.shortest_path("1:1:0", "1:3:0", true).unwrap().unwrap();
+    assert_eq!(path.nodes.len(), 4);
+    assert!(graph.shortest_path("1:1:0", "2:255:0", false).unwrap().is_none());
+
+    // Ranges written redundantly normalize to the same node.
+    let components = graph.components();
+    assert_eq!(components.len(), 2);
+    assert_eq!(components[0].len(), 4);
+    assert_eq!(components[1], vec!["2:255:0", "2:255:3"]);
+
+    let stats = graph.degree_stats();
+    assert_eq!(stats.edges, 5);
+    assert_eq!(stats.max_degree, 2);
+    assert_eq!(stats.degrees[0].in_degree + stats.degrees[0].out_degree, 2);
+}
+
+#[test]
+fn subgraphs_export_to_graph_formats() {
+    let mut subgraph = ConnectionGraph::new(edges()).to_subgraph();
+    subgraph.nodes[0].text = Some("بِسْمِ <&>".into());
+
+    let graphml = subgraph.export(GraphFormat::GraphML).unwrap();
+    assert!(graphml.contains("<graph id=\"connections\" edgedefault=\"directed\">"));
+    assert!(graphml.contains("<edge id=\"d\" source=\"1:3:0\" target=\"1:1:0\">"));
+    assert!(graphml.contains("بِسْمِ &lt;&amp;&gt;"));
+
+    let dot = subgraph.export(GraphFormat::Dot).unwrap();
+    assert!(dot.starts_with("digraph connections {"));
+    assert!(dot.contains("\"1:1:0\" -> \"1:1:1\" [label=\"syntax\", id=\"a\"];"));
+
+    let json: serde_json::Value = serde_json::from_str(&subgraph.export(GraphFormat::Json).unwrap()).unwrap();
+    assert_eq!(json["graph"]["nodes"].as_object().unwrap().len(), 6);
+    assert_eq!(json["graph"]["nodes"]["1:1:0"]["metadata"]["kind"], "token");
+    assert_eq!(json["graph"]["edges"][4]["target"], "2:255:3");
+    assert!("gexf".parse::<GraphFormat>().is_err());
+}
+
+#[tokio::test]
+async fn graph_queries_over_http() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    for edge in edges() {
+        storage.upsert_connection(&edge, None).await.unwrap();
+    }
+    storage.delete_connection("d", None).await.unwrap();
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    let json = |body: &[u8]| serde_json::from_slice::<serde_json::Value>(body).unwrap();
+
+    // Deleted connections are not part of the graph.
+    let response = app.clone().oneshot(get("/api/graph/path?from=1:1:0&to=1:3:0")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["nodes"].as_array().unwrap().len(), 4);
+
+    let response = app.clone().oneshot(get("/api/graph/path?from=1:1:0&to=2:255:0")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app.clone().oneshot(get("/api/graph/neighbours/1:1:1?depth=2&layer=syntax")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["nodes"].as_array().unwrap().len(), 4);
+
+    let response = app.clone().oneshot(get("/api/graph/neighbours/1:1:1?depth=two")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let error = &json(&body)["error"];
+    assert_eq!(error["code"], "validation_failed");
+    assert_eq!(error["details"], serde_json::json!({ "field": "depth", "value": "two" }));
+
+    let response = app.clone().oneshot(get("/api/graph/components")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["count"], 2);
+
+    let response = app.clone().oneshot(get("/api/graph/degrees?limit=1")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let stats = json(&body);
+    assert_eq!(stats["edges"], 4);
+    assert_eq!(stats["degrees"].as_array().unwrap().len(), 1);
+
+    let response = app.clone().oneshot(get("/api/graph/export?format=dot&node=2:255:0")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/vnd.graphviz");
+    assert!(response.headers()[header::CONTENT_DISPOSITION].to_str().unwrap().contains("connections.dot"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let dot = String::from_utf8(body.to_vec()).unwrap();
+    assert!(dot.contains("\"2:255:0\" -> \"2:255:3\""));
+    assert!(!dot.contains("1:1:0"));
+
+    let response = app.oneshot(get("/api/graph/export?format=gexf")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
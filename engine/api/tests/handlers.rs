@@ -0,0 +1,79 @@
+use api::{router, AppState, ServerConfig};
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{EngineError, EngineResult, LemmaDetail, LexiconStore, RootDetail, RootSummary};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::SqliteStorage;
+use tower::ServiceExt;
+
+struct FixedLexicon;
+
+#[async_trait]
+impl LexiconStore for FixedLexicon {
+    async fn list_roots(&self) -> EngineResult<Vec<RootSummary>> {
+        Ok(vec![
+            RootSummary { root: "ktb".into(), root_ar: Some("كتب".into()), frequency: 3 },
+            RootSummary { root: "Elm".into(), root_ar: Some("علم".into()), frequency: 1 },
+        ])
+    }
+
+    async fn get_root(&self, _root: &str) -> EngineResult<Option<RootDetail>> {
+        Ok(None)
+    }
+
+    async fn get_lemma(&self, _lemma: &str) -> EngineResult<Option<LemmaDetail>> {
+        Ok(None)
+    }
+
+    async fn list_unique_patterns(&self) -> EngineResult<Vec<String>> {
+        Err(EngineError::Storage("lexicon offline".into()))
+    }
+
+    async fn list_unique_pos(&self) -> EngineResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+async fn state_with(dir: &tempfile::TempDir) -> AppState {
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    AppState { lexicon: Arc::new(FixedLexicon), ..AppState::new(storage, search, config) }
+}
+
+async fn get(state: AppState, uri: &str) -> (StatusCode, String) {
+    let response = router(state)
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn handlers_use_the_injected_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let (status, body) = get(state_with(&dir).await, "/api/roots").await;
+    assert_eq!(status, StatusCode::OK);
+    let roots: Vec<RootSummary> = serde_json::from_str(&body).unwrap();
+    let arabic: Vec<&str> = roots.iter().filter_map(|r| r.root_ar.as_deref()).collect();
+    assert_eq!(arabic, vec!["كتب", "علم"]);
+
+    let (status, _) = get(state_with(&dir).await, "/api/roots/ktb").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = get(state_with(&dir).await, "/api/morph_patterns").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body.contains("lexicon offline"));
+}
+
+#[tokio::test]
+async fn other_stores_fall_back_to_sqlite() {
+    let dir = tempfile::tempdir().unwrap();
+    let (status, body) = get(state_with(&dir).await, "/annotations").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "[]");
+}
@@ -0,0 +1,146 @@
+use common::{Annotation, EngineError};
+use store::{SqliteStorage, RECORD_ANNOTATION, RECORD_HYPOTHESIS, RECORD_TAG, RECORD_TRANSLATION};
+
+#[tokio::test]
+async fn annotation_history_and_revert() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let mut ann = Annotation {
+        id: "a1".into(),
+        target_id: "2:255".into(),
+        layer: "interpretation".into(),
+        payload: serde_json::json!({"text": "first"}),
+        author: None,
+        created_at: None,
+        updated_at: None,
+    };
+    storage.upsert_annotation(&ann, Some("amina")).await.unwrap();
+    ann.payload = serde_json::json!({"text": "second"});
+    storage.upsert_annotation(&ann, Some("yusuf")).await.unwrap();
+    storage.delete_annotation("a1", None).await.unwrap();
+
+    let history = storage.list_record_history(RECORD_ANNOTATION, "a1").await.unwrap();
+    let actions: Vec<_> = history.iter().map(|c| c.action.as_str()).collect();
+    assert_eq!(actions, ["create", "update", "delete"]);
+    assert_eq!(history[1].actor.as_deref(), Some("yusuf"));
+    assert_eq!(history[1].verse_ref.as_deref(), Some("2:255"));
+
+    // Restore the first version.
+    storage
+        .revert_record(RECORD_ANNOTATION, "a1", history[0].id, None)
+        .await
+        .unwrap();
+    let restored = storage.get_annotation("a1").await.unwrap().unwrap();
+    assert_eq!(restored.payload["text"], "first");
+    assert_eq!(storage.list_verse_history("2:255").await.unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn verse_metadata_entries_are_logged_individually() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let v1 = serde_json::json!([{"id": "h1", "text": "a"}, {"id": "h2", "text": "b"}]);
+    let v2 = serde_json::json!([{"id": "h1", "text": "a"}]);
+    storage.set_verse_metadata("1:1", "hypotheses", &v1, None).await.unwrap();
+    storage.set_verse_metadata("1:1", "hypotheses", &v2, None).await.unwrap();
+
+    assert_eq!(storage.list_record_history(RECORD_HYPOTHESIS, "h1").await.unwrap().len(), 1);
+    let h2 = storage.list_record_history(RECORD_HYPOTHESIS, "h2").await.unwrap();
+    assert_eq!(h2.len(), 2);
+
+    storage
+        .revert_record(RECORD_HYPOTHESIS, "h2", h2[0].id, None)
+        .await
+        .unwrap();
+    let entries = storage.get_verse_metadata("1:1", "hypotheses").await.unwrap();
+    assert_eq!(entries.len(), 2);
+}
+
+#[tokio::test]
+async fn verse_metadata_reverts_match_numeric_ids_within_their_field() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let hypotheses = serde_json::json!([{"id": 7, "text": "hypothesis"}]);
+    let translations = serde_json::json!([{"id": 7, "text": "first"}]);
+    storage.set_verse_metadata("1:1", "hypotheses", &hypotheses, None).await.unwrap();
+    storage.set_verse_metadata("1:1", "translations", &translations, None).await.unwrap();
+    let edited = serde_json::json!([{"id": 7, "text": "second"}]);
+    storage.set_verse_metadata("1:1", "translations", &edited, None).await.unwrap();
+
+    let history = storage.list_record_history(RECORD_TRANSLATION, "7").await.unwrap();
+    assert_eq!(history.len(), 2);
+    storage
+        .revert_record(RECORD_TRANSLATION, "7", history[0].id, None)
+        .await
+        .unwrap();
+    let entries = storage.get_verse_metadata("1:1", "translations").await.unwrap();
+    assert_eq!(entries, vec![serde_json::json!({"id": 7, "text": "first"})]);
+    let entries = storage.get_verse_metadata("1:1", "hypotheses").await.unwrap();
+    assert_eq!(entries[0]["text"], "hypothesis");
+
+    let err = storage
+        .set_verse_metadata("1:1", "glosses", &serde_json::json!([]), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, EngineError::Invalid(_)));
+}
+
+#[tokio::test]
+async fn tag_versions_in_the_legacy_shape_revert() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    // Versions logged before tags had their own table: the blob entry, keyed
+    // by name, with no `name` field.
+    for after in [r#"{"description": "Verses on mercy", "colour": "green"}"#, r#""mercy""#] {
+        sqlx::query("INSERT INTO change_log (record_type, record_id, action, after_value) VALUES (?1, 'mercy', 'update', ?2)")
+            .bind(RECORD_TAG)
+            .bind(after)
+            .execute(storage.pool())
+            .await
+            .unwrap();
+    }
+    let history = storage.list_record_history(RECORD_TAG, "mercy").await.unwrap();
+
+    storage.revert_record(RECORD_TAG, "mercy", history[0].id, None).await.unwrap();
+    let tag = storage.get_tag("mercy").await.unwrap().unwrap();
+    assert_eq!(tag.description.as_deref(), Some("Verses on mercy"));
+    assert_eq!(tag.meta["colour"], "green");
+
+    let err = storage.revert_record(RECORD_TAG, "mercy", history[1].id, None).await.unwrap_err();
+    assert!(matches!(err, EngineError::Invalid(_)));
+}
+
+#[tokio::test]
+async fn trash_restore_and_replace() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let ann = Annotation {
+        id: "a1".into(),
+        target_id: "1:1".into(),
+        layer: "interpretation".into(),
+        payload: serde_json::json!({"text": "old"}),
+        author: None,
+        created_at: None,
+        updated_at: None,
+    };
+    storage.upsert_annotation(&ann, None).await.unwrap();
+
+    storage.delete_annotation("a1", None).await.unwrap();
+    assert!(storage.list_annotations(Some("1:1"), None).await.unwrap().is_empty());
+    let trash = storage.list_trash().await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].verse_ref.as_deref(), Some("1:1"));
+
+    storage.restore_from_trash(RECORD_ANNOTATION, "a1", None).await.unwrap();
+    assert_eq!(storage.list_annotations(Some("1:1"), None).await.unwrap().len(), 1);
+
+    let replacement = Annotation {
+        id: "a2".into(),
+        payload: serde_json::json!({"text": "new"}),
+        ..ann.clone()
+    };
+    storage.replace_annotation("a1", &replacement, None).await.unwrap();
+    let live = storage.list_annotations(Some("1:1"), None).await.unwrap();
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].id, "a2");
+
+    // Nothing is old enough to purge yet; a zero-day retention empties the trash.
+    assert_eq!(storage.purge_trash(30).await.unwrap(), 0);
+    assert_eq!(storage.purge_trash(0).await.unwrap(), 1);
+    assert!(storage.list_trash().await.unwrap().is_empty());
+}
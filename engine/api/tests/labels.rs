@@ -0,0 +1,129 @@
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{Lang, SearchBackend, Segment, SegmentView, TagLabels};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::SqliteStorage;
+use tower::ServiceExt;
+
+#[test]
+fn codes_have_arabic_and_english_names() {
+    let labels = TagLabels::bundled();
+    assert_eq!(labels.pos("PN").unwrap().get(Lang::En), "Proper noun");
+    assert_eq!(labels.pos("N").unwrap().get(Lang::Ar), "اسم");
+    assert_eq!(labels.relation("Pred").unwrap().get(Lang::Ar), "خبر");
+    assert_eq!(labels.relation("gen").unwrap().get(Lang::En), "Genitive");
+    assert_eq!(labels.relation("subj<<kan>>").unwrap().en, "Subject of kan");
+    assert_eq!(labels.constituent("NS").unwrap().ar, "جملة اسمية");
+    assert_eq!(labels.feature("case", "GEN").unwrap().ar, "مجرور");
+    assert_eq!(labels.feature("verb_form", "(IV)").unwrap().en, "Form IV");
+    assert!(labels.relation("nsubj").is_none());
+    assert!("fr".parse::<Lang>().is_err());
+
+    let segment = Segment {
+        id: "1:1:0:1".into(),
+        r#type: "STEM".into(),
+        form: "سْمِ".into(),
+        pos: Some("N".into()),
+        case_: Some("GEN".into()),
+        gender: Some("M".into()),
+        dependency_rel: Some("gen".into()),
+        state: Some("XYZ".into()),
+        ..Default::default()
+    };
+    let names = labels.segment(&segment, Lang::Ar);
+    assert_eq!(names["type"], "جذع");
+    assert_eq!(names["pos"], "اسم");
+    assert_eq!(names["case"], "مجرور");
+    assert_eq!(names["dependency_rel"], "مجرور");
+    // Unknown codes are passed through.
+    assert_eq!(names["state"], "XYZ");
+    assert!(!names.contains_key("mood"));
+}
+
+#[tokio::test]
+async fn endpoints_name_codes_in_the_requested_language() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    let doc = SegmentView {
+        id: "1:1:0".into(),
+        verse_ref: "1:1".into(),
+        token_index: 0,
+        text: "بِسْمِ".into(),
+        segments: vec![Segment {
+            id: "1:1:0:0".into(),
+            r#type: "STEM".into(),
+            form: "بِسْمِ".into(),
+            root: Some("smw".into()),
+            pos: Some("N".into()),
+            case_: Some("GEN".into()),
+            dependency_rel: Some("gen".into()),
+            ..Default::default()
+        }],
+        annotations: vec![],
+    };
+    let mut batch = storage.begin_batch().await.unwrap();
+    batch.upsert_many(std::slice::from_ref(&doc)).await.unwrap();
+    batch.set_verse_text(1, 1, "بِسْمِ").await.unwrap();
+    batch.commit().await.unwrap();
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    search.index_document(&doc).await.unwrap();
+    search.commit().unwrap();
+    let mut config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    config.lang = Lang::Ar;
+    let app = router(AppState::new(storage, search, config));
+
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    let json = |body: &[u8]| serde_json::from_slice::<serde_json::Value>(body).unwrap();
+
+    // The server default applies without ?lang=.
+    let response = app.clone().oneshot(get("/api/morphology/1/1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let v = json(&body);
+    assert_eq!(v["morphology"][0]["pos"], "N");
+    assert_eq!(v["morphology"][0]["labels"]["pos"], "اسم");
+    assert_eq!(v["morphology"][0]["labels"]["case"], "مجرور");
+
+    let response = app.clone().oneshot(get("/api/morphology/parsed/1/1?lang=en")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["tokens"][0]["segments"][0]["labels"]["case"], "Genitive");
+
+    let response = app.clone().oneshot(get("/api/dependency/1/1?lang=en")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let first = &json(&body)["dependency_tree"][0];
+    assert_eq!(first["relation_label"], "Genitive");
+    assert_eq!(first["pos_label"], "Noun");
+
+    let response = app.clone().oneshot(get("/api/syntax_patterns")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)[0]["label"], "اسم");
+
+    let response = app.clone().oneshot(get("/api/labels?lang=ar")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let v = json(&body);
+    assert_eq!(v["relations"]["Pred"], "خبر");
+    assert_eq!(v["features"]["mood"]["JUS"], "مجزوم");
+
+    let response = app.clone().oneshot(get("/api/labels")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["pos"]["PN"]["en"], "Proper noun");
+
+    // Verse, segment and search responses name their segments' codes too.
+    let response = app.clone().oneshot(get("/v1/verses/1/1?lang=en")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["tokens"][0]["segments"][0]["labels"]["pos"], "Noun");
+
+    let response = app.clone().oneshot(get("/v1/segments/1:1:0")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["segments"][0]["labels"]["dependency_rel"], "مجرور");
+
+    let response = app.clone().oneshot(get("/v1/search?root=smw&lang=en")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(json(&body)["items"][0]["segments"][0]["labels"]["case"], "Genitive");
+
+    let response = app.oneshot(get("/api/morphology/1/1?lang=fr")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
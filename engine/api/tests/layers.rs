@@ -0,0 +1,120 @@
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{Annotation, AnnotationLayer, SegmentView};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::SqliteStorage;
+use tower::ServiceExt;
+
+fn annotation(target: &str, layer: &str, payload: serde_json::Value) -> Annotation {
+    Annotation {
+        id: format!("{}-{}", layer, target),
+        target_id: target.into(),
+        layer: layer.into(),
+        payload,
+        author: None,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[tokio::test]
+async fn payloads_are_checked_against_their_layer() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let names: Vec<String> = storage.list_layers().await.unwrap().into_iter().map(|l| l.name).collect();
+    assert_eq!(names, vec!["interpretation", "rhetoric", "tajweed"]);
+
+    let ok = annotation("1:1", "interpretation", serde_json::json!({ "text": "In the name" }));
+    storage.upsert_annotation(&ok, None).await.unwrap();
+
+    let err = storage
+        .upsert_annotation(&annotation("1:1", "interpretation", serde_json::json!({ "body": 1 })), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("text"), "{}", err);
+
+    // Tajweed annotates words, not verses.
+    let wrong_target = annotation("1:1", "tajweed", serde_json::json!({ "rule": "idgham" }));
+    assert!(storage.upsert_annotation(&wrong_target, None).await.is_err());
+    let word = annotation("1:1:2", "tajweed", serde_json::json!({ "rule": "idgham" }));
+    storage.upsert_annotation(&word, None).await.unwrap();
+
+    // Replacing validates the new version too.
+    let bad_edit = annotation("1:1", "interpretation", serde_json::json!({ "text": "" }));
+    assert!(storage.replace_annotation(&ok.id, &bad_edit, None).await.is_err());
+    assert_eq!(storage.get_annotation(&ok.id).await.unwrap().unwrap().payload["text"], "In the name");
+
+    // Unregistered layers stay free-form.
+    storage
+        .upsert_annotation(&annotation("anything", "scratch", serde_json::json!([1, 2])), None)
+        .await
+        .unwrap();
+
+    // Layers in use cannot be removed; unused ones can.
+    assert!(storage.delete_layer("tajweed").await.is_err());
+    storage.delete_layer("rhetoric").await.unwrap();
+    assert!(storage.get_layer("rhetoric").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn layers_are_managed_over_http() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    // Annotation targets must exist in the corpus.
+    storage
+        .upsert_segment(&SegmentView {
+            id: "2:4:1".into(),
+            verse_ref: "2:4".into(),
+            token_index: 1,
+            text: "يُؤْمِنُونَ".into(),
+            segments: vec![],
+            annotations: vec![],
+        })
+        .await
+        .unwrap();
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let send = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let bad_schema = serde_json::json!({ "name": "qiraat", "target": "token", "schema": { "type": 5 } });
+    let response = app.clone().oneshot(send("POST", "/api/layers", bad_schema)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let layer = serde_json::json!({
+        "name": "qiraat",
+        "description": "Variant readings of a word",
+        "target": "token",
+        "schema": { "type": "object", "required": ["reader"], "properties": { "reader": { "type": "string" } } }
+    });
+    let response = app.clone().oneshot(send("POST", "/api/layers", layer)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/api/layers").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let layers: Vec<AnnotationLayer> = serde_json::from_slice(&body).unwrap();
+    let qiraat = layers.iter().find(|l| l.name == "qiraat").unwrap();
+    assert_eq!(qiraat.target, "token");
+    assert_eq!(qiraat.schema["required"][0], "reader");
+
+    let invalid = serde_json::json!({ "target_id": "2:4:1", "layer": "qiraat", "payload": { "reader": 7 } });
+    let response = app.clone().oneshot(send("POST", "/annotations", invalid)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let valid = serde_json::json!({ "target_id": "2:4:1", "layer": "qiraat", "payload": { "reader": "Warsh" } });
+    let response = app.oneshot(send("POST", "/annotations", valid)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
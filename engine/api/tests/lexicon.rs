@@ -0,0 +1,108 @@
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{RootDetail, Segment, SegmentView};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::{Lexicon, SqliteStorage};
+use tower::ServiceExt;
+
+fn segment(id: &str, root: &str, lemma: &str, pos: &str) -> Segment {
+    Segment {
+        id: id.into(),
+        r#type: "stem".into(),
+        form: id.into(),
+        root: Some(root.into()),
+        lemma: Some(lemma.into()),
+        pos: Some(pos.into()),
+        ..Default::default()
+    }
+}
+
+/// Segments spelling the same root and lemmas three different ways, plus a
+/// root the lexicon does not list.
+async fn seeded() -> SqliteStorage {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    storage.load_lexicon(&Lexicon::bundled().unwrap()).await.unwrap();
+    let segments = vec![
+        segment("2:2:1:0", "ك ت ب", "كِتَٰب", "N"),
+        segment("2:79:4:0", "كتب", "كَتَبَ", "V"),
+        segment("2:79:7:0", "ktb", "kataba", "V"),
+        segment("2:79:9:0", "zzz", "zzz", "N"),
+    ];
+    for (i, seg) in segments.into_iter().enumerate() {
+        storage
+            .upsert_segment(&SegmentView {
+                id: format!("2:{}:0", i + 1),
+                verse_ref: format!("2:{}", i + 1),
+                token_index: 0,
+                text: seg.form.clone(),
+                segments: vec![seg],
+                annotations: vec![],
+            })
+            .await
+            .unwrap();
+    }
+    storage
+}
+
+#[tokio::test]
+async fn bundled_tables_load_and_link_to_segments() {
+    let lexicon = Lexicon::bundled().unwrap();
+    assert_eq!(lexicon.roots.len(), 1643);
+    assert!(lexicon.pos.iter().any(|p| p.tag == "PN" && p.name_en == "Proper noun"));
+
+    let storage = seeded().await;
+    let roots = storage.list_roots().await.unwrap();
+    let ktb = roots.iter().find(|r| r.root == "ktb").unwrap();
+    assert_eq!(ktb.root_ar.as_deref(), Some("كتب"));
+    assert_eq!(ktb.frequency, 3);
+    assert!(roots.iter().any(|r| r.root == "zzz" && r.root_ar.is_none() && r.frequency == 1));
+    // Lexicon roots the corpus never uses are not listed.
+    assert!(roots.iter().all(|r| r.root != "Abd"));
+
+    for spelling in ["ktb", "كتب", "ك ت ب"] {
+        let detail = storage.get_root(spelling).await.unwrap().unwrap();
+        assert_eq!(detail.summary.root, "ktb");
+        assert_eq!(detail.lexicon_frequency, Some(319));
+        let lemmas: Vec<(&str, i64)> = detail.lemmas.iter().map(|l| (l.lemma.as_str(), l.frequency)).collect();
+        assert_eq!(lemmas, vec![("kataba", 2), ("kita`b", 1)]);
+    }
+    assert!(storage.get_root("qqq").await.unwrap().is_none());
+
+    let kataba = storage.get_lemma("كَتَبَ").await.unwrap().unwrap();
+    assert_eq!(kataba.summary.lemma, "kataba");
+    assert_eq!(kataba.summary.frequency, 2);
+    assert_eq!(kataba.roots[0].root, "ktb");
+    assert_eq!(kataba.pos[0].pos, "V");
+    assert_eq!(kataba.pos[0].name_ar.as_deref(), Some("فعل"));
+}
+
+#[tokio::test]
+async fn lexicon_endpoints_serve_root_and_lemma_detail() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(seeded().await);
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(get("/api/roots/%D9%83%D8%AA%D8%A8")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let root: RootDetail = serde_json::from_slice(&body).unwrap();
+    assert_eq!(root.summary.frequency, 3);
+    let raw: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(raw["root"], "ktb");
+    assert_eq!(raw["lemmas"][0]["lemma_ar"], "كَتَبَ");
+
+    let response = app.clone().oneshot(get("/api/lemmas/kita%60b")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let lemma: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(lemma["lemma_ar"], "كِتَٰب");
+    assert_eq!(lemma["pos"][0]["name_en"], "Noun");
+
+    let response = app.oneshot(get("/api/lemmas/nothing")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,40 @@
+use store::{QuranMetadata, SqliteStorage};
+
+#[tokio::test]
+async fn bundled_metadata_loads_surahs_and_divisions() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    assert!(storage.ensure_metadata().await.unwrap());
+    assert!(!storage.ensure_metadata().await.unwrap());
+
+    let surahs = storage.list_surahs().await.unwrap();
+    assert_eq!(surahs.len(), 114);
+    assert_eq!(surahs.iter().map(|s| s.ayah_count).sum::<i64>(), 6236);
+    let baqarah = &surahs[1];
+    assert_eq!(baqarah.name_translit.as_deref(), Some("Al-Baqara"));
+    assert_eq!(baqarah.revelation_place.as_deref(), Some("medinan"));
+
+    let juz = storage.list_divisions("juz").await.unwrap();
+    assert_eq!(juz.len(), 30);
+    assert_eq!((juz[0].start_surah, juz[0].start_ayah), (1, 1));
+    assert_eq!((juz[0].end_surah, juz[0].end_ayah), (2, 141));
+    assert_eq!((juz[29].end_surah, juz[29].end_ayah), (114, 6));
+
+    let located = storage.locate_verse(2, 255).await.unwrap();
+    let juz_of = located.iter().find(|d| d.kind == "juz").unwrap();
+    assert_eq!(juz_of.number, 3);
+    assert!(storage.list_divisions("chapter").await.is_err());
+    // No ruku or page boundaries ship yet, so those kinds are not offered.
+    assert!(storage.list_divisions("page").await.is_err());
+    let kinds: Vec<String> = storage.count_divisions().await.unwrap().into_iter().map(|(k, _)| k).collect();
+    assert_eq!(kinds, ["juz", "hizb", "rub", "manzil"]);
+}
+
+#[test]
+fn metadata_rejects_out_of_range_boundaries() {
+    let json = r#"{
+        "surahs": [{"number": 1, "name_ar": "الفاتحة", "name_translit": "Al-Fatihah",
+                    "ayah_count": 7, "revelation_place": "meccan", "revelation_order": 5}],
+        "divisions": {"juz": [[1, 1], [1, 9]]}
+    }"#;
+    assert!(QuranMetadata::from_json(json).is_err());
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use ::common::Note;
+use common::seed_verse;
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::{note_title, parse_note_links, SqliteStorage, RECORD_NOTE};
+use tower::ServiceExt;
+
+const THRONE: &str = "# The Throne verse
+
+Kursi as knowledge: [[2:255]] and again [[2:255|the Throne verse]].
+Compare [[2:255:3-5]] with [[al-Baqarah:256]], but not [[Background]], [[2:0]] or [[2:257]].";
+
+/// The corpus the notes cite: 2:255 and 2:256, one token each.
+async fn seed(storage: &SqliteStorage) {
+    seed_verse(storage, 2, 255, "ٱللَّهُ").await;
+    seed_verse(storage, 2, 256, "ٱللَّهُ").await;
+}
+
+#[test]
+fn references_in_note_bodies_become_links() {
+    let links = parse_note_links(THRONE);
+    let targets: Vec<&str> = links.iter().map(|l| l.target.as_str()).collect();
+    assert_eq!(targets, vec!["2:255", "2:255:3-5", "2:256", "2:257"]);
+    assert_eq!(links[0].context, "Kursi as knowledge: [[2:255]] and again [[2:255|the Throne verse]].");
+    assert!(parse_note_links("[[2:255").is_empty());
+    let named = parse_note_links("[[al-Baqarah:255]], [[البقرة ٢٥٥]] and [[al-Fatihah]]");
+    let targets: Vec<&str> = named.iter().map(|l| l.target.as_str()).collect();
+    assert_eq!(targets, vec!["2:255", "1"]);
+
+    assert_eq!(note_title(THRONE), "The Throne verse");
+    assert_eq!(note_title("\n  first line\nsecond"), "first line");
+}
+
+#[tokio::test]
+async fn notes_are_stored_with_backlinks_and_history() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    seed(&storage).await;
+    let note = Note { id: "throne".into(), body: THRONE.into(), ..Default::default() };
+    storage.upsert_note(&note, Some("amina")).await.unwrap();
+    // 2:257 parses but the corpus does not have it.
+    assert_eq!(storage.note_links("throne").await.unwrap().len(), 3);
+    assert_eq!(storage.unresolved_note_links(THRONE).await.unwrap(), vec!["Background", "2:0", "2:257"]);
+
+    let stored = storage.get_note("throne").await.unwrap().unwrap();
+    assert_eq!(stored.title, "The Throne verse");
+    assert_eq!(stored.author.as_deref(), Some("amina"));
+
+    let backlinks = storage.note_backlinks(&"2:255".parse().unwrap()).await.unwrap();
+    let targets: Vec<&str> = backlinks.iter().map(|b| b.target.as_str()).collect();
+    assert_eq!(targets, vec!["2:255", "2:255:3-5"]);
+    assert!(storage.note_backlinks(&"2:255:1".parse().unwrap()).await.unwrap().len() == 1);
+
+    // Editing the body re-indexes its links.
+    let edited = Note { body: "Only [[2:256]] now".into(), ..stored.clone() };
+    storage.upsert_note(&edited, Some("bilal")).await.unwrap();
+    assert!(storage.note_backlinks(&"2:255".parse().unwrap()).await.unwrap().is_empty());
+    assert_eq!(storage.get_note("throne").await.unwrap().unwrap().author.as_deref(), Some("amina"));
+
+    storage.delete_note("throne", None).await.unwrap();
+    assert!(storage.note_backlinks(&"2:256".parse().unwrap()).await.unwrap().is_empty());
+    let history = storage.list_record_history(RECORD_NOTE, "throne").await.unwrap();
+    let first = history.iter().find(|c| c.action == "create").unwrap();
+    storage.revert_record(RECORD_NOTE, "throne", first.id, None).await.unwrap();
+    assert_eq!(storage.note_backlinks(&"2:255".parse().unwrap()).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn note_endpoints_no_longer_touch_the_filesystem() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    seed(&storage).await;
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let send = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("x-kalima-user", "amina")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    async fn json(response: axum::response::Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    let created = json(app.clone().oneshot(send("POST", "/api/notes", serde_json::json!({ "body": THRONE }))).await.unwrap()).await;
+    assert_eq!(created["title"], "The Throne verse");
+    assert_eq!(created["links"].as_array().unwrap().len(), 3);
+    assert_eq!(created["unresolved"], serde_json::json!(["Background", "2:0", "2:257"]));
+    let id = created["id"].as_str().unwrap().to_string();
+
+    let body = serde_json::json!({ "title": "Sayd", "body": "Sayd, 'hunting', refers to hunting for knowledge [[5:94]]." });
+    let response = app.clone().oneshot(send("PUT", "/api/notes/sayd", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let listed = json(app.clone().oneshot(get("/api/notes?author=amina")).await.unwrap()).await;
+    assert_eq!(listed["notes"].as_array().unwrap().len(), 2);
+    assert!(listed["notes"][0].get("body").is_none());
+
+    let found = json(app.clone().oneshot(get("/api/library_search?q=HUNTING")).await.unwrap()).await;
+    assert_eq!(found.as_array().unwrap().len(), 1);
+    assert_eq!(found[0]["id"], "sayd");
+    assert!(found[0]["snippet"].as_str().unwrap().contains("hunting"));
+
+    let cited = json(app.clone().oneshot(get("/api/verse/2/255/notes")).await.unwrap()).await;
+    assert_eq!(cited.as_array().unwrap().len(), 2);
+    assert_eq!(cited[0]["note_id"], id.as_str());
+    assert_eq!(cited[0]["title"], "The Throne verse");
+
+    // The old endpoint read whatever path it was given.
+    let response = app.clone().oneshot(get("/api/notes/content?path=/etc/passwd")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app.clone().oneshot(send("DELETE", &format!("/api/notes/{}", id), serde_json::json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.oneshot(get(&format!("/api/notes/{}", id))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
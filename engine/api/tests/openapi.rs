@@ -0,0 +1,103 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::app;
+use std::collections::BTreeSet;
+use tower::ServiceExt;
+
+fn function_body<'a>(source: &'a str, signature: &str) -> &'a str {
+    let body = &source[source.find(signature).unwrap()..];
+    &body[..body.find("\n}\n").unwrap()]
+}
+
+/// `(METHOD, /path/{param})` for every operation in the spec.
+fn spec_operations(spec: &serde_json::Value) -> BTreeSet<(String, String)> {
+    let mut ops = BTreeSet::new();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for method in ["get", "post", "put", "delete", "patch"] {
+            if item.get(method).is_some() {
+                ops.insert((method.to_uppercase(), path.clone()));
+            }
+        }
+    }
+    ops
+}
+
+#[tokio::test]
+async fn spec_operations_are_routed() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir).await;
+    let response = app.clone().oneshot(Request::get("/openapi.json").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let spec: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+
+    // No route takes PATCH, so a routed path answers 405 with the methods it
+    // does take, where an unrouted one would be a 404.
+    let placeholder = regex::Regex::new(r"\{[a-z_]+\}").unwrap();
+    let documented = spec_operations(&spec);
+    assert!(documented.len() > 90, "the spec lists only {} operations", documented.len());
+    for (method, path) in &documented {
+        let uri = placeholder.replace_all(path, "1").to_string();
+        let response = app.clone().oneshot(Request::patch(&uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED, "{} is not routed", path);
+        let allow = response.headers()["allow"].to_str().unwrap();
+        assert!(allow.split(',').any(|m| m == method), "{} {} is not routed (allows {})", method, path, allow);
+    }
+}
+
+#[test]
+fn legacy_routes_name_a_v1_successor() {
+    let source = include_str!("../src/legacy.rs");
+    let legacy = regex::Regex::new(r#"\.route\("([^"]+)", "([^"?]+)[^"]*","#).unwrap();
+    let param = regex::Regex::new(r":([a-z_]+)").unwrap();
+    let spec = serde_json::to_value(api::spec()).unwrap();
+    let v1: BTreeSet<String> = spec_operations(&spec).into_iter().map(|(_, path)| path).collect();
+
+    let mut count = 0;
+    for caps in legacy.captures_iter(function_body(source, "pub(crate) fn routes(")) {
+        let successor = caps[2].replace(":verse_ref", ":surah/:ayah");
+        let successor = param.replace_all(&successor, "{$1}").to_string();
+        let known = v1.iter().any(|path| param_shape(path) == param_shape(&successor));
+        assert!(known, "{} names {} as its successor, which is not routed", &caps[1], &caps[2]);
+        count += 1;
+    }
+    assert!(count > 75, "parsed only {} legacy routes", count);
+}
+
+/// A path with its parameter names blanked out.
+fn param_shape(path: &str) -> String {
+    regex::Regex::new(r"\{[a-z_]+\}").unwrap().replace_all(path, "{}").to_string()
+}
+
+#[test]
+fn operations_declare_their_path_parameters_and_errors() {
+    let spec = serde_json::to_value(api::spec()).unwrap();
+    let placeholder = regex::Regex::new(r"\{([a-z_]+)\}").unwrap();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        let in_path: BTreeSet<String> = placeholder.captures_iter(path).map(|c| c[1].to_string()).collect();
+        for (method, op) in item.as_object().unwrap() {
+            let declared: BTreeSet<String> = op["parameters"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|p| p["in"] == "path")
+                .map(|p| p["name"].as_str().unwrap().to_string())
+                .collect();
+            assert_eq!(declared, in_path, "{} {}", method, path);
+            assert!(op["responses"]["default"].is_object(), "{} {} has no error response", method, path);
+        }
+    }
+    assert!(spec["components"]["schemas"]["ErrorEnvelope"].is_object());
+    assert!(spec["components"]["schemas"]["Verse"].is_object());
+}
+
+#[tokio::test]
+async fn docs_page_is_served() {
+    let dir = tempfile::tempdir().unwrap();
+    let response = app(&dir).await.oneshot(Request::get("/docs/").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+}
@@ -0,0 +1,96 @@
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{ayah_count, parse_verse_ref, surah_by_name, Anchor, VerseRef};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::SqliteStorage;
+use tower::ServiceExt;
+
+fn verses(s: &str) -> Vec<(i64, i64)> {
+    s.parse::<VerseRef>().unwrap().verses().collect()
+}
+
+#[test]
+fn references_parse_every_written_form() {
+    assert_eq!(verses("2:255"), vec![(2, 255)]);
+    assert_eq!(verses("2:1-3"), vec![(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(verses("2:285-3:2"), vec![(2, 285), (2, 286), (3, 1), (3, 2)]);
+    assert_eq!(verses("2:255, 257; 3:1-2"), vec![(2, 255), (2, 257), (3, 1), (3, 2)]);
+    assert_eq!(verses("1").len(), 7);
+    assert_eq!(verses("113-114").len(), 11);
+    assert_eq!(verses("al-Baqarah:255"), vec![(2, 255)]);
+    assert_eq!(verses("Surah Al-Fatihah 1-2"), vec![(1, 1), (1, 2)]);
+    assert_eq!(verses("آل عمران ٧"), vec![(3, 7)]);
+    assert_eq!(verses("البقرة ٢٥٥-٢٥٦"), vec![(2, 255), (2, 256)]);
+    assert_eq!(verses("سورة الإخلاص ۱"), vec![(112, 1)]);
+
+    for bad in ["0:1", "115", "2:0", "2:287", "1:7-2", "2:257-255", "3:1-2:5", "al-Nowhere:1", "2:255,", "2:x"] {
+        assert!(bad.parse::<VerseRef>().is_err(), "{} should not parse", bad);
+    }
+}
+
+#[test]
+fn surah_names_ignore_spelling_differences() {
+    for name in ["Al-Faatiha", "al-Fatihah", "Fatiha", "الفاتحة", "الْفَاتِحَة", "فاتحة"] {
+        assert_eq!(surah_by_name(name), Some(1), "{}", name);
+    }
+    for (name, number) in [("Yaseen", 36), ("Ya-Sin", 36), ("Al Imran", 3), ("Ta-Ha", 20), ("An-Nas", 114), ("Mu'minun", 23)] {
+        assert_eq!(surah_by_name(name), Some(number), "{}", name);
+    }
+    assert_eq!(ayah_count(2), Some(286));
+    assert_eq!((1..=114).map(|s| ayah_count(s).unwrap()).sum::<i64>(), 6236);
+    assert_eq!(ayah_count(115), None);
+}
+
+#[test]
+fn references_print_canonically_and_become_anchors() {
+    for (input, printed) in [
+        ("al-Baqarah 255", "2:255"),
+        ("2:1-5", "2:1-5"),
+        ("2:286-3:5", "2:286-3:5"),
+        ("البقرة", "2"),
+        ("1-2", "1:1-2:286"),
+        ("2:255,256", "2:255, 2:256"),
+    ] {
+        assert_eq!(input.parse::<VerseRef>().unwrap().to_string(), printed);
+    }
+    let reference: VerseRef = "al-Kahf, 2:255-257".parse().unwrap();
+    let anchors: Vec<Anchor> = reference.ranges().iter().map(|r| r.anchor()).collect();
+    assert_eq!(anchors, vec![Anchor::Surah(18), "2:255-257".parse().unwrap()]);
+
+    assert_eq!(parse_verse_ref("البقرة ٢٥٥").unwrap(), (2, 255));
+    assert!(parse_verse_ref("2:255-256").is_err());
+    assert!(parse_verse_ref("2").is_err());
+}
+
+#[tokio::test]
+async fn endpoints_accept_named_references() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let hypothesis = serde_json::json!({ "text": "kursi as knowledge" });
+    let request = Request::post("/api/hypotheses/al-Baqarah:255")
+        .header("content-type", "application/json")
+        .body(Body::from(hypothesis.to_string()))
+        .unwrap();
+    assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::OK);
+
+    // Stored under the canonical key, so every spelling finds it.
+    for uri in ["/api/hypotheses/2:255", "/api/hypotheses/%D8%A7%D9%84%D8%A8%D9%82%D8%B1%D8%A9%20%D9%A2%D9%A5%D9%A5"] {
+        let response = app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let entries: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(entries.as_array().unwrap().len(), 1, "{}", uri);
+    }
+
+    for uri in ["/api/hypotheses/2:287", "/connections?verse=al-Nowhere:1", "/api/pronouns/2:1-5"] {
+        let response = app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+    }
+    let response = app.oneshot(Request::get("/connections?verse=Fatiha:1").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use ::common::{SearchBackend, SegmentView, Tag, TagAssignment};
+use common::{seed_verse, verse_docs};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::{ConflictStrategy, SqliteStorage, RECORD_TAG, RECORD_TAG_ASSIGNMENT};
+use tower::ServiceExt;
+
+const VERSES: [(i64, &str); 3] = [
+    (255, "ٱللَّهُ لَآ إِلَٰهَ إِلَّا هُوَ"),
+    (256, "لَآ إِكْرَاهَ فِى ٱلدِّينِ"),
+    (257, "ٱللَّهُ وَلِىُّ ٱلَّذِينَ ءَامَنُوا۟"),
+];
+
+fn docs() -> Vec<SegmentView> {
+    VERSES.iter().flat_map(|(ayah, text)| verse_docs(2, *ayah, text)).collect()
+}
+
+async fn seed(storage: &SqliteStorage) {
+    for (ayah, text) in VERSES {
+        seed_verse(storage, 2, ayah, text).await;
+    }
+}
+
+fn tag(name: &str, parent: Option<&str>) -> Tag {
+    Tag { name: name.into(), parent: parent.map(str::to_string), ..Default::default() }
+}
+
+fn assignment(id: &str, tag: &str, target: &str) -> TagAssignment {
+    TagAssignment { id: id.into(), tag: tag.into(), target: target.into(), ..Default::default() }
+}
+
+#[tokio::test]
+async fn tags_form_a_hierarchy_that_counts_towards_ancestors() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    seed(&storage).await;
+
+    storage.upsert_tag(&tag("theology", None), Some("amina")).await.unwrap();
+    storage.upsert_tag(&tag("tawhid", Some("theology")), Some("amina")).await.unwrap();
+    storage.upsert_tag(&tag("freedom", None), None).await.unwrap();
+    assert!(storage.upsert_tag(&tag("x", Some("missing")), None).await.is_err());
+    let cycle = storage.upsert_tag(&tag("theology", Some("tawhid")), None).await.unwrap_err();
+    assert!(cycle.to_string().contains("descendant"), "{}", cycle);
+
+    storage.assign_tag(&assignment("t1", "tawhid", "2:255:1-3"), Some("amina")).await.unwrap();
+    storage.assign_tag(&assignment("t2", "theology", "2:257"), None).await.unwrap();
+    storage.assign_tag(&assignment("t3", "freedom", "2:256:1"), None).await.unwrap();
+    assert!(storage.assign_tag(&assignment("t4", "nope", "2:255"), None).await.is_err());
+    assert!(storage.assign_tag(&assignment("t4", "freedom", "note-1"), None).await.is_err());
+
+    assert_eq!(storage.tagged_verses("tawhid", true).await.unwrap(), vec![(2, 255)]);
+    assert_eq!(storage.tagged_verses("theology", true).await.unwrap(), vec![(2, 255), (2, 257)]);
+    assert_eq!(storage.tagged_verses("theology", false).await.unwrap(), vec![(2, 257)]);
+
+    let overlapping = storage.list_tag_assignments(None, false, Some(&"2:255:2".parse().unwrap()), None).await.unwrap();
+    assert_eq!(overlapping.len(), 1);
+    assert_eq!(overlapping[0].author.as_deref(), Some("amina"));
+
+    let counts = storage.tag_counts().await.unwrap();
+    let theology = counts.iter().find(|c| c.tag == "theology").unwrap();
+    assert_eq!((theology.assignments, theology.total, theology.verses), (1, 2, 2));
+
+    // A parent cannot go while it has children; deleting a tag drops its assignments.
+    assert!(storage.delete_tag("theology", None).await.is_err());
+    storage.delete_tag("tawhid", Some("amina")).await.unwrap();
+    assert!(storage.get_tag_assignment("t1").await.unwrap().is_none());
+
+    // Both the tag and its assignment can be brought back from the change log.
+    let history = storage.list_record_history(RECORD_TAG_ASSIGNMENT, "t1").await.unwrap();
+    let created = history.iter().find(|c| c.action == "create").unwrap();
+    assert_eq!(created.verse_ref.as_deref(), Some("2:255"));
+    let history = storage.list_record_history(RECORD_TAG, "tawhid").await.unwrap();
+    let created = history.iter().find(|c| c.action == "create").unwrap();
+    storage.revert_record(RECORD_TAG, "tawhid", created.id, None).await.unwrap();
+    let history = storage.list_record_history(RECORD_TAG_ASSIGNMENT, "t1").await.unwrap();
+    let created = history.iter().find(|c| c.action == "create").unwrap();
+    storage.revert_record(RECORD_TAG_ASSIGNMENT, "t1", created.id, None).await.unwrap();
+    assert_eq!(storage.tagged_verses("theology", true).await.unwrap(), vec![(2, 255), (2, 257)]);
+}
+
+#[tokio::test]
+async fn legacy_tag_blobs_move_into_the_tag_tables() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("kalima.db");
+    {
+        let storage = SqliteStorage::connect(db.to_str().unwrap()).await.unwrap();
+        let blob = serde_json::json!({ "tags": {
+            "mercy": { "color": "green", "author": "amina" },
+            "rahma": { "parent": "mercy", "description": "root r-h-m" },
+            "orphan": { "parent": "gone" }
+        }});
+        storage.set_research_data("tags", &blob, None).await.unwrap();
+    }
+    let storage = SqliteStorage::connect(db.to_str().unwrap()).await.unwrap();
+    assert!(storage.get_research_data("tags").await.unwrap().is_none());
+
+    let tags = storage.list_tags().await.unwrap();
+    assert_eq!(tags.len(), 3);
+    let mercy = storage.get_tag("mercy").await.unwrap().unwrap();
+    assert_eq!(mercy.meta, serde_json::json!({ "color": "green" }));
+    assert_eq!(mercy.author.as_deref(), Some("amina"));
+    let rahma = storage.get_tag("rahma").await.unwrap().unwrap();
+    assert_eq!((rahma.parent.as_deref(), rahma.description.as_deref()), (Some("mercy"), Some("root r-h-m")));
+    assert_eq!(storage.get_tag("orphan").await.unwrap().unwrap().parent, None);
+}
+
+#[tokio::test]
+async fn tags_and_assignments_travel_in_bundles() {
+    let a = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    let b = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    seed(&a).await;
+    seed(&b).await;
+
+    a.upsert_tag(&tag("theology", None), None).await.unwrap();
+    a.upsert_tag(&tag("tawhid", Some("theology")), None).await.unwrap();
+    a.upsert_tag(&tag("names", Some("tawhid")), None).await.unwrap();
+    a.assign_tag(&assignment("t1", "names", "2:255:0"), None).await.unwrap();
+
+    let bundle = a.export_research(None).await.unwrap();
+    let report = b.import_research(&bundle, ConflictStrategy::Report, None, false).await.unwrap();
+    assert_eq!(report.created, 4);
+    assert_eq!(b.tagged_verses("theology", true).await.unwrap(), vec![(2, 255)]);
+
+    // Deletions are applied children first, whatever order the bundle lists them in.
+    b.delete_tag("names", None).await.unwrap();
+    b.delete_tag("tawhid", None).await.unwrap();
+    let mut bundle = b.export_research(None).await.unwrap();
+    bundle.records.reverse();
+    let report = a.import_research(&bundle, ConflictStrategy::Report, None, false).await.unwrap();
+    assert_eq!(report.deleted, 3);
+    assert_eq!(a.list_tags().await.unwrap().len(), 1);
+    assert!(a.get_tag_assignment("t1").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn tag_endpoints_and_tag_scoped_search() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    seed(&storage).await;
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    for doc in docs() {
+        search.index_document(&doc).await.unwrap();
+    }
+    search.commit().unwrap();
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let send = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("x-kalima-user", "amina")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+    async fn json(response: axum::response::Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    let response = app.clone().oneshot(send("POST", "/api/tags", serde_json::json!({ "name": "theology" }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = serde_json::json!({ "parent": "theology", "color": "gold" });
+    let tawhid = json(app.clone().oneshot(send("PUT", "/api/tags/tawhid", body)).await.unwrap()).await;
+    assert_eq!(tawhid["meta"]["color"], "gold");
+    assert_eq!(tawhid["author"], "amina");
+    let response = app.clone().oneshot(send("PUT", "/api/tags/theology", serde_json::json!({ "parent": "tawhid" }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let target = serde_json::json!({ "target": "2:257:0" });
+    let assigned = json(app.clone().oneshot(send("POST", "/api/tags/tawhid/assignments", target)).await.unwrap()).await;
+    assert_eq!(assigned["target"], "2:257:0");
+    let response = app
+        .clone()
+        .oneshot(send("POST", "/api/tags/tawhid/assignments", serde_json::json!({ "target": "2:258" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let verses = json(app.clone().oneshot(get("/api/tags/theology/verses")).await.unwrap()).await;
+    assert_eq!(verses["verses"], serde_json::json!(["2:257"]));
+    let verses = json(app.clone().oneshot(get("/api/tags/theology/verses?descendants=false")).await.unwrap()).await;
+    assert_eq!(verses["count"], 0);
+
+    let tree = json(app.clone().oneshot(get("/api/tags?tree=true")).await.unwrap()).await;
+    assert_eq!(tree[0]["name"], "theology");
+    assert_eq!(tree[0]["total"], 1);
+    assert_eq!(tree[0]["children"][0]["name"], "tawhid");
+    let detail = json(app.clone().oneshot(get("/api/tags/tawhid")).await.unwrap()).await;
+    assert_eq!(detail["ancestors"], serde_json::json!(["theology"]));
+    let on_verse = json(app.clone().oneshot(get("/api/tag_assignments?overlaps=2:257")).await.unwrap()).await;
+    assert_eq!(on_verse.as_array().unwrap().len(), 1);
+
+    // "ٱللَّهُ" opens 2:255 and 2:257; only 2:257 carries the tag.
+    let spec = serde_json::json!({ "query": "ٱللَّهُ", "filters": [{ "field": "tag", "op": "eq", "value": "theology" }], "limit": 1 });
+    let hits = json(app.clone().oneshot(send("POST", "/search", spec)).await.unwrap()).await;
+    let ids: Vec<&str> = hits.as_array().unwrap().iter().map(|h| h["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec!["2:257:0"]);
+    let q: String = "ٱللَّهُ".bytes().map(|b| format!("%{:02X}", b)).collect();
+    let results = json(app.clone().oneshot(get(&format!("/api/search?q={}&tag=tawhid", q))).await.unwrap()).await;
+    assert_eq!(results["count"], 1);
+
+    let id = assigned["id"].as_str().unwrap();
+    let response = app.clone().oneshot(send("DELETE", "/api/tags/theology", serde_json::json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app.clone().oneshot(send("DELETE", &format!("/api/tag_assignments/{}", id), serde_json::json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let counts = json(app.oneshot(get("/api/tags/counts")).await.unwrap()).await;
+    assert!(counts.as_array().unwrap().iter().all(|c| c["total"] == 0));
+}
@@ -0,0 +1,112 @@
+use api::{router, AppState, ServerConfig};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use common::{Segment, SegmentView, TagLabels};
+use search::TantivyIndex;
+use std::sync::Arc;
+use store::{decode_text, parse_treebank, SqliteStorage};
+use tower::ServiceExt;
+
+/// 1:1 as the corpus analyses it: the prepositional phrase attaches to an
+/// elided verb.
+const BASMALA: &str = "\
+# ID\tKIND\tTAG\tFORM\tHEAD\tREL
+(1:1:1:1)\tsegment\tP\tbi\t-\t-
+(1:1:1:2)\tsegment\tN\t-\t(1:1:1:1)\tgen
+(1:1:2:1)\tsegment\tPN\t{ll~ahi\t(1:1:1:2)\tPoss
+(1:1:3:1)\tsegment\tN\t{lr~aHoma`ni\t(1:1:2:1)\tAdj
+(1:1:4:1)\tsegment\tN\t{lr~aHiymi\t(1:1:2:1)\tAdj
+(1:1:*1)\telided\tV\t>abotadi}u\t-\t-
+(1:1:P1)\tphrase\tPP\t(1:1:1:1)-(1:1:2:1)\t(1:1:*1)\tlink
+";
+
+#[test]
+fn tag_sets_load_from_the_bundled_utf16_tables() {
+    let tags = TagLabels::bundled();
+    let relation = |tag: &str| tags.relation(tag).map(|l| (l.code.as_str(), l.ar.as_str()));
+    assert_eq!(relation("gen"), Some(("gen", "مجرور")));
+    assert_eq!(relation("subj<<kan>>"), Some(("subj <<kan>>", "اسم كان")));
+    let pp = tags.constituent("PP").unwrap();
+    assert_eq!((pp.en.as_str(), pp.ar.as_str()), ("Prepositional phrase", "جار ومجرور"));
+    assert!(tags.relation("nsubj").is_none());
+
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend("a\tb\r\n".encode_utf16().flat_map(|u| u.to_le_bytes()));
+    assert_eq!(decode_text(&utf16).unwrap(), "a\tb\r\n");
+}
+
+#[test]
+fn treebank_files_are_checked_as_they_are_parsed() {
+    let tags = TagLabels::bundled();
+    let trees = parse_treebank(BASMALA, tags).unwrap();
+    assert_eq!(trees.len(), 1);
+    let tree = &trees[0];
+    assert_eq!(tree.nodes.len(), 7);
+    assert_eq!(tree.nodes[1].id, "1:1:0:1");
+    assert_eq!(tree.nodes[1].token_index, Some(0));
+    assert_eq!(tree.nodes[6].start.as_deref(), Some("1:1:0:0"));
+    assert_eq!(tree.edges.len(), 5);
+    assert_eq!(tree.roots(), vec!["1:1:0:0", "*1"]);
+
+    let bad = |text: &str| parse_treebank(text, tags).unwrap_err().to_string();
+    assert!(bad("(1:1:1:1)\tsegment\tN\t-\t(1:1:1:2)\tnsubj\n(1:1:1:2)\tsegment\tN\n").contains("unknown relation"));
+    assert!(bad("(1:1:1:1)\tsegment\tN\t-\t(1:1:1:9)\tgen\n").contains("line 1"));
+    assert!(bad("(1:1:1:1)\tsegment\tN\t-\t(1:2:1:1)\tgen\n(1:2:1:1)\tsegment\tN\n").contains("outside verse"));
+    assert!(bad("(1:1:P1)\tphrase\tXP\t(1:1:1:1)-(1:1:1:1)\n").contains("constituent"));
+    assert!(bad("(1:1:1:1)\tphrase\tPP\t-\n").contains("cannot be a phrase"));
+}
+
+#[tokio::test]
+async fn dependency_endpoint_returns_the_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(SqliteStorage::connect("sqlite::memory:").await.unwrap());
+    storage
+        .upsert_segment(&SegmentView {
+            id: "1:1:0".into(),
+            verse_ref: "1:1".into(),
+            token_index: 0,
+            text: "بِسْمِ".into(),
+            segments: vec![
+                Segment { id: "1:1:0:0".into(), r#type: "prefix".into(), form: "بِ".into(), ..Default::default() },
+                Segment { id: "1:1:0:1".into(), r#type: "stem".into(), form: "سْمِ".into(), ..Default::default() },
+            ],
+            annotations: vec![],
+        })
+        .await
+        .unwrap();
+
+    let trees = parse_treebank(BASMALA, TagLabels::bundled()).unwrap();
+    assert_eq!(storage.import_treebank(&trees).await.unwrap(), 5);
+    // Re-importing a verse replaces its analysis.
+    assert_eq!(storage.import_treebank(&trees).await.unwrap(), 5);
+    assert_eq!(storage.get_dependency_tree(1, 1).await.unwrap().edges.len(), 5);
+    assert!(storage.get_dependency_tree(1, 2).await.unwrap().nodes.is_empty());
+
+    let search = Arc::new(TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = router(AppState::new(storage, search, config));
+
+    let response = app
+        .oneshot(Request::get("/api/dependency/1/1").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(v["roots"], serde_json::json!(["1:1:0:0", "*1"]));
+    let phrase = &v["nodes"][6];
+    assert_eq!(phrase["kind"], "phrase");
+    assert_eq!(phrase["label_en"], "Prepositional phrase");
+    // Segments without a treebank form take the corpus form.
+    assert_eq!(v["nodes"][1]["form"], "سْمِ");
+
+    let first = &v["dependency_tree"][0];
+    assert_eq!(first["rel_label"], "gen");
+    assert_eq!(first["rel_ar"], "مجرور");
+    assert_eq!(first["word"], "سْمِ");
+    assert_eq!(first["head_word"], "bi");
+    let link = &v["dependency_tree"][4];
+    assert_eq!(link["word"], "PP");
+    assert_eq!(link["head"], "*1");
+}
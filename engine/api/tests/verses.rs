@@ -0,0 +1,140 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use ::common::{group_tokens, Segment, SegmentView, Verse};
+use common::seed_verse;
+use store::SqliteStorage;
+use tower::ServiceExt;
+
+fn segment(id: &str, form: &str, pos: &str) -> Segment {
+    Segment {
+        id: id.into(),
+        r#type: "stem".into(),
+        form: form.into(),
+        pos: Some(pos.into()),
+        case_: Some("gen".into()),
+        role: Some("mudaf_ilayh".into()),
+        derived_noun_type: Some("masdar".into()),
+        state: Some("definite".into()),
+        ..Default::default()
+    }
+}
+
+async fn seed(storage: &SqliteStorage) {
+    let mut batch = storage.begin_batch().await.unwrap();
+    let docs = vec![
+        SegmentView {
+            id: "1:1:0".into(),
+            verse_ref: "1:1".into(),
+            token_index: 0,
+            text: "بِسْمِ".into(),
+            segments: vec![segment("1:1:0:0", "بِ", "P"), segment("1:1:0:1", "سْمِ", "N")],
+            annotations: vec![],
+        },
+        SegmentView {
+            id: "1:1:1".into(),
+            verse_ref: "1:1".into(),
+            token_index: 1,
+            text: "ٱللَّهِ".into(),
+            segments: vec![segment("1:1:1:0", "ٱللَّهِ", "PN")],
+            annotations: vec![],
+        },
+    ];
+    batch.upsert_many(&docs).await.unwrap();
+    batch.set_verse_text(1, 1, "بِسْمِ ٱللَّهِ").await.unwrap();
+    batch.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn verses_carry_every_segment_field() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    seed(&storage).await;
+
+    let verse = storage.get_verse(1, 1).await.unwrap().unwrap();
+    assert_eq!(verse.text, "بِسْمِ ٱللَّهِ");
+    assert_eq!(verse.tokens.len(), 2);
+    assert_eq!(verse.tokens[0].text, "بِسْمِ");
+    assert_eq!(verse.tokens[0].segments.len(), 2);
+    let seg = &verse.tokens[1].segments[0];
+    assert_eq!(seg.role.as_deref(), Some("mudaf_ilayh"));
+    assert_eq!(seg.derived_noun_type.as_deref(), Some("masdar"));
+    assert_eq!(seg.state.as_deref(), Some("definite"));
+
+    // The JSON shape is what clients deserialize.
+    let json = serde_json::to_value(&verse).unwrap();
+    assert_eq!(json["tokens"][1]["segments"][0]["case"], "gen");
+    assert!(json.get("edition").is_none());
+    let back: Verse = serde_json::from_value(json).unwrap();
+    assert_eq!(back.tokens[1].segments[0].case_.as_deref(), Some("gen"));
+
+    // Segment listings regroup into the same tokens.
+    let segments = storage.get_verse_segments(1, 1).await.unwrap();
+    assert_eq!(segments[0].word_index, 1);
+    let tokens = group_tokens(segments);
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[1].text, "ٱللَّهِ");
+    assert_eq!(tokens[1].segments[0].state.as_deref(), Some("definite"));
+
+    let listed = storage.list_verses(0, 10).await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(listed[0].tokens.is_empty());
+}
+
+#[tokio::test]
+async fn verse_ranges_cross_surahs_with_tokens() {
+    let storage = SqliteStorage::connect("sqlite::memory:").await.unwrap();
+    seed(&storage).await;
+    for (surah, ayah, text) in
+        [(1, 6, "ٱهْدِنَا ٱلصِّرَٰطَ ٱلْمُسْتَقِيمَ"), (1, 7, "صِرَٰطَ ٱلَّذِينَ"), (2, 1, "الٓمٓ"), (2, 2, "ذَٰلِكَ ٱلْكِتَٰبُ")]
+    {
+        seed_verse(&storage, surah, ayah, text).await;
+    }
+
+    let verses = storage.get_verse_range((1, 6), (2, 1)).await.unwrap();
+    let refs: Vec<(i64, i64)> = verses.iter().map(|v| (v.surah.number, v.ayah)).collect();
+    assert_eq!(refs, vec![(1, 6), (1, 7), (2, 1)]);
+    assert_eq!(verses[0].tokens.len(), 3);
+    assert_eq!(verses[0].tokens[2].segments[0].form, "ٱلْمُسْتَقِيمَ");
+    assert_eq!(verses[2].text, "الٓمٓ");
+    // The same tokens as the single-verse lookup.
+    let single = storage.get_verse(1, 1).await.unwrap().unwrap();
+    let ranged = storage.get_verse_range((1, 1), (1, 1)).await.unwrap();
+    assert_eq!(serde_json::to_value(&ranged[0]).unwrap(), serde_json::to_value(&single).unwrap());
+
+    let dir = tempfile::tempdir().unwrap();
+    let search = std::sync::Arc::new(search::TantivyIndex::open_or_create(dir.path()).unwrap());
+    let config = api::ServerConfig::new("sqlite::memory:".into(), dir.path().to_string_lossy().to_string());
+    let app = api::router(api::AppState::new(std::sync::Arc::new(storage), search, config));
+    let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(get("/api/verses/range?ref=1:7-2:2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let passage: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(passage["ref"], "1:7-2:2");
+    assert_eq!(passage["verses"].as_array().unwrap().len(), 3);
+    assert_eq!(passage["verses"][2]["tokens"][1]["text"], "ٱلْكِتَٰبُ");
+
+    // Lists keep the order they were written in.
+    let response = app.clone().oneshot(get("/api/verses/range?ref=2:2,%201:1")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let passage: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let ayat: Vec<i64> = passage["verses"].as_array().unwrap().iter().map(|v| v["ayah"].as_i64().unwrap()).collect();
+    assert_eq!(ayat, vec![2, 1]);
+
+    let response = app.clone().oneshot(get("/api/surah/1")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let surah: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(surah["verses"].as_array().unwrap().len(), 3);
+    assert_eq!(surah["verses"][1]["tokens"].as_array().unwrap().len(), 3);
+
+    for (uri, status) in [
+        ("/api/verses/range", StatusCode::BAD_REQUEST),
+        ("/api/verses/range?ref=2:1-300", StatusCode::BAD_REQUEST),
+        ("/api/verses/range?ref=1-114", StatusCode::BAD_REQUEST),
+        ("/api/verses/range?ref=3:1-5", StatusCode::NOT_FOUND),
+    ] {
+        assert_eq!(app.clone().oneshot(get(uri)).await.unwrap().status(), status, "{}", uri);
+    }
+}
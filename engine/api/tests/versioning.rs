@@ -0,0 +1,97 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{app, app_with, json, send};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn v1_lists_are_paged_and_deletes_answer_no_content() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir).await;
+    for name in ["mercy", "justice", "patience"] {
+        let (status, tag) = json(&app, send("POST", "/v1/tags", &format!(r#"{{"name":"{}"}}"#, name))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(tag["name"], name);
+    }
+
+    let (status, page) = json(&app, send("GET", "/v1/tags?start=1&limit=1", "")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["start"], 1);
+    assert_eq!(page["limit"], 1);
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+
+    let (_, page) = json(&app, send("GET", "/v1/tags", "")).await;
+    assert_eq!(page["limit"], 50);
+    assert_eq!(page["items"].as_array().unwrap().len(), 3);
+
+    let (status, err) = json(&app, send("GET", "/v1/tags?limit=lots", "")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["error"]["details"]["field"], "limit");
+
+    let response = app.clone().oneshot(send("DELETE", "/v1/tags/mercy", "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let (_, page) = json(&app, send("GET", "/v1/tags", "")).await;
+    assert_eq!(page["total"], 2);
+}
+
+#[tokio::test]
+async fn v1_search_takes_filters_as_query_parameters() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir).await;
+
+    let (status, page) = json(&app, send("GET", "/v1/search?root=ktb&pos=V", "")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["total"], 0);
+    assert!(page["items"].as_array().unwrap().is_empty());
+
+    let (status, err) = json(&app, send("GET", "/v1/search?colour=red", "")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(err["error"]["code"], "validation_failed");
+    assert_eq!(err["error"]["details"]["field"], "colour");
+
+    let (status, page) = json(&app, send("POST", "/v1/search", r#"{"query":"","offset":10,"limit":5}"#)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["start"], 10);
+    assert_eq!(page["limit"], 5);
+}
+
+#[tokio::test]
+async fn legacy_routes_are_marked_deprecated() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(&dir).await;
+
+    let response = app.clone().oneshot(send("GET", "/api/tags", "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["deprecation"], "@1792281600");
+    assert_eq!(response.headers()["link"], r#"</v1/tags>; rel="successor-version""#);
+
+    let response = app.clone().oneshot(send("GET", "/api/pronouns/al-Baqarah:255", "")).await.unwrap();
+    assert_eq!(response.headers()["link"], r#"</v1/verses/2/255/pronouns>; rel="successor-version""#);
+
+    let response = app.clone().oneshot(send("GET", "/search/root/%D9%83%D8%AA%D8%A8", "")).await.unwrap();
+    assert_eq!(
+        response.headers()["link"],
+        r#"</v1/search?root=%D9%83%D8%AA%D8%A8>; rel="successor-version""#
+    );
+
+    let response = app.clone().oneshot(send("GET", "/v1/tags", "")).await.unwrap();
+    assert!(response.headers().get("deprecation").is_none());
+    let response = app.clone().oneshot(send("GET", "/health", "")).await.unwrap();
+    assert!(response.headers().get("deprecation").is_none());
+}
+
+#[tokio::test]
+async fn legacy_routes_can_be_switched_off() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app_with(&dir, |config| config.legacy_routes = false).await;
+
+    let (status, err) = json(&app, send("GET", "/api/tags", "")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(err["error"]["code"], "not_found");
+
+    let (status, _) = json(&app, send("GET", "/v1/tags", "")).await;
+    assert_eq!(status, StatusCode::OK);
+    let response = app.clone().oneshot(send("GET", "/health", "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
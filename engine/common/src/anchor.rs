@@ -0,0 +1,266 @@
+//! Typed anchors for annotation targets and connection ends.
+//!
+//! Anchors are written as compact strings so existing `target_id`,
+//! `from_token` and `to_token` values keep working:
+//!
+//! | Form            | Anchor                                   |
+//! |-----------------|------------------------------------------|
+//! | `2`             | surah 2                                  |
+//! | `2:255`         | verse                                    |
+//! | `2:255-257`     | verse range within a surah               |
+//! | `2:286-3:2`     | verse range across surahs                |
+//! | `2:255:3`       | token 3 of the verse                     |
+//! | `2:255:3-5`     | tokens 3 to 5                            |
+//! | `2:255:3:1`     | segment 1 of token 3                     |
+//! | `2:255:3@0-2`   | letters 0 to 2 of token 3                |
+//!
+//! Tokens, segments and letters count from 0, surahs and ayat from 1, and
+//! every range is inclusive.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use crate::{EngineError, EngineResult};
+
+/// Largest index a span key component can hold; see [`Anchor::span`].
+const SPAN_MAX: i64 = 999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    Surah(i64),
+    Verse { surah: i64, ayah: i64 },
+    VerseRange { start: (i64, i64), end: (i64, i64) },
+    Token { surah: i64, ayah: i64, token: i64 },
+    TokenRange { surah: i64, ayah: i64, start: i64, end: i64 },
+    Segment { surah: i64, ayah: i64, token: i64, segment: i64 },
+    Letters { surah: i64, ayah: i64, token: i64, start: i64, end: i64 },
+}
+
+fn span_key(surah: i64, ayah: i64, token: i64, letter: i64) -> i64 {
+    let c = |n: i64| n.clamp(0, SPAN_MAX);
+    ((c(surah) * 1000 + c(ayah)) * 1000 + c(token)) * 1000 + c(letter)
+}
+
+impl Anchor {
+    /// Parse a target id. Ids that do not start with a digit are opaque and
+    /// give `Ok(None)`; ids that do must be well-formed anchors.
+    pub fn parse_target(s: &str) -> EngineResult<Option<Anchor>> {
+        if !s.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+        s.parse().map(Some)
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Anchor::Surah(_) => "surah",
+            Anchor::Verse { .. } => "verse",
+            Anchor::VerseRange { .. } => "verse_range",
+            Anchor::Token { .. } => "token",
+            Anchor::TokenRange { .. } => "token_range",
+            Anchor::Segment { .. } => "segment",
+            Anchor::Letters { .. } => "letters",
+        }
+    }
+
+    /// The surah and ayah the anchor starts in, if it is not a whole surah.
+    pub fn first_verse(&self) -> Option<(i64, i64)> {
+        match *self {
+            Anchor::Surah(_) => None,
+            Anchor::Verse { surah, ayah }
+            | Anchor::Token { surah, ayah, .. }
+            | Anchor::TokenRange { surah, ayah, .. }
+            | Anchor::Segment { surah, ayah, .. }
+            | Anchor::Letters { surah, ayah, .. } => Some((surah, ayah)),
+            Anchor::VerseRange { start, .. } => Some(start),
+        }
+    }
+
+    /// Inclusive bounds of the anchor in corpus order, as sortable integers.
+    ///
+    /// Two anchors overlap when their spans intersect. Segments cover their
+    /// whole token, since segment boundaries are not tied to letter offsets.
+    pub fn span(&self) -> (i64, i64) {
+        match *self {
+            Anchor::Surah(s) => (span_key(s, 0, 0, 0), span_key(s, SPAN_MAX, SPAN_MAX, SPAN_MAX)),
+            Anchor::Verse { surah, ayah } => {
+                (span_key(surah, ayah, 0, 0), span_key(surah, ayah, SPAN_MAX, SPAN_MAX))
+            }
+            Anchor::VerseRange { start, end } => {
+                (span_key(start.0, start.1, 0, 0), span_key(end.0, end.1, SPAN_MAX, SPAN_MAX))
+            }
+            Anchor::Token { surah, ayah, token } | Anchor::Segment { surah, ayah, token, .. } => {
+                (span_key(surah, ayah, token, 0), span_key(surah, ayah, token, SPAN_MAX))
+            }
+            Anchor::TokenRange { surah, ayah, start, end } => {
+                (span_key(surah, ayah, start, 0), span_key(surah, ayah, end, SPAN_MAX))
+            }
+            Anchor::Letters { surah, ayah, token, start, end } => {
+                (span_key(surah, ayah, token, start), span_key(surah, ayah, token, end))
+            }
+        }
+    }
+
+    pub fn overlaps(&self, other: &Anchor) -> bool {
+        let (a0, a1) = self.span();
+        let (b0, b1) = other.span();
+        a0 <= b1 && b0 <= a1
+    }
+}
+
+fn number(s: &str, what: &str, input: &str) -> EngineResult<i64> {
+    s.parse::<i64>()
+        .ok()
+        .filter(|n| *n >= 0 && s.chars().all(|c| c.is_ascii_digit()))
+        .ok_or_else(|| EngineError::Invalid(format!("Invalid {} '{}' in anchor {}", what, s, input)))
+}
+
+fn range(s: &str, what: &str, input: &str) -> EngineResult<(i64, i64)> {
+    let (a, b) = s.split_once('-').unwrap_or((s, s));
+    let (a, b) = (number(a, what, input)?, number(b, what, input)?);
+    if a > b {
+        return Err(EngineError::Invalid(format!("Range {} runs backwards in anchor {}", s, input)));
+    }
+    Ok((a, b))
+}
+
+impl FromStr for Anchor {
+    type Err = EngineError;
+
+    fn from_str(input: &str) -> EngineResult<Self> {
+        let s = input.trim();
+        let (base, letters) = match s.split_once('@') {
+            Some((base, letters)) => (base, Some(letters)),
+            None => (s, None),
+        };
+        let parts: Vec<&str> = base.split(':').collect();
+
+        // `S:A-S:A` is the only form with a colon after the dash.
+        let cross_surah = match base.split_once('-') {
+            Some((from, to)) if letters.is_none() && to.contains(':') => Some((from, to)),
+            _ => None,
+        };
+
+        let anchor = if let Some((from, to)) = cross_surah {
+            let verse = |v: &str| -> EngineResult<(i64, i64)> {
+                let (s, a) = v
+                    .split_once(':')
+                    .ok_or_else(|| EngineError::Invalid(format!("Unrecognized anchor {}", input)))?;
+                Ok((number(s, "surah", input)?, number(a, "ayah", input)?))
+            };
+            let (start, end) = (verse(from)?, verse(to)?);
+            if end < start {
+                return Err(EngineError::Invalid(format!("Verse range runs backwards in anchor {}", input)));
+            }
+            Anchor::VerseRange { start, end }
+        } else {
+            match (parts.as_slice(), letters) {
+                ([surah], None) => Anchor::Surah(number(surah, "surah", input)?),
+                ([surah, ayah], None) => {
+                    let surah = number(surah, "surah", input)?;
+                    match ayah.split_once('-') {
+                        None => Anchor::Verse { surah, ayah: number(ayah, "ayah", input)? },
+                        Some((from, to)) => {
+                            let from = number(from, "ayah", input)?;
+                            let end = (surah, number(to, "ayah", input)?);
+                            if end < (surah, from) {
+                                return Err(EngineError::Invalid(format!("Verse range runs backwards in anchor {}", input)));
+                            }
+                            Anchor::VerseRange { start: (surah, from), end }
+                        }
+                    }
+                }
+                ([surah, ayah, token], None) => {
+                    let (surah, ayah) = (number(surah, "surah", input)?, number(ayah, "ayah", input)?);
+                    let (start, end) = range(token, "token", input)?;
+                    if start == end {
+                        Anchor::Token { surah, ayah, token: start }
+                    } else {
+                        Anchor::TokenRange { surah, ayah, start, end }
+                    }
+                }
+                ([surah, ayah, token, segment], None) => Anchor::Segment {
+                    surah: number(surah, "surah", input)?,
+                    ayah: number(ayah, "ayah", input)?,
+                    token: number(token, "token", input)?,
+                    segment: number(segment, "segment", input)?,
+                },
+                ([surah, ayah, token], Some(letters)) => {
+                    let (start, end) = range(letters, "letter", input)?;
+                    Anchor::Letters {
+                        surah: number(surah, "surah", input)?,
+                        ayah: number(ayah, "ayah", input)?,
+                        token: number(token, "token", input)?,
+                        start,
+                        end,
+                    }
+                }
+                _ => return Err(EngineError::Invalid(format!("Unrecognized anchor {}", input))),
+            }
+        };
+
+        let (surah, ayah) = match anchor {
+            Anchor::Surah(s) => (s, 1),
+            other => other.first_verse().unwrap_or((0, 0)),
+        };
+        if !(1..=114).contains(&surah) || ayah < 1 {
+            return Err(EngineError::Invalid(format!("Anchor {} is outside the Quran", input)));
+        }
+        if let Anchor::VerseRange { end, .. } = anchor {
+            if !(1..=114).contains(&end.0) || end.1 < 1 {
+                return Err(EngineError::Invalid(format!("Anchor {} is outside the Quran", input)));
+            }
+        }
+        Ok(anchor)
+    }
+}
+
+impl fmt::Display for Anchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Anchor::Surah(s) => write!(f, "{}", s),
+            Anchor::Verse { surah, ayah } => write!(f, "{}:{}", surah, ayah),
+            Anchor::VerseRange { start, end } if start.0 == end.0 => {
+                write!(f, "{}:{}-{}", start.0, start.1, end.1)
+            }
+            Anchor::VerseRange { start, end } => write!(f, "{}:{}-{}:{}", start.0, start.1, end.0, end.1),
+            Anchor::Token { surah, ayah, token } => write!(f, "{}:{}:{}", surah, ayah, token),
+            Anchor::TokenRange { surah, ayah, start, end } => write!(f, "{}:{}:{}-{}", surah, ayah, start, end),
+            Anchor::Segment { surah, ayah, token, segment } => {
+                write!(f, "{}:{}:{}:{}", surah, ayah, token, segment)
+            }
+            Anchor::Letters { surah, ayah, token, start, end } if start == end => {
+                write!(f, "{}:{}:{}@{}", surah, ayah, token, start)
+            }
+            Anchor::Letters { surah, ayah, token, start, end } => {
+                write!(f, "{}:{}:{}@{}-{}", surah, ayah, token, start, end)
+            }
+        }
+    }
+}
+
+impl Serialize for Anchor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Anchor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// An anchor checked against the corpus, with the text it covers.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResolvedAnchor {
+    #[schema(value_type = String, example = "2:255:3-5")]
+    pub anchor: Anchor,
+    pub kind: String,
+    /// Verses the anchor touches, in order.
+    pub verses: Vec<(i64, i64)>,
+    pub text: String,
+}
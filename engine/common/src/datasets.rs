@@ -0,0 +1,31 @@
+//! Reading the tables shipped in `datasets/Quranic`: tab separated, usually
+//! UTF-16 with a byte order mark.
+
+use crate::{EngineError, EngineResult};
+
+/// Decode a dataset file: UTF-16 (either byte order) when it starts with a
+/// byte order mark, UTF-8 otherwise.
+pub fn decode_text(bytes: &[u8]) -> EngineResult<String> {
+    let utf16 = |bytes: &[u8], unit: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| unit([c[0], c[1]])).collect();
+        String::from_utf16(&units).map_err(|e| EngineError::Invalid(format!("Invalid UTF-16 text: {}", e)))
+    };
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => {
+            String::from_utf8(rest.to_vec()).map_err(|e| EngineError::Invalid(format!("Invalid UTF-8 text: {}", e)))
+        }
+        _ => String::from_utf8(bytes.to_vec()).map_err(|e| EngineError::Invalid(format!("Invalid UTF-8 text: {}", e))),
+    }
+}
+
+/// Rows of a tab-separated table after its header line.
+pub fn tsv_rows(text: &str) -> Vec<Vec<&str>> {
+    text.lines()
+        .skip(1)
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split('\t').map(str::trim).collect())
+        .collect()
+}
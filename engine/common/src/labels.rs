@@ -0,0 +1,321 @@
+//! Arabic and English names for the grammatical codes the corpus uses:
+//! part-of-speech tags, morphological features, dependency relations and
+//! phrase tags.
+//!
+//! POS, relation and constituent names come from the Quranic Arabic Corpus
+//! tables in `datasets/Quranic` (`CAPoS.csv`, `RelLabels.csv`,
+//! `ConstituentsTags.csv`). `RelLabels.csv` only gives the English tag, so
+//! relations get their English names from the corpus documentation. Feature
+//! values (case, mood, verb form, ...) follow the corpus morphology guide.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::datasets::{decode_text, tsv_rows};
+use crate::{EngineError, Segment};
+
+pub const BUNDLED_POS: &[u8] = include_bytes!("../../../datasets/Quranic/CAPoS.csv");
+pub const BUNDLED_RELATIONS: &[u8] = include_bytes!("../../../datasets/Quranic/RelLabels.csv");
+pub const BUNDLED_CONSTITUENTS: &[u8] = include_bytes!("../../../datasets/Quranic/ConstituentsTags.csv");
+
+/// A feature value: code, English, Arabic.
+type FeatureValue = (&'static str, &'static str, &'static str);
+
+/// Feature categories and their values.
+const FEATURES: &[(&str, &[FeatureValue])] = &[
+    ("type", &[("PREFIX", "Prefix", "سابقة"), ("STEM", "Stem", "جذع"), ("SUFFIX", "Suffix", "لاحقة")]),
+    ("case", &[("NOM", "Nominative", "مرفوع"), ("ACC", "Accusative", "منصوب"), ("GEN", "Genitive", "مجرور")]),
+    ("state", &[("DEF", "Definite", "معرفة"), ("INDEF", "Indefinite", "نكرة")]),
+    ("number", &[("S", "Singular", "مفرد"), ("D", "Dual", "مثنى"), ("P", "Plural", "جمع")]),
+    ("gender", &[("M", "Masculine", "مذكر"), ("F", "Feminine", "مؤنث")]),
+    ("person", &[("1", "First person", "متكلم"), ("2", "Second person", "مخاطب"), ("3", "Third person", "غائب")]),
+    ("voice", &[("ACT", "Active voice", "مبني للمعلوم"), ("PASS", "Passive voice", "مبني للمجهول")]),
+    ("mood", &[("IND", "Indicative", "مرفوع"), ("SUBJ", "Subjunctive", "منصوب"), ("JUS", "Jussive", "مجزوم")]),
+    ("aspect", &[("PERF", "Perfect", "فعل ماض"), ("IMPF", "Imperfect", "فعل مضارع"), ("IMPV", "Imperative", "فعل أمر")]),
+    (
+        "verb_form",
+        &[
+            ("I", "Form I", "فَعَلَ"),
+            ("II", "Form II", "فَعَّلَ"),
+            ("III", "Form III", "فَاعَلَ"),
+            ("IV", "Form IV", "أَفْعَلَ"),
+            ("V", "Form V", "تَفَعَّلَ"),
+            ("VI", "Form VI", "تَفَاعَلَ"),
+            ("VII", "Form VII", "اِنْفَعَلَ"),
+            ("VIII", "Form VIII", "اِفْتَعَلَ"),
+            ("IX", "Form IX", "اِفْعَلَّ"),
+            ("X", "Form X", "اِسْتَفْعَلَ"),
+            ("XI", "Form XI", "اِفْعَالَّ"),
+            ("XII", "Form XII", "اِفْعَوْعَلَ"),
+        ],
+    ),
+    (
+        "derived_noun_type",
+        &[("ACT_PCPL", "Active participle", "اسم فاعل"), ("PASS_PCPL", "Passive participle", "اسم مفعول"), ("VN", "Verbal noun", "مصدر")],
+    ),
+];
+
+/// English names of the relation tags in `RelLabels.csv`.
+const RELATION_NAMES: &[(&str, &str)] = &[
+    ("gen", "Genitive"),
+    ("Pred", "Predicate"),
+    ("Adj", "Adjective"),
+    ("cog", "Cognate accusative"),
+    ("cert", "Certainty"),
+    ("Pass", "Passive subject"),
+    ("state", "Clarification"),
+    ("ans", "Answer"),
+    ("neg", "Negation"),
+    ("intg", "Interrogative"),
+    ("sub", "Subordinate"),
+    ("voc", "Vocative"),
+    ("circ", "Circumstantial"),
+    ("prp", "Purpose"),
+    ("exp", "Exceptive"),
+    ("sup", "Supplemental"),
+    ("caus", "Cause"),
+    ("conj", "Coordinating conjunction"),
+    ("Spec", "Specification"),
+    ("fut", "Future"),
+    ("res", "Restriction"),
+    ("amd", "Amendment"),
+    ("link", "Attachment"),
+    ("impv", "Imperative"),
+    ("exl", "Explanation"),
+    ("Poss", "Possessive"),
+    ("emph", "Emphasis"),
+    ("ret", "Retraction"),
+    ("Cpnd", "Compound"),
+    ("eq", "Equalization"),
+    ("prev", "Preventive"),
+    ("sur", "Surprise"),
+    ("Subj", "Subject"),
+    ("avr", "Aversion"),
+    ("rslt", "Result"),
+    ("imrs", "Imperative result"),
+    ("int", "Interpretation"),
+    ("Pro", "Prohibition"),
+    ("exh", "Exhortation"),
+    ("inc", "Inceptive"),
+    ("Obj", "Object"),
+    ("cond", "Condition"),
+    ("App", "Apposition"),
+    ("root", "Root"),
+    ("NonRel", "No relation"),
+];
+
+/// Language grammatical terms are given in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    En,
+    Ar,
+}
+
+impl FromStr for Lang {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "en" => Ok(Lang::En),
+            "ar" => Ok(Lang::Ar),
+            other => Err(EngineError::Invalid(format!("Unknown language '{}' (expected ar or en)", other))),
+        }
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Lang::En => "en",
+            Lang::Ar => "ar",
+        })
+    }
+}
+
+/// A code with its English and Arabic names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Label {
+    pub code: String,
+    pub en: String,
+    pub ar: String,
+}
+
+impl Label {
+    fn new(code: &str, en: &str, ar: &str) -> Self {
+        Label { code: code.to_string(), en: en.to_string(), ar: ar.to_string() }
+    }
+
+    pub fn get(&self, lang: Lang) -> &str {
+        match lang {
+            Lang::En => &self.en,
+            Lang::Ar => &self.ar,
+        }
+    }
+}
+
+/// Names for every POS tag, feature value, relation and constituent tag.
+#[derive(Debug, Clone, Default)]
+pub struct TagLabels {
+    pos: BTreeMap<String, Label>,
+    /// Keyed by the tag with spaces removed.
+    relations: BTreeMap<String, Label>,
+    constituents: BTreeMap<String, Label>,
+    features: BTreeMap<&'static str, BTreeMap<String, Label>>,
+}
+
+fn relation_key(tag: &str) -> String {
+    tag.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// English name of a relation tag: `subj <<kan>>` is the subject of kāna.
+fn relation_english(tag: &str) -> String {
+    if let Some((head, rest)) = tag.split_once("<<") {
+        let verb = rest.trim_end_matches(">>").trim();
+        let role = match head.trim() {
+            "subj" => "Subject",
+            "pred" => "Predicate",
+            other => other,
+        };
+        return format!("{} of {}", role, verb);
+    }
+    RELATION_NAMES
+        .iter()
+        .find(|(code, _)| *code == tag)
+        .map(|(_, en)| en.to_string())
+        .unwrap_or_else(|| tag.to_string())
+}
+
+/// Look a code up exactly, then ignoring case.
+fn lookup<'a>(map: &'a BTreeMap<String, Label>, key: &str) -> Option<&'a Label> {
+    map.get(key).or_else(|| map.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v))
+}
+
+impl TagLabels {
+    /// Build from `CAPoS.csv` (`pid, pos, pos_ar, pos_en`), `RelLabels.csv`
+    /// (`rel_id, rel_en, rel_ar`) and `ConstituentsTags.csv`
+    /// (`pid, tag, tag_ar, tag_en`) contents.
+    pub fn from_tables(pos: &str, relations: &str, constituents: &str) -> Self {
+        let mut labels = TagLabels::default();
+        for row in tsv_rows(pos) {
+            if let [_, tag, ar, en, ..] = row.as_slice() {
+                labels.pos.insert(tag.to_string(), Label::new(tag, en, ar));
+            }
+        }
+        for row in tsv_rows(relations) {
+            if let [_, tag, ar, ..] = row.as_slice() {
+                if *tag == "_" || tag.is_empty() {
+                    continue;
+                }
+                let label = Label { code: tag.to_string(), en: relation_english(tag), ar: ar.to_string() };
+                labels.relations.insert(relation_key(tag), label);
+            }
+        }
+        for row in tsv_rows(constituents) {
+            if let [_, tag, ar, en, ..] = row.as_slice() {
+                labels.constituents.insert(tag.to_string(), Label::new(tag, en, ar));
+            }
+        }
+        for (category, values) in FEATURES {
+            let values = values.iter().map(|(code, en, ar)| (code.to_string(), Label::new(code, en, ar))).collect();
+            labels.features.insert(category, values);
+        }
+        labels
+    }
+
+    /// The tables shipped in `datasets/Quranic`.
+    pub fn bundled() -> &'static TagLabels {
+        static LABELS: OnceLock<TagLabels> = OnceLock::new();
+        LABELS.get_or_init(|| {
+            let read = |bytes: &[u8]| decode_text(bytes).unwrap_or_default();
+            Self::from_tables(&read(BUNDLED_POS), &read(BUNDLED_RELATIONS), &read(BUNDLED_CONSTITUENTS))
+        })
+    }
+
+    pub fn pos(&self, tag: &str) -> Option<&Label> {
+        lookup(&self.pos, tag.trim())
+    }
+
+    /// Spacing is not significant (`subj<<kan>>` is `subj <<kan>>`).
+    pub fn relation(&self, tag: &str) -> Option<&Label> {
+        lookup(&self.relations, &relation_key(tag))
+    }
+
+    pub fn constituent(&self, tag: &str) -> Option<&Label> {
+        lookup(&self.constituents, tag.trim())
+    }
+
+    /// A feature value within its category (`case`, `mood`, `verb_form`, ...).
+    /// Verb forms may be written with or without parentheses.
+    pub fn feature(&self, category: &str, code: &str) -> Option<&Label> {
+        let code = code.trim().trim_start_matches('(').trim_end_matches(')');
+        lookup(self.features.get(category)?, code)
+    }
+
+    /// Names of a segment's codes in one language, keyed by field. Codes the
+    /// tables do not know are given as they are.
+    pub fn segment(&self, segment: &Segment, lang: Lang) -> BTreeMap<&'static str, String> {
+        let mut out = BTreeMap::new();
+        let mut put = |field: &'static str, code: Option<&str>, label: Option<&Label>| {
+            if let Some(code) = code.filter(|c| !c.trim().is_empty()) {
+                out.insert(field, label.map(|l| l.get(lang)).unwrap_or(code).to_string());
+            }
+        };
+        put("type", Some(&segment.r#type), self.feature("type", &segment.r#type));
+        if let Some(pos) = segment.pos.as_deref() {
+            put("pos", Some(pos), self.pos(pos));
+        }
+        if let Some(rel) = segment.dependency_rel.as_deref() {
+            put("dependency_rel", Some(rel), self.relation(rel));
+        }
+        if let Some(role) = segment.role.as_deref() {
+            put("role", Some(role), self.relation(role));
+        }
+        let features = [
+            ("case", &segment.case_),
+            ("state", &segment.state),
+            ("number", &segment.number),
+            ("gender", &segment.gender),
+            ("person", &segment.person),
+            ("voice", &segment.voice),
+            ("mood", &segment.mood),
+            ("aspect", &segment.aspect),
+            ("verb_form", &segment.verb_form),
+            ("derived_noun_type", &segment.derived_noun_type),
+        ];
+        for (field, value) in features {
+            if let Some(code) = value.as_deref() {
+                put(field, Some(code), self.feature(field, code));
+            }
+        }
+        out
+    }
+
+    /// Every code by kind: names in `lang`, or both languages when `None`.
+    pub fn catalogue(&self, lang: Option<Lang>) -> Value {
+        let table = |labels: &mut dyn Iterator<Item = &Label>| -> Value {
+            let mut map = Map::new();
+            for label in labels {
+                let name = match lang {
+                    Some(lang) => json!(label.get(lang)),
+                    None => json!({ "en": label.en, "ar": label.ar }),
+                };
+                map.insert(label.code.clone(), name);
+            }
+            Value::Object(map)
+        };
+        let features: Map<String, Value> =
+            self.features.iter().map(|(category, values)| (category.to_string(), table(&mut values.values()))).collect();
+        json!({
+            "pos": table(&mut self.pos.values()),
+            "relations": table(&mut self.relations.values()),
+            "constituents": table(&mut self.constituents.values()),
+            "features": features,
+        })
+    }
+}
@@ -3,11 +3,21 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use std::str::FromStr;
+use utoipa::ToSchema;
+
+mod anchor;
+mod datasets;
+mod labels;
+mod reference;
+
+pub use anchor::{Anchor, ResolvedAnchor};
+pub use datasets::{decode_text, tsv_rows};
+pub use labels::{Label, Lang, TagLabels, BUNDLED_CONSTITUENTS, BUNDLED_POS, BUNDLED_RELATIONS};
+pub use reference::{ayah_count, surah_by_name, VerseRange, VerseRef};
 
 // --- Models -----------------------------------------------------------------
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QueryFilter {
     pub field: String,
     pub op: String,
@@ -15,14 +25,14 @@ pub struct QueryFilter {
     pub value: serde_json::Value,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SortSpec {
     pub field: String,
     #[serde(default = "default_sort_dir")]
     pub direction: SortDirection,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SortDirection {
     Asc,
@@ -33,7 +43,7 @@ fn default_sort_dir() -> SortDirection {
     SortDirection::Asc
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QuerySpec {
     pub query: serde_json::Value,
     #[serde(default)]
@@ -50,7 +60,7 @@ fn default_limit() -> usize {
     50
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct Segment {
     pub id: String,
     pub r#type: String,
@@ -77,7 +87,7 @@ pub struct Segment {
     pub number: Option<String>,
     #[serde(default)]
     pub gender: Option<String>,
-    #[serde(default)]
+    #[serde(default, rename = "case", alias = "case_")]
     pub case_: Option<String>,
     #[serde(default)]
     pub dependency_rel: Option<String>,
@@ -89,34 +99,137 @@ pub struct Segment {
     pub state: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Annotation {
     pub id: String,
     pub target_id: String,
     pub layer: String,
     pub payload: serde_json::Value,
+    /// Researcher who created the annotation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// A registered annotation layer.
+///
+/// Annotations on a registered layer must target ids of its `target`
+/// granularity and carry a payload valid against its JSON Schema.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnnotationLayer {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Kind of [`Anchor`] its annotations target: `surah`, `verse` (or a
+    /// verse range), `token` (or a token range), `segment`, `letters` or `any`.
+    #[serde(default = "default_layer_target")]
+    pub target: String,
+    /// JSON Schema for annotation payloads; `{}` accepts anything.
+    #[serde(default = "default_layer_schema")]
+    pub schema: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// Target granularities an [`AnnotationLayer`] can declare.
+pub const LAYER_TARGETS: &[&str] = &["surah", "verse", "token", "segment", "letters", "any"];
+
+fn default_layer_target() -> String {
+    "any".into()
+}
+
+fn default_layer_schema() -> serde_json::Value {
+    serde_json::json!({})
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct SurahInfo {
     pub number: i64,
     #[serde(default)]
     pub name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VerseResponse {
+/// A word of a verse with its morphological segments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Token {
+    pub index: i64,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+}
+
+/// A verse and, where loaded, its tokens.
+///
+/// `edition` and `alignment` are only set when another edition's text was
+/// requested in place of the analysed one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Verse {
     pub surah: SurahInfo,
     pub ayah: i64,
     #[serde(default)]
     pub text: String,
     #[serde(default)]
-    pub tokens: Vec<SegmentView>,
-    #[serde(default)]
-    pub annotations: Vec<Annotation>,
+    pub tokens: Vec<Token>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alignment: Option<Vec<AlignedWord>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Surah {
+    pub surah: SurahInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    pub verses: Vec<Verse>,
+}
+
+/// A segment listed with the token it belongs to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct VerseSegment {
+    #[serde(flatten)]
+    pub segment: Segment,
+    pub token_index: i64,
+    /// 1-based position of the token in the verse.
+    pub word_index: i64,
+    /// The segment's form, kept under the name morphology views display.
+    pub text: String,
+    pub token_text: String,
+}
+
+/// Group verse segments into tokens, in token order.
+pub fn group_tokens(segments: Vec<VerseSegment>) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    for seg in segments {
+        match tokens.iter_mut().find(|t| t.index == seg.token_index) {
+            Some(token) => token.segments.push(seg.segment),
+            None => tokens.push(Token {
+                index: seg.token_index,
+                text: seg.token_text,
+                segments: vec![seg.segment],
+            }),
+        }
+    }
+    tokens.sort_by_key(|t| t.index);
+    tokens
+}
+
+/// One page of a longer listing.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub start: i64,
+    pub limit: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SegmentView {
     pub id: String,
     pub verse_ref: String,
@@ -127,12 +240,373 @@ pub struct SegmentView {
     pub annotations: Vec<Annotation>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SearchHit {
     pub id: String,
     pub score: f32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SurahSummary {
+    pub number: i64,
+    pub name: String,
+    pub ayah_count: i64,
+    #[serde(default)]
+    pub name_translit: Option<String>,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    #[serde(default)]
+    pub revelation_place: Option<String>,
+    #[serde(default)]
+    pub revelation_order: Option<i64>,
+}
+
+/// A canonical division (juz, hizb, ...) as a verse range.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Division {
+    pub kind: String,
+    pub number: i64,
+    pub start_surah: i64,
+    pub start_ayah: i64,
+    pub end_surah: i64,
+    pub end_ayah: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Edition {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub riwayah: Option<String>,
+    #[serde(default)]
+    pub script: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// A node of a verse's syntax graph: a morphological segment, a word the
+/// grammar implies but the text leaves out, or a phrase over other nodes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SyntaxNode {
+    /// Segment anchor (`1:1:0:1`) for segments; `*1`, `P1`, ... within the verse otherwise.
+    pub id: String,
+    /// `segment`, `elided` or `phrase`.
+    pub kind: String,
+    /// Part of speech, or the constituent tag (`PP`, `NS`, ...) of a phrase.
+    pub tag: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_index: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_index: Option<i64>,
+    /// First and last node a phrase covers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    /// English and Arabic names of a phrase's constituent tag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_en: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_ar: Option<String>,
+}
+
+/// A labelled dependency from a head node to one of its dependents.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DependencyEdge {
+    pub head: String,
+    pub dependent: String,
+    pub relation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relation_ar: Option<String>,
+}
+
+/// The treebank analysis of one verse.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DependencyTree {
+    pub surah: i64,
+    pub ayah: i64,
+    pub nodes: Vec<SyntaxNode>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl DependencyTree {
+    /// Nodes that are not the dependent of any edge, in node order.
+    pub fn roots(&self) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .filter(|n| !self.edges.iter().any(|e| e.dependent == n.id))
+            .map(|n| n.id.as_str())
+            .collect()
+    }
+
+    pub fn node(&self, id: &str) -> Option<&SyntaxNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+}
+
+/// A word-by-word gloss of a token, or of one of its segments, from a named
+/// source such as a published word-by-word translation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Gloss {
+    pub surah: i64,
+    pub ayah: i64,
+    pub token_index: i64,
+    /// Set when the gloss is for one segment rather than the whole token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_index: Option<i64>,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    pub text: String,
+}
+
+/// A gloss source with the number of glosses it provides.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GlossSource {
+    pub source: String,
+    pub lang: Option<String>,
+    pub glosses: i64,
+}
+
+/// Tokens and translation words that render each other; either side may
+/// hold several. Translation words are counted from 0 in whitespace order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AlignmentLink {
+    pub tokens: Vec<i64>,
+    pub words: Vec<usize>,
+}
+
+/// A verse-level translation aligned to the verse's tokens.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TranslationAlignment {
+    pub surah: i64,
+    pub ayah: i64,
+    pub source: String,
+    pub text: String,
+    #[serde(default)]
+    pub links: Vec<AlignmentLink>,
+}
+
+impl TranslationAlignment {
+    pub fn words(&self) -> Vec<&str> {
+        self.text.split_whitespace().collect()
+    }
+
+    /// Translation words linked to a token, in translation order.
+    pub fn words_for_token(&self, token_index: i64) -> Vec<&str> {
+        let words = self.words();
+        let mut indexes: Vec<usize> = self
+            .links
+            .iter()
+            .filter(|link| link.tokens.contains(&token_index))
+            .flat_map(|link| link.words.iter().copied())
+            .collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+        indexes.into_iter().filter_map(|i| words.get(i).copied()).collect()
+    }
+}
+
+/// A root with the number of corpus segments derived from it. `root` is the
+/// Buckwalter form from the lexicon, or the corpus spelling of roots the
+/// lexicon does not list.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RootSummary {
+    pub root: String,
+    pub root_ar: Option<String>,
+    pub frequency: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LemmaSummary {
+    pub lemma: String,
+    pub lemma_ar: Option<String>,
+    pub frequency: i64,
+}
+
+/// How often a part of speech occurs, with its names from the POS table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PosCount {
+    pub pos: String,
+    pub name_en: Option<String>,
+    pub name_ar: Option<String>,
+    pub frequency: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RootDetail {
+    #[serde(flatten)]
+    pub summary: RootSummary,
+    /// Occurrences recorded in the lexicon table, when the root is listed.
+    pub lexicon_frequency: Option<i64>,
+    /// Lemmas derived from the root, most frequent first.
+    pub lemmas: Vec<LemmaSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LemmaDetail {
+    #[serde(flatten)]
+    pub summary: LemmaSummary,
+    pub lexicon_frequency: Option<i64>,
+    pub roots: Vec<RootSummary>,
+    pub pos: Vec<PosCount>,
+}
+
+/// A word of an edition and the primary-edition token it aligns with.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlignedWord {
+    pub word_index: i64,
+    pub form: String,
+    pub token_index: Option<i64>,
+}
+
+/// One hunk of a word-level diff between two editions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TextChange {
+    /// One of `equal`, `replace`, `delete` or `insert`.
+    pub op: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerseDiff {
+    pub surah: i64,
+    pub ayah: i64,
+    pub from_text: String,
+    pub to_text: String,
+    pub changes: Vec<TextChange>,
+}
+
+impl VerseDiff {
+    pub fn is_identical(&self) -> bool {
+        self.changes.iter().all(|c| c.op == "equal")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConnectionRecord {
+    pub id: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub layer: String,
+    pub meta: serde_json::Value,
+    /// Researcher who created the connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// A research tag. Tags form a hierarchy through `parent`; a tag applied to
+/// a verse also counts for every ancestor tag.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Tag {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Any other fields the researcher keeps on the tag (colour, notes, ...).
+    #[serde(default)]
+    pub meta: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// A tag applied to a verse, verse range, token, token range or letter span
+/// (any anchor).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TagAssignment {
+    pub id: String,
+    pub tag: String,
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// How often a tag is used: `assignments` of the tag itself, and `total`
+/// assignments and distinct `verses` counting its descendants.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TagCount {
+    pub tag: String,
+    pub parent: Option<String>,
+    pub assignments: i64,
+    pub total: i64,
+    pub verses: i64,
+}
+
+/// A markdown research note. References such as `[[2:255]]` or
+/// `[[2:255:3-5|the Throne verse]]` in the body link it to the corpus.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Note {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+/// An anchor cited by a note, with the line it is cited on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct NoteLink {
+    pub target: String,
+    pub context: String,
+}
+
+/// A note citing a verse (or something inside it).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct NoteBacklink {
+    pub note_id: String,
+    pub title: String,
+    pub target: String,
+    pub context: String,
+}
+
+/// One entry of the append-only change log.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChangeRecord {
+    pub id: i64,
+    pub record_type: String,
+    pub record_id: String,
+    pub verse_ref: Option<String>,
+    /// One of `create`, `update`, `delete` or `restore`.
+    pub action: String,
+    pub actor: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub changed_at: String,
+}
+
+/// A soft-deleted annotation or connection.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrashItem {
+    pub record_type: String,
+    pub id: String,
+    pub verse_ref: Option<String>,
+    pub deleted_at: String,
+    pub record: serde_json::Value,
+}
+
 // --- Errors -----------------------------------------------------------------
 
 #[derive(Debug, Error)]
@@ -145,21 +619,36 @@ pub enum EngineError {
     Search(String),
     #[error("invalid request: {0}")]
     Invalid(String),
+    /// Input that parsed but broke a rule; `details` says where, e.g.
+    /// `{"field": "filters[0].field", "value": "colour"}`.
+    #[error("validation failed: {message}")]
+    Validation { message: String, details: serde_json::Value },
+    /// The request clashes with the current state, e.g. deleting a tag that
+    /// still has children.
+    #[error("conflict: {0}")]
+    Conflict(String),
+    /// A well-formed request for something this build does not do.
+    #[error("unsupported: {0}")]
+    Unsupported(String),
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+impl EngineError {
+    pub fn validation(message: impl Into<String>, details: serde_json::Value) -> Self {
+        EngineError::Validation { message: message.into(), details }
+    }
+}
+
 pub type EngineResult<T> = Result<T, EngineError>;
 
+/// A single verse, written in any form [`VerseRef`] accepts (`2:255`,
+/// `al-Baqarah:255`, `البقرة ٢٥٥`).
 pub fn parse_verse_ref(s: &str) -> EngineResult<(i64, i64)> {
-    let (a, b) = s
-        .split_once(':')
-        .ok_or_else(|| EngineError::Invalid(format!("Invalid verse_ref: {}", s)))?;
-    let surah = i64::from_str(a)
-        .map_err(|_| EngineError::Invalid(format!("Invalid surah number in {}", s)))?;
-    let ayah = i64::from_str(b)
-        .map_err(|_| EngineError::Invalid(format!("Invalid ayah number in {}", s)))?;
-    Ok((surah, ayah))
+    let reference: VerseRef = s.parse()?;
+    reference
+        .single()
+        .ok_or_else(|| EngineError::Invalid(format!("{} is not a single verse", s.trim())))
 }
 
 // --- Traits -----------------------------------------------------------------
@@ -170,6 +659,176 @@ pub trait StorageBackend: Send + Sync {
     async fn hydrate_segments(&self, ids: &[SearchHit]) -> EngineResult<Vec<SegmentView>>;
 }
 
+/// Read access to the text of the Quran: surahs, verses, tokens, editions
+/// and canonical divisions.
+#[async_trait]
+pub trait CorpusStore: StorageBackend {
+    async fn list_surahs(&self) -> EngineResult<Vec<SurahSummary>>;
+    /// Verses of a surah without their tokens.
+    async fn get_surah_verses(&self, surah_number: i64) -> EngineResult<Vec<Verse>>;
+    async fn get_verse(&self, surah: i64, ayah: i64) -> EngineResult<Option<Verse>>;
+    async fn get_verse_by_index(&self, index: i64) -> EngineResult<Option<Verse>>;
+    /// Verses in mushaf order without their tokens.
+    async fn list_verses(&self, start: i64, limit: i64) -> EngineResult<Vec<Verse>>;
+    async fn list_verses_between(&self, start: (i64, i64), end: (i64, i64)) -> EngineResult<Vec<Verse>>;
+    /// Verses from `start` to `end` with their tokens and segments; ranges may
+    /// cross surahs.
+    async fn get_verse_range(&self, start: (i64, i64), end: (i64, i64)) -> EngineResult<Vec<Verse>>;
+    async fn get_verse_segments(&self, surah: i64, ayah: i64) -> EngineResult<Vec<VerseSegment>>;
+    /// Treebank analysis of a verse; empty when the treebank has not been imported.
+    async fn get_dependency_tree(&self, surah: i64, ayah: i64) -> EngineResult<DependencyTree>;
+    /// Word-by-word glosses of a verse in token order, from one source or all.
+    async fn get_glosses(&self, surah: i64, ayah: i64, source: Option<&str>) -> EngineResult<Vec<Gloss>>;
+    async fn list_gloss_sources(&self) -> EngineResult<Vec<GlossSource>>;
+    /// Verse translations aligned to tokens, from one source or all.
+    async fn get_alignments(&self, surah: i64, ayah: i64, source: Option<&str>) -> EngineResult<Vec<TranslationAlignment>>;
+    /// `(verse_ref, text)` pairs in mushaf order, at most `limit` of them.
+    async fn get_all_verse_texts(&self, limit: usize) -> EngineResult<Vec<(String, String)>>;
+    async fn count_verses(&self) -> EngineResult<i64>;
+    async fn count_verses_with_tokens(&self) -> EngineResult<i64>;
+    async fn count_verses_by_revelation_place(&self) -> EngineResult<Vec<(String, i64)>>;
+
+    async fn list_editions(&self) -> EngineResult<Vec<Edition>>;
+    async fn get_edition(&self, id: &str) -> EngineResult<Option<Edition>>;
+    async fn get_edition_text(&self, edition: &str, surah: i64, ayah: i64) -> EngineResult<Option<String>>;
+    async fn get_edition_surah_texts(&self, edition: &str, surah: i64) -> EngineResult<Vec<(i64, String)>>;
+    async fn get_edition_alignment(&self, edition: &str, surah: i64, ayah: i64) -> EngineResult<Vec<AlignedWord>>;
+    async fn diff_editions(
+        &self,
+        from: &str,
+        to: &str,
+        surah: i64,
+        ayah: Option<i64>,
+        normalize: bool,
+    ) -> EngineResult<Vec<VerseDiff>>;
+
+    async fn count_divisions(&self) -> EngineResult<Vec<(String, i64)>>;
+    async fn list_divisions(&self, kind: &str) -> EngineResult<Vec<Division>>;
+    async fn get_division(&self, kind: &str, number: i64) -> EngineResult<Option<Division>>;
+    async fn locate_verse(&self, surah: i64, ayah: i64) -> EngineResult<Vec<Division>>;
+
+    /// Check that an anchor exists in the corpus; errors with `Invalid` if not.
+    async fn resolve_anchor(&self, anchor: &Anchor) -> EngineResult<ResolvedAnchor>;
+}
+
+/// Researcher-authored records, their change history and the trash.
+///
+/// `actor` is the researcher making the change; it is recorded in the change
+/// log and becomes the author of newly created records.
+#[async_trait]
+pub trait ResearchStore: Send + Sync {
+    async fn upsert_annotation(&self, annotation: &Annotation, actor: Option<&str>) -> EngineResult<()>;
+    async fn replace_annotation(&self, old_id: &str, annotation: &Annotation, actor: Option<&str>) -> EngineResult<()>;
+    async fn get_annotation(&self, id: &str) -> EngineResult<Option<Annotation>>;
+    async fn list_annotations(&self, target: Option<&str>, author: Option<&str>) -> EngineResult<Vec<Annotation>>;
+    /// Annotations whose target overlaps `anchor`, e.g. every annotation on a
+    /// verse range, token or letter range that touches 2:255 token 3.
+    async fn list_annotations_overlapping(&self, anchor: &Anchor, author: Option<&str>) -> EngineResult<Vec<Annotation>>;
+    async fn delete_annotation(&self, id: &str, actor: Option<&str>) -> EngineResult<()>;
+    async fn count_annotations(&self) -> EngineResult<i64>;
+
+    async fn list_layers(&self) -> EngineResult<Vec<AnnotationLayer>>;
+    async fn get_layer(&self, name: &str) -> EngineResult<Option<AnnotationLayer>>;
+    /// Register or redefine a layer. Existing annotations are not revalidated.
+    async fn upsert_layer(&self, layer: &AnnotationLayer) -> EngineResult<()>;
+    /// Unregister a layer; refused while live annotations still use it.
+    async fn delete_layer(&self, name: &str) -> EngineResult<()>;
+
+    async fn upsert_connection(&self, conn: &ConnectionRecord, actor: Option<&str>) -> EngineResult<()>;
+    async fn get_connection(&self, id: &str) -> EngineResult<Option<ConnectionRecord>>;
+    async fn list_connections_for_verse(
+        &self,
+        surah: i64,
+        ayah: i64,
+        author: Option<&str>,
+    ) -> EngineResult<Vec<ConnectionRecord>>;
+    /// Connections with either end overlapping `anchor`.
+    async fn list_connections_overlapping(
+        &self,
+        anchor: &Anchor,
+        author: Option<&str>,
+    ) -> EngineResult<Vec<ConnectionRecord>>;
+    /// Every live connection, optionally limited to one layer and/or author.
+    async fn list_connections(&self, layer: Option<&str>, author: Option<&str>) -> EngineResult<Vec<ConnectionRecord>>;
+    async fn delete_connection(&self, id: &str, actor: Option<&str>) -> EngineResult<()>;
+
+    /// Pronoun, hypothesis or translation entries of a verse (`field` names the list).
+    async fn get_verse_metadata(&self, verse_ref: &str, field: &str) -> EngineResult<Vec<serde_json::Value>>;
+    async fn set_verse_metadata(
+        &self,
+        verse_ref: &str,
+        field: &str,
+        data: &serde_json::Value,
+        actor: Option<&str>,
+    ) -> EngineResult<()>;
+    async fn list_tags(&self) -> EngineResult<Vec<Tag>>;
+    async fn get_tag(&self, name: &str) -> EngineResult<Option<Tag>>;
+    /// Create or update a tag. The parent must exist and must not be the tag
+    /// or one of its descendants.
+    async fn upsert_tag(&self, tag: &Tag, actor: Option<&str>) -> EngineResult<()>;
+    /// Delete a tag and its assignments; refused while it has child tags.
+    async fn delete_tag(&self, name: &str, actor: Option<&str>) -> EngineResult<()>;
+    async fn assign_tag(&self, assignment: &TagAssignment, actor: Option<&str>) -> EngineResult<()>;
+    async fn get_tag_assignment(&self, id: &str) -> EngineResult<Option<TagAssignment>>;
+    async fn unassign_tag(&self, id: &str, actor: Option<&str>) -> EngineResult<()>;
+    /// Assignments of a tag (with `descendants`, of its subtree too), or of
+    /// every tag, optionally only those overlapping `anchor`.
+    async fn list_tag_assignments(
+        &self,
+        tag: Option<&str>,
+        descendants: bool,
+        anchor: Option<&Anchor>,
+        author: Option<&str>,
+    ) -> EngineResult<Vec<TagAssignment>>;
+    /// Verses carrying a tag, directly or through a token or span inside them,
+    /// in mushaf order.
+    async fn tagged_verses(&self, tag: &str, descendants: bool) -> EngineResult<Vec<(i64, i64)>>;
+    async fn tag_counts(&self) -> EngineResult<Vec<TagCount>>;
+    async fn list_notes(&self, author: Option<&str>) -> EngineResult<Vec<Note>>;
+    async fn get_note(&self, id: &str) -> EngineResult<Option<Note>>;
+    /// Create or update a note and re-index the references in its body.
+    async fn upsert_note(&self, note: &Note, actor: Option<&str>) -> EngineResult<()>;
+    async fn delete_note(&self, id: &str, actor: Option<&str>) -> EngineResult<()>;
+    /// Notes whose title or body contains `query` (case-insensitive).
+    async fn search_notes(&self, query: &str) -> EngineResult<Vec<Note>>;
+    async fn note_links(&self, id: &str) -> EngineResult<Vec<NoteLink>>;
+    /// References in a note body that do not link to the corpus.
+    async fn unresolved_note_links(&self, body: &str) -> EngineResult<Vec<String>>;
+    /// Notes citing anything that overlaps `anchor`.
+    async fn note_backlinks(&self, anchor: &Anchor) -> EngineResult<Vec<NoteBacklink>>;
+
+    /// Free-form research documents such as `patterns`.
+    async fn get_research_data(&self, key: &str) -> EngineResult<Option<serde_json::Value>>;
+    async fn set_research_data(&self, key: &str, value: &serde_json::Value, actor: Option<&str>) -> EngineResult<()>;
+
+    async fn list_record_history(&self, record_type: &str, record_id: &str) -> EngineResult<Vec<ChangeRecord>>;
+    async fn list_verse_history(&self, verse_ref: &str) -> EngineResult<Vec<ChangeRecord>>;
+    async fn revert_record(
+        &self,
+        record_type: &str,
+        record_id: &str,
+        change_id: i64,
+        actor: Option<&str>,
+    ) -> EngineResult<Option<serde_json::Value>>;
+
+    async fn list_trash(&self) -> EngineResult<Vec<TrashItem>>;
+    async fn restore_from_trash(&self, record_type: &str, id: &str, actor: Option<&str>) -> EngineResult<()>;
+    async fn purge_trash(&self, older_than_days: i64) -> EngineResult<u64>;
+}
+
+/// Inventories of the morphological vocabulary: roots, patterns and POS tags.
+#[async_trait]
+pub trait LexiconStore: Send + Sync {
+    /// Lexicon roots and roots found in the corpus, with corpus frequencies.
+    async fn list_roots(&self) -> EngineResult<Vec<RootSummary>>;
+    /// A root by its Buckwalter or Arabic form (spaced or not).
+    async fn get_root(&self, root: &str) -> EngineResult<Option<RootDetail>>;
+    /// A lemma by its Buckwalter or Arabic form.
+    async fn get_lemma(&self, lemma: &str) -> EngineResult<Option<LemmaDetail>>;
+    async fn list_unique_patterns(&self) -> EngineResult<Vec<String>>;
+    async fn list_unique_pos(&self) -> EngineResult<Vec<String>>;
+}
+
 #[async_trait]
 pub trait SearchBackend: Send + Sync {
     async fn search(&self, query: &QuerySpec) -> EngineResult<Vec<SearchHit>>;
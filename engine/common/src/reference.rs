@@ -0,0 +1,369 @@
+//! Verse references as people write them.
+//!
+//! | Form                 | Reference                                   |
+//! |----------------------|---------------------------------------------|
+//! | `2:255`              | one verse                                   |
+//! | `2:1-5`              | verses 1 to 5 of a surah                    |
+//! | `2:286-3:5`          | a range across surahs                       |
+//! | `2`, `2-3`           | whole surahs                                |
+//! | `2:255, 257, 3:1-5`  | a list; a bare number after a verse is      |
+//! |                      | another ayah of the same surah              |
+//! | `al-Baqarah:255`     | surah by transliterated name                |
+//! | `البقرة ٢٥٥`          | surah by Arabic name, Arabic-Indic digits   |
+//!
+//! Surah and ayah are separated by `:`, `.` or a space, and list items by
+//! `,`, `،` or `;`. Names ignore the article, case, apostrophes and doubled
+//! vowels, so `Al-Faatiha`, `al-Fatihah` and `Fatiha` are the same surah.
+//! Every reference is checked against the ayah counts of the Hafs numbering
+//! in `datasets/quran-metadata.json`.
+//!
+//! [`Anchor`](crate::Anchor) stays the strict, numeric form stored in the
+//! database; a [`VerseRef`] is what user input is parsed into.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::{Anchor, EngineError, EngineResult};
+
+const BUNDLED_METADATA: &str = include_str!("../../../datasets/quran-metadata.json");
+
+/// Articles dropped from the front of a transliterated name.
+const ARTICLES: &[&str] = &["al", "an", "ar", "as", "ash", "at", "az", "ad", "adh", "ath"];
+
+/// Common spellings the bundled transliterations do not reduce to.
+const ALIASES: &[(&str, i64)] = &[
+    ("Imran", 3),
+    ("Taubah", 9),
+    ("Bani Israil", 17),
+    ("Mumin", 40),
+    ("Ha Mim Sajdah", 41),
+    ("Muddathir", 74),
+    ("Dahr", 76),
+    ("Layl", 92),
+    ("Duha", 93),
+    ("Inshirah", 94),
+    ("Zilzal", 99),
+    ("Quraysh", 106),
+    ("Kauthar", 108),
+    ("Lahab", 111),
+];
+
+#[derive(Deserialize)]
+struct MetadataFile {
+    surahs: Vec<MetadataSurah>,
+}
+
+#[derive(Deserialize)]
+struct MetadataSurah {
+    number: i64,
+    name_ar: String,
+    name_translit: String,
+    ayah_count: i64,
+}
+
+struct SurahTable {
+    /// Ayah count of surah `n` at index `n - 1`.
+    ayahs: Vec<i64>,
+    /// Normalised Arabic and transliterated names.
+    names: HashMap<String, i64>,
+}
+
+fn table() -> &'static SurahTable {
+    static TABLE: OnceLock<SurahTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let file: MetadataFile = serde_json::from_str(BUNDLED_METADATA).expect("bundled quran-metadata.json is valid");
+        let mut names = HashMap::new();
+        for surah in &file.surahs {
+            let arabic = arabic_key(&surah.name_ar);
+            if let Some(bare) = arabic.strip_prefix("ال").filter(|s| !s.is_empty()) {
+                names.entry(bare.to_string()).or_insert(surah.number);
+            }
+            names.entry(arabic).or_insert(surah.number);
+            names.entry(translit_key(&surah.name_translit)).or_insert(surah.number);
+        }
+        for (alias, number) in ALIASES {
+            names.entry(translit_key(alias)).or_insert(*number);
+        }
+        SurahTable { ayahs: file.surahs.iter().map(|s| s.ayah_count).collect(), names }
+    })
+}
+
+/// Number of ayat in `surah`, or `None` outside 1-114.
+pub fn ayah_count(surah: i64) -> Option<i64> {
+    usize::try_from(surah - 1).ok().and_then(|i| table().ayahs.get(i)).copied()
+}
+
+/// The surah called `name`, in Arabic or transliteration.
+pub fn surah_by_name(name: &str) -> Option<i64> {
+    let name = name.trim();
+    let name = ["surah ", "surat ", "sura ", "سورة ", "سوره "]
+        .iter()
+        .find_map(|prefix| {
+            name.get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| name[prefix.len()..].trim())
+        })
+        .unwrap_or(name);
+    let key = if name.chars().any(is_arabic) { arabic_key(name) } else { translit_key(name) };
+    let names = &table().names;
+    names.get(&key).or_else(|| key.strip_prefix("ال").and_then(|bare| names.get(bare))).copied()
+}
+
+fn is_arabic(c: char) -> bool {
+    ('\u{0600}'..='\u{06FF}').contains(&c)
+}
+
+/// Arabic without vowel marks, tatweel or spaces, with alef, ta marbuta and
+/// alef maqsura folded to one form each.
+fn arabic_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}'))
+        .map(|c| match c {
+            'أ' | 'إ' | 'آ' | 'ٱ' => 'ا',
+            'ة' => 'ه',
+            'ى' => 'ي',
+            c => c,
+        })
+        .collect()
+}
+
+/// Lowercase letters of a transliterated name without its article, with
+/// doubled vowels and a final `-ah` reduced (`Al-Baqarah` → `baqara`).
+fn translit_key(name: &str) -> String {
+    let lower = name.to_lowercase();
+    let words: Vec<&str> = lower.split(|c: char| c == '-' || c == '_' || c.is_whitespace()).filter(|w| !w.is_empty()).collect();
+    let words = match words.as_slice() {
+        [article, rest @ ..] if !rest.is_empty() && ARTICLES.contains(article) => rest,
+        all => all,
+    };
+    let mut key: String = words.concat().chars().filter(char::is_ascii_alphabetic).collect();
+    for (long, short) in [("aa", "a"), ("ee", "i"), ("ii", "i"), ("oo", "u"), ("uu", "u")] {
+        while key.contains(long) {
+            key = key.replace(long, short);
+        }
+    }
+    if key.len() > 2 && key.ends_with("ah") {
+        key.pop();
+    }
+    key
+}
+
+/// ASCII digits in place of Arabic-Indic and Extended Arabic-Indic ones.
+fn ascii_digits(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '\u{0660}'..='\u{0669}' => char::from(b'0' + (c as u32 - 0x0660) as u8),
+            '\u{06F0}'..='\u{06F9}' => char::from(b'0' + (c as u32 - 0x06F0) as u8),
+            c => c,
+        })
+        .collect()
+}
+
+/// A run of consecutive verses, inclusive at both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VerseRange {
+    pub start: (i64, i64),
+    pub end: (i64, i64),
+}
+
+impl VerseRange {
+    pub fn surah(surah: i64) -> EngineResult<Self> {
+        let count = ayah_count(surah).ok_or_else(|| EngineError::Invalid(format!("Surah {} does not exist", surah)))?;
+        Ok(VerseRange { start: (surah, 1), end: (surah, count) })
+    }
+
+    fn is_whole_surah(&self) -> bool {
+        self.start.0 == self.end.0 && self.start.1 == 1 && ayah_count(self.end.0) == Some(self.end.1)
+    }
+
+    /// The range as an anchor: a surah, a verse or a verse range.
+    pub fn anchor(&self) -> Anchor {
+        if self.is_whole_surah() {
+            Anchor::Surah(self.start.0)
+        } else if self.start == self.end {
+            Anchor::Verse { surah: self.start.0, ayah: self.start.1 }
+        } else {
+            Anchor::VerseRange { start: self.start, end: self.end }
+        }
+    }
+
+    /// Every verse in the range, in order.
+    pub fn verses(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        (self.start.0..=self.end.0).flat_map(move |surah| {
+            let first = if surah == self.start.0 { self.start.1 } else { 1 };
+            let last = if surah == self.end.0 { self.end.1 } else { ayah_count(surah).unwrap_or(0) };
+            (first..=last).map(move |ayah| (surah, ayah))
+        })
+    }
+}
+
+impl fmt::Display for VerseRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ((s0, a0), (s1, a1)) = (self.start, self.end);
+        if self.is_whole_surah() {
+            write!(f, "{}", s0)
+        } else if self.start == self.end {
+            write!(f, "{}:{}", s0, a0)
+        } else if s0 == s1 {
+            write!(f, "{}:{}-{}", s0, a0, a1)
+        } else {
+            write!(f, "{}:{}-{}:{}", s0, a0, s1, a1)
+        }
+    }
+}
+
+/// One or more verse ranges, parsed from any of the forms in the module docs
+/// and checked against the ayah counts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VerseRef(Vec<VerseRange>);
+
+impl VerseRef {
+    pub fn ranges(&self) -> &[VerseRange] {
+        &self.0
+    }
+
+    /// The verse, when the reference is exactly one.
+    pub fn single(&self) -> Option<(i64, i64)> {
+        match self.0.as_slice() {
+            [range] if range.start == range.end => Some(range.start),
+            _ => None,
+        }
+    }
+
+    /// Every verse referred to, in the order written.
+    pub fn verses(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.0.iter().flat_map(VerseRange::verses)
+    }
+}
+
+/// One side of a range: a surah, and an ayah unless the whole surah is meant.
+struct Endpoint {
+    surah: i64,
+    ayah: Option<i64>,
+}
+
+fn is_number(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn parse_number(s: &str, what: &str, input: &str) -> EngineResult<i64> {
+    s.parse::<i64>()
+        .ok()
+        .filter(|_| is_number(s))
+        .ok_or_else(|| EngineError::Invalid(format!("Invalid {} '{}' in reference {}", what, s, input)))
+}
+
+fn parse_surah(s: &str, input: &str) -> EngineResult<i64> {
+    if is_number(s) {
+        return parse_number(s, "surah", input);
+    }
+    surah_by_name(s).ok_or_else(|| EngineError::Invalid(format!("Unknown surah '{}' in reference {}", s, input)))
+}
+
+/// `s` on its own is an ayah of `surah` when one is in context, else a surah.
+fn parse_endpoint(s: &str, surah: Option<i64>, input: &str) -> EngineResult<Endpoint> {
+    let s = s.trim();
+    if let (true, Some(surah)) = (is_number(s), surah) {
+        return Ok(Endpoint { surah, ayah: Some(parse_number(s, "ayah", input)?) });
+    }
+    let split = s.rfind([':', '.']).map(|i| (&s[..i], &s[i + 1..])).or_else(|| {
+        s.rfind(char::is_whitespace).map(|i| (&s[..i], &s[i..])).filter(|(_, ayah)| is_number(ayah.trim()))
+    });
+    match split {
+        Some((surah, ayah)) => Ok(Endpoint {
+            surah: parse_surah(surah.trim(), input)?,
+            ayah: Some(parse_number(ayah.trim(), "ayah", input)?),
+        }),
+        None => Ok(Endpoint { surah: parse_surah(s, input)?, ayah: None }),
+    }
+}
+
+/// Split `a-b` at the first dash that follows a number; dashes inside
+/// transliterated names (`al-Baqarah`) follow letters.
+fn split_range(item: &str) -> (&str, Option<&str>) {
+    let mut last = None;
+    for (i, c) in item.char_indices() {
+        if matches!(c, '-' | '–' | '—') && last.is_some_and(|l: char| l.is_ascii_digit()) {
+            return (&item[..i], Some(&item[i + c.len_utf8()..]));
+        }
+        if !c.is_whitespace() {
+            last = Some(c);
+        }
+    }
+    (item, None)
+}
+
+fn check_verse(surah: i64, ayah: i64, input: &str) -> EngineResult<()> {
+    let count = ayah_count(surah)
+        .ok_or_else(|| EngineError::Invalid(format!("Surah {} does not exist (reference {})", surah, input)))?;
+    if !(1..=count).contains(&ayah) {
+        return Err(EngineError::Invalid(format!(
+            "Surah {} has {} ayat; {}:{} does not exist (reference {})",
+            surah, count, surah, ayah, input
+        )));
+    }
+    Ok(())
+}
+
+impl FromStr for VerseRef {
+    type Err = EngineError;
+
+    fn from_str(input: &str) -> EngineResult<Self> {
+        let input = input.trim();
+        let text = ascii_digits(input);
+        let mut ranges = Vec::new();
+        // Surah of the previous item when it ended on an ayah (`2:255, 257`).
+        let mut context = None;
+        for item in text.split([',', '،', ';']) {
+            let item = item.trim();
+            if item.is_empty() {
+                return Err(EngineError::Invalid(format!("Empty item in reference '{}'", input)));
+            }
+            let (from, to) = split_range(item);
+            let from = parse_endpoint(from, context, input)?;
+            let to = match to {
+                Some(to) => parse_endpoint(to, from.ayah.map(|_| from.surah), input)?,
+                None => Endpoint { surah: from.surah, ayah: from.ayah },
+            };
+            let start = (from.surah, from.ayah.unwrap_or(1));
+            let last = ayah_count(to.surah).unwrap_or(0);
+            let end = (to.surah, to.ayah.unwrap_or(last));
+            check_verse(start.0, start.1, input)?;
+            check_verse(end.0, end.1, input)?;
+            if end < start {
+                return Err(EngineError::Invalid(format!("Range {} runs backwards in reference {}", item, input)));
+            }
+            context = to.ayah.map(|_| to.surah);
+            ranges.push(VerseRange { start, end });
+        }
+        Ok(VerseRef(ranges))
+    }
+}
+
+impl fmt::Display for VerseRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, range) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", range)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for VerseRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for VerseRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -12,6 +12,10 @@ use tantivy::{
     Index, IndexReader, IndexWriter,
 };
 
+/// Filter fields [`SearchBackend::search`] accepts.
+pub const FILTER_FIELDS: [&str; 11] =
+    ["root", "lemma", "pos", "pattern", "verb_form", "gender", "number", "case", "voice", "mood", "aspect"];
+
 pub struct TantivyIndex {
     index: Index,
     writer: Arc<RwLock<IndexWriter>>,
@@ -115,43 +119,62 @@ impl TantivyIndex {
             queries.push(main_q);
         }
 
-        for f in &spec.filters {
-            let field = match f.field.as_str() {
-                "root" | "roots" => Some(self.roots_field),
-                "lemma" | "lemmas" => Some(self.lemmas_field),
-                "pos" => Some(self.pos_field),
-                "pattern" => Some(self.pattern_field),
-                "verb_form" => Some(self.verb_form_field),
-                "gender" => Some(self.gender_field),
-                "number" => Some(self.number_field),
-                "case" | "case_" => Some(self.case_field),
-                "voice" => Some(self.voice_field),
-                "mood" => Some(self.mood_field),
-                "aspect" => Some(self.aspect_field),
-                _ => None,
+        for (i, f) in spec.filters.iter().enumerate() {
+            let field_ref = match f.field.as_str() {
+                "root" | "roots" => self.roots_field,
+                "lemma" | "lemmas" => self.lemmas_field,
+                "pos" => self.pos_field,
+                "pattern" => self.pattern_field,
+                "verb_form" => self.verb_form_field,
+                "gender" => self.gender_field,
+                "number" => self.number_field,
+                "case" | "case_" => self.case_field,
+                "voice" => self.voice_field,
+                "mood" => self.mood_field,
+                "aspect" => self.aspect_field,
+                "person" | "tense" | "dependency_rel" => {
+                    return Err(EngineError::Unsupported(format!("The search index cannot filter by {}", f.field)))
+                }
+                other => {
+                    return Err(EngineError::validation(
+                        format!("Unknown filter field: {}", other),
+                        serde_json::json!({
+                            "field": format!("filters[{}].field", i),
+                            "value": other,
+                            "allowed": FILTER_FIELDS,
+                        }),
+                    ))
+                }
             };
-            if let Some(field_ref) = field {
-                let values: Vec<String> = if f.op == "in" {
-                    f.value
-                        .as_array()
-                        .cloned()
-                        .unwrap_or_default()
-                        .into_iter()
-                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                        .collect()
-                } else {
-                    f.value
-                        .as_str()
-                        .map(|s| vec![s.to_string()])
-                        .unwrap_or_default()
-                };
-                for val in values {
-                    let term = tantivy::Term::from_field_text(field_ref, &val);
-                    queries.push(Box::new(tantivy::query::TermQuery::new(
-                        term,
-                        tantivy::schema::IndexRecordOption::Basic,
-                    )));
+            let values: Option<Vec<String>> = match f.op.as_str() {
+                "in" => f.value.as_array().and_then(|values| {
+                    values.iter().map(|v| v.as_str().map(str::to_string)).collect()
+                }),
+                "eq" => f.value.as_str().map(|s| vec![s.to_string()]),
+                other => {
+                    return Err(EngineError::validation(
+                        format!("Unknown filter op: {}", other),
+                        serde_json::json!({
+                            "field": format!("filters[{}].op", i),
+                            "value": other,
+                            "allowed": ["eq", "in"],
+                        }),
+                    ))
                 }
+            };
+            let Some(values) = values else {
+                let expected = if f.op == "in" { "an array of strings" } else { "a string" };
+                return Err(EngineError::validation(
+                    format!("Filter {} with op {} takes {}", f.field, f.op, expected),
+                    serde_json::json!({ "field": format!("filters[{}].value", i), "value": f.value }),
+                ));
+            };
+            for val in values {
+                let term = tantivy::Term::from_field_text(field_ref, &val);
+                queries.push(Box::new(tantivy::query::TermQuery::new(
+                    term,
+                    tantivy::schema::IndexRecordOption::Basic,
+                )));
             }
         }
 
@@ -0,0 +1,203 @@
+//! Anchors: resolving them against the corpus and finding research records
+//! whose targets overlap them.
+//!
+//! Annotation targets and connection ends that parse as [`Anchor`]s carry
+//! their span (see [`Anchor::span`]) in `anchor_start`/`anchor_end`,
+//! `from_start`/`from_end` and `to_start`/`to_end`, so overlap queries are a
+//! range comparison. Opaque ids leave the span columns NULL.
+
+use common::{Anchor, Annotation, ConnectionRecord, EngineError, EngineResult, ResolvedAnchor};
+use sqlx::{Pool, Row, Sqlite};
+
+use crate::{annotation_from_row, connection_from_row, SqliteStorage, ANNOTATION_COLUMNS, CONNECTION_COLUMNS};
+
+/// Span of a target id, if it is an anchor.
+pub(crate) fn target_span(target: &str) -> Option<(i64, i64)> {
+    Anchor::parse_target(target).ok().flatten().map(|a| a.span())
+}
+
+/// Fill in spans for records written before anchors existed, or restored
+/// from a snapshot that predates them.
+pub(crate) async fn backfill_spans(pool: &Pool<Sqlite>) -> EngineResult<()> {
+    let rows = sqlx::query(
+        r#"SELECT id, target_id FROM annotations WHERE anchor_start IS NULL AND target_id GLOB '[0-9]*'"#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    for row in rows {
+        let id: String = row.try_get("id").unwrap_or_default();
+        let target: String = row.try_get("target_id").unwrap_or_default();
+        if let Some((start, end)) = target_span(&target) {
+            sqlx::query("UPDATE annotations SET anchor_start = ?1, anchor_end = ?2 WHERE id = ?3")
+                .bind(start)
+                .bind(end)
+                .bind(&id)
+                .execute(pool)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+        }
+    }
+
+    let rows = sqlx::query(
+        r#"SELECT id, from_token, to_token FROM connections
+           WHERE (from_start IS NULL AND from_token GLOB '[0-9]*')
+              OR (to_start IS NULL AND to_token GLOB '[0-9]*')"#,
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    for row in rows {
+        let id: String = row.try_get("id").unwrap_or_default();
+        let from = target_span(&row.try_get::<String, _>("from_token").unwrap_or_default());
+        let to = target_span(&row.try_get::<String, _>("to_token").unwrap_or_default());
+        if from.is_none() && to.is_none() {
+            continue;
+        }
+        sqlx::query(
+            r#"UPDATE connections SET from_start = ?1, from_end = ?2, to_start = ?3, to_end = ?4 WHERE id = ?5"#,
+        )
+        .bind(from.map(|s| s.0))
+        .bind(from.map(|s| s.1))
+        .bind(to.map(|s| s.0))
+        .bind(to.map(|s| s.1))
+        .bind(&id)
+        .execute(pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn missing(anchor: &Anchor) -> EngineError {
+    EngineError::Invalid(format!("Anchor {} is not in the corpus", anchor))
+}
+
+impl SqliteStorage {
+    /// Check that an anchor exists in the corpus and collect the text it covers.
+    pub async fn resolve_anchor(&self, anchor: &Anchor) -> EngineResult<ResolvedAnchor> {
+        let resolved = |verses: Vec<(i64, i64)>, text: String| ResolvedAnchor {
+            anchor: *anchor,
+            kind: anchor.kind().to_string(),
+            verses,
+            text,
+        };
+
+        let (surah, ayah, first, last) = match *anchor {
+            Anchor::Surah(_) | Anchor::Verse { .. } | Anchor::VerseRange { .. } => {
+                let (start, end) = match *anchor {
+                    Anchor::Surah(s) => ((s, 1), (s, i64::MAX)),
+                    Anchor::VerseRange { start, end } => (start, end),
+                    _ => {
+                        let v = anchor.first_verse().unwrap_or_default();
+                        (v, v)
+                    }
+                };
+                let verses = self.list_verses_between(start, end).await?;
+                let refs: Vec<(i64, i64)> = verses.iter().map(|v| (v.surah.number, v.ayah)).collect();
+                let complete = match *anchor {
+                    Anchor::Surah(_) => !refs.is_empty(),
+                    _ => refs.first() == Some(&start) && refs.last() == Some(&end),
+                };
+                if !complete {
+                    return Err(missing(anchor));
+                }
+                let text = verses.iter().map(|v| v.text.as_str()).collect::<Vec<_>>().join(" ");
+                return Ok(resolved(refs, text));
+            }
+            Anchor::Token { surah, ayah, token }
+            | Anchor::Segment { surah, ayah, token, .. }
+            | Anchor::Letters { surah, ayah, token, .. } => (surah, ayah, token, token),
+            Anchor::TokenRange { surah, ayah, start, end } => (surah, ayah, start, end),
+        };
+
+        let rows = sqlx::query(
+            r#"SELECT id, text FROM tokens
+               WHERE verse_surah = ?1 AND verse_ayah = ?2 AND token_index BETWEEN ?3 AND ?4
+               ORDER BY token_index"#,
+        )
+        .bind(surah)
+        .bind(ayah)
+        .bind(first)
+        .bind(last)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if rows.len() as i64 != last - first + 1 {
+            return Err(missing(anchor));
+        }
+        let texts: Vec<String> = rows.iter().map(|r| r.try_get("text").unwrap_or_default()).collect();
+
+        let text = match *anchor {
+            Anchor::Letters { start, end, .. } => {
+                let letters: Vec<char> = texts[0].chars().collect();
+                if end as usize >= letters.len() {
+                    return Err(missing(anchor));
+                }
+                letters[start as usize..=end as usize].iter().collect()
+            }
+            Anchor::Segment { segment, .. } => {
+                let token_id: String = rows[0].try_get("id").unwrap_or_default();
+                sqlx::query_scalar::<_, String>(
+                    r#"SELECT form FROM segments WHERE token_id = ?1 ORDER BY id LIMIT 1 OFFSET ?2"#,
+                )
+                .bind(&token_id)
+                .bind(segment)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?
+                .ok_or_else(|| missing(anchor))?
+            }
+            _ => texts.join(" "),
+        };
+        Ok(resolved(vec![(surah, ayah)], text))
+    }
+
+    /// Live annotations whose target overlaps `anchor`, in corpus order.
+    pub async fn list_annotations_overlapping(
+        &self,
+        anchor: &Anchor,
+        author_filter: Option<&str>,
+    ) -> EngineResult<Vec<Annotation>> {
+        let (start, end) = anchor.span();
+        let rows = sqlx::query(&format!(
+            r#"SELECT {} FROM annotations
+               WHERE deleted_at IS NULL
+                 AND anchor_start <= ?2 AND anchor_end >= ?1
+                 AND (?3 IS NULL OR author = ?3)
+               ORDER BY anchor_start, anchor_end, created_at"#,
+            ANNOTATION_COLUMNS
+        ))
+        .bind(start)
+        .bind(end)
+        .bind(author_filter)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        rows.iter().map(annotation_from_row).collect()
+    }
+
+    /// Live connections with either end overlapping `anchor`.
+    pub async fn list_connections_overlapping(
+        &self,
+        anchor: &Anchor,
+        author_filter: Option<&str>,
+    ) -> EngineResult<Vec<ConnectionRecord>> {
+        let (start, end) = anchor.span();
+        let rows = sqlx::query(&format!(
+            r#"SELECT {} FROM connections
+               WHERE deleted_at IS NULL
+                 AND ((from_start <= ?2 AND from_end >= ?1) OR (to_start <= ?2 AND to_end >= ?1))
+                 AND (?3 IS NULL OR author = ?3)
+               ORDER BY COALESCE(from_start, to_start), created_at"#,
+            CONNECTION_COLUMNS
+        ))
+        .bind(start)
+        .bind(end)
+        .bind(author_filter)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows.iter().map(connection_from_row).collect())
+    }
+}
@@ -0,0 +1,366 @@
+//! `CorpusStore`, `ResearchStore` and `LexiconStore` for `SqliteStorage`.
+//!
+//! The SQL lives in the inherent methods; these impls only forward to them so
+//! the API can hold the storage behind trait objects.
+
+use async_trait::async_trait;
+use common::{
+    AlignedWord, Anchor, Annotation, AnnotationLayer, ChangeRecord, ConnectionRecord, CorpusStore, DependencyTree,
+    Division, Edition, EngineResult, Gloss, GlossSource, LemmaDetail, LexiconStore, Note, NoteBacklink, NoteLink,
+    ResearchStore, ResolvedAnchor, RootDetail, RootSummary, SurahSummary, Tag, TagAssignment, TagCount,
+    TranslationAlignment, TrashItem, Verse, VerseDiff, VerseSegment,
+};
+use serde_json::Value;
+
+use crate::SqliteStorage;
+
+#[async_trait]
+impl CorpusStore for SqliteStorage {
+    async fn list_surahs(&self) -> EngineResult<Vec<SurahSummary>> {
+        SqliteStorage::list_surahs(self).await
+    }
+
+    async fn get_surah_verses(&self, surah_number: i64) -> EngineResult<Vec<Verse>> {
+        SqliteStorage::get_surah_verses(self, surah_number).await
+    }
+
+    async fn get_verse(&self, surah: i64, ayah: i64) -> EngineResult<Option<Verse>> {
+        SqliteStorage::get_verse(self, surah, ayah).await
+    }
+
+    async fn get_verse_by_index(&self, index: i64) -> EngineResult<Option<Verse>> {
+        SqliteStorage::get_verse_by_index(self, index).await
+    }
+
+    async fn list_verses(&self, start: i64, limit: i64) -> EngineResult<Vec<Verse>> {
+        SqliteStorage::list_verses(self, start, limit).await
+    }
+
+    async fn list_verses_between(&self, start: (i64, i64), end: (i64, i64)) -> EngineResult<Vec<Verse>> {
+        SqliteStorage::list_verses_between(self, start, end).await
+    }
+
+    async fn get_verse_range(&self, start: (i64, i64), end: (i64, i64)) -> EngineResult<Vec<Verse>> {
+        SqliteStorage::get_verse_range(self, start, end).await
+    }
+
+    async fn get_verse_segments(&self, surah: i64, ayah: i64) -> EngineResult<Vec<VerseSegment>> {
+        SqliteStorage::get_verse_segments(self, surah, ayah).await
+    }
+
+    async fn get_dependency_tree(&self, surah: i64, ayah: i64) -> EngineResult<DependencyTree> {
+        SqliteStorage::get_dependency_tree(self, surah, ayah).await
+    }
+
+    async fn get_glosses(&self, surah: i64, ayah: i64, source: Option<&str>) -> EngineResult<Vec<Gloss>> {
+        SqliteStorage::get_glosses(self, surah, ayah, source).await
+    }
+
+    async fn list_gloss_sources(&self) -> EngineResult<Vec<GlossSource>> {
+        SqliteStorage::list_gloss_sources(self).await
+    }
+
+    async fn get_alignments(&self, surah: i64, ayah: i64, source: Option<&str>) -> EngineResult<Vec<TranslationAlignment>> {
+        SqliteStorage::get_alignments(self, surah, ayah, source).await
+    }
+
+    async fn get_all_verse_texts(&self, limit: usize) -> EngineResult<Vec<(String, String)>> {
+        SqliteStorage::get_all_verse_texts(self, limit).await
+    }
+
+    async fn count_verses(&self) -> EngineResult<i64> {
+        SqliteStorage::count_verses(self).await
+    }
+
+    async fn count_verses_with_tokens(&self) -> EngineResult<i64> {
+        SqliteStorage::count_verses_with_tokens(self).await
+    }
+
+    async fn count_verses_by_revelation_place(&self) -> EngineResult<Vec<(String, i64)>> {
+        SqliteStorage::count_verses_by_revelation_place(self).await
+    }
+
+    async fn list_editions(&self) -> EngineResult<Vec<Edition>> {
+        SqliteStorage::list_editions(self).await
+    }
+
+    async fn get_edition(&self, id: &str) -> EngineResult<Option<Edition>> {
+        SqliteStorage::get_edition(self, id).await
+    }
+
+    async fn get_edition_text(&self, edition: &str, surah: i64, ayah: i64) -> EngineResult<Option<String>> {
+        SqliteStorage::get_edition_text(self, edition, surah, ayah).await
+    }
+
+    async fn get_edition_surah_texts(&self, edition: &str, surah: i64) -> EngineResult<Vec<(i64, String)>> {
+        SqliteStorage::get_edition_surah_texts(self, edition, surah).await
+    }
+
+    async fn get_edition_alignment(&self, edition: &str, surah: i64, ayah: i64) -> EngineResult<Vec<AlignedWord>> {
+        SqliteStorage::get_edition_alignment(self, edition, surah, ayah).await
+    }
+
+    async fn diff_editions(
+        &self,
+        from: &str,
+        to: &str,
+        surah: i64,
+        ayah: Option<i64>,
+        normalize: bool,
+    ) -> EngineResult<Vec<VerseDiff>> {
+        SqliteStorage::diff_editions(self, from, to, surah, ayah, normalize).await
+    }
+
+    async fn count_divisions(&self) -> EngineResult<Vec<(String, i64)>> {
+        SqliteStorage::count_divisions(self).await
+    }
+
+    async fn list_divisions(&self, kind: &str) -> EngineResult<Vec<Division>> {
+        SqliteStorage::list_divisions(self, kind).await
+    }
+
+    async fn get_division(&self, kind: &str, number: i64) -> EngineResult<Option<Division>> {
+        SqliteStorage::get_division(self, kind, number).await
+    }
+
+    async fn locate_verse(&self, surah: i64, ayah: i64) -> EngineResult<Vec<Division>> {
+        SqliteStorage::locate_verse(self, surah, ayah).await
+    }
+
+    async fn resolve_anchor(&self, anchor: &Anchor) -> EngineResult<ResolvedAnchor> {
+        SqliteStorage::resolve_anchor(self, anchor).await
+    }
+}
+
+#[async_trait]
+impl ResearchStore for SqliteStorage {
+    async fn upsert_annotation(&self, annotation: &Annotation, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::upsert_annotation(self, annotation, actor).await
+    }
+
+    async fn replace_annotation(&self, old_id: &str, annotation: &Annotation, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::replace_annotation(self, old_id, annotation, actor).await
+    }
+
+    async fn get_annotation(&self, id: &str) -> EngineResult<Option<Annotation>> {
+        SqliteStorage::get_annotation(self, id).await
+    }
+
+    async fn list_annotations(&self, target: Option<&str>, author: Option<&str>) -> EngineResult<Vec<Annotation>> {
+        SqliteStorage::list_annotations(self, target, author).await
+    }
+
+    async fn list_annotations_overlapping(&self, anchor: &Anchor, author: Option<&str>) -> EngineResult<Vec<Annotation>> {
+        SqliteStorage::list_annotations_overlapping(self, anchor, author).await
+    }
+
+    async fn delete_annotation(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::delete_annotation(self, id, actor).await
+    }
+
+    async fn count_annotations(&self) -> EngineResult<i64> {
+        SqliteStorage::count_annotations(self).await
+    }
+
+    async fn list_layers(&self) -> EngineResult<Vec<AnnotationLayer>> {
+        SqliteStorage::list_layers(self).await
+    }
+
+    async fn get_layer(&self, name: &str) -> EngineResult<Option<AnnotationLayer>> {
+        SqliteStorage::get_layer(self, name).await
+    }
+
+    async fn upsert_layer(&self, layer: &AnnotationLayer) -> EngineResult<()> {
+        SqliteStorage::upsert_layer(self, layer).await
+    }
+
+    async fn delete_layer(&self, name: &str) -> EngineResult<()> {
+        SqliteStorage::delete_layer(self, name).await
+    }
+
+    async fn upsert_connection(&self, conn: &ConnectionRecord, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::upsert_connection(self, conn, actor).await
+    }
+
+    async fn get_connection(&self, id: &str) -> EngineResult<Option<ConnectionRecord>> {
+        SqliteStorage::get_connection(self, id).await
+    }
+
+    async fn list_connections_for_verse(
+        &self,
+        surah: i64,
+        ayah: i64,
+        author: Option<&str>,
+    ) -> EngineResult<Vec<ConnectionRecord>> {
+        SqliteStorage::list_connections_for_verse(self, surah, ayah, author).await
+    }
+
+    async fn list_connections_overlapping(
+        &self,
+        anchor: &Anchor,
+        author: Option<&str>,
+    ) -> EngineResult<Vec<ConnectionRecord>> {
+        SqliteStorage::list_connections_overlapping(self, anchor, author).await
+    }
+
+    async fn list_connections(&self, layer: Option<&str>, author: Option<&str>) -> EngineResult<Vec<ConnectionRecord>> {
+        SqliteStorage::list_connections(self, layer, author).await
+    }
+
+    async fn delete_connection(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::delete_connection(self, id, actor).await
+    }
+
+    async fn get_verse_metadata(&self, verse_ref: &str, field: &str) -> EngineResult<Vec<Value>> {
+        SqliteStorage::get_verse_metadata(self, verse_ref, field).await
+    }
+
+    async fn set_verse_metadata(
+        &self,
+        verse_ref: &str,
+        field: &str,
+        data: &Value,
+        actor: Option<&str>,
+    ) -> EngineResult<()> {
+        SqliteStorage::set_verse_metadata(self, verse_ref, field, data, actor).await
+    }
+
+    async fn list_tags(&self) -> EngineResult<Vec<Tag>> {
+        SqliteStorage::list_tags(self).await
+    }
+
+    async fn get_tag(&self, name: &str) -> EngineResult<Option<Tag>> {
+        SqliteStorage::get_tag(self, name).await
+    }
+
+    async fn upsert_tag(&self, tag: &Tag, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::upsert_tag(self, tag, actor).await
+    }
+
+    async fn delete_tag(&self, name: &str, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::delete_tag(self, name, actor).await
+    }
+
+    async fn assign_tag(&self, assignment: &TagAssignment, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::assign_tag(self, assignment, actor).await
+    }
+
+    async fn get_tag_assignment(&self, id: &str) -> EngineResult<Option<TagAssignment>> {
+        SqliteStorage::get_tag_assignment(self, id).await
+    }
+
+    async fn unassign_tag(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::unassign_tag(self, id, actor).await
+    }
+
+    async fn list_tag_assignments(
+        &self,
+        tag: Option<&str>,
+        descendants: bool,
+        anchor: Option<&Anchor>,
+        author: Option<&str>,
+    ) -> EngineResult<Vec<TagAssignment>> {
+        SqliteStorage::list_tag_assignments(self, tag, descendants, anchor, author).await
+    }
+
+    async fn tagged_verses(&self, tag: &str, descendants: bool) -> EngineResult<Vec<(i64, i64)>> {
+        SqliteStorage::tagged_verses(self, tag, descendants).await
+    }
+
+    async fn tag_counts(&self) -> EngineResult<Vec<TagCount>> {
+        SqliteStorage::tag_counts(self).await
+    }
+
+    async fn list_notes(&self, author: Option<&str>) -> EngineResult<Vec<Note>> {
+        SqliteStorage::list_notes(self, author).await
+    }
+
+    async fn get_note(&self, id: &str) -> EngineResult<Option<Note>> {
+        SqliteStorage::get_note(self, id).await
+    }
+
+    async fn upsert_note(&self, note: &Note, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::upsert_note(self, note, actor).await
+    }
+
+    async fn delete_note(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::delete_note(self, id, actor).await
+    }
+
+    async fn search_notes(&self, query: &str) -> EngineResult<Vec<Note>> {
+        SqliteStorage::search_notes(self, query).await
+    }
+
+    async fn note_links(&self, id: &str) -> EngineResult<Vec<NoteLink>> {
+        SqliteStorage::note_links(self, id).await
+    }
+
+    async fn unresolved_note_links(&self, body: &str) -> EngineResult<Vec<String>> {
+        SqliteStorage::unresolved_note_links(self, body).await
+    }
+
+    async fn note_backlinks(&self, anchor: &Anchor) -> EngineResult<Vec<NoteBacklink>> {
+        SqliteStorage::note_backlinks(self, anchor).await
+    }
+
+    async fn get_research_data(&self, key: &str) -> EngineResult<Option<Value>> {
+        SqliteStorage::get_research_data(self, key).await
+    }
+
+    async fn set_research_data(&self, key: &str, value: &Value, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::set_research_data(self, key, value, actor).await
+    }
+
+    async fn list_record_history(&self, record_type: &str, record_id: &str) -> EngineResult<Vec<ChangeRecord>> {
+        SqliteStorage::list_record_history(self, record_type, record_id).await
+    }
+
+    async fn list_verse_history(&self, verse_ref: &str) -> EngineResult<Vec<ChangeRecord>> {
+        SqliteStorage::list_verse_history(self, verse_ref).await
+    }
+
+    async fn revert_record(
+        &self,
+        record_type: &str,
+        record_id: &str,
+        change_id: i64,
+        actor: Option<&str>,
+    ) -> EngineResult<Option<Value>> {
+        SqliteStorage::revert_record(self, record_type, record_id, change_id, actor).await
+    }
+
+    async fn list_trash(&self) -> EngineResult<Vec<TrashItem>> {
+        SqliteStorage::list_trash(self).await
+    }
+
+    async fn restore_from_trash(&self, record_type: &str, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        SqliteStorage::restore_from_trash(self, record_type, id, actor).await
+    }
+
+    async fn purge_trash(&self, older_than_days: i64) -> EngineResult<u64> {
+        SqliteStorage::purge_trash(self, older_than_days).await
+    }
+}
+
+#[async_trait]
+impl LexiconStore for SqliteStorage {
+    async fn list_roots(&self) -> EngineResult<Vec<RootSummary>> {
+        SqliteStorage::list_roots(self).await
+    }
+
+    async fn get_root(&self, root: &str) -> EngineResult<Option<RootDetail>> {
+        SqliteStorage::get_root(self, root).await
+    }
+
+    async fn get_lemma(&self, lemma: &str) -> EngineResult<Option<LemmaDetail>> {
+        SqliteStorage::get_lemma(self, lemma).await
+    }
+
+    async fn list_unique_patterns(&self) -> EngineResult<Vec<String>> {
+        SqliteStorage::list_unique_patterns(self).await
+    }
+
+    async fn list_unique_pos(&self) -> EngineResult<Vec<String>> {
+        SqliteStorage::list_unique_pos(self).await
+    }
+}
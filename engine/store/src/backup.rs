@@ -0,0 +1,265 @@
+//! Online snapshots of the SQLite database.
+//!
+//! Snapshots are written with `VACUUM INTO`, which produces a consistent,
+//! compacted copy while the server keeps serving requests. Restoring copies a
+//! snapshot's rows back into the live database inside one transaction, so
+//! open connections never see a half-restored state.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use common::{EngineError, EngineResult};
+use sqlx::{Connection, Row, SqliteConnection};
+
+use crate::SqliteStorage;
+
+/// File name prefix shared by every snapshot.
+pub const SNAPSHOT_PREFIX: &str = "kalima-";
+
+/// Tables a restore leaves alone. The change log is append-only, so it keeps
+/// recording history across restores instead of being rolled back.
+const PRESERVED_TABLES: &[&str] = &["change_log"];
+
+/// Number of unlabelled snapshots kept by scheduled rotation.
+pub const DEFAULT_BACKUP_KEEP: usize = 7;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct SnapshotInfo {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: Option<String>,
+}
+
+impl SnapshotInfo {
+    fn from_path(path: &Path) -> EngineResult<Self> {
+        let meta = std::fs::metadata(path).map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(Self {
+            name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+            size_bytes: meta.len(),
+            created_at: meta
+                .modified()
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+        })
+    }
+}
+
+fn is_snapshot_name(name: &str) -> bool {
+    name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(".db")
+}
+
+/// Whether a snapshot name carries no label: `kalima-YYYYMMDDTHHMMSSZ[-n].db`,
+/// where `-n` only separates snapshots taken in the same second.
+fn is_unlabelled(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix(SNAPSHOT_PREFIX).and_then(|n| n.strip_suffix(".db")) else {
+        return false;
+    };
+    let stamp_len = "YYYYMMDDTHHMMSSZ".len();
+    if rest.len() < stamp_len || !rest.is_char_boundary(stamp_len) {
+        return false;
+    }
+    match &rest[stamp_len..] {
+        "" => true,
+        suffix => suffix
+            .strip_prefix('-')
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())),
+    }
+}
+
+/// A fresh `kalima-YYYYMMDDTHHMMSSZ[-label].db` path inside `dir`.
+pub fn snapshot_path(dir: &Path, label: Option<&str>) -> PathBuf {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let label: String = label
+        .unwrap_or("")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let base = if label.is_empty() {
+        format!("{}{}", SNAPSHOT_PREFIX, stamp)
+    } else {
+        format!("{}{}-{}", SNAPSHOT_PREFIX, stamp, label)
+    };
+
+    let mut path = dir.join(format!("{}.db", base));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.db", base, n));
+        n += 1;
+    }
+    path
+}
+
+/// Snapshots in `dir`, newest first. A missing directory has none.
+pub fn list_snapshots(dir: &Path) -> EngineResult<Vec<SnapshotInfo>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(EngineError::Storage(e.to_string())),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_snapshot_name(&name) {
+            snapshots.push(SnapshotInfo::from_path(&entry.path())?);
+        }
+    }
+    // Names start with a UTC timestamp, so they sort chronologically; compare
+    // without the extension so a same-second `-2` sorts after its sibling.
+    snapshots.sort_by(|a, b| b.name.trim_end_matches(".db").cmp(a.name.trim_end_matches(".db")));
+    Ok(snapshots)
+}
+
+/// Delete all but the `keep` newest unlabelled snapshots in `dir`; returns
+/// the removed names. Labelled snapshots, such as manual ones taken with a
+/// label or the `pre-restore` safety copy, are never rotated out.
+pub fn prune_snapshots(dir: &Path, keep: usize) -> EngineResult<Vec<String>> {
+    let mut removed = Vec::new();
+    let unlabelled = list_snapshots(dir)?.into_iter().filter(|s| is_unlabelled(&s.name));
+    for snapshot in unlabelled.skip(keep) {
+        std::fs::remove_file(&snapshot.path).map_err(|e| EngineError::Storage(e.to_string()))?;
+        removed.push(snapshot.name);
+    }
+    Ok(removed)
+}
+
+/// Resolve a snapshot by bare file name, refusing anything outside `dir`.
+pub fn find_snapshot(dir: &Path, name: &str) -> EngineResult<PathBuf> {
+    if !is_snapshot_name(name) || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(EngineError::Invalid(format!("Not a snapshot name: {}", name)));
+    }
+    let path = dir.join(name);
+    if !path.is_file() {
+        return Err(EngineError::NotFound);
+    }
+    Ok(path)
+}
+
+impl SqliteStorage {
+    /// Write a consistent copy of the live database to `path`.
+    pub async fn snapshot_to(&self, path: &Path) -> EngineResult<SnapshotInfo> {
+        if path.exists() {
+            return Err(EngineError::Conflict(format!("{} already exists", path.display())));
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| EngineError::Storage(e.to_string()))?;
+        }
+        sqlx::query("VACUUM INTO ?1")
+            .bind(path.to_string_lossy().to_string())
+            .execute(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        SnapshotInfo::from_path(path)
+    }
+
+    /// Write a timestamped snapshot into `dir`.
+    pub async fn snapshot_into(&self, dir: &Path, label: Option<&str>) -> EngineResult<SnapshotInfo> {
+        self.snapshot_to(&snapshot_path(dir, label)).await
+    }
+
+    /// Replace the contents of the live database with those of a snapshot.
+    ///
+    /// Tables the snapshot predates are emptied and columns added since are
+    /// left at their defaults, so older snapshots restore into newer schemas.
+    /// The change log is kept as is.
+    pub async fn restore_from(&self, path: &Path) -> EngineResult<()> {
+        if !path.is_file() {
+            return Err(EngineError::NotFound);
+        }
+        let mut conn = self.pool().acquire().await.map_err(|e| EngineError::Storage(e.to_string()))?;
+        sqlx::query("ATTACH DATABASE ?1 AS snapshot")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+        let result = copy_from_snapshot(&mut conn).await;
+
+        // Detach even on failure so the pooled connection goes back clean.
+        sqlx::query("DETACH DATABASE snapshot")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        result?;
+        drop(conn);
+        // Snapshots taken before anchors existed carry no spans, and older
+        // ones still keep tags in a research_data blob.
+        crate::anchors::backfill_spans(self.pool()).await?;
+        crate::tags::migrate_legacy_tags(self.pool()).await
+    }
+}
+
+async fn table_names(conn: &mut SqliteConnection, schema: &str) -> EngineResult<Vec<String>> {
+    let rows = sqlx::query(&format!(
+        r#"SELECT name FROM {}.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name"#,
+        schema
+    ))
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(rows.iter().map(|r| r.try_get("name").unwrap_or_default()).collect())
+}
+
+async fn column_names(conn: &mut SqliteConnection, schema: &str, table: &str) -> EngineResult<Vec<String>> {
+    let rows = sqlx::query(r#"SELECT name FROM pragma_table_info(?1, ?2)"#)
+        .bind(table)
+        .bind(schema)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(rows.iter().map(|r| r.try_get("name").unwrap_or_default()).collect())
+}
+
+async fn copy_from_snapshot(conn: &mut SqliteConnection) -> EngineResult<()> {
+    let check: String = sqlx::query_scalar("PRAGMA snapshot.quick_check")
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Invalid(format!("Not a readable snapshot: {}", e)))?;
+    if check != "ok" {
+        return Err(EngineError::Invalid(format!("Snapshot failed integrity check: {}", check)));
+    }
+    let snapshot_tables: HashSet<String> = table_names(conn, "snapshot").await?.into_iter().collect();
+    if !snapshot_tables.contains("verses") {
+        return Err(EngineError::Invalid("Snapshot is not a Kalima database".into()));
+    }
+
+    let mut tx = conn.begin().await.map_err(|e| EngineError::Storage(e.to_string()))?;
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    for table in table_names(&mut tx, "main").await? {
+        if PRESERVED_TABLES.contains(&table.as_str()) {
+            continue;
+        }
+        sqlx::query(&format!(r#"DELETE FROM main."{}""#, table))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if !snapshot_tables.contains(&table) {
+            continue;
+        }
+        let theirs: HashSet<String> = column_names(&mut tx, "snapshot", &table).await?.into_iter().collect();
+        let columns: Vec<String> = column_names(&mut tx, "main", &table)
+            .await?
+            .into_iter()
+            .filter(|c| theirs.contains(c))
+            .map(|c| format!(r#""{}""#, c))
+            .collect();
+        if columns.is_empty() {
+            continue;
+        }
+        let columns = columns.join(", ");
+        sqlx::query(&format!(
+            r#"INSERT INTO main."{table}" ({columns}) SELECT {columns} FROM snapshot."{table}""#
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+
+    tx.commit().await.map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(())
+}
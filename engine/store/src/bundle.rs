@@ -0,0 +1,424 @@
+//! Portable research bundles and three-way merge on import.
+//!
+//! A bundle carries every research record (annotations, connections, verse
+//! metadata entries, patterns, tags, tag assignments and notes) together with
+//! its *base*: the version the exporting database last agreed on with a peer.
+//! The importing database also remembers the version it last agreed on
+//! (`sync_base`). A record only changed on one side since that base is taken
+//! from that side; a record changed on both sides is a conflict.
+
+use common::{EngineError, EngineResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Row, SqliteConnection};
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use crate::history::{
+    self, RECORD_ANNOTATION, RECORD_CONNECTION, RECORD_HYPOTHESIS, RECORD_NOTE, RECORD_PATTERN, RECORD_PRONOUN,
+    RECORD_TAG, RECORD_TAG_ASSIGNMENT, RECORD_TRANSLATION,
+};
+use crate::{connection_from_row, SqliteStorage, CONNECTION_COLUMNS};
+
+pub const BUNDLE_FORMAT: &str = "kalima-research-bundle";
+pub const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResearchBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    #[serde(default)]
+    pub exported_by: Option<String>,
+    pub records: Vec<BundleRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleRecord {
+    pub record_type: String,
+    pub id: String,
+    /// Verse the record belongs to; required for verse metadata entries.
+    #[serde(default)]
+    pub verse_ref: Option<String>,
+    /// Current value, or `null` when the record was deleted.
+    pub value: Option<Value>,
+    /// Version last agreed on with a peer, or `null` if never synced.
+    #[serde(default)]
+    pub base: Option<Value>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl BundleRecord {
+    fn key(&self) -> String {
+        record_key(&self.record_type, self.verse_ref.as_deref(), &self.id)
+    }
+}
+
+/// What to do when a record was edited on both sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Keep the local version and report the conflict.
+    Report,
+    /// Keep the local version and mark the incoming one as seen.
+    Ours,
+    /// Take the incoming version.
+    Theirs,
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "report" => Ok(Self::Report),
+            "ours" => Ok(Self::Ours),
+            "theirs" => Ok(Self::Theirs),
+            other => Err(EngineError::Invalid(format!(
+                "Unknown conflict strategy: {} (expected report, ours or theirs)",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub record_type: String,
+    pub id: String,
+    pub verse_ref: Option<String>,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    /// Records where only the local side had changed.
+    pub kept_local: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+fn record_key(record_type: &str, verse_ref: Option<&str>, id: &str) -> String {
+    match (record_type, verse_ref) {
+        (RECORD_PRONOUN | RECORD_HYPOTHESIS | RECORD_TRANSLATION, Some(v)) => format!("{}/{}", v, id),
+        _ => id.to_string(),
+    }
+}
+
+fn verse_of_key(key: &str) -> (Option<String>, String) {
+    match key.split_once('/') {
+        Some((verse, id)) => (Some(verse.to_string()), id.to_string()),
+        None => (None, key.to_string()),
+    }
+}
+
+/// Order in which bundle records are applied: tags first, parents before
+/// children, so that child tags and assignments find what they point to;
+/// tag deletions last, children before parents.
+fn apply_order(records: &[BundleRecord]) -> Vec<&BundleRecord> {
+    let parents: HashMap<&str, &str> = records
+        .iter()
+        .filter(|r| r.record_type == RECORD_TAG)
+        .filter_map(|r| Some((r.id.as_str(), r.value.as_ref().or(r.base.as_ref())?.get("parent")?.as_str()?)))
+        .collect();
+    let depth = |name: &str| {
+        let mut depth = 0;
+        let mut current = name;
+        while let Some(parent) = parents.get(current) {
+            depth += 1;
+            current = parent;
+            if depth > parents.len() {
+                break;
+            }
+        }
+        depth
+    };
+
+    let (tags, rest): (Vec<&BundleRecord>, Vec<&BundleRecord>) =
+        records.iter().partition(|r| r.record_type == RECORD_TAG);
+    let (mut upserts, mut deletions): (Vec<&BundleRecord>, Vec<&BundleRecord>) =
+        tags.into_iter().partition(|r| r.value.is_some());
+    upserts.sort_by_key(|r| depth(&r.id));
+    deletions.sort_by_key(|r| std::cmp::Reverse(depth(&r.id)));
+    upserts.into_iter().chain(rest).chain(deletions).collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Unchanged,
+    KeepLocal,
+    TakeTheirs,
+    Conflict,
+}
+
+struct SyncBase {
+    value: Option<Value>,
+    bundle_exported_at: Option<String>,
+}
+
+async fn set_sync_base(
+    conn: &mut SqliteConnection,
+    record_type: &str,
+    key: &str,
+    value: Option<&Value>,
+    bundle_exported_at: &str,
+) -> EngineResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO sync_base (record_type, record_key, value, bundle_exported_at, synced_at)
+        VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)
+        ON CONFLICT(record_type, record_key) DO UPDATE SET
+          value=excluded.value,
+          bundle_exported_at=excluded.bundle_exported_at,
+          synced_at=excluded.synced_at
+        "#,
+    )
+    .bind(record_type)
+    .bind(key)
+    .bind(value)
+    .bind(bundle_exported_at)
+    .execute(conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(())
+}
+
+impl SqliteStorage {
+    /// Every live research record, keyed by `(record_type, key)`.
+    async fn current_records(&self) -> EngineResult<BTreeMap<(String, String), BundleRecord>> {
+        let mut out = BTreeMap::new();
+        let mut push = |record_type: &str, verse_ref: Option<String>, id: String, value: Value, updated_at: Option<String>| {
+            let rec = BundleRecord {
+                record_type: record_type.to_string(),
+                id,
+                verse_ref,
+                value: Some(value),
+                base: None,
+                updated_at,
+            };
+            out.insert((rec.record_type.clone(), rec.key()), rec);
+        };
+
+        for ann in self.list_annotations(None, None).await? {
+            let updated_at = ann.updated_at.clone().or(ann.created_at.clone());
+            push(RECORD_ANNOTATION, history::verse_of(&ann.target_id), ann.id.clone(), history::snapshot(&ann), updated_at);
+        }
+
+        let rows = sqlx::query(&format!(
+            r#"SELECT {} FROM connections WHERE deleted_at IS NULL ORDER BY id"#,
+            CONNECTION_COLUMNS
+        ))
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        for r in &rows {
+            let conn = connection_from_row(r);
+            let updated_at = conn.updated_at.clone().or(conn.created_at.clone());
+            push(RECORD_CONNECTION, history::verse_of(&conn.from_token), conn.id.clone(), history::snapshot(&conn), updated_at);
+        }
+
+        let rows = sqlx::query(r#"SELECT verse_ref, pronouns, hypotheses, translations FROM verse_metadata ORDER BY verse_ref"#)
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        for r in rows {
+            let verse_ref: String = r.try_get("verse_ref").unwrap_or_default();
+            for (column, record_type) in history::METADATA_FIELDS {
+                let entries: Option<Value> = r.try_get(column).unwrap_or(None);
+                for (i, entry) in entries.as_ref().and_then(|v| v.as_array()).into_iter().flatten().enumerate() {
+                    let updated_at = entry
+                        .get("updated_at")
+                        .or(entry.get("created_at"))
+                        .and_then(|t| t.as_str())
+                        .map(str::to_string);
+                    push(record_type, Some(verse_ref.clone()), history::entry_key(i, entry), entry.clone(), updated_at);
+                }
+            }
+        }
+
+        if let Some(Value::Object(patterns)) = self.get_research_data("patterns").await? {
+            for (id, pattern) in patterns {
+                push(RECORD_PATTERN, None, id, pattern, None);
+            }
+        }
+        for tag in self.list_tags().await? {
+            let updated_at = tag.updated_at.clone().or(tag.created_at.clone());
+            push(RECORD_TAG, None, tag.name.clone(), history::snapshot(&tag), updated_at);
+        }
+        for assignment in self.list_tag_assignments(None, false, None, None).await? {
+            let updated_at = assignment.updated_at.clone().or(assignment.created_at.clone());
+            push(
+                RECORD_TAG_ASSIGNMENT,
+                history::verse_of(&assignment.target),
+                assignment.id.clone(),
+                history::snapshot(&assignment),
+                updated_at,
+            );
+        }
+        for note in self.list_notes(None).await? {
+            let updated_at = note.updated_at.clone().or(note.created_at.clone());
+            push(RECORD_NOTE, None, note.id.clone(), history::snapshot(&note), updated_at);
+        }
+        Ok(out)
+    }
+
+    async fn sync_bases(&self) -> EngineResult<HashMap<(String, String), SyncBase>> {
+        let rows = sqlx::query(r#"SELECT record_type, record_key, value, bundle_exported_at FROM sync_base"#)
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                (
+                    (r.try_get("record_type").unwrap_or_default(), r.try_get("record_key").unwrap_or_default()),
+                    SyncBase {
+                        value: r.try_get("value").unwrap_or(None),
+                        bundle_exported_at: r.try_get("bundle_exported_at").unwrap_or(None),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// Package all research records, plus deletions of previously synced
+    /// records, into a bundle.
+    pub async fn export_research(&self, exported_by: Option<&str>) -> EngineResult<ResearchBundle> {
+        let mut current = self.current_records().await?;
+        let bases = self.sync_bases().await?;
+
+        for ((record_type, key), base) in &bases {
+            let entry = current.entry((record_type.clone(), key.clone()));
+            match entry {
+                std::collections::btree_map::Entry::Occupied(mut rec) => {
+                    rec.get_mut().base = base.value.clone();
+                }
+                std::collections::btree_map::Entry::Vacant(slot) => {
+                    // Synced before but gone now: export the deletion.
+                    if base.value.is_some() {
+                        let (verse_ref, id) = match record_type.as_str() {
+                            RECORD_PRONOUN | RECORD_HYPOTHESIS | RECORD_TRANSLATION => verse_of_key(key),
+                            _ => (None, key.clone()),
+                        };
+                        slot.insert(BundleRecord {
+                            record_type: record_type.clone(),
+                            id,
+                            verse_ref,
+                            value: None,
+                            base: base.value.clone(),
+                            updated_at: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(ResearchBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            exported_by: exported_by.map(str::to_string),
+            records: current.into_values().collect(),
+        })
+    }
+
+    /// Three-way merge a bundle into this database.
+    ///
+    /// All writes, and the sync bases recorded for them, share one
+    /// transaction: an import that fails partway leaves the database as it
+    /// was. With `dry_run` nothing is written; the report shows what would happen.
+    pub async fn import_research(
+        &self,
+        bundle: &ResearchBundle,
+        strategy: ConflictStrategy,
+        actor: Option<&str>,
+        dry_run: bool,
+    ) -> EngineResult<MergeReport> {
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(EngineError::Invalid(format!("Not a research bundle: {}", bundle.format)));
+        }
+        if bundle.version > BUNDLE_VERSION {
+            return Err(EngineError::Unsupported(format!(
+                "Bundle version {} is newer than supported version {}",
+                bundle.version, BUNDLE_VERSION
+            )));
+        }
+
+        let current = self.current_records().await?;
+        let bases = self.sync_bases().await?;
+        let mut report = MergeReport::default();
+        let mut tx = if dry_run { None } else { Some(self.begin_tx().await?) };
+
+        for rec in apply_order(&bundle.records) {
+            let key = rec.key();
+            let ident = (rec.record_type.clone(), key.clone());
+            let ours = current.get(&ident).and_then(|r| r.value.clone());
+            let theirs = rec.value.clone();
+            let local_base = bases.get(&ident);
+
+            // A bundle older than the one this record was last synced from
+            // cannot carry anything new for it.
+            let stale = local_base
+                .and_then(|b| b.bundle_exported_at.as_deref())
+                .map(|seen| bundle.exported_at.as_str() < seen)
+                .unwrap_or(false);
+            let is_local_base = |v: &Option<Value>| local_base.map(|b| b.value == *v).unwrap_or(false);
+            // Their unchanged base only counts against a record we hold; a
+            // record we never had still has to arrive even if they relayed it.
+            let theirs_unchanged = is_local_base(&theirs) || (ours.is_some() && theirs == rec.base);
+            let ours_unchanged = is_local_base(&ours) || ours == rec.base;
+
+            let outcome = if ours == theirs {
+                Outcome::Unchanged
+            } else if stale || theirs_unchanged {
+                Outcome::KeepLocal
+            } else if ours_unchanged || strategy == ConflictStrategy::Theirs {
+                Outcome::TakeTheirs
+            } else {
+                Outcome::Conflict
+            };
+
+            match outcome {
+                Outcome::Unchanged => report.unchanged += 1,
+                Outcome::KeepLocal => report.kept_local += 1,
+                Outcome::TakeTheirs => match (&ours, &theirs) {
+                    (None, _) => report.created += 1,
+                    (Some(_), None) => report.deleted += 1,
+                    (Some(_), Some(_)) => report.updated += 1,
+                },
+                Outcome::Conflict => report.conflicts.push(MergeConflict {
+                    record_type: rec.record_type.clone(),
+                    id: rec.id.clone(),
+                    verse_ref: rec.verse_ref.clone(),
+                    base: rec.base.clone().or_else(|| local_base.and_then(|b| b.value.clone())),
+                    ours: ours.clone(),
+                    theirs: theirs.clone(),
+                }),
+            }
+            let Some(tx) = tx.as_mut() else {
+                continue;
+            };
+
+            if outcome == Outcome::TakeTheirs {
+                history::write_record(tx, &rec.record_type, &rec.id, rec.verse_ref.as_deref(), theirs.as_ref(), actor)
+                    .await?;
+            }
+            // Reported conflicts keep their old base so they come up again on
+            // the next import; everything else now agrees on the incoming version.
+            let resolved = outcome != Outcome::Conflict || strategy == ConflictStrategy::Ours;
+            if resolved && !stale {
+                set_sync_base(tx, &rec.record_type, &key, theirs.as_ref(), &bundle.exported_at)
+                    .await?;
+            }
+        }
+        if let Some(tx) = tx {
+            Self::commit_tx(tx).await?;
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,418 @@
+//! Text editions (riwayat and scripts) stored alongside the analysed text.
+//!
+//! The morphologically analysed text in `verse_texts`/`tokens` is the primary
+//! edition. Other editions keep their own verse texts in `edition_texts`, and
+//! each of their words is aligned to a token of the primary edition in
+//! `edition_words` so analyses can be shown against any edition.
+
+use common::{AlignedWord, Edition, EngineError, EngineResult, TextChange, VerseDiff};
+use sqlx::Row;
+use std::collections::HashMap;
+
+use crate::SqliteStorage;
+
+/// Id of the analysed edition backed by `verse_texts` and `tokens`.
+pub const PRIMARY_EDITION: &str = "hafs-uthmani";
+
+/// Reduce a word to its consonantal skeleton so editions in different
+/// orthographies can be compared: drops harakat, Quranic annotation marks and
+/// tatweel, and folds alef, ya and ta marbuta variants.
+pub fn skeleton(word: &str) -> String {
+    word.chars()
+        .filter(|c| {
+            !matches!(*c,
+                '\u{0610}'..='\u{061A}'
+                | '\u{064B}'..='\u{065F}'
+                | '\u{0640}'
+                | '\u{0670}'
+                | '\u{06D6}'..='\u{06ED}')
+        })
+        .map(|c| match c {
+            'ٱ' | 'أ' | 'إ' | 'آ' => 'ا',
+            'ى' | 'ی' => 'ي',
+            'ة' => 'ه',
+            other => other,
+        })
+        .collect()
+}
+
+/// Longest common subsequence of two key sequences as index pairs.
+fn lcs_pairs(a: &[String], b: &[String]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let mut dp = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            dp[i][j] = if a[i] == b[j] {
+                dp[i + 1][j + 1] + 1
+            } else {
+                dp[i + 1][j].max(dp[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+    while i < n && j < m {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if dp[i + 1][j] >= dp[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Align edition words to primary tokens.
+///
+/// Equal word counts align positionally. Otherwise words are anchored on
+/// matching skeletons and the words between two anchors are spread over the
+/// tokens between them; words with no tokens left in their gap stay unaligned.
+pub fn align_words(words: &[&str], tokens: &[&str]) -> Vec<Option<i64>> {
+    if words.len() == tokens.len() {
+        return (0..words.len()).map(|i| Some(i as i64)).collect();
+    }
+    let a: Vec<String> = words.iter().map(|w| skeleton(w)).collect();
+    let b: Vec<String> = tokens.iter().map(|t| skeleton(t)).collect();
+    let mut anchors = lcs_pairs(&a, &b);
+    anchors.push((words.len(), tokens.len()));
+
+    let mut out = vec![None; words.len()];
+    let (mut prev_w, mut prev_t) = (0usize, 0usize);
+    for (w, t) in anchors {
+        let gap_words = w - prev_w;
+        let gap_tokens = t - prev_t;
+        if gap_tokens > 0 {
+            for k in 0..gap_words {
+                out[prev_w + k] = Some((prev_t + k * gap_tokens / gap_words) as i64);
+            }
+        }
+        if w < words.len() {
+            out[w] = Some(t as i64);
+        }
+        prev_w = w + 1;
+        prev_t = t + 1;
+    }
+    out
+}
+
+/// Word-level diff of two texts. With `normalize`, words are compared by
+/// skeleton so purely orthographic differences count as equal.
+pub fn diff_words(from: &str, to: &str, normalize: bool) -> Vec<TextChange> {
+    let a: Vec<&str> = from.split_whitespace().collect();
+    let b: Vec<&str> = to.split_whitespace().collect();
+    let key = |w: &&str| if normalize { skeleton(w) } else { w.to_string() };
+    let ka: Vec<String> = a.iter().map(key).collect();
+    let kb: Vec<String> = b.iter().map(key).collect();
+
+    let mut changes: Vec<TextChange> = Vec::new();
+    let mut push = |op: &str, from: Option<&str>, to: Option<&str>| {
+        // Merge runs of the same op so each hunk reads as a phrase.
+        if let Some(last) = changes.last_mut() {
+            if last.op == op && op != "equal" {
+                if let (Some(f), Some(lf)) = (from, last.from.as_mut()) {
+                    lf.push(' ');
+                    lf.push_str(f);
+                }
+                if let (Some(t), Some(lt)) = (to, last.to.as_mut()) {
+                    lt.push(' ');
+                    lt.push_str(t);
+                }
+                return;
+            }
+        }
+        changes.push(TextChange {
+            op: op.to_string(),
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+        });
+    };
+
+    let mut anchors = lcs_pairs(&ka, &kb);
+    anchors.push((a.len(), b.len()));
+    let (mut i, mut j) = (0usize, 0usize);
+    for (ai, bj) in anchors {
+        let removed = &a[i..ai];
+        let added = &b[j..bj];
+        let common = removed.len().min(added.len());
+        for k in 0..common {
+            push("replace", Some(removed[k]), Some(added[k]));
+        }
+        for w in &removed[common..] {
+            push("delete", Some(*w), None);
+        }
+        for w in &added[common..] {
+            push("insert", None, Some(*w));
+        }
+        if ai < a.len() {
+            push("equal", Some(a[ai]), Some(b[bj]));
+        }
+        i = ai + 1;
+        j = bj + 1;
+    }
+    changes
+}
+
+impl SqliteStorage {
+    pub async fn list_editions(&self) -> EngineResult<Vec<Edition>> {
+        let rows = sqlx::query(
+            r#"SELECT id, name, riwayah, script, source FROM editions
+               ORDER BY id = ?1 DESC, id"#,
+        )
+        .bind(PRIMARY_EDITION)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| Edition {
+                id: r.try_get("id").unwrap_or_default(),
+                name: r.try_get("name").unwrap_or_default(),
+                riwayah: r.try_get("riwayah").unwrap_or(None),
+                script: r.try_get("script").unwrap_or(None),
+                source: r.try_get("source").unwrap_or(None),
+            })
+            .collect())
+    }
+
+    pub async fn get_edition(&self, id: &str) -> EngineResult<Option<Edition>> {
+        Ok(self.list_editions().await?.into_iter().find(|e| e.id == id))
+    }
+
+    /// Replace the texts of an edition and realign its words to the primary
+    /// edition, in one transaction. Returns the number of verses stored.
+    pub async fn import_edition(
+        &self,
+        edition: &Edition,
+        verses: &[(i64, i64, String)],
+    ) -> EngineResult<usize> {
+        if edition.id == PRIMARY_EDITION {
+            return Err(EngineError::Invalid(format!(
+                "{} is the analysed edition and is loaded by ingest",
+                PRIMARY_EDITION
+            )));
+        }
+
+        // Primary tokens for every verse, keyed by (surah, ayah).
+        let rows = sqlx::query(
+            r#"SELECT verse_surah, verse_ayah, text FROM tokens ORDER BY verse_surah, verse_ayah, token_index"#,
+        )
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        let mut tokens: HashMap<(i64, i64), Vec<String>> = HashMap::new();
+        for r in rows {
+            let key = (
+                r.try_get("verse_surah").unwrap_or(0),
+                r.try_get("verse_ayah").unwrap_or(0),
+            );
+            tokens.entry(key).or_default().push(r.try_get("text").unwrap_or_default());
+        }
+
+        let mut tx = self.begin_tx().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO editions (id, name, riwayah, script, source) VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(id) DO UPDATE SET name=excluded.name, riwayah=excluded.riwayah,
+                script=excluded.script, source=excluded.source
+            "#,
+        )
+        .bind(&edition.id)
+        .bind(&edition.name)
+        .bind(edition.riwayah.as_ref())
+        .bind(edition.script.as_ref())
+        .bind(edition.source.as_ref())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        for table in ["edition_texts", "edition_words"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE edition_id = ?1", table))
+                .bind(&edition.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+        }
+
+        for (surah, ayah, text) in verses {
+            sqlx::query(
+                r#"INSERT OR REPLACE INTO edition_texts (edition_id, surah_number, ayah_number, text) VALUES (?1, ?2, ?3, ?4)"#,
+            )
+            .bind(&edition.id)
+            .bind(surah)
+            .bind(ayah)
+            .bind(text)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+            let words: Vec<&str> = text.split_whitespace().collect();
+            let verse_tokens: Vec<&str> = tokens
+                .get(&(*surah, *ayah))
+                .map(|t| t.iter().map(String::as_str).collect())
+                .unwrap_or_default();
+            let alignment = align_words(&words, &verse_tokens);
+            for (i, (form, token_index)) in words.iter().zip(alignment).enumerate() {
+                sqlx::query(
+                    r#"
+                    INSERT OR REPLACE INTO edition_words (edition_id, surah_number, ayah_number, word_index, form, token_index)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    "#,
+                )
+                .bind(&edition.id)
+                .bind(surah)
+                .bind(ayah)
+                .bind(i as i64)
+                .bind(form)
+                .bind(token_index)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+            }
+        }
+        Self::commit_tx(tx).await?;
+        Ok(verses.len())
+    }
+
+    /// Text of a verse in an edition; `None` if the edition lacks the verse.
+    pub async fn get_edition_text(&self, edition: &str, surah: i64, ayah: i64) -> EngineResult<Option<String>> {
+        let text: Option<String> = if edition == PRIMARY_EDITION {
+            sqlx::query_scalar(r#"SELECT text FROM verse_texts WHERE surah_number = ?1 AND ayah_number = ?2"#)
+                .bind(surah)
+                .bind(ayah)
+                .fetch_optional(self.pool())
+                .await
+        } else {
+            sqlx::query_scalar(
+                r#"SELECT text FROM edition_texts WHERE edition_id = ?1 AND surah_number = ?2 AND ayah_number = ?3"#,
+            )
+            .bind(edition)
+            .bind(surah)
+            .bind(ayah)
+            .fetch_optional(self.pool())
+            .await
+        }
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(text)
+    }
+
+    /// All verse texts of a surah in an edition, in ayah order.
+    pub async fn get_edition_surah_texts(&self, edition: &str, surah: i64) -> EngineResult<Vec<(i64, String)>> {
+        let rows = if edition == PRIMARY_EDITION {
+            sqlx::query(
+                r#"SELECT ayah_number, text FROM verse_texts WHERE surah_number = ?1 ORDER BY ayah_number"#,
+            )
+            .bind(surah)
+            .fetch_all(self.pool())
+            .await
+        } else {
+            sqlx::query(
+                r#"SELECT ayah_number, text FROM edition_texts WHERE edition_id = ?1 AND surah_number = ?2 ORDER BY ayah_number"#,
+            )
+            .bind(edition)
+            .bind(surah)
+            .fetch_all(self.pool())
+            .await
+        }
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                (
+                    r.try_get("ayah_number").unwrap_or(0),
+                    r.try_get::<Option<String>, _>("text").unwrap_or(None).unwrap_or_default(),
+                )
+            })
+            .collect())
+    }
+
+    /// Words of a verse in an edition with their primary-edition token.
+    pub async fn get_edition_alignment(&self, edition: &str, surah: i64, ayah: i64) -> EngineResult<Vec<AlignedWord>> {
+        if edition == PRIMARY_EDITION {
+            let rows = sqlx::query(
+                r#"SELECT token_index, text FROM tokens WHERE verse_surah = ?1 AND verse_ayah = ?2 ORDER BY token_index"#,
+            )
+            .bind(surah)
+            .bind(ayah)
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+            return Ok(rows
+                .into_iter()
+                .map(|r| {
+                    let index: i64 = r.try_get("token_index").unwrap_or(0);
+                    AlignedWord {
+                        word_index: index,
+                        form: r.try_get("text").unwrap_or_default(),
+                        token_index: Some(index),
+                    }
+                })
+                .collect());
+        }
+        let rows = sqlx::query(
+            r#"
+            SELECT word_index, form, token_index FROM edition_words
+            WHERE edition_id = ?1 AND surah_number = ?2 AND ayah_number = ?3
+            ORDER BY word_index
+            "#,
+        )
+        .bind(edition)
+        .bind(surah)
+        .bind(ayah)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| AlignedWord {
+                word_index: r.try_get("word_index").unwrap_or(0),
+                form: r.try_get("form").unwrap_or_default(),
+                token_index: r.try_get("token_index").unwrap_or(None),
+            })
+            .collect())
+    }
+
+    /// Word-level differences between two editions for one verse, or for
+    /// every verse of a surah when `ayah` is `None`.
+    pub async fn diff_editions(
+        &self,
+        from: &str,
+        to: &str,
+        surah: i64,
+        ayah: Option<i64>,
+        normalize: bool,
+    ) -> EngineResult<Vec<VerseDiff>> {
+        for edition in [from, to] {
+            if self.get_edition(edition).await?.is_none() {
+                return Err(EngineError::Invalid(format!("Unknown edition: {}", edition)));
+            }
+        }
+        let (from_texts, to_texts) = match ayah {
+            Some(a) => {
+                let f = self.get_edition_text(from, surah, a).await?;
+                let t = self.get_edition_text(to, surah, a).await?;
+                (f.map(|f| vec![(a, f)]).unwrap_or_default(), t.map(|t| vec![(a, t)]).unwrap_or_default())
+            }
+            None => (
+                self.get_edition_surah_texts(from, surah).await?,
+                self.get_edition_surah_texts(to, surah).await?,
+            ),
+        };
+        let to_texts: HashMap<i64, String> = to_texts.into_iter().collect();
+
+        Ok(from_texts
+            .into_iter()
+            .filter_map(|(a, from_text)| {
+                let to_text = to_texts.get(&a)?.clone();
+                Some(VerseDiff {
+                    surah,
+                    ayah: a,
+                    changes: diff_words(&from_text, &to_text, normalize),
+                    from_text,
+                    to_text,
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,447 @@
+//! Word-by-word glosses and verse translations aligned to tokens.
+//!
+//! Glosses come from named sources (one per published word-by-word
+//! translation) and attach to a token or to one of its segments. Import files
+//! count words and segments from 1 as word-by-word datasets do:
+//!
+//! ```text
+//! # TSV: a location and a gloss, or surah, ayah, word and gloss
+//! (1:1:1)     In (the) name
+//! 1:1:2:1     (of) Allah
+//! 1           1    3        the Most Gracious
+//! ```
+//!
+//! JSON files hold either a list of records (`location`, or `surah`/`ayah`/
+//! `word` with an optional `segment`, and `gloss`/`text`/`translation`), an
+//! object mapping locations to glosses, or the `{"verses": [{"verse_key",
+//! "words": [{"position", "translation": {"text"}}]}]}` shape of the
+//! quran.com API.
+//!
+//! Alignments link verse translations to tokens many-to-many. Their import
+//! format is the API's: a JSON list (or JSON lines) of `{verse, source, text,
+//! links: [{tokens, words}]}` with tokens and translation words counted from
+//! 0. Entries without links are aligned from the verse's glosses.
+
+use common::{AlignmentLink, EngineError, EngineResult, Gloss, GlossSource, TranslationAlignment};
+use serde_json::Value;
+use sqlx::{Row, SqliteConnection};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use crate::SqliteStorage;
+
+/// Words too common to align on their own; they only join a token next to a
+/// word already aligned to it.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "the", "of", "in", "on", "to", "and", "or", "for", "by", "with", "from", "at", "is", "are", "be",
+    "was", "that", "this", "who", "which", "it", "he", "she", "they", "we", "you", "i",
+];
+
+/// Layout of a gloss import file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlossFormat {
+    Tsv,
+    Json,
+}
+
+impl FromStr for GlossFormat {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tsv" | "tab" | "txt" => Ok(GlossFormat::Tsv),
+            "json" => Ok(GlossFormat::Json),
+            other => Err(EngineError::Invalid(format!("Unknown gloss format '{}' (expected tsv or json)", other))),
+        }
+    }
+}
+
+/// `(S:A:W)` or `S:A:W:M`, counted from 1, as 0-based token and segment.
+fn parse_location(text: &str) -> Option<(i64, i64, i64, Option<i64>)> {
+    let inner = text.trim().trim_start_matches('(').trim_end_matches(')');
+    let parts: Vec<i64> = inner.split(':').map(|p| p.trim().parse().ok()).collect::<Option<_>>()?;
+    if parts.iter().any(|&n| n < 1) {
+        return None;
+    }
+    match parts.as_slice() {
+        [s, a, w] => Some((*s, *a, w - 1, None)),
+        [s, a, w, m] => Some((*s, *a, w - 1, Some(m - 1))),
+        _ => None,
+    }
+}
+
+fn gloss(location: (i64, i64, i64, Option<i64>), source: &str, lang: Option<&str>, text: &str) -> Gloss {
+    let (surah, ayah, token_index, segment_index) = location;
+    Gloss {
+        surah,
+        ayah,
+        token_index,
+        segment_index,
+        source: source.to_string(),
+        lang: lang.map(str::to_string),
+        text: text.trim().to_string(),
+    }
+}
+
+/// Parse a gloss file for `source`. Empty glosses are skipped.
+pub fn parse_glosses(text: &str, format: GlossFormat, source: &str, lang: Option<&str>) -> EngineResult<Vec<Gloss>> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut out = match format {
+        GlossFormat::Tsv => parse_tsv(text, source, lang)?,
+        GlossFormat::Json => parse_json(text, source, lang)?,
+    };
+    out.retain(|g| !g.text.is_empty());
+    Ok(out)
+}
+
+fn parse_tsv(text: &str, source: &str, lang: Option<&str>) -> EngineResult<Vec<Gloss>> {
+    let mut out = Vec::new();
+    let mut seen_data = false;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let cols: Vec<&str> = line.split('\t').map(str::trim).collect();
+        let number = |s: &str| s.parse::<i64>().ok().filter(|&n| n >= 1);
+        let location = match cols.as_slice() {
+            [loc, text, ..] if parse_location(loc).is_some() => parse_location(loc).map(|l| (l, *text)),
+            [s, a, w, text, ..] => match (number(s), number(a), number(w)) {
+                (Some(s), Some(a), Some(w)) => Some(((s, a, w - 1, None), *text)),
+                _ => None,
+            },
+            _ => None,
+        };
+        match location {
+            Some((location, text)) => out.push(gloss(location, source, lang, text)),
+            // A header line before the data.
+            None if !seen_data => {}
+            None => return Err(EngineError::Invalid(format!("line {}: expected a location and a gloss", n + 1))),
+        }
+        seen_data = true;
+    }
+    Ok(out)
+}
+
+/// Gloss text given as a string or as `{"text": ...}`.
+fn text_field(record: &Value) -> Option<&str> {
+    ["gloss", "text", "translation"].iter().find_map(|key| match record.get(*key) {
+        Some(Value::String(s)) => Some(s.as_str()),
+        Some(Value::Object(o)) => o.get("text").and_then(Value::as_str),
+        _ => None,
+    })
+}
+
+fn parse_json(text: &str, source: &str, lang: Option<&str>) -> EngineResult<Vec<Gloss>> {
+    let value: Value = serde_json::from_str(text).map_err(|e| EngineError::Invalid(format!("Invalid JSON: {}", e)))?;
+    let bad = |what: String| EngineError::Invalid(what);
+    let mut out = Vec::new();
+    match &value {
+        Value::Object(map) if map.get("verses").map(Value::is_array).unwrap_or(false) => {
+            for (i, verse) in map["verses"].as_array().into_iter().flatten().enumerate() {
+                let key = verse.get("verse_key").and_then(Value::as_str).unwrap_or_default();
+                let (surah, ayah) = common::parse_verse_ref(key)
+                    .map_err(|_| bad(format!("verse {}: invalid verse_key '{}'", i + 1, key)))?;
+                for word in verse.get("words").and_then(Value::as_array).into_iter().flatten() {
+                    // The verse-end marker is listed as a word.
+                    if word.get("char_type_name").and_then(Value::as_str) == Some("end") {
+                        continue;
+                    }
+                    let position = word.get("position").and_then(Value::as_i64).filter(|&p| p >= 1);
+                    if let (Some(position), Some(text)) = (position, text_field(word)) {
+                        out.push(gloss((surah, ayah, position - 1, None), source, lang, text));
+                    }
+                }
+            }
+        }
+        Value::Object(map) => {
+            for (location, text) in map {
+                let parsed = parse_location(location).ok_or_else(|| bad(format!("invalid location '{}'", location)))?;
+                let text = text.as_str().ok_or_else(|| bad(format!("{}: gloss must be a string", location)))?;
+                out.push(gloss(parsed, source, lang, text));
+            }
+        }
+        Value::Array(records) => {
+            for (i, record) in records.iter().enumerate() {
+                let location = match record.get("location").and_then(Value::as_str) {
+                    Some(location) => parse_location(location),
+                    None => {
+                        let field = |key: &str| record.get(key).and_then(Value::as_i64).filter(|&n| n >= 1);
+                        match (field("surah"), field("ayah"), field("word").or_else(|| field("position"))) {
+                            (Some(s), Some(a), Some(w)) => Some((s, a, w - 1, field("segment").map(|m| m - 1))),
+                            _ => None,
+                        }
+                    }
+                };
+                let location = location.ok_or_else(|| bad(format!("entry {}: missing or invalid location", i + 1)))?;
+                let text = text_field(record).ok_or_else(|| bad(format!("entry {}: missing gloss text", i + 1)))?;
+                let lang = record.get("lang").and_then(Value::as_str).or(lang);
+                out.push(gloss(location, source, lang, text));
+            }
+        }
+        _ => return Err(bad("expected a JSON list or object of glosses".into())),
+    }
+    Ok(out)
+}
+
+/// Parse an alignment file. Entries without a `source` take `default_source`.
+pub fn parse_alignments(text: &str, default_source: &str) -> EngineResult<Vec<TranslationAlignment>> {
+    let text = text.trim_start_matches('\u{feff}').trim();
+    let entries: Vec<Value> = if text.starts_with('[') {
+        serde_json::from_str(text).map_err(|e| EngineError::Invalid(format!("Invalid JSON: {}", e)))?
+    } else {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .enumerate()
+            .map(|(i, l)| serde_json::from_str(l).map_err(|e| EngineError::Invalid(format!("line {}: {}", i + 1, e))))
+            .collect::<EngineResult<_>>()?
+    };
+
+    let mut out = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let bad = |what: &str| EngineError::Invalid(format!("entry {}: {}", i + 1, what));
+        let verse = match entry.get("verse").and_then(Value::as_str) {
+            Some(verse) => common::parse_verse_ref(verse).ok(),
+            None => entry.get("surah").and_then(Value::as_i64).zip(entry.get("ayah").and_then(Value::as_i64)),
+        };
+        let (surah, ayah) = verse.ok_or_else(|| bad("missing or invalid verse"))?;
+        let alignment = TranslationAlignment {
+            surah,
+            ayah,
+            source: entry.get("source").and_then(Value::as_str).unwrap_or(default_source).to_string(),
+            text: entry.get("text").and_then(Value::as_str).ok_or_else(|| bad("missing text"))?.to_string(),
+            links: match entry.get("links") {
+                Some(links) => serde_json::from_value(links.clone()).map_err(|e| bad(&e.to_string()))?,
+                None => Vec::new(),
+            },
+        };
+        let words = alignment.words().len();
+        if let Some(word) = alignment.links.iter().flat_map(|l| l.words.iter()).find(|&&w| w >= words) {
+            return Err(bad(&format!("word {} is past the end of the translation ({} words)", word, words)));
+        }
+        out.push(alignment);
+    }
+    Ok(out)
+}
+
+fn normalize(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Align a verse translation to tokens from their glosses: a translation
+/// word goes with every token whose gloss contains it. Common words (`the`,
+/// `of`, ...) only join a token next to a word already aligned to it. Tokens
+/// aligned to the same words share a link.
+pub fn align_translation(text: &str, glosses: &[Gloss]) -> Vec<AlignmentLink> {
+    let words: Vec<String> = text.split_whitespace().map(normalize).collect();
+    let mut vocab: BTreeMap<i64, BTreeSet<String>> = BTreeMap::new();
+    for g in glosses {
+        vocab.entry(g.token_index).or_default().extend(g.text.split_whitespace().map(normalize));
+    }
+
+    let mut aligned: BTreeMap<i64, BTreeSet<usize>> = BTreeMap::new();
+    for (token, gloss_words) in &vocab {
+        let mut set: BTreeSet<usize> = words
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| !w.is_empty() && !STOPWORDS.contains(&w.as_str()) && gloss_words.contains(*w))
+            .map(|(i, _)| i)
+            .collect();
+        // Grow through neighbouring common words until nothing changes.
+        loop {
+            let next: Vec<usize> = words
+                .iter()
+                .enumerate()
+                .filter(|(i, w)| {
+                    !set.contains(i)
+                        && STOPWORDS.contains(&w.as_str())
+                        && gloss_words.contains(*w)
+                        && ((*i > 0 && set.contains(&(i - 1))) || set.contains(&(i + 1)))
+                })
+                .map(|(i, _)| i)
+                .collect();
+            if next.is_empty() {
+                break;
+            }
+            set.extend(next);
+        }
+        if !set.is_empty() {
+            aligned.insert(*token, set);
+        }
+    }
+
+    let mut links: Vec<AlignmentLink> = Vec::new();
+    for (token, set) in aligned {
+        let words: Vec<usize> = set.into_iter().collect();
+        match links.iter_mut().find(|l| l.words == words) {
+            Some(link) => link.tokens.push(token),
+            None => links.push(AlignmentLink { tokens: vec![token], words }),
+        }
+    }
+    links
+}
+
+/// Segment counts of a verse's tokens, by token index.
+async fn verse_tokens(conn: &mut SqliteConnection, surah: i64, ayah: i64) -> EngineResult<BTreeMap<i64, i64>> {
+    let rows = sqlx::query(
+        r#"SELECT t.token_index, COUNT(s.id) AS segments FROM tokens t
+           LEFT JOIN segments s ON s.token_id = t.id
+           WHERE t.verse_surah = ?1 AND t.verse_ayah = ?2
+           GROUP BY t.token_index"#,
+    )
+    .bind(surah)
+    .bind(ayah)
+    .fetch_all(conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(rows
+        .iter()
+        .map(|r| (r.try_get("token_index").unwrap_or_default(), r.try_get("segments").unwrap_or_default()))
+        .collect())
+}
+
+impl SqliteStorage {
+    /// Add or replace glosses; a gloss replaces the one its source gave the
+    /// same token or segment. Every gloss must name a token (and segment) of
+    /// the corpus. Returns the number written.
+    pub async fn import_glosses(&self, glosses: &[Gloss]) -> EngineResult<usize> {
+        let mut tx = self.begin_tx().await?;
+        let mut verses: BTreeMap<(i64, i64), BTreeMap<i64, i64>> = BTreeMap::new();
+        for g in glosses {
+            let tokens = match verses.entry((g.surah, g.ayah)) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(verse_tokens(&mut tx, g.surah, g.ayah).await?),
+            };
+            let known = match (tokens.get(&g.token_index), g.segment_index) {
+                (Some(&segments), Some(segment)) => segment < segments,
+                (found, _) => found.is_some(),
+            };
+            if !known {
+                let segment = g.segment_index.map(|m| format!(":{}", m + 1)).unwrap_or_default();
+                return Err(EngineError::Invalid(format!(
+                    "Gloss location ({}:{}:{}{}) is not in the corpus",
+                    g.surah,
+                    g.ayah,
+                    g.token_index + 1,
+                    segment
+                )));
+            }
+            sqlx::query(
+                r#"INSERT OR REPLACE INTO glosses (surah, ayah, token_index, segment_index, source, lang, text)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            )
+            .bind(g.surah)
+            .bind(g.ayah)
+            .bind(g.token_index)
+            .bind(g.segment_index.unwrap_or(-1))
+            .bind(&g.source)
+            .bind(g.lang.as_ref())
+            .bind(&g.text)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        }
+        Self::commit_tx(tx).await?;
+        Ok(glosses.len())
+    }
+
+    pub async fn get_glosses(&self, surah: i64, ayah: i64, source: Option<&str>) -> EngineResult<Vec<Gloss>> {
+        let rows = sqlx::query(
+            r#"SELECT token_index, segment_index, source, lang, text FROM glosses
+               WHERE surah = ?1 AND ayah = ?2 AND (?3 IS NULL OR source = ?3)
+               ORDER BY token_index, segment_index, source"#,
+        )
+        .bind(surah)
+        .bind(ayah)
+        .bind(source)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| Gloss {
+                surah,
+                ayah,
+                token_index: r.try_get("token_index").unwrap_or_default(),
+                segment_index: r.try_get::<i64, _>("segment_index").ok().filter(|&i| i >= 0),
+                source: r.try_get("source").unwrap_or_default(),
+                lang: r.try_get("lang").unwrap_or(None),
+                text: r.try_get("text").unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    pub async fn list_gloss_sources(&self) -> EngineResult<Vec<GlossSource>> {
+        let rows = sqlx::query(
+            r#"SELECT source, MAX(lang) AS lang, COUNT(*) AS glosses FROM glosses GROUP BY source ORDER BY source"#,
+        )
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| GlossSource {
+                source: r.try_get("source").unwrap_or_default(),
+                lang: r.try_get("lang").unwrap_or(None),
+                glosses: r.try_get("glosses").unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Add or replace verse alignments, one per verse and source. Returns the
+    /// number of links written.
+    pub async fn import_alignments(&self, alignments: &[TranslationAlignment]) -> EngineResult<usize> {
+        let mut tx = self.begin_tx().await?;
+        let mut links = 0;
+        for a in alignments {
+            sqlx::query(
+                r#"INSERT OR REPLACE INTO translation_alignments (surah, ayah, source, text, links)
+                   VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            )
+            .bind(a.surah)
+            .bind(a.ayah)
+            .bind(&a.source)
+            .bind(&a.text)
+            .bind(serde_json::to_string(&a.links).map_err(|e| EngineError::Other(e.into()))?)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+            links += a.links.len();
+        }
+        Self::commit_tx(tx).await?;
+        Ok(links)
+    }
+
+    pub async fn get_alignments(
+        &self,
+        surah: i64,
+        ayah: i64,
+        source: Option<&str>,
+    ) -> EngineResult<Vec<TranslationAlignment>> {
+        let rows = sqlx::query(
+            r#"SELECT source, text, links FROM translation_alignments
+               WHERE surah = ?1 AND ayah = ?2 AND (?3 IS NULL OR source = ?3) ORDER BY source"#,
+        )
+        .bind(surah)
+        .bind(ayah)
+        .bind(source)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| TranslationAlignment {
+                surah,
+                ayah,
+                source: r.try_get("source").unwrap_or_default(),
+                text: r.try_get("text").unwrap_or_default(),
+                links: r
+                    .try_get::<String, _>("links")
+                    .ok()
+                    .and_then(|links| serde_json::from_str(&links).ok())
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,430 @@
+//! The connections layer as a graph.
+//!
+//! Nodes are connection ends (anchors in canonical form, or opaque ids) and
+//! every live connection is an edge from `from_token` to `to_token`.
+//! Traversals follow edges in both directions unless asked to be directed.
+//! Subgraphs export to GraphML and DOT for Gephi/Graphviz, and to the JSON
+//! Graph Format.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use common::{Anchor, ConnectionRecord, EngineError, EngineResult};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{connection_from_row, SqliteStorage, CONNECTION_COLUMNS};
+
+/// Largest neighbourhood depth served, to keep responses bounded.
+pub const MAX_GRAPH_DEPTH: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GraphNode {
+    pub id: String,
+    /// Anchor kind (`token`, `verse_range`, ...) or `opaque`.
+    pub kind: String,
+    /// Text the node covers, when it has been resolved against the corpus.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// A set of nodes and the connections between them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Subgraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<ConnectionRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GraphPath {
+    /// Nodes from start to end, inclusive.
+    pub nodes: Vec<String>,
+    /// The connection crossed at each step.
+    pub edges: Vec<ConnectionRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NodeDegree {
+    pub node: String,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DegreeStats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub mean_degree: f64,
+    pub max_degree: usize,
+    /// Nodes by descending degree.
+    pub degrees: Vec<NodeDegree>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    Json,
+    GraphML,
+    Dot,
+}
+
+impl GraphFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Json => "application/json",
+            GraphFormat::GraphML => "application/graphml+xml",
+            GraphFormat::Dot => "text/vnd.graphviz",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Json => "json",
+            GraphFormat::GraphML => "graphml",
+            GraphFormat::Dot => "dot",
+        }
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> EngineResult<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" | "jgf" => Ok(GraphFormat::Json),
+            "graphml" => Ok(GraphFormat::GraphML),
+            "dot" | "gv" => Ok(GraphFormat::Dot),
+            other => Err(EngineError::Invalid(format!(
+                "Unknown graph format '{}' (expected json, graphml or dot)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Canonical node id: anchors in their normal string form, other ids as is.
+pub fn node_id(end: &str) -> String {
+    match Anchor::parse_target(end) {
+        Ok(Some(anchor)) => anchor.to_string(),
+        _ => end.trim().to_string(),
+    }
+}
+
+fn node(id: &str) -> GraphNode {
+    let kind = match Anchor::parse_target(id) {
+        Ok(Some(anchor)) => anchor.kind(),
+        _ => "opaque",
+    };
+    GraphNode { id: id.to_string(), kind: kind.to_string(), text: None }
+}
+
+/// Live connections held as an adjacency list.
+pub struct ConnectionGraph {
+    edges: Vec<ConnectionRecord>,
+    /// Node to (edge index, neighbour, edge points away from the node).
+    adjacency: BTreeMap<String, Vec<(usize, String, bool)>>,
+}
+
+impl ConnectionGraph {
+    pub fn new(edges: Vec<ConnectionRecord>) -> Self {
+        let mut adjacency: BTreeMap<String, Vec<(usize, String, bool)>> = BTreeMap::new();
+        for (i, edge) in edges.iter().enumerate() {
+            let (from, to) = (node_id(&edge.from_token), node_id(&edge.to_token));
+            adjacency.entry(from.clone()).or_default().push((i, to.clone(), true));
+            adjacency.entry(to).or_default().push((i, from, false));
+        }
+        Self { edges, adjacency }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn steps<'a>(&'a self, node: &str, directed: bool) -> impl Iterator<Item = &'a (usize, String, bool)> + 'a {
+        self.adjacency
+            .get(node)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+            .iter()
+            .filter(move |(_, _, outgoing)| !directed || *outgoing)
+    }
+
+    /// Everything within `depth` steps of `start`, with the edges among it.
+    pub fn neighbourhood(&self, start: &str, depth: usize, directed: bool) -> EngineResult<Subgraph> {
+        let start = node_id(start);
+        if !self.adjacency.contains_key(&start) {
+            return Err(EngineError::NotFound);
+        }
+        let depth = depth.min(MAX_GRAPH_DEPTH);
+        let mut seen: BTreeSet<String> = BTreeSet::from([start.clone()]);
+        let mut edges: BTreeSet<usize> = BTreeSet::new();
+        let mut queue = VecDeque::from([(start, 0usize)]);
+        while let Some((current, d)) = queue.pop_front() {
+            if d == depth {
+                continue;
+            }
+            for (edge, next, _) in self.steps(&current, directed) {
+                edges.insert(*edge);
+                if seen.insert(next.clone()) {
+                    queue.push_back((next.clone(), d + 1));
+                }
+            }
+        }
+        Ok(self.subgraph_of(&seen, edges))
+    }
+
+    /// Fewest-hop path between two nodes, if they are connected.
+    pub fn shortest_path(&self, from: &str, to: &str, directed: bool) -> EngineResult<Option<GraphPath>> {
+        let (from, to) = (node_id(from), node_id(to));
+        for end in [&from, &to] {
+            if !self.adjacency.contains_key(end) {
+                return Err(EngineError::NotFound);
+            }
+        }
+        let mut came_from: HashMap<String, (String, usize)> = HashMap::new();
+        let mut queue = VecDeque::from([from.clone()]);
+        let mut seen: BTreeSet<String> = BTreeSet::from([from.clone()]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                break;
+            }
+            for (edge, next, _) in self.steps(&current, directed) {
+                if seen.insert(next.clone()) {
+                    came_from.insert(next.clone(), (current.clone(), *edge));
+                    queue.push_back(next.clone());
+                }
+            }
+        }
+        if !seen.contains(&to) {
+            return Ok(None);
+        }
+        let mut nodes = vec![to.clone()];
+        let mut edges = Vec::new();
+        let mut current = to;
+        while let Some((prev, edge)) = came_from.get(&current) {
+            nodes.push(prev.clone());
+            edges.push(self.edges[*edge].clone());
+            current = prev.clone();
+        }
+        nodes.reverse();
+        edges.reverse();
+        Ok(Some(GraphPath { nodes, edges }))
+    }
+
+    /// Weakly connected components, largest first.
+    pub fn components(&self) -> Vec<Vec<String>> {
+        let mut seen: BTreeSet<&str> = BTreeSet::new();
+        let mut components = Vec::new();
+        for start in self.adjacency.keys() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let mut component = vec![start.clone()];
+            let mut stack = vec![start.as_str()];
+            while let Some(current) = stack.pop() {
+                for (_, next, _) in self.steps(current, false) {
+                    if seen.insert(next) {
+                        component.push(next.clone());
+                        stack.push(next);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        components
+    }
+
+    pub fn degree_stats(&self) -> DegreeStats {
+        let mut degrees: Vec<NodeDegree> = self
+            .adjacency
+            .iter()
+            .map(|(node, steps)| {
+                let out_degree = steps.iter().filter(|(_, _, outgoing)| *outgoing).count();
+                NodeDegree {
+                    node: node.clone(),
+                    in_degree: steps.len() - out_degree,
+                    out_degree,
+                    degree: steps.len(),
+                }
+            })
+            .collect();
+        degrees.sort_by(|a, b| b.degree.cmp(&a.degree).then_with(|| a.node.cmp(&b.node)));
+        let nodes = degrees.len();
+        DegreeStats {
+            nodes,
+            edges: self.edges.len(),
+            mean_degree: if nodes == 0 { 0.0 } else { 2.0 * self.edges.len() as f64 / nodes as f64 },
+            max_degree: degrees.first().map(|d| d.degree).unwrap_or(0),
+            degrees,
+        }
+    }
+
+    /// The whole graph as a subgraph.
+    pub fn to_subgraph(&self) -> Subgraph {
+        let nodes: BTreeSet<String> = self.adjacency.keys().cloned().collect();
+        self.subgraph_of(&nodes, (0..self.edges.len()).collect())
+    }
+
+    fn subgraph_of(&self, nodes: &BTreeSet<String>, edges: BTreeSet<usize>) -> Subgraph {
+        Subgraph {
+            nodes: nodes.iter().map(|id| node(id)).collect(),
+            edges: edges.into_iter().map(|i| self.edges[i].clone()).collect(),
+        }
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+impl Subgraph {
+    pub fn export(&self, format: GraphFormat) -> EngineResult<String> {
+        match format {
+            GraphFormat::Json => serde_json::to_string_pretty(&self.to_json_graph())
+                .map_err(|e| EngineError::Other(e.into())),
+            GraphFormat::GraphML => Ok(self.to_graphml()),
+            GraphFormat::Dot => Ok(self.to_dot()),
+        }
+    }
+
+    /// JSON Graph Format (v2) document.
+    pub fn to_json_graph(&self) -> serde_json::Value {
+        let nodes: serde_json::Map<String, serde_json::Value> = self
+            .nodes
+            .iter()
+            .map(|n| {
+                let mut value = serde_json::json!({ "label": n.text.as_deref().unwrap_or(&n.id), "metadata": { "kind": n.kind } });
+                if let Some(text) = &n.text {
+                    value["metadata"]["text"] = serde_json::json!(text);
+                }
+                (n.id.clone(), value)
+            })
+            .collect();
+        let edges: Vec<serde_json::Value> = self
+            .edges
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "id": e.id,
+                    "source": node_id(&e.from_token),
+                    "target": node_id(&e.to_token),
+                    "relation": e.layer,
+                    "directed": true,
+                    "metadata": { "author": e.author, "meta": e.meta }
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "graph": {
+                "directed": true,
+                "type": "kalima-connections",
+                "nodes": nodes,
+                "edges": edges
+            }
+        })
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+        out.push_str("  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+        out.push_str("  <key id=\"text\" for=\"node\" attr.name=\"text\" attr.type=\"string\"/>\n");
+        out.push_str("  <key id=\"layer\" for=\"edge\" attr.name=\"layer\" attr.type=\"string\"/>\n");
+        out.push_str("  <key id=\"author\" for=\"edge\" attr.name=\"author\" attr.type=\"string\"/>\n");
+        out.push_str("  <key id=\"meta\" for=\"edge\" attr.name=\"meta\" attr.type=\"string\"/>\n");
+        out.push_str("  <graph id=\"connections\" edgedefault=\"directed\">\n");
+        for n in &self.nodes {
+            let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(&n.id));
+            let _ = writeln!(out, "      <data key=\"label\">{}</data>", xml_escape(&n.id));
+            let _ = writeln!(out, "      <data key=\"kind\">{}</data>", xml_escape(&n.kind));
+            if let Some(text) = &n.text {
+                let _ = writeln!(out, "      <data key=\"text\">{}</data>", xml_escape(text));
+            }
+            out.push_str("    </node>\n");
+        }
+        for e in &self.edges {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+                xml_escape(&e.id),
+                xml_escape(&node_id(&e.from_token)),
+                xml_escape(&node_id(&e.to_token))
+            );
+            let _ = writeln!(out, "      <data key=\"layer\">{}</data>", xml_escape(&e.layer));
+            if let Some(author) = &e.author {
+                let _ = writeln!(out, "      <data key=\"author\">{}</data>", xml_escape(author));
+            }
+            if !e.meta.is_null() && e.meta != serde_json::json!({}) {
+                let _ = writeln!(out, "      <data key=\"meta\">{}</data>", xml_escape(&e.meta.to_string()));
+            }
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph connections {\n");
+        for n in &self.nodes {
+            let label = match &n.text {
+                Some(text) => format!("{}\\n{}", dot_escape(&n.id), dot_escape(text)),
+                None => dot_escape(&n.id),
+            };
+            let _ = writeln!(out, "  \"{}\" [label=\"{}\", kind=\"{}\"];", dot_escape(&n.id), label, n.kind);
+        }
+        for e in &self.edges {
+            let _ = writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [label=\"{}\", id=\"{}\"];",
+                dot_escape(&node_id(&e.from_token)),
+                dot_escape(&node_id(&e.to_token)),
+                dot_escape(&e.layer),
+                dot_escape(&e.id)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl SqliteStorage {
+    /// Live connections, optionally restricted to one layer and/or author.
+    pub async fn list_connections(
+        &self,
+        layer_filter: Option<&str>,
+        author_filter: Option<&str>,
+    ) -> EngineResult<Vec<ConnectionRecord>> {
+        let rows = sqlx::query(&format!(
+            r#"SELECT {} FROM connections
+               WHERE deleted_at IS NULL
+                 AND (?1 IS NULL OR layer = ?1)
+                 AND (?2 IS NULL OR author = ?2)
+               ORDER BY created_at, id"#,
+            CONNECTION_COLUMNS
+        ))
+        .bind(layer_filter)
+        .bind(author_filter)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows.iter().map(connection_from_row).collect())
+    }
+}
@@ -0,0 +1,371 @@
+//! Append-only change log for research records.
+//!
+//! Every write to annotations, connections, verse metadata entries (pronouns,
+//! hypotheses, translations), tags, tag assignments, notes and research data
+//! (patterns) records the before/after value of the affected record, so the
+//! evolution of an interpretation can be inspected and earlier versions
+//! restored.
+
+use common::{Annotation, ChangeRecord, EngineError, EngineResult, Note, TagAssignment};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::{Row, SqliteConnection};
+
+use crate::{layers, notes, tags, ConnectionRecord, SqliteStorage};
+
+pub const RECORD_ANNOTATION: &str = "annotation";
+pub const RECORD_CONNECTION: &str = "connection";
+pub const RECORD_PRONOUN: &str = "pronoun";
+pub const RECORD_HYPOTHESIS: &str = "hypothesis";
+pub const RECORD_TRANSLATION: &str = "translation";
+pub const RECORD_PATTERN: &str = "pattern";
+pub const RECORD_TAG: &str = "tag";
+pub const RECORD_TAG_ASSIGNMENT: &str = "tag_assignment";
+pub const RECORD_NOTE: &str = "note";
+pub const RECORD_RESEARCH_DATA: &str = "research_data";
+
+/// JSON form of a record as stored in the change log.
+///
+/// Timestamps are dropped so that rewriting identical content is a no-op and
+/// reverting to an old version does not resurrect old timestamps.
+pub(crate) fn snapshot<T: serde::Serialize>(record: &T) -> Value {
+    let mut value = serde_json::json!(record);
+    if let Some(obj) = value.as_object_mut() {
+        obj.remove("created_at");
+        obj.remove("updated_at");
+    }
+    value
+}
+
+/// `verse_metadata` columns and the record type each one's entries are logged under.
+pub(crate) const METADATA_FIELDS: [(&str, &str); 3] = [
+    ("pronouns", RECORD_PRONOUN),
+    ("hypotheses", RECORD_HYPOTHESIS),
+    ("translations", RECORD_TRANSLATION),
+];
+
+/// Record type used in the change log for a `verse_metadata` column.
+pub(crate) fn metadata_record_type(field: &str) -> EngineResult<&'static str> {
+    METADATA_FIELDS
+        .iter()
+        .find(|(column, _)| *column == field)
+        .map(|(_, record_type)| *record_type)
+        .ok_or_else(|| EngineError::Invalid(format!("Unknown metadata field: {}", field)))
+}
+
+/// `verse_metadata` column holding the entries of a record type.
+fn metadata_field(record_type: &str) -> EngineResult<&'static str> {
+    METADATA_FIELDS
+        .iter()
+        .find(|(_, rt)| *rt == record_type)
+        .map(|(column, _)| *column)
+        .ok_or_else(|| EngineError::Invalid(format!("Unknown record type: {}", record_type)))
+}
+
+/// Id of a verse metadata entry: its `id` (string or number), else its position.
+pub(crate) fn entry_key(i: usize, v: &Value) -> String {
+    v.get("id")
+        .and_then(|id| id.as_str().map(str::to_string).or_else(|| id.as_i64().map(|n| n.to_string())))
+        .unwrap_or_else(|| format!("#{}", i))
+}
+
+/// Derive the `S:A` verse reference from a verse or token id (`S:A` or `S:A:T`).
+pub(crate) fn verse_of(target: &str) -> Option<String> {
+    let mut parts = target.split(':');
+    let surah: i64 = parts.next()?.parse().ok()?;
+    let ayah: i64 = parts.next()?.parse().ok()?;
+    Some(format!("{}:{}", surah, ayah))
+}
+
+pub(crate) async fn record_change(
+    conn: &mut SqliteConnection,
+    record_type: &str,
+    record_id: &str,
+    verse_ref: Option<&str>,
+    actor: Option<&str>,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> EngineResult<()> {
+    let action = match (before, after) {
+        (None, None) => return Ok(()),
+        (Some(b), Some(a)) if b == a => return Ok(()),
+        (None, Some(_)) => "create",
+        (Some(_), None) => "delete",
+        (Some(_), Some(_)) => "update",
+    };
+    insert_change(conn, action, record_type, record_id, verse_ref, actor, before, after).await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn insert_change(
+    conn: &mut SqliteConnection,
+    action: &str,
+    record_type: &str,
+    record_id: &str,
+    verse_ref: Option<&str>,
+    actor: Option<&str>,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> EngineResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO change_log (record_type, record_id, verse_ref, action, actor, before_value, after_value)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        "#,
+    )
+    .bind(record_type)
+    .bind(record_id)
+    .bind(verse_ref)
+    .bind(action)
+    .bind(actor)
+    .bind(before)
+    .bind(after)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(())
+}
+
+/// Log per-entry changes between two JSON arrays whose entries carry an `id`.
+pub(crate) async fn record_entry_changes(
+    conn: &mut SqliteConnection,
+    record_type: &str,
+    verse_ref: &str,
+    actor: Option<&str>,
+    before: &[Value],
+    after: &[Value],
+) -> EngineResult<()> {
+    let before: Vec<(String, &Value)> = before.iter().enumerate().map(|(i, v)| (entry_key(i, v), v)).collect();
+    let after: Vec<(String, &Value)> = after.iter().enumerate().map(|(i, v)| (entry_key(i, v), v)).collect();
+
+    for (id, old) in &before {
+        let new = after.iter().find(|(k, _)| k == id).map(|(_, v)| *v);
+        record_change(conn, record_type, id, Some(verse_ref), actor, Some(old), new).await?;
+    }
+    for (id, new) in &after {
+        if !before.iter().any(|(k, _)| k == id) {
+            record_change(conn, record_type, id, Some(verse_ref), actor, None, Some(new)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Log per-key changes between two JSON objects (patterns keyed by id, tags keyed by name).
+pub(crate) async fn record_map_changes(
+    conn: &mut SqliteConnection,
+    record_type: &str,
+    actor: Option<&str>,
+    before: Option<&serde_json::Map<String, Value>>,
+    after: Option<&serde_json::Map<String, Value>>,
+) -> EngineResult<()> {
+    let empty = serde_json::Map::new();
+    let before = before.unwrap_or(&empty);
+    let after = after.unwrap_or(&empty);
+    for (id, old) in before {
+        record_change(conn, record_type, id, None, actor, Some(old), after.get(id)).await?;
+    }
+    for (id, new) in after {
+        if !before.contains_key(id) {
+            record_change(conn, record_type, id, None, actor, None, Some(new)).await?;
+        }
+    }
+    Ok(())
+}
+
+fn row_to_change(r: sqlx::sqlite::SqliteRow) -> EngineResult<ChangeRecord> {
+    Ok(ChangeRecord {
+        id: r.try_get("id").map_err(|e| EngineError::Storage(e.to_string()))?,
+        record_type: r.try_get("record_type").map_err(|e| EngineError::Storage(e.to_string()))?,
+        record_id: r.try_get("record_id").map_err(|e| EngineError::Storage(e.to_string()))?,
+        verse_ref: r.try_get("verse_ref").unwrap_or(None),
+        action: r.try_get("action").map_err(|e| EngineError::Storage(e.to_string()))?,
+        actor: r.try_get("actor").unwrap_or(None),
+        before: r.try_get("before_value").unwrap_or(None),
+        after: r.try_get("after_value").unwrap_or(None),
+        changed_at: r.try_get("changed_at").unwrap_or_default(),
+    })
+}
+
+impl SqliteStorage {
+    /// Full history of one record, oldest change first.
+    pub async fn list_record_history(
+        &self,
+        record_type: &str,
+        record_id: &str,
+    ) -> EngineResult<Vec<ChangeRecord>> {
+        let rows = sqlx::query(
+            r#"SELECT id, record_type, record_id, verse_ref, action, actor, before_value, after_value, changed_at
+               FROM change_log WHERE record_type = ?1 AND record_id = ?2
+               ORDER BY id"#,
+        )
+        .bind(record_type)
+        .bind(record_id)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        rows.into_iter().map(row_to_change).collect()
+    }
+
+    /// Every change touching records attached to a verse, oldest first.
+    pub async fn list_verse_history(&self, verse_ref: &str) -> EngineResult<Vec<ChangeRecord>> {
+        let rows = sqlx::query(
+            r#"SELECT id, record_type, record_id, verse_ref, action, actor, before_value, after_value, changed_at
+               FROM change_log WHERE verse_ref = ?1
+               ORDER BY id"#,
+        )
+        .bind(verse_ref)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        rows.into_iter().map(row_to_change).collect()
+    }
+
+    pub async fn get_change(&self, change_id: i64) -> EngineResult<Option<ChangeRecord>> {
+        let row = sqlx::query(
+            r#"SELECT id, record_type, record_id, verse_ref, action, actor, before_value, after_value, changed_at
+               FROM change_log WHERE id = ?1"#,
+        )
+        .bind(change_id)
+        .fetch_optional(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        row.map(row_to_change).transpose()
+    }
+
+    /// Restore a record to the version it had right after `change_id`.
+    ///
+    /// The restore is itself a regular write, so it shows up in the history.
+    /// Returns the restored value, or `None` if that version was a deletion.
+    pub async fn revert_record(
+        &self,
+        record_type: &str,
+        record_id: &str,
+        change_id: i64,
+        actor: Option<&str>,
+    ) -> EngineResult<Option<Value>> {
+        let change = self.get_change(change_id).await?.ok_or(EngineError::NotFound)?;
+        if change.record_type != record_type || change.record_id != record_id {
+            return Err(EngineError::Invalid(format!(
+                "Change {} does not belong to {} {}",
+                change_id, record_type, record_id
+            )));
+        }
+        let target = change.after.clone();
+        let mut tx = self.begin_tx().await?;
+        write_record(&mut tx, record_type, record_id, change.verse_ref.as_deref(), target.as_ref(), actor).await?;
+        Self::commit_tx(tx).await?;
+        Ok(target)
+    }
+}
+
+/// A stored version as the record it describes.
+fn parse_value<T: DeserializeOwned>(record_type: &str, record_id: &str, value: &Value) -> EngineResult<T> {
+    serde_json::from_value(value.clone())
+        .map_err(|e| EngineError::Invalid(format!("{} {}: {}", record_type, record_id, e)))
+}
+
+/// Set one research record to `value`, or delete it when `value` is `None`.
+///
+/// Goes through the regular write paths on `conn`, so the write is itself
+/// logged and joins whatever transaction `conn` is in. Verse metadata entries
+/// need the `verse_ref` they belong to.
+pub(crate) async fn write_record(
+    conn: &mut SqliteConnection,
+    record_type: &str,
+    record_id: &str,
+    verse_ref: Option<&str>,
+    value: Option<&Value>,
+    actor: Option<&str>,
+) -> EngineResult<()> {
+    match record_type {
+        RECORD_ANNOTATION => match value {
+            Some(v) => {
+                let ann: Annotation = parse_value(record_type, record_id, v)?;
+                layers::validate_annotation(&mut *conn, &ann).await?;
+                crate::write_annotation(conn, &ann, actor).await?;
+            }
+            None => crate::trash_annotation(conn, record_id, actor).await?,
+        },
+        RECORD_CONNECTION => match value {
+            Some(v) => {
+                let record: ConnectionRecord = parse_value(record_type, record_id, v)?;
+                crate::write_connection(conn, &record, actor).await?;
+            }
+            None => crate::trash_connection(conn, record_id, actor).await?,
+        },
+        RECORD_PRONOUN | RECORD_HYPOTHESIS | RECORD_TRANSLATION => {
+            let field = metadata_field(record_type)?;
+            let verse_ref =
+                verse_ref.ok_or_else(|| EngineError::Storage("change has no verse_ref".into()))?;
+            let mut entries = crate::fetch_verse_metadata(&mut *conn, verse_ref, field).await?;
+            let pos = entries
+                .iter()
+                .enumerate()
+                .position(|(i, e)| entry_key(i, e) == record_id);
+            match (value, pos) {
+                (Some(v), Some(i)) => entries[i] = v.clone(),
+                (Some(v), None) => entries.push(v.clone()),
+                (None, Some(i)) => {
+                    entries.remove(i);
+                }
+                (None, None) => {}
+            }
+            crate::write_verse_metadata(conn, verse_ref, field, &Value::Array(entries), actor).await?;
+        }
+        RECORD_PATTERN => {
+            let mut patterns = crate::fetch_research_data(&mut *conn, "patterns")
+                .await?
+                .unwrap_or_else(|| serde_json::json!({}));
+            if let Some(obj) = patterns.as_object_mut() {
+                match value {
+                    Some(v) => obj.insert(record_id.to_string(), v.clone()),
+                    None => obj.remove(record_id),
+                };
+            }
+            crate::write_research_data(conn, "patterns", &patterns, actor).await?;
+        }
+        RECORD_TAG => match value {
+            Some(v) => {
+                // Older versions keep the legacy shape, keyed by name.
+                if !v.is_object() {
+                    return Err(EngineError::Invalid(format!("{} {}: expected an object", record_type, record_id)));
+                }
+                let tag = tags::tag_from_value(record_id, v);
+                tags::write_tag(conn, &tag, actor).await?;
+            }
+            None => match tags::remove_tag(conn, record_id, actor).await {
+                Err(EngineError::NotFound) => {}
+                other => other?,
+            },
+        },
+        RECORD_TAG_ASSIGNMENT => match value {
+            Some(v) => {
+                let assignment: TagAssignment = parse_value(record_type, record_id, v)?;
+                tags::write_assignment(conn, &assignment, actor).await?;
+            }
+            None => tags::delete_assignment(conn, record_id, actor).await?,
+        },
+        RECORD_NOTE => match value {
+            Some(v) => {
+                let note: Note = parse_value(record_type, record_id, v)?;
+                notes::write_note(conn, &note, actor).await?;
+            }
+            None => match notes::remove_note(conn, record_id, actor).await {
+                Err(EngineError::NotFound) => {}
+                other => other?,
+            },
+        },
+        RECORD_RESEARCH_DATA => match value {
+            Some(v) => crate::write_research_data(conn, record_id, v, actor).await?,
+            None => {
+                return Err(EngineError::Unsupported(
+                    "Research data keys cannot be reverted to a deleted state".into(),
+                ))
+            }
+        },
+        other => {
+            return Err(EngineError::Invalid(format!("Unknown record type: {}", other)));
+        }
+    }
+    Ok(())
+}
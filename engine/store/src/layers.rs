@@ -0,0 +1,235 @@
+//! Registry of annotation layers.
+//!
+//! Each layer names the granularity of ids its annotations target and a JSON
+//! Schema for their payloads. Annotations written through
+//! [`SqliteStorage::upsert_annotation`] and [`SqliteStorage::replace_annotation`]
+//! are checked against their layer; layers that are not registered stay
+//! free-form.
+
+use common::{Anchor, Annotation, AnnotationLayer, EngineError, EngineResult, LAYER_TARGETS};
+use sqlx::{Pool, Row, Sqlite, SqliteConnection};
+
+use crate::SqliteStorage;
+
+/// Layers registered on first start. Redefining them through the API sticks;
+/// they are only inserted when missing.
+const DEFAULT_LAYERS: &str = r#"[
+  {
+    "name": "interpretation",
+    "description": "Free-text interpretation of a verse",
+    "target": "verse",
+    "schema": {
+      "type": "object",
+      "required": ["text"],
+      "properties": { "text": { "type": "string", "minLength": 1 } }
+    }
+  },
+  {
+    "name": "rhetoric",
+    "description": "Rhetorical device (balagha) observed in a verse or word",
+    "target": "any",
+    "schema": {
+      "type": "object",
+      "required": ["device"],
+      "properties": {
+        "device": { "type": "string", "minLength": 1 },
+        "note": { "type": "string" }
+      }
+    }
+  },
+  {
+    "name": "tajweed",
+    "description": "Tajweed rule applying to a word",
+    "target": "token",
+    "schema": {
+      "type": "object",
+      "required": ["rule"],
+      "properties": {
+        "rule": { "type": "string", "minLength": 1 },
+        "letters": { "type": "string" },
+        "note": { "type": "string" }
+      }
+    }
+  }
+]"#;
+
+const LAYER_COLUMNS: &str = "name, description, target, schema, created_at, updated_at";
+
+fn layer_from_row(row: &sqlx::sqlite::SqliteRow) -> AnnotationLayer {
+    AnnotationLayer {
+        name: row.try_get("name").unwrap_or_default(),
+        description: row.try_get::<Option<String>, _>("description").unwrap_or(None).unwrap_or_default(),
+        target: row.try_get("target").unwrap_or_else(|_| "any".into()),
+        schema: row.try_get("schema").unwrap_or(serde_json::json!({})),
+        created_at: row.try_get("created_at").unwrap_or(None),
+        updated_at: row.try_get("updated_at").unwrap_or(None),
+    }
+}
+
+pub(crate) async fn seed_default_layers(pool: &Pool<Sqlite>) -> EngineResult<()> {
+    let layers: Vec<AnnotationLayer> =
+        serde_json::from_str(DEFAULT_LAYERS).map_err(|e| EngineError::Storage(e.to_string()))?;
+    for layer in layers {
+        sqlx::query(
+            r#"INSERT OR IGNORE INTO annotation_layers (name, description, target, schema)
+               VALUES (?1, ?2, ?3, ?4)"#,
+        )
+        .bind(&layer.name)
+        .bind(&layer.description)
+        .bind(&layer.target)
+        .bind(&layer.schema)
+        .execute(pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Check a layer definition: a usable name, a known target and a schema that
+/// is itself valid JSON Schema.
+pub fn validate_layer(layer: &AnnotationLayer) -> EngineResult<()> {
+    if layer.name.trim().is_empty() {
+        return Err(EngineError::validation(
+            "Layer name must not be empty",
+            serde_json::json!({ "field": "name" }),
+        ));
+    }
+    if !LAYER_TARGETS.contains(&layer.target.as_str()) {
+        return Err(EngineError::validation(
+            format!("Unknown layer target '{}' (expected one of {})", layer.target, LAYER_TARGETS.join(", ")),
+            serde_json::json!({ "field": "target", "value": layer.target, "allowed": LAYER_TARGETS }),
+        ));
+    }
+    jsonschema::validator_for(&layer.schema).map_err(|e| {
+        EngineError::validation(
+            format!("Invalid schema for layer '{}': {}", layer.name, e),
+            serde_json::json!({ "field": "schema" }),
+        )
+    })?;
+    Ok(())
+}
+
+/// Check an annotation against its layer's target granularity and schema.
+pub fn check_annotation(layer: &AnnotationLayer, annotation: &Annotation) -> EngineResult<()> {
+    if layer.target != "any" {
+        // `verse` and `token` layers also take ranges of verses and tokens.
+        let fits = Anchor::parse_target(&annotation.target_id)
+            .ok()
+            .flatten()
+            .map(|a| a.kind().strip_suffix("_range").unwrap_or(a.kind()) == layer.target)
+            .unwrap_or(false);
+        if !fits {
+            return Err(EngineError::validation(
+                format!(
+                    "Layer '{}' annotates {} anchors, but target '{}' is not one",
+                    layer.name, layer.target, annotation.target_id
+                ),
+                serde_json::json!({ "field": "target_id", "layer": layer.name, "value": annotation.target_id }),
+            ));
+        }
+    }
+
+    let validator = jsonschema::validator_for(&layer.schema)
+        .map_err(|e| EngineError::Invalid(format!("Invalid schema for layer '{}': {}", layer.name, e)))?;
+    let errors: Vec<(String, String)> = validator
+        .iter_errors(&annotation.payload)
+        .map(|e| (e.instance_path.to_string(), e.to_string()))
+        .collect();
+    if !errors.is_empty() {
+        let summary: Vec<String> = errors
+            .iter()
+            .map(|(path, msg)| if path.is_empty() { msg.clone() } else { format!("{}: {}", path, msg) })
+            .collect();
+        let details: Vec<serde_json::Value> = errors
+            .iter()
+            .map(|(path, msg)| serde_json::json!({ "path": path, "message": msg }))
+            .collect();
+        return Err(EngineError::validation(
+            format!("Payload does not match layer '{}': {}", layer.name, summary.join("; ")),
+            serde_json::json!({ "field": "payload", "layer": layer.name, "errors": details }),
+        ));
+    }
+    Ok(())
+}
+
+/// Validate `annotation` if its layer is registered.
+pub(crate) async fn validate_annotation(conn: &mut SqliteConnection, annotation: &Annotation) -> EngineResult<()> {
+    match fetch_layer(&mut *conn, &annotation.layer).await? {
+        Some(layer) => check_annotation(&layer, annotation),
+        None => Ok(()),
+    }
+}
+
+async fn fetch_layer<'e, E>(executor: E, name: &str) -> EngineResult<Option<AnnotationLayer>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(&format!("SELECT {} FROM annotation_layers WHERE name = ?1", LAYER_COLUMNS))
+        .bind(name)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(row.as_ref().map(layer_from_row))
+}
+
+impl SqliteStorage {
+    pub async fn list_layers(&self) -> EngineResult<Vec<AnnotationLayer>> {
+        let rows = sqlx::query(&format!("SELECT {} FROM annotation_layers ORDER BY name", LAYER_COLUMNS))
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows.iter().map(layer_from_row).collect())
+    }
+
+    pub async fn get_layer(&self, name: &str) -> EngineResult<Option<AnnotationLayer>> {
+        fetch_layer(self.pool(), name).await
+    }
+
+    pub async fn upsert_layer(&self, layer: &AnnotationLayer) -> EngineResult<()> {
+        validate_layer(layer)?;
+        sqlx::query(
+            r#"
+            INSERT INTO annotation_layers (name, description, target, schema, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+            ON CONFLICT(name) DO UPDATE SET
+              description=excluded.description,
+              target=excluded.target,
+              schema=excluded.schema,
+              updated_at=CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(&layer.name)
+        .bind(&layer.description)
+        .bind(&layer.target)
+        .bind(&layer.schema)
+        .execute(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn delete_layer(&self, name: &str) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        let in_use: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM annotations WHERE layer = ?1 AND deleted_at IS NULL")
+                .bind(name)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if in_use > 0 {
+            return Err(EngineError::Conflict(format!(
+                "Layer '{}' still has {} annotations",
+                name, in_use
+            )));
+        }
+        let result = sqlx::query("DELETE FROM annotation_layers WHERE name = ?1")
+            .bind(name)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if result.rows_affected() == 0 {
+            return Err(EngineError::NotFound);
+        }
+        Self::commit_tx(tx).await
+    }
+}
@@ -0,0 +1,350 @@
+//! Root, lemma and part-of-speech lexicon from the Quranic Arabic Corpus
+//! tables in `datasets/Quranic` (`CARootLexicon.csv`, `CALemmaLexicon.csv`,
+//! `CAPoS.csv`).
+//!
+//! Lexicon entries carry a Buckwalter and an Arabic form. Segments may spell
+//! roots and lemmas either way (roots also letter by letter with spaces), so
+//! lookups match a segment against every spelling of an entry.
+
+use common::{
+    decode_text, tsv_rows, EngineError, EngineResult, LemmaDetail, LemmaSummary, PosCount, RootDetail, RootSummary,
+    BUNDLED_POS,
+};
+use sqlx::Row;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::SqliteStorage;
+
+const BUNDLED_ROOTS: &[u8] = include_bytes!("../../../datasets/Quranic/CARootLexicon.csv");
+const BUNDLED_LEMMAS: &[u8] = include_bytes!("../../../datasets/Quranic/CALemmaLexicon.csv");
+
+/// One lexicon row: Buckwalter form, Arabic form, occurrences in the corpus
+/// the table was built from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexiconEntry {
+    pub form: String,
+    pub form_ar: String,
+    pub size: i64,
+}
+
+/// A part-of-speech tag with its Arabic and English names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PosTag {
+    pub tag: String,
+    pub name_ar: String,
+    pub name_en: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Lexicon {
+    pub roots: Vec<LexiconEntry>,
+    pub lemmas: Vec<LexiconEntry>,
+    pub pos: Vec<PosTag>,
+}
+
+fn entries(text: &str) -> Vec<LexiconEntry> {
+    tsv_rows(text)
+        .into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [form, form_ar, size, ..] if !form.is_empty() => Some(LexiconEntry {
+                form: form.to_string(),
+                form_ar: form_ar.to_string(),
+                size: size.parse().unwrap_or(0),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Lexicon {
+    /// Build from the contents of the root (`root, root_ar, size`), lemma
+    /// (`lemma, lemma_ar, size`) and POS (`pid, pos, pos_ar, pos_en`) tables.
+    pub fn from_tables(roots: &str, lemmas: &str, pos: &str) -> Self {
+        Lexicon {
+            roots: entries(roots),
+            lemmas: entries(lemmas),
+            pos: tsv_rows(pos)
+                .into_iter()
+                .filter_map(|row| match row.as_slice() {
+                    [_, tag, ar, en, ..] => Some(PosTag {
+                        tag: tag.to_string(),
+                        name_ar: ar.to_string(),
+                        name_en: en.to_string(),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// The tables bundled with the engine.
+    pub fn bundled() -> EngineResult<Self> {
+        Ok(Self::from_tables(
+            &decode_text(BUNDLED_ROOTS)?,
+            &decode_text(BUNDLED_LEMMAS)?,
+            &decode_text(BUNDLED_POS)?,
+        ))
+    }
+
+    /// Read the three tables from a directory laid out like `datasets/Quranic`.
+    pub fn from_dir(dir: &Path) -> EngineResult<Self> {
+        let read = |name: &str| -> EngineResult<String> {
+            let bytes = std::fs::read(dir.join(name))
+                .map_err(|e| EngineError::Invalid(format!("{}: {}", dir.join(name).display(), e)))?;
+            decode_text(&bytes)
+        };
+        Ok(Self::from_tables(
+            &read("CARootLexicon.csv")?,
+            &read("CALemmaLexicon.csv")?,
+            &read("CAPoS.csv")?,
+        ))
+    }
+}
+
+/// Arabic root written letter by letter, as some corpora store `segments.root`.
+fn spaced(root_ar: &str) -> String {
+    root_ar.chars().map(String::from).collect::<Vec<_>>().join(" ")
+}
+
+fn unspaced(s: &str) -> String {
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+impl SqliteStorage {
+    /// Replace the lexicon tables in one transaction.
+    pub async fn load_lexicon(&self, lexicon: &Lexicon) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        for table in ["lexicon_roots", "lexicon_lemmas", "lexicon_pos"] {
+            sqlx::query(&format!("DELETE FROM {}", table))
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+        }
+        for (table, key, entries) in [
+            ("lexicon_roots", "root", &lexicon.roots),
+            ("lexicon_lemmas", "lemma", &lexicon.lemmas),
+        ] {
+            for entry in entries {
+                sqlx::query(&format!(
+                    "INSERT OR REPLACE INTO {} ({}, {}_ar, size) VALUES (?1, ?2, ?3)",
+                    table, key, key
+                ))
+                .bind(&entry.form)
+                .bind(&entry.form_ar)
+                .bind(entry.size)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+            }
+        }
+        for pos in &lexicon.pos {
+            sqlx::query("INSERT OR REPLACE INTO lexicon_pos (tag, name_ar, name_en) VALUES (?1, ?2, ?3)")
+                .bind(&pos.tag)
+                .bind(&pos.name_ar)
+                .bind(&pos.name_en)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+        }
+        Self::commit_tx(tx).await
+    }
+
+    /// Segment counts per distinct value of a segment column.
+    async fn segment_counts(&self, column: &str) -> EngineResult<HashMap<String, i64>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {0} AS value, COUNT(*) AS n FROM segments WHERE {0} IS NOT NULL GROUP BY {0}",
+            column
+        ))
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| (r.try_get("value").unwrap_or_default(), r.try_get("n").unwrap_or(0)))
+            .collect())
+    }
+
+    /// Roots that occur in the corpus, with their lexicon spelling where the
+    /// lexicon lists them. Lexicon roots the corpus never uses are left out.
+    pub async fn list_roots(&self) -> EngineResult<Vec<RootSummary>> {
+        let mut counts = self.segment_counts("root").await?;
+        let rows = sqlx::query("SELECT root, root_ar FROM lexicon_roots ORDER BY root")
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        let mut roots: Vec<RootSummary> = rows
+            .iter()
+            .map(|r| {
+                let root: String = r.try_get("root").unwrap_or_default();
+                let root_ar: String = r.try_get("root_ar").unwrap_or_default();
+                let frequency = [root.clone(), spaced(&root_ar), root_ar.clone()]
+                    .iter()
+                    .filter_map(|k| counts.remove(k))
+                    .sum();
+                RootSummary { root, root_ar: Some(root_ar), frequency }
+            })
+            .collect();
+        roots.retain(|r| r.frequency > 0);
+        // Roots the corpus uses but the lexicon does not list.
+        roots.extend(counts.into_iter().map(|(root, frequency)| RootSummary { root, root_ar: None, frequency }));
+        roots.sort_by(|a, b| a.root.cmp(&b.root));
+        Ok(roots)
+    }
+
+    /// Lexicon row matching any spelling of `value`.
+    async fn lexicon_entry(&self, table: &str, key: &str, value: &str) -> EngineResult<Option<LexiconEntry>> {
+        let row = sqlx::query(&format!(
+            "SELECT {0} AS form, {0}_ar AS form_ar, size FROM {1} WHERE {0} = ?1 OR {0}_ar = ?1 OR {0}_ar = ?2 LIMIT 1",
+            key, table
+        ))
+        .bind(value)
+        .bind(unspaced(value))
+        .fetch_optional(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(row.map(|r| LexiconEntry {
+            form: r.try_get("form").unwrap_or_default(),
+            form_ar: r.try_get("form_ar").unwrap_or_default(),
+            size: r.try_get("size").unwrap_or(0),
+        }))
+    }
+
+    /// Segment counts of `group_by` values among segments whose `column`
+    /// is one of `keys`, most frequent first. Each value is resolved to the
+    /// `table` entry spelled that way, if any, and counts of different
+    /// spellings of one entry are merged; all in one query.
+    async fn grouped_entries(
+        &self,
+        column: &str,
+        keys: &[String],
+        group_by: &str,
+        table: &str,
+        key: &str,
+    ) -> EngineResult<Vec<(String, Option<String>, i64)>> {
+        let rows = sqlx::query(&format!(
+            r#"WITH counts AS (
+                 SELECT {1} AS value, COUNT(*) AS n FROM segments
+                 WHERE {0} IN (?1, ?2, ?3) AND {1} IS NOT NULL
+                 GROUP BY {1}
+               )
+               SELECT COALESCE(e.{3}, c.value) AS form, e.{3}_ar AS form_ar, SUM(c.n) AS n
+               FROM counts c
+               LEFT JOIN {2} e ON e.rowid = (
+                 SELECT rowid FROM {2}
+                 WHERE {3} = c.value OR {3}_ar = c.value OR {3}_ar = REPLACE(c.value, ' ', '')
+                 LIMIT 1
+               )
+               GROUP BY form, form_ar ORDER BY n DESC, form"#,
+            column, group_by, table, key
+        ))
+        .bind(&keys[0])
+        .bind(keys.get(1).unwrap_or(&keys[0]))
+        .bind(keys.get(2).unwrap_or(&keys[0]))
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| {
+                (
+                    r.try_get("form").unwrap_or_default(),
+                    r.try_get("form_ar").unwrap_or(None),
+                    r.try_get("n").unwrap_or(0),
+                )
+            })
+            .collect())
+    }
+
+    async fn count_where_in(&self, column: &str, keys: &[String]) -> EngineResult<i64> {
+        sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM segments WHERE {} IN (?1, ?2, ?3)", column))
+            .bind(&keys[0])
+            .bind(keys.get(1).unwrap_or(&keys[0]))
+            .bind(keys.get(2).unwrap_or(&keys[0]))
+            .fetch_one(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))
+    }
+
+    pub async fn get_root(&self, root: &str) -> EngineResult<Option<RootDetail>> {
+        let entry = self.lexicon_entry("lexicon_roots", "root", root).await?;
+        let keys = match &entry {
+            Some(e) => vec![e.form.clone(), e.form_ar.clone(), spaced(&e.form_ar)],
+            None => vec![root.to_string()],
+        };
+        let frequency = self.count_where_in("root", &keys).await?;
+        if entry.is_none() && frequency == 0 {
+            return Ok(None);
+        }
+
+        let mut lemmas: Vec<LemmaSummary> = self
+            .grouped_entries("root", &keys, "lemma", "lexicon_lemmas", "lemma")
+            .await?
+            .into_iter()
+            .map(|(lemma, lemma_ar, frequency)| LemmaSummary { lemma, lemma_ar, frequency })
+            .collect();
+        lemmas.sort_by(|a, b| b.frequency.cmp(&a.frequency).then_with(|| a.lemma.cmp(&b.lemma)));
+
+        Ok(Some(RootDetail {
+            summary: RootSummary {
+                root: entry.as_ref().map(|e| e.form.clone()).unwrap_or_else(|| root.to_string()),
+                root_ar: entry.as_ref().map(|e| e.form_ar.clone()),
+                frequency,
+            },
+            lexicon_frequency: entry.map(|e| e.size),
+            lemmas,
+        }))
+    }
+
+    pub async fn get_lemma(&self, lemma: &str) -> EngineResult<Option<LemmaDetail>> {
+        let entry = self.lexicon_entry("lexicon_lemmas", "lemma", lemma).await?;
+        let keys = match &entry {
+            Some(e) => vec![e.form.clone(), e.form_ar.clone()],
+            None => vec![lemma.to_string()],
+        };
+        let frequency = self.count_where_in("lemma", &keys).await?;
+        if entry.is_none() && frequency == 0 {
+            return Ok(None);
+        }
+
+        let roots: Vec<RootSummary> = self
+            .grouped_entries("lemma", &keys, "root", "lexicon_roots", "root")
+            .await?
+            .into_iter()
+            .map(|(root, root_ar, frequency)| RootSummary { root, root_ar, frequency })
+            .collect();
+
+        let rows = sqlx::query(
+            r#"SELECT s.pos AS tag, p.name_en, p.name_ar, COUNT(*) AS n
+               FROM segments s LEFT JOIN lexicon_pos p ON p.tag = s.pos
+               WHERE s.lemma IN (?1, ?2, ?3) AND s.pos IS NOT NULL
+               GROUP BY s.pos ORDER BY n DESC, s.pos"#,
+        )
+        .bind(&keys[0])
+        .bind(keys.get(1).unwrap_or(&keys[0]))
+        .bind(keys.get(2).unwrap_or(&keys[0]))
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        let pos = rows
+            .iter()
+            .map(|r| PosCount {
+                pos: r.try_get("tag").unwrap_or_default(),
+                name_en: r.try_get("name_en").unwrap_or(None),
+                name_ar: r.try_get("name_ar").unwrap_or(None),
+                frequency: r.try_get("n").unwrap_or(0),
+            })
+            .collect();
+
+        Ok(Some(LemmaDetail {
+            summary: LemmaSummary {
+                lemma: entry.as_ref().map(|e| e.form.clone()).unwrap_or_else(|| lemma.to_string()),
+                lemma_ar: entry.as_ref().map(|e| e.form_ar.clone()),
+                frequency,
+            },
+            lexicon_frequency: entry.map(|e| e.size),
+            roots,
+            pos,
+        }))
+    }
+}
@@ -2,21 +2,62 @@
 //! Normalized schema for surahs/verses/tokens/segments/annotations/connections,
 //! plus JSON payloads for SegmentView as a denormalized view.
 
+mod anchors;
+mod backend;
+mod backup;
+mod bundle;
+mod editions;
+mod glosses;
+mod graph;
+mod history;
+mod layers;
+mod lexicon;
+mod metadata;
+mod notes;
+mod tags;
+mod trash;
+mod treebank;
+
+pub use backup::{
+    find_snapshot, list_snapshots, prune_snapshots, snapshot_path, SnapshotInfo, DEFAULT_BACKUP_KEEP, SNAPSHOT_PREFIX,
+};
+pub use bundle::{
+    BundleRecord, ConflictStrategy, MergeConflict, MergeReport, ResearchBundle, BUNDLE_FORMAT, BUNDLE_VERSION,
+};
+pub use editions::{align_words, diff_words, skeleton, PRIMARY_EDITION};
+pub use glosses::{align_translation, parse_alignments, parse_glosses, GlossFormat};
+pub use graph::{
+    node_id, ConnectionGraph, DegreeStats, GraphFormat, GraphNode, GraphPath, NodeDegree, Subgraph, MAX_GRAPH_DEPTH,
+};
+pub use history::{
+    RECORD_ANNOTATION, RECORD_CONNECTION, RECORD_HYPOTHESIS, RECORD_NOTE, RECORD_PATTERN,
+    RECORD_PRONOUN, RECORD_RESEARCH_DATA, RECORD_TAG, RECORD_TAG_ASSIGNMENT, RECORD_TRANSLATION,
+};
+pub use layers::{check_annotation, validate_layer};
+pub use lexicon::{Lexicon, LexiconEntry, PosTag};
+pub use metadata::{QuranMetadata, SurahMetadata, DIVISION_KINDS};
+pub use notes::{note_title, parse_note_links};
+pub use tags::tag_from_value;
+pub use trash::DEFAULT_TRASH_RETENTION_DAYS;
+pub use common::{decode_text, tsv_rows};
+pub use treebank::{parse_treebank, NODE_ELIDED, NODE_PHRASE, NODE_SEGMENT};
+// Record types now live in `common` next to the storage traits; re-exported
+// so existing `store::` paths keep working.
+pub use common::{
+    AlignedWord, ChangeRecord, ConnectionRecord, Division, Edition, SurahSummary, TextChange, TrashItem, VerseDiff,
+};
+
 use async_trait::async_trait;
-use common::{parse_verse_ref, EngineError, EngineResult, SearchHit, Segment, SegmentView, StorageBackend};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite};
+use common::{
+    parse_verse_ref, EngineError, EngineResult, SearchHit, Segment, SegmentView, StorageBackend, SurahInfo, Token, Verse,
+    VerseSegment,
+};
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Row, Sqlite, SqliteConnection};
 
 pub struct SqliteStorage {
     pool: Pool<Sqlite>,
 }
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
-pub struct SurahSummary {
-    pub number: i64,
-    pub name: String,
-    pub ayah_count: i64,
-}
-
 impl SqliteStorage {
     pub fn pool(&self) -> &Pool<Sqlite> {
         &self.pool
@@ -59,261 +100,195 @@ impl SqliteStorage {
             .await
             .map_err(|e| EngineError::Storage(e.to_string()))?;
 
+        // Columns added after the initial schema; databases created by older
+        // builds are upgraded in place.
+        ensure_column(&pool, "annotations", "deleted_at", "TEXT").await?;
+        ensure_column(&pool, "connections", "deleted_at", "TEXT").await?;
+        ensure_column(&pool, "annotations", "author", "TEXT").await?;
+        ensure_column(&pool, "annotations", "updated_at", "TEXT").await?;
+        ensure_column(&pool, "connections", "author", "TEXT").await?;
+        ensure_column(&pool, "connections", "created_at", "TEXT").await?;
+        ensure_column(&pool, "connections", "updated_at", "TEXT").await?;
+        ensure_column(&pool, "surahs", "name_translit", "TEXT").await?;
+        ensure_column(&pool, "surahs", "name_en", "TEXT").await?;
+        ensure_column(&pool, "surahs", "revelation_place", "TEXT").await?;
+        ensure_column(&pool, "surahs", "revelation_order", "INTEGER").await?;
+        ensure_column(&pool, "surahs", "ayah_count", "INTEGER").await?;
+        ensure_column(&pool, "annotations", "anchor_start", "INTEGER").await?;
+        ensure_column(&pool, "annotations", "anchor_end", "INTEGER").await?;
+        ensure_column(&pool, "connections", "from_start", "INTEGER").await?;
+        ensure_column(&pool, "connections", "from_end", "INTEGER").await?;
+        ensure_column(&pool, "connections", "to_start", "INTEGER").await?;
+        ensure_column(&pool, "connections", "to_end", "INTEGER").await?;
+        sqlx::query(MIGRATION_INDEXES)
+            .execute(&pool)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        anchors::backfill_spans(&pool).await?;
+        layers::seed_default_layers(&pool).await?;
+        tags::migrate_legacy_tags(&pool).await?;
+
         Ok(Self { pool })
     }
 
+    /// Upsert a single token and its segments atomically.
+    ///
+    /// For bulk loads prefer [`SqliteStorage::begin_batch`] or
+    /// [`SqliteStorage::upsert_many`], which share one transaction across many tokens.
     pub async fn upsert_segment(&self, doc: &SegmentView) -> EngineResult<()> {
-        // Upsert surah and verse metadata
-        let (surah_num, ayah_num) = parse_verse_ref(&doc.verse_ref)?;
-        sqlx::query(
-            r#"INSERT OR IGNORE INTO surahs (number, name) VALUES (?1, ?2)"#,
-        )
-        .bind(surah_num as i64)
-        .bind("") // name unknown in current payload
-        .execute(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-
-        sqlx::query(
-            r#"
-            INSERT INTO verses (surah_number, ayah_number)
-            VALUES (?1, ?2)
-            ON CONFLICT(surah_number, ayah_number) DO NOTHING;
-            "#,
-        )
-        .bind(surah_num as i64)
-        .bind(ayah_num as i64)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-
-        // Note: verse text is managed by the caller (ingest.rs) to avoid overwriting
-        // with token text. The ingest process stores complete verse text separately.
+        let mut batch = self.begin_batch().await?;
+        batch.upsert_segment(doc).await?;
+        batch.commit().await
+    }
 
-        // Upsert token row
-        let token_uid = format!("{}:{}:{}", surah_num, ayah_num, doc.token_index);
-        sqlx::query(
-            r#"
-            INSERT INTO tokens (id, verse_surah, verse_ayah, token_index, text)
-            VALUES (?1, ?2, ?3, ?4, ?5)
-            ON CONFLICT(id) DO UPDATE SET text=excluded.text;
-            "#,
-        )
-        .bind(&token_uid)
-        .bind(surah_num as i64)
-        .bind(ayah_num as i64)
-        .bind(doc.token_index as i64)
-        .bind(&doc.text)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    /// Upsert many tokens (e.g. a whole verse or file) in a single transaction.
+    pub async fn upsert_many(&self, docs: &[SegmentView]) -> EngineResult<()> {
+        let mut batch = self.begin_batch().await?;
+        batch.upsert_many(docs).await?;
+        batch.commit().await
+    }
 
-        // Upsert segments normalized
-        for seg in &doc.segments {
-            sqlx::query(
-                r#"
-                INSERT INTO segments (
-                    id, token_id, type, form, root, lemma, pattern, pos, verb_form,
-                    voice, mood, aspect, person, number, gender, case_value, dependency_rel, role, derived_noun_type, state
-                )
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
-                ON CONFLICT(id) DO UPDATE SET
-                    token_id=excluded.token_id,
-                    type=excluded.type,
-                    form=excluded.form,
-                    root=excluded.root,
-                    lemma=excluded.lemma,
-                    pattern=excluded.pattern,
-                    pos=excluded.pos,
-                    verb_form=excluded.verb_form,
-                    voice=excluded.voice,
-                    mood=excluded.mood,
-                    aspect=excluded.aspect,
-                    person=excluded.person,
-                    number=excluded.number,
-                    gender=excluded.gender,
-                    case_value=excluded.case_value,
-                    dependency_rel=excluded.dependency_rel,
-                    role=excluded.role,
-                    derived_noun_type=excluded.derived_noun_type,
-                    state=excluded.state;
-                "#,
-            )
-            .bind(&seg.id)
-            .bind(&token_uid)
-            .bind(&seg.r#type)
-            .bind(&seg.form)
-            .bind(seg.root.as_ref())
-            .bind(seg.lemma.as_ref())
-            .bind(seg.pattern.as_ref())
-            .bind(seg.pos.as_ref())
-            .bind(seg.verb_form.as_ref())
-            .bind(seg.voice.as_ref())
-            .bind(seg.mood.as_ref())
-            .bind(seg.aspect.as_ref())
-            .bind(seg.person.as_ref())
-            .bind(seg.number.as_ref())
-            .bind(seg.gender.as_ref())
-            .bind(seg.case_.as_ref())
-            .bind(seg.dependency_rel.as_ref())
-            .bind(seg.role.as_ref())
-            .bind(seg.derived_noun_type.as_ref())
-            .bind(seg.state.as_ref())
-            .execute(&self.pool)
+    /// Open a write batch. Nothing is visible to other connections until
+    /// [`StorageBatch::commit`] is called; dropping the batch rolls it back.
+    pub async fn begin_batch(&self) -> EngineResult<StorageBatch> {
+        let tx = self
+            .pool
+            .begin()
             .await
             .map_err(|e| EngineError::Storage(e.to_string()))?;
-        }
+        Ok(StorageBatch { tx, pending: 0 })
+    }
 
-        Ok(())
+    pub(crate) async fn begin_tx(&self) -> EngineResult<sqlx::Transaction<'static, Sqlite>> {
+        self.pool
+            .begin()
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))
     }
 
+    pub(crate) async fn commit_tx(tx: sqlx::Transaction<'static, Sqlite>) -> EngineResult<()> {
+        tx.commit()
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))
+    }
+
+    /// Create or replace an annotation, recording the change under `actor`.
+    ///
+    /// Upserting an annotation that is in the trash restores it.
     pub async fn upsert_annotation(
         &self,
         annotation: &common::Annotation,
+        actor: Option<&str>,
     ) -> EngineResult<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO annotations (id, target_id, layer, payload)
-            VALUES (?1, ?2, ?3, ?4)
-            ON CONFLICT(id) DO UPDATE SET
-              target_id=excluded.target_id,
-              layer=excluded.layer,
-              payload=excluded.payload;
-            "#,
-        )
-        .bind(&annotation.id)
-        .bind(&annotation.target_id)
-        .bind(&annotation.layer)
-        .bind(&annotation.payload)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-        Ok(())
+        let mut tx = self.begin_tx().await?;
+        layers::validate_annotation(&mut tx, annotation).await?;
+        write_annotation(&mut tx, annotation, actor).await?;
+        Self::commit_tx(tx).await
     }
 
+    /// Atomically replace annotation `old_id` with `annotation`.
+    ///
+    /// When the ids differ the old annotation is moved to the trash in the
+    /// same transaction, so a failure never leaves the verse without either.
+    pub async fn replace_annotation(
+        &self,
+        old_id: &str,
+        annotation: &common::Annotation,
+        actor: Option<&str>,
+    ) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        if fetch_annotation(&mut *tx, old_id).await?.is_none() {
+            return Err(EngineError::NotFound);
+        }
+        layers::validate_annotation(&mut tx, annotation).await?;
+        if old_id != annotation.id {
+            trash_annotation(&mut tx, old_id, actor).await?;
+        }
+        write_annotation(&mut tx, annotation, actor).await?;
+        Self::commit_tx(tx).await
+    }
+
+    pub async fn get_annotation(&self, id: &str) -> EngineResult<Option<common::Annotation>> {
+        fetch_annotation(&self.pool, id).await
+    }
+
+    /// Live annotations, newest first, optionally restricted to one target
+    /// and/or one author.
     pub async fn list_annotations(
         &self,
         target_filter: Option<&str>,
+        author_filter: Option<&str>,
     ) -> EngineResult<Vec<common::Annotation>> {
-        let rows = if let Some(target) = target_filter {
-            sqlx::query(
-                r#"SELECT id, target_id, layer, payload as "payload: serde_json::Value"
-                   FROM annotations WHERE target_id = ?1
-                   ORDER BY created_at DESC"#,
-            )
-            .bind(target)
-            .fetch_all(&self.pool)
-            .await
-        } else {
-            sqlx::query(
-                r#"SELECT id, target_id, layer, payload as "payload: serde_json::Value"
-                   FROM annotations
-                   ORDER BY created_at DESC"#,
-            )
-            .fetch_all(&self.pool)
-            .await
-        }
+        let rows = sqlx::query(&format!(
+            r#"SELECT {} FROM annotations
+               WHERE deleted_at IS NULL
+                 AND (?1 IS NULL OR target_id = ?1)
+                 AND (?2 IS NULL OR author = ?2)
+               ORDER BY created_at DESC"#,
+            ANNOTATION_COLUMNS
+        ))
+        .bind(target_filter)
+        .bind(author_filter)
+        .fetch_all(&self.pool)
+        .await
         .map_err(|e| EngineError::Storage(e.to_string()))?;
 
-        let mut out = Vec::with_capacity(rows.len());
-        for row in rows {
-            out.push(common::Annotation {
-                id: row.try_get::<String, _>(0).map_err(|e| EngineError::Storage(e.to_string()))?,
-                target_id: row.try_get::<String, _>(1).map_err(|e| EngineError::Storage(e.to_string()))?,
-                layer: row.try_get::<String, _>(2).map_err(|e| EngineError::Storage(e.to_string()))?,
-                payload: row.try_get::<serde_json::Value, _>(3).map_err(|e| EngineError::Storage(e.to_string()))?,
-            });
-        }
-        Ok(out)
+        rows.iter().map(annotation_from_row).collect()
     }
 
-    pub async fn delete_annotation(&self, id: &str) -> EngineResult<()> {
-        sqlx::query(r#"DELETE FROM annotations WHERE id = ?1"#)
-            .bind(id)
-            .execute(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-        Ok(())
+    /// Move an annotation to the trash. It can be restored until purged.
+    pub async fn delete_annotation(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        trash_annotation(&mut tx, id, actor).await?;
+        Self::commit_tx(tx).await
     }
 
     pub async fn upsert_connection(
-        self: &SqliteStorage,
+        &self,
         conn: &ConnectionRecord,
+        actor: Option<&str>,
     ) -> EngineResult<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO connections (id, from_token, to_token, layer, meta)
-            VALUES (?1, ?2, ?3, ?4, ?5)
-            ON CONFLICT(id) DO UPDATE SET
-              from_token=excluded.from_token,
-              to_token=excluded.to_token,
-              layer=excluded.layer,
-              meta=excluded.meta;
-            "#,
-        )
-        .bind(&conn.id)
-        .bind(&conn.from_token)
-        .bind(&conn.to_token)
-        .bind(&conn.layer)
-        .bind(&conn.meta)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-        Ok(())
+        let mut tx = self.begin_tx().await?;
+        write_connection(&mut tx, conn, actor).await?;
+        Self::commit_tx(tx).await
     }
 
+    /// Live connections whose `from` end lies in (or spans) the verse,
+    /// optionally restricted to one author.
     pub async fn list_connections_for_verse(
         &self,
         surah: i64,
         ayah: i64,
+        author_filter: Option<&str>,
     ) -> EngineResult<Vec<ConnectionRecord>> {
-        let rows = sqlx::query(
+        let (start, end) = common::Anchor::Verse { surah, ayah }.span();
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT c.id, c.from_token, c.to_token, c.layer, c.meta as "meta: serde_json::Value"
-            FROM connections c
-            JOIN tokens tf ON c.from_token = tf.id
-            WHERE tf.verse_surah = ?1 AND tf.verse_ayah = ?2
+            SELECT {} FROM connections
+            WHERE from_start <= ?2 AND from_end >= ?1 AND deleted_at IS NULL
+              AND (?3 IS NULL OR author = ?3)
             "#,
-        )
-        .bind(surah)
-        .bind(ayah)
+            CONNECTION_COLUMNS
+        ))
+        .bind(start)
+        .bind(end)
+        .bind(author_filter)
         .fetch_all(&self.pool)
         .await
         .map_err(|e| EngineError::Storage(e.to_string()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| ConnectionRecord {
-                id: r.try_get("id").unwrap_or_default(),
-                from_token: r.try_get("from_token").unwrap_or_default(),
-                to_token: r.try_get("to_token").unwrap_or_default(),
-                layer: r.try_get("layer").unwrap_or_default(),
-                meta: r.try_get("meta").unwrap_or(serde_json::json!({})),
-            })
-            .collect())
+        Ok(rows.iter().map(connection_from_row).collect())
     }
 
-    pub async fn delete_connection(&self, id: &str) -> EngineResult<()> {
-        sqlx::query(r#"DELETE FROM connections WHERE id = ?1"#)
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| EngineError::Storage(e.to_string()))?;
-        Ok(())
+    pub async fn get_connection(&self, id: &str) -> EngineResult<Option<ConnectionRecord>> {
+        fetch_connection(&self.pool, id).await
     }
 
-    pub async fn list_unique_roots(&self) -> EngineResult<Vec<String>> {
-        let rows = sqlx::query(
-            r#"SELECT DISTINCT root FROM segments WHERE root IS NOT NULL ORDER BY root"#
-        )
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-
-        Ok(rows
-            .into_iter()
-            .filter_map(|r| r.try_get::<String, _>(0).ok())
-            .collect())
+    /// Move a connection to the trash. It can be restored until purged.
+    pub async fn delete_connection(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        trash_connection(&mut tx, id, actor).await?;
+        Self::commit_tx(tx).await
     }
 
     pub async fn list_unique_patterns(&self) -> EngineResult<Vec<String>> {
@@ -347,10 +322,11 @@ impl SqliteStorage {
     pub async fn list_surahs(&self) -> EngineResult<Vec<SurahSummary>> {
         let rows = sqlx::query(
             r#"
-            SELECT s.number, s.name, COUNT(DISTINCT v.ayah_number) as ayah_count
+            SELECT s.number, s.name, s.name_translit, s.name_en, s.revelation_place, s.revelation_order,
+                   COALESCE(s.ayah_count, COUNT(DISTINCT v.ayah_number)) as ayah_count
             FROM surahs s
             LEFT JOIN verses v ON s.number = v.surah_number
-            GROUP BY s.number, s.name
+            GROUP BY s.number
             ORDER BY s.number
             "#
         )
@@ -364,27 +340,21 @@ impl SqliteStorage {
                 number: r.try_get("number").unwrap_or(0),
                 name: r.try_get("name").unwrap_or_default(),
                 ayah_count: r.try_get("ayah_count").unwrap_or(0),
+                name_translit: r.try_get("name_translit").unwrap_or(None),
+                name_en: r.try_get("name_en").unwrap_or(None),
+                revelation_place: r.try_get("revelation_place").unwrap_or(None),
+                revelation_order: r.try_get("revelation_order").unwrap_or(None),
             })
             .collect())
     }
 
-    pub async fn get_surah_verses(&self, surah_number: i64) -> EngineResult<Vec<serde_json::Value>> {
-        // Get surah name
-        let surah_name: String = sqlx::query_scalar(
-            r#"SELECT name FROM surahs WHERE number = ?1"#
-        )
-        .bind(surah_number)
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?
-        .unwrap_or_else(|| "Unknown".to_string());
-
-        // Get all verses for this surah with their text
+    pub async fn get_surah_verses(&self, surah_number: i64) -> EngineResult<Vec<Verse>> {
         let rows = sqlx::query(
             r#"
-            SELECT v.ayah_number, vt.text
+            SELECT v.surah_number, v.ayah_number, vt.text, s.name as surah_name
             FROM verses v
             LEFT JOIN verse_texts vt ON v.surah_number = vt.surah_number AND v.ayah_number = vt.ayah_number
+            LEFT JOIN surahs s ON v.surah_number = s.number
             WHERE v.surah_number = ?1
             ORDER BY v.ayah_number
             "#
@@ -394,19 +364,10 @@ impl SqliteStorage {
         .await
         .map_err(|e| EngineError::Storage(e.to_string()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| {
-                serde_json::json!({
-                    "surah_name": surah_name,
-                    "ayah": r.try_get::<i64, _>("ayah_number").unwrap_or(0),
-                    "text": r.try_get::<Option<String>, _>("text").unwrap_or(None).unwrap_or_default()
-                })
-            })
-            .collect())
+        Ok(rows.iter().map(verse_from_row).collect())
     }
 
-    pub async fn get_verse(&self, surah: i64, ayah: i64) -> EngineResult<Option<serde_json::Value>> {
+    pub async fn get_verse(&self, surah: i64, ayah: i64) -> EngineResult<Option<Verse>> {
         let text: Option<String> = sqlx::query_scalar(
             r#"SELECT text FROM verse_texts WHERE surah_number = ?1 AND ayah_number = ?2"#
         )
@@ -430,99 +391,37 @@ impl SqliteStorage {
         .unwrap_or_else(|| "Unknown".to_string());
 
         // Get tokens with their morphological segments
-        let rows = sqlx::query(
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT t.id as token_id, t.token_index, t.text as token_text,
-                   s.id, s.type, s.form, s.root, s.lemma, s.pattern, s.pos,
-                   s.verb_form, s.voice, s.mood, s.aspect, s.person,
-                   s.number, s.gender, s.case_value, s.dependency_rel
+            SELECT t.token_index, t.text as token_text, {}
             FROM tokens t
             LEFT JOIN segments s ON s.token_id = t.id
             WHERE t.verse_surah = ?1 AND t.verse_ayah = ?2
             ORDER BY t.token_index, s.id
-            "#
-        )
+            "#,
+            SEGMENT_COLUMNS
+        ))
         .bind(surah)
         .bind(ayah)
         .fetch_all(&self.pool)
         .await
         .map_err(|e| EngineError::Storage(e.to_string()))?;
 
-        // Group segments by token
-        use std::collections::HashMap;
-        let mut tokens_map: HashMap<i64, serde_json::Value> = HashMap::new();
-
-        for row in rows {
-            let token_index: i64 = row.try_get("token_index").unwrap_or(0);
-            let token_text: String = row.try_get("token_text").unwrap_or_default();
-
-            let token = tokens_map.entry(token_index).or_insert_with(|| {
-                serde_json::json!({
-                    "index": token_index,
-                    "text": token_text,
-                    "segments": []
-                })
-            });
-
-            // Add segment if it exists
-            if let Ok(seg_id) = row.try_get::<String, _>("id") {
-                if !seg_id.is_empty() {
-                    let segment = serde_json::json!({
-                        "id": seg_id,
-                        "type": row.try_get::<String, _>("type").unwrap_or_default(),
-                        "form": row.try_get::<String, _>("form").unwrap_or_default(),
-                        "root": row.try_get::<Option<String>, _>("root").unwrap_or(None),
-                        "lemma": row.try_get::<Option<String>, _>("lemma").unwrap_or(None),
-                        "pattern": row.try_get::<Option<String>, _>("pattern").unwrap_or(None),
-                        "pos": row.try_get::<Option<String>, _>("pos").unwrap_or(None),
-                        "verb_form": row.try_get::<Option<String>, _>("verb_form").unwrap_or(None),
-                        "voice": row.try_get::<Option<String>, _>("voice").unwrap_or(None),
-                        "mood": row.try_get::<Option<String>, _>("mood").unwrap_or(None),
-                        "aspect": row.try_get::<Option<String>, _>("aspect").unwrap_or(None),
-                        "person": row.try_get::<Option<String>, _>("person").unwrap_or(None),
-                        "number": row.try_get::<Option<String>, _>("number").unwrap_or(None),
-                        "gender": row.try_get::<Option<String>, _>("gender").unwrap_or(None),
-                        "case": row.try_get::<Option<String>, _>("case_value").unwrap_or(None),
-                        "dependency_rel": row.try_get::<Option<String>, _>("dependency_rel").unwrap_or(None),
-                    });
-
-                    if let Some(segments) = token.get_mut("segments").and_then(|s| s.as_array_mut()) {
-                        segments.push(segment);
-                    }
-                }
-            }
+        let mut tokens: Vec<Token> = Vec::new();
+        for row in &rows {
+            push_token_row(&mut tokens, row);
         }
 
-        // Convert to sorted array
-        let mut tokens: Vec<_> = tokens_map.into_iter().collect();
-        tokens.sort_by_key(|(idx, _)| *idx);
-        let tokens_array: Vec<_> = tokens.into_iter().map(|(_, token)| token).collect();
-
-        // Prefer the stored verse text when present; otherwise fall back to the longest token text.
-        let verse_text = text
-            .clone()
-            .filter(|t| !t.is_empty())
-            .or_else(|| {
-                tokens_array
-                    .iter()
-                    .filter_map(|t| t.get("text").and_then(|v| v.as_str()))
-                    .max_by_key(|s| s.len())
-                    .map(|s| s.to_string())
-            })
-            .unwrap_or_default();
-
-        Ok(Some(serde_json::json!({
-            "surah": {
-                "number": surah,
-                "name": surah_name
-            },
-            "ayah": ayah,
-            "text": verse_text,
-            "tokens": tokens_array
-        })))
+        Ok(Some(Verse {
+            surah: SurahInfo { number: surah, name: surah_name },
+            ayah,
+            text: verse_text_or_longest_token(text, &tokens),
+            tokens,
+            ..Default::default()
+        }))
     }
 
-    pub async fn get_verse_by_index(&self, index: i64) -> EngineResult<Option<serde_json::Value>> {
+    pub async fn get_verse_by_index(&self, index: i64) -> EngineResult<Option<Verse>> {
         // Get verse by absolute index (row number)
         let row: Option<(i64, i64)> = sqlx::query_as(
             r#"
@@ -544,7 +443,7 @@ impl SqliteStorage {
         }
     }
 
-    pub async fn list_verses(&self, start: i64, limit: i64) -> EngineResult<Vec<serde_json::Value>> {
+    pub async fn list_verses(&self, start: i64, limit: i64) -> EngineResult<Vec<Verse>> {
         let rows = sqlx::query(
             r#"
             SELECT v.surah_number, v.ayah_number, vt.text, s.name as surah_name
@@ -561,20 +460,7 @@ impl SqliteStorage {
         .await
         .map_err(|e| EngineError::Storage(e.to_string()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| {
-                serde_json::json!({
-                    "surah": {
-                        "number": r.try_get::<i64, _>("surah_number").unwrap_or(0),
-                        "name": r.try_get::<String, _>("surah_name").unwrap_or_default()
-                    },
-                    "ayah": r.try_get::<i64, _>("ayah_number").unwrap_or(0),
-                    "text": r.try_get::<Option<String>, _>("text").unwrap_or(None).unwrap_or_default(),
-                    "tokens": []
-                })
-            })
-            .collect())
+        Ok(rows.iter().map(verse_from_row).collect())
     }
 
     pub async fn count_verses(&self) -> EngineResult<i64> {
@@ -585,19 +471,17 @@ impl SqliteStorage {
         Ok(count)
     }
 
-    pub async fn get_verse_segments(&self, surah: i64, ayah: i64) -> EngineResult<Vec<serde_json::Value>> {
-        let rows = sqlx::query(
+    pub async fn get_verse_segments(&self, surah: i64, ayah: i64) -> EngineResult<Vec<VerseSegment>> {
+        let rows = sqlx::query(&format!(
             r#"
-            SELECT s.id, s.type, s.form, s.root, s.lemma, s.pattern, s.pos,
-                   s.verb_form, s.voice, s.mood, s.aspect, s.person,
-                   s.number, s.gender, s.case_value, s.dependency_rel,
-                   t.token_index, t.text as token_text
+            SELECT {}, t.token_index, t.text as token_text
             FROM segments s
             JOIN tokens t ON s.token_id = t.id
             WHERE t.verse_surah = ?1 AND t.verse_ayah = ?2
             ORDER BY t.token_index
-            "#
-        )
+            "#,
+            SEGMENT_COLUMNS
+        ))
         .bind(surah)
         .bind(ayah)
         .fetch_all(&self.pool)
@@ -605,103 +489,59 @@ impl SqliteStorage {
         .map_err(|e| EngineError::Storage(e.to_string()))?;
 
         Ok(rows
-            .into_iter()
+            .iter()
             .map(|r| {
-                serde_json::json!({
-                    "id": r.try_get::<String, _>("id").unwrap_or_default(),
-                    "type": r.try_get::<String, _>("type").unwrap_or_default(),
-                    "form": r.try_get::<String, _>("form").unwrap_or_default(),
-                    "root": r.try_get::<Option<String>, _>("root").unwrap_or(None),
-                    "lemma": r.try_get::<Option<String>, _>("lemma").unwrap_or(None),
-                    "pattern": r.try_get::<Option<String>, _>("pattern").unwrap_or(None),
-                    "pos": r.try_get::<Option<String>, _>("pos").unwrap_or(None),
-                    "verb_form": r.try_get::<Option<String>, _>("verb_form").unwrap_or(None),
-                    "voice": r.try_get::<Option<String>, _>("voice").unwrap_or(None),
-                    "mood": r.try_get::<Option<String>, _>("mood").unwrap_or(None),
-                    "aspect": r.try_get::<Option<String>, _>("aspect").unwrap_or(None),
-                    "person": r.try_get::<Option<String>, _>("person").unwrap_or(None),
-                    "number": r.try_get::<Option<String>, _>("number").unwrap_or(None),
-                    "gender": r.try_get::<Option<String>, _>("gender").unwrap_or(None),
-                    "case": r.try_get::<Option<String>, _>("case_value").unwrap_or(None),
-                    "dependency_rel": r.try_get::<Option<String>, _>("dependency_rel").unwrap_or(None),
-                    "token_index": r.try_get::<i64, _>("token_index").unwrap_or(0),
-                    "word_index": r.try_get::<i64, _>("token_index").unwrap_or(0) + 1,
+                let segment = segment_from_row(r);
+                let token_index: i64 = r.try_get("token_index").unwrap_or(0);
+                VerseSegment {
+                    token_index,
+                    word_index: token_index + 1,
                     // Prefer the segment form for morphology display; keep token text separately for context.
-                    "text": r.try_get::<String, _>("form").unwrap_or_default(),
-                    "token_text": r.try_get::<String, _>("token_text").unwrap_or_default()
-                })
+                    text: segment.form.clone(),
+                    token_text: r.try_get("token_text").unwrap_or_default(),
+                    segment,
+                }
             })
             .collect())
     }
 
     // Research data methods
     pub async fn get_verse_metadata(&self, verse_ref: &str, field: &str) -> EngineResult<Vec<serde_json::Value>> {
-        let row = sqlx::query(&format!(
-            r#"SELECT {} as "data: serde_json::Value" FROM verse_metadata WHERE verse_ref = ?1"#,
-            field
-        ))
-        .bind(verse_ref)
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-
-        if let Some(row) = row {
-            if let Ok(data) = row.try_get::<Option<serde_json::Value>, _>("data") {
-                if let Some(arr) = data.and_then(|v| v.as_array().cloned()) {
-                    return Ok(arr);
-                }
-            }
-        }
-        Ok(vec![])
-    }
-
-    pub async fn set_verse_metadata(&self, verse_ref: &str, field: &str, data: &serde_json::Value) -> EngineResult<()> {
-        let sql = match field {
-            "pronouns" => r#"INSERT INTO verse_metadata (verse_ref, pronouns) VALUES (?1, ?2)
-                ON CONFLICT(verse_ref) DO UPDATE SET pronouns=excluded.pronouns"#,
-            "hypotheses" => r#"INSERT INTO verse_metadata (verse_ref, hypotheses) VALUES (?1, ?2)
-                ON CONFLICT(verse_ref) DO UPDATE SET hypotheses=excluded.hypotheses"#,
-            "translations" => r#"INSERT INTO verse_metadata (verse_ref, translations) VALUES (?1, ?2)
-                ON CONFLICT(verse_ref) DO UPDATE SET translations=excluded.translations"#,
-            _ => return Err(EngineError::Invalid(format!("Unknown metadata field: {}", field))),
-        };
+        fetch_verse_metadata(&self.pool, verse_ref, field).await
+    }
 
-        sqlx::query(sql)
-            .bind(verse_ref)
-            .bind(data)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| EngineError::Storage(e.to_string()))?;
-        Ok(())
+    /// Replace a verse metadata list, logging each added, changed or removed entry.
+    pub async fn set_verse_metadata(
+        &self,
+        verse_ref: &str,
+        field: &str,
+        data: &serde_json::Value,
+        actor: Option<&str>,
+    ) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        write_verse_metadata(&mut tx, verse_ref, field, data, actor).await?;
+        Self::commit_tx(tx).await
     }
 
     pub async fn get_research_data(&self, key: &str) -> EngineResult<Option<serde_json::Value>> {
-        let row = sqlx::query(
-            r#"SELECT value as "value: serde_json::Value" FROM research_data WHERE key = ?1"#
-        )
-        .bind(key)
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-
-        Ok(row.and_then(|r| r.try_get("value").ok()))
+        fetch_research_data(&self.pool, key).await
     }
 
-    pub async fn set_research_data(&self, key: &str, value: &serde_json::Value) -> EngineResult<()> {
-        sqlx::query(
-            r#"INSERT INTO research_data (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)
-               ON CONFLICT(key) DO UPDATE SET value=excluded.value, updated_at=CURRENT_TIMESTAMP"#
-        )
-        .bind(key)
-        .bind(value)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| EngineError::Storage(e.to_string()))?;
-        Ok(())
+    /// Store a research data blob. Patterns are logged per entry;
+    /// any other key is logged as a single record.
+    pub async fn set_research_data(
+        &self,
+        key: &str,
+        value: &serde_json::Value,
+        actor: Option<&str>,
+    ) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        write_research_data(&mut tx, key, value, actor).await?;
+        Self::commit_tx(tx).await
     }
 
     pub async fn count_annotations(&self) -> EngineResult<i64> {
-        let count: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM annotations"#)
+        let count: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM annotations WHERE deleted_at IS NULL"#)
             .fetch_one(&self.pool)
             .await
             .map_err(|e| EngineError::Storage(e.to_string()))?;
@@ -718,6 +558,73 @@ impl SqliteStorage {
         Ok(count)
     }
 
+    /// Verses from `start` to `end` (inclusive, as `(surah, ayah)`) in reading order.
+    pub async fn list_verses_between(&self, start: (i64, i64), end: (i64, i64)) -> EngineResult<Vec<Verse>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT v.surah_number, v.ayah_number, vt.text, s.name as surah_name
+            FROM verses v
+            LEFT JOIN verse_texts vt ON v.surah_number = vt.surah_number AND v.ayah_number = vt.ayah_number
+            LEFT JOIN surahs s ON v.surah_number = s.number
+            WHERE (v.surah_number > ?1 OR (v.surah_number = ?1 AND v.ayah_number >= ?2))
+              AND (v.surah_number < ?3 OR (v.surah_number = ?3 AND v.ayah_number <= ?4))
+            ORDER BY v.surah_number, v.ayah_number
+            "#
+        )
+        .bind(start.0)
+        .bind(start.1)
+        .bind(end.0)
+        .bind(end.1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+        Ok(rows.iter().map(verse_from_row).collect())
+    }
+
+    /// Verses from `start` to `end` (inclusive) with their tokens and
+    /// segments, in one query. Ranges may cross surahs.
+    pub async fn get_verse_range(&self, start: (i64, i64), end: (i64, i64)) -> EngineResult<Vec<Verse>> {
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT v.surah_number, v.ayah_number, vt.text, sr.name as surah_name,
+                   t.token_index, t.text as token_text, {}
+            FROM verses v
+            LEFT JOIN verse_texts vt ON v.surah_number = vt.surah_number AND v.ayah_number = vt.ayah_number
+            LEFT JOIN surahs sr ON v.surah_number = sr.number
+            LEFT JOIN tokens t ON t.verse_surah = v.surah_number AND t.verse_ayah = v.ayah_number
+            LEFT JOIN segments s ON s.token_id = t.id
+            WHERE (v.surah_number > ?1 OR (v.surah_number = ?1 AND v.ayah_number >= ?2))
+              AND (v.surah_number < ?3 OR (v.surah_number = ?3 AND v.ayah_number <= ?4))
+            ORDER BY v.surah_number, v.ayah_number, t.token_index, s.id
+            "#,
+            SEGMENT_COLUMNS
+        ))
+        .bind(start.0)
+        .bind(start.1)
+        .bind(end.0)
+        .bind(end.1)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+        // Rows arrive in reading order; start a new verse whenever the reference changes.
+        let mut verses: Vec<Verse> = Vec::new();
+        for row in &rows {
+            let verse = verse_from_row(row);
+            if verses.last().map(|v| (v.surah.number, v.ayah)) != Some((verse.surah.number, verse.ayah)) {
+                verses.push(verse);
+            }
+            if let Some(current) = verses.last_mut() {
+                push_token_row(&mut current.tokens, row);
+            }
+        }
+        for verse in &mut verses {
+            verse.text = verse_text_or_longest_token(Some(std::mem::take(&mut verse.text)), &verse.tokens);
+        }
+        Ok(verses)
+    }
+
     pub async fn get_all_verse_texts(&self, limit: usize) -> EngineResult<Vec<(String, String)>> {
         let rows = sqlx::query(
             r#"
@@ -745,13 +652,593 @@ impl SqliteStorage {
     }
 }
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
-pub struct ConnectionRecord {
-    pub id: String,
-    pub from_token: String,
-    pub to_token: String,
-    pub layer: String,
-    pub meta: serde_json::Value,
+const SEGMENT_COLUMNS: &str = "s.id, s.type, s.form, s.root, s.lemma, s.pattern, s.pos, s.verb_form, s.voice, s.mood, \
+     s.aspect, s.person, s.number, s.gender, s.case_value, s.dependency_rel, s.role, s.derived_noun_type, s.state";
+
+/// A segment from a row selecting `SEGMENT_COLUMNS`.
+fn segment_from_row(r: &sqlx::sqlite::SqliteRow) -> Segment {
+    Segment {
+        id: r.try_get::<Option<String>, _>("id").unwrap_or(None).unwrap_or_default(),
+        r#type: r.try_get::<Option<String>, _>("type").unwrap_or(None).unwrap_or_default(),
+        form: r.try_get::<Option<String>, _>("form").unwrap_or(None).unwrap_or_default(),
+        root: r.try_get("root").unwrap_or(None),
+        lemma: r.try_get("lemma").unwrap_or(None),
+        pattern: r.try_get("pattern").unwrap_or(None),
+        pos: r.try_get("pos").unwrap_or(None),
+        verb_form: r.try_get("verb_form").unwrap_or(None),
+        voice: r.try_get("voice").unwrap_or(None),
+        mood: r.try_get("mood").unwrap_or(None),
+        aspect: r.try_get("aspect").unwrap_or(None),
+        person: r.try_get("person").unwrap_or(None),
+        number: r.try_get("number").unwrap_or(None),
+        gender: r.try_get("gender").unwrap_or(None),
+        case_: r.try_get("case_value").unwrap_or(None),
+        dependency_rel: r.try_get("dependency_rel").unwrap_or(None),
+        role: r.try_get("role").unwrap_or(None),
+        derived_noun_type: r.try_get("derived_noun_type").unwrap_or(None),
+        state: r.try_get("state").unwrap_or(None),
+    }
+}
+
+/// Add a row selecting `token_index`, `token_text` and `SEGMENT_COLUMNS` to
+/// `tokens`. Rows arrive in token order, so a new token starts whenever the
+/// index changes; rows without a token (a verse with no analysis) add nothing.
+fn push_token_row(tokens: &mut Vec<Token>, row: &sqlx::sqlite::SqliteRow) {
+    let Some(index) = row.try_get::<Option<i64>, _>("token_index").unwrap_or(None) else {
+        return;
+    };
+    if tokens.last().map(|t| t.index) != Some(index) {
+        tokens.push(Token {
+            index,
+            text: row.try_get("token_text").unwrap_or_default(),
+            segments: Vec::new(),
+        });
+    }
+    let has_segment = row
+        .try_get::<Option<String>, _>("id")
+        .unwrap_or(None)
+        .is_some_and(|id| !id.is_empty());
+    if has_segment {
+        if let Some(token) = tokens.last_mut() {
+            token.segments.push(segment_from_row(row));
+        }
+    }
+}
+
+/// Prefer the stored verse text when present; otherwise fall back to the longest token text.
+fn verse_text_or_longest_token(text: Option<String>, tokens: &[Token]) -> String {
+    text.filter(|t| !t.is_empty())
+        .or_else(|| {
+            tokens
+                .iter()
+                .map(|t| t.text.as_str())
+                .max_by_key(|s| s.len())
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_default()
+}
+
+/// A verse without tokens from a row with `surah_number`, `ayah_number`, `text` and `surah_name`.
+fn verse_from_row(r: &sqlx::sqlite::SqliteRow) -> Verse {
+    Verse {
+        surah: SurahInfo {
+            number: r.try_get("surah_number").unwrap_or(0),
+            name: r.try_get::<Option<String>, _>("surah_name").unwrap_or(None).unwrap_or_default(),
+        },
+        ayah: r.try_get("ayah_number").unwrap_or(0),
+        text: r.try_get::<Option<String>, _>("text").unwrap_or(None).unwrap_or_default(),
+        ..Default::default()
+    }
+}
+
+pub(crate) const ANNOTATION_COLUMNS: &str = "id, target_id, layer, payload, author, created_at, updated_at";
+pub(crate) const CONNECTION_COLUMNS: &str = "id, from_token, to_token, layer, meta, author, created_at, updated_at";
+
+pub(crate) fn annotation_from_row(row: &sqlx::sqlite::SqliteRow) -> EngineResult<common::Annotation> {
+    Ok(common::Annotation {
+        id: row.try_get("id").map_err(|e| EngineError::Storage(e.to_string()))?,
+        target_id: row.try_get("target_id").map_err(|e| EngineError::Storage(e.to_string()))?,
+        layer: row.try_get::<Option<String>, _>("layer").unwrap_or(None).unwrap_or_default(),
+        payload: row.try_get("payload").map_err(|e| EngineError::Storage(e.to_string()))?,
+        author: row.try_get("author").unwrap_or(None),
+        created_at: row.try_get("created_at").unwrap_or(None),
+        updated_at: row.try_get("updated_at").unwrap_or(None),
+    })
+}
+
+pub(crate) fn connection_from_row(row: &sqlx::sqlite::SqliteRow) -> ConnectionRecord {
+    ConnectionRecord {
+        id: row.try_get("id").unwrap_or_default(),
+        from_token: row.try_get("from_token").unwrap_or_default(),
+        to_token: row.try_get("to_token").unwrap_or_default(),
+        layer: row.try_get("layer").unwrap_or_default(),
+        meta: row.try_get("meta").unwrap_or(serde_json::json!({})),
+        author: row.try_get("author").unwrap_or(None),
+        created_at: row.try_get("created_at").unwrap_or(None),
+        updated_at: row.try_get("updated_at").unwrap_or(None),
+    }
+}
+
+pub(crate) async fn write_annotation(
+    conn: &mut SqliteConnection,
+    annotation: &common::Annotation,
+    actor: Option<&str>,
+) -> EngineResult<()> {
+    let before = fetch_annotation(&mut *conn, &annotation.id).await?;
+    let span = anchors::target_span(&annotation.target_id);
+    // The author is whoever created the record unless one is given explicitly;
+    // updated_at only moves when the content actually changes.
+    sqlx::query(
+        r#"
+        INSERT INTO annotations (id, target_id, layer, payload, author, created_at, updated_at, anchor_start, anchor_end)
+        VALUES (?1, ?2, ?3, ?4, COALESCE(?5, ?6), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, ?7, ?8)
+        ON CONFLICT(id) DO UPDATE SET
+          updated_at=CASE
+            WHEN annotations.deleted_at IS NOT NULL
+              OR annotations.target_id IS NOT excluded.target_id
+              OR annotations.layer IS NOT excluded.layer
+              OR annotations.payload IS NOT excluded.payload
+            THEN CURRENT_TIMESTAMP ELSE annotations.updated_at END,
+          target_id=excluded.target_id,
+          layer=excluded.layer,
+          payload=excluded.payload,
+          author=COALESCE(?5, annotations.author, ?6),
+          anchor_start=excluded.anchor_start,
+          anchor_end=excluded.anchor_end,
+          deleted_at=NULL;
+        "#,
+    )
+    .bind(&annotation.id)
+    .bind(&annotation.target_id)
+    .bind(&annotation.layer)
+    .bind(&annotation.payload)
+    .bind(annotation.author.as_deref())
+    .bind(actor)
+    .bind(span.map(|s| s.0))
+    .bind(span.map(|s| s.1))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    let before = before.map(|a| history::snapshot(&a));
+    let after = fetch_annotation(&mut *conn, &annotation.id)
+        .await?
+        .map(|a| history::snapshot(&a));
+    history::record_change(
+        conn,
+        RECORD_ANNOTATION,
+        &annotation.id,
+        history::verse_of(&annotation.target_id).as_deref(),
+        actor,
+        before.as_ref(),
+        after.as_ref(),
+    )
+    .await
+}
+
+pub(crate) async fn trash_annotation(conn: &mut SqliteConnection, id: &str, actor: Option<&str>) -> EngineResult<()> {
+    let before = fetch_annotation(&mut *conn, id).await?;
+    sqlx::query(r#"UPDATE annotations SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?1 AND deleted_at IS NULL"#)
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    if let Some(before) = before {
+        history::record_change(
+            conn,
+            RECORD_ANNOTATION,
+            id,
+            history::verse_of(&before.target_id).as_deref(),
+            actor,
+            Some(&history::snapshot(&before)),
+            None,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn write_connection(
+    conn: &mut SqliteConnection,
+    record: &ConnectionRecord,
+    actor: Option<&str>,
+) -> EngineResult<()> {
+    let before = fetch_connection(&mut *conn, &record.id).await?;
+    let from = anchors::target_span(&record.from_token);
+    let to = anchors::target_span(&record.to_token);
+    // The author is whoever created the record unless one is given explicitly;
+    // updated_at only moves when the content actually changes.
+    sqlx::query(
+        r#"
+        INSERT INTO connections (
+            id, from_token, to_token, layer, meta, author, created_at, updated_at,
+            from_start, from_end, to_start, to_end
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, COALESCE(?6, ?7), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP, ?8, ?9, ?10, ?11)
+        ON CONFLICT(id) DO UPDATE SET
+          updated_at=CASE
+            WHEN connections.deleted_at IS NOT NULL
+              OR connections.from_token IS NOT excluded.from_token
+              OR connections.to_token IS NOT excluded.to_token
+              OR connections.layer IS NOT excluded.layer
+              OR connections.meta IS NOT excluded.meta
+            THEN CURRENT_TIMESTAMP ELSE connections.updated_at END,
+          from_token=excluded.from_token,
+          to_token=excluded.to_token,
+          layer=excluded.layer,
+          meta=excluded.meta,
+          author=COALESCE(?6, connections.author, ?7),
+          from_start=excluded.from_start,
+          from_end=excluded.from_end,
+          to_start=excluded.to_start,
+          to_end=excluded.to_end,
+          deleted_at=NULL;
+        "#,
+    )
+    .bind(&record.id)
+    .bind(&record.from_token)
+    .bind(&record.to_token)
+    .bind(&record.layer)
+    .bind(&record.meta)
+    .bind(record.author.as_deref())
+    .bind(actor)
+    .bind(from.map(|s| s.0))
+    .bind(from.map(|s| s.1))
+    .bind(to.map(|s| s.0))
+    .bind(to.map(|s| s.1))
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    let before = before.map(|c| history::snapshot(&c));
+    let after = fetch_connection(&mut *conn, &record.id).await?.map(|c| history::snapshot(&c));
+    history::record_change(
+        conn,
+        RECORD_CONNECTION,
+        &record.id,
+        history::verse_of(&record.from_token).as_deref(),
+        actor,
+        before.as_ref(),
+        after.as_ref(),
+    )
+    .await
+}
+
+pub(crate) async fn trash_connection(conn: &mut SqliteConnection, id: &str, actor: Option<&str>) -> EngineResult<()> {
+    let before = fetch_connection(&mut *conn, id).await?;
+    sqlx::query(r#"UPDATE connections SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?1 AND deleted_at IS NULL"#)
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    if let Some(before) = before {
+        history::record_change(
+            conn,
+            RECORD_CONNECTION,
+            id,
+            history::verse_of(&before.from_token).as_deref(),
+            actor,
+            Some(&history::snapshot(&before)),
+            None,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn write_verse_metadata(
+    conn: &mut SqliteConnection,
+    verse_ref: &str,
+    field: &str,
+    data: &serde_json::Value,
+    actor: Option<&str>,
+) -> EngineResult<()> {
+    let sql = match field {
+        "pronouns" => r#"INSERT INTO verse_metadata (verse_ref, pronouns) VALUES (?1, ?2)
+            ON CONFLICT(verse_ref) DO UPDATE SET pronouns=excluded.pronouns"#,
+        "hypotheses" => r#"INSERT INTO verse_metadata (verse_ref, hypotheses) VALUES (?1, ?2)
+            ON CONFLICT(verse_ref) DO UPDATE SET hypotheses=excluded.hypotheses"#,
+        "translations" => r#"INSERT INTO verse_metadata (verse_ref, translations) VALUES (?1, ?2)
+            ON CONFLICT(verse_ref) DO UPDATE SET translations=excluded.translations"#,
+        _ => return Err(EngineError::Invalid(format!("Unknown metadata field: {}", field))),
+    };
+
+    let before = fetch_verse_metadata(&mut *conn, verse_ref, field).await?;
+    sqlx::query(sql)
+        .bind(verse_ref)
+        .bind(data)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    let after = data.as_array().cloned().unwrap_or_default();
+    history::record_entry_changes(
+        conn,
+        history::metadata_record_type(field)?,
+        verse_ref,
+        actor,
+        &before,
+        &after,
+    )
+    .await
+}
+
+pub(crate) async fn write_research_data(
+    conn: &mut SqliteConnection,
+    key: &str,
+    value: &serde_json::Value,
+    actor: Option<&str>,
+) -> EngineResult<()> {
+    let before = fetch_research_data(&mut *conn, key).await?;
+    sqlx::query(
+        r#"INSERT INTO research_data (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)
+           ON CONFLICT(key) DO UPDATE SET value=excluded.value, updated_at=CURRENT_TIMESTAMP"#
+    )
+    .bind(key)
+    .bind(value)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    match key {
+        "patterns" => {
+            history::record_map_changes(
+                conn,
+                RECORD_PATTERN,
+                actor,
+                before.as_ref().and_then(|v| v.as_object()),
+                value.as_object(),
+            )
+            .await?
+        }
+        _ => {
+            history::record_change(conn, RECORD_RESEARCH_DATA, key, None, actor, before.as_ref(), Some(value))
+                .await?
+        }
+    }
+    Ok(())
+}
+
+async fn fetch_annotation<'e, E>(executor: E, id: &str) -> EngineResult<Option<common::Annotation>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(&format!(
+        r#"SELECT {} FROM annotations WHERE id = ?1 AND deleted_at IS NULL"#,
+        ANNOTATION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    row.as_ref().map(annotation_from_row).transpose()
+}
+
+async fn fetch_connection<'e, E>(executor: E, id: &str) -> EngineResult<Option<ConnectionRecord>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(&format!(
+        r#"SELECT {} FROM connections WHERE id = ?1 AND deleted_at IS NULL"#,
+        CONNECTION_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(row.as_ref().map(connection_from_row))
+}
+
+pub(crate) async fn fetch_verse_metadata<'e, E>(
+    executor: E,
+    verse_ref: &str,
+    field: &str,
+) -> EngineResult<Vec<serde_json::Value>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let column = match field {
+        "pronouns" | "hypotheses" | "translations" => field,
+        _ => return Err(EngineError::Invalid(format!("Unknown metadata field: {}", field))),
+    };
+    let row = sqlx::query(&format!(
+        r#"SELECT {} AS data FROM verse_metadata WHERE verse_ref = ?1"#,
+        column
+    ))
+    .bind(verse_ref)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    if let Some(row) = row {
+        if let Ok(data) = row.try_get::<Option<serde_json::Value>, _>("data") {
+            if let Some(arr) = data.and_then(|v| v.as_array().cloned()) {
+                return Ok(arr);
+            }
+        }
+    }
+    Ok(vec![])
+}
+
+pub(crate) async fn fetch_research_data<'e, E>(executor: E, key: &str) -> EngineResult<Option<serde_json::Value>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(
+        r#"SELECT value FROM research_data WHERE key = ?1"#
+    )
+    .bind(key)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    Ok(row.and_then(|r| r.try_get("value").ok()))
+}
+
+/// A group of corpus writes sharing one SQLite transaction.
+///
+/// Statements are issued on a single connection, so sqlx reuses its cached
+/// prepared statements for every token and segment in the batch.
+pub struct StorageBatch {
+    tx: sqlx::Transaction<'static, Sqlite>,
+    pending: usize,
+}
+
+impl StorageBatch {
+    /// Number of tokens written since the batch was opened.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    pub async fn upsert_segment(&mut self, doc: &SegmentView) -> EngineResult<()> {
+        write_segment(&mut self.tx, doc).await?;
+        self.pending += 1;
+        Ok(())
+    }
+
+    pub async fn upsert_many(&mut self, docs: &[SegmentView]) -> EngineResult<()> {
+        for doc in docs {
+            self.upsert_segment(doc).await?;
+        }
+        Ok(())
+    }
+
+    /// Set the canonical text of a verse, replacing any previous value.
+    pub async fn set_verse_text(&mut self, surah: i64, ayah: i64, text: &str) -> EngineResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO verse_texts (surah_number, ayah_number, text)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(surah_number, ayah_number) DO UPDATE SET text=excluded.text;
+            "#,
+        )
+        .bind(surah)
+        .bind(ayah)
+        .bind(text)
+        .execute(&mut *self.tx)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    pub async fn commit(self) -> EngineResult<()> {
+        self.tx
+            .commit()
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))
+    }
+
+    pub async fn rollback(self) -> EngineResult<()> {
+        self.tx
+            .rollback()
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))
+    }
+}
+
+async fn write_segment(conn: &mut SqliteConnection, doc: &SegmentView) -> EngineResult<()> {
+    // Upsert surah and verse metadata
+    let (surah_num, ayah_num) = parse_verse_ref(&doc.verse_ref)?;
+    sqlx::query(
+        r#"INSERT OR IGNORE INTO surahs (number, name) VALUES (?1, ?2)"#,
+    )
+    .bind(surah_num)
+    .bind("") // name unknown in current payload
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO verses (surah_number, ayah_number)
+        VALUES (?1, ?2)
+        ON CONFLICT(surah_number, ayah_number) DO NOTHING;
+        "#,
+    )
+    .bind(surah_num)
+    .bind(ayah_num)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    // Note: verse text is managed by the caller (ingest.rs) to avoid overwriting
+    // with token text. The ingest process stores complete verse text separately.
+
+    // Upsert token row
+    let token_uid = format!("{}:{}:{}", surah_num, ayah_num, doc.token_index);
+    sqlx::query(
+        r#"
+        INSERT INTO tokens (id, verse_surah, verse_ayah, token_index, text)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(id) DO UPDATE SET text=excluded.text;
+        "#,
+    )
+    .bind(&token_uid)
+    .bind(surah_num)
+    .bind(ayah_num)
+    .bind(doc.token_index as i64)
+    .bind(&doc.text)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    // Upsert segments normalized
+    for seg in &doc.segments {
+        sqlx::query(
+            r#"
+            INSERT INTO segments (
+                id, token_id, type, form, root, lemma, pattern, pos, verb_form,
+                voice, mood, aspect, person, number, gender, case_value, dependency_rel, role, derived_noun_type, state
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
+            ON CONFLICT(id) DO UPDATE SET
+                token_id=excluded.token_id,
+                type=excluded.type,
+                form=excluded.form,
+                root=excluded.root,
+                lemma=excluded.lemma,
+                pattern=excluded.pattern,
+                pos=excluded.pos,
+                verb_form=excluded.verb_form,
+                voice=excluded.voice,
+                mood=excluded.mood,
+                aspect=excluded.aspect,
+                person=excluded.person,
+                number=excluded.number,
+                gender=excluded.gender,
+                case_value=excluded.case_value,
+                dependency_rel=excluded.dependency_rel,
+                role=excluded.role,
+                derived_noun_type=excluded.derived_noun_type,
+                state=excluded.state;
+            "#,
+        )
+        .bind(&seg.id)
+        .bind(&token_uid)
+        .bind(&seg.r#type)
+        .bind(&seg.form)
+        .bind(seg.root.as_ref())
+        .bind(seg.lemma.as_ref())
+        .bind(seg.pattern.as_ref())
+        .bind(seg.pos.as_ref())
+        .bind(seg.verb_form.as_ref())
+        .bind(seg.voice.as_ref())
+        .bind(seg.mood.as_ref())
+        .bind(seg.aspect.as_ref())
+        .bind(seg.person.as_ref())
+        .bind(seg.number.as_ref())
+        .bind(seg.gender.as_ref())
+        .bind(seg.case_.as_ref())
+        .bind(seg.dependency_rel.as_ref())
+        .bind(seg.role.as_ref())
+        .bind(seg.derived_noun_type.as_ref())
+        .bind(seg.state.as_ref())
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+
+    Ok(())
 }
 
 #[async_trait]
@@ -773,38 +1260,14 @@ impl StorageBackend for SqliteStorage {
         let text: String = token.try_get("text").map_err(|e| EngineError::Storage(e.to_string()))?;
 
         let seg_rows = sqlx::query(
-            r#"SELECT id, type, form, root, lemma, pattern, pos, verb_form, voice, mood, aspect, person, number, gender, case_value, dependency_rel, role, derived_noun_type, state
-                FROM segments WHERE token_id = ?1"#,
+            &format!(r#"SELECT {} FROM segments s WHERE s.token_id = ?1"#, SEGMENT_COLUMNS),
         )
         .bind(id)
         .fetch_all(&self.pool)
         .await
         .map_err(|e| EngineError::Storage(e.to_string()))?;
 
-        let mut segments = Vec::with_capacity(seg_rows.len());
-        for r in seg_rows {
-            segments.push(Segment {
-                id: r.try_get("id").map_err(|e| EngineError::Storage(e.to_string()))?,
-                r#type: r.try_get::<String, _>("type").unwrap_or_default(),
-                form: r.try_get::<String, _>("form").unwrap_or_default(),
-                root: r.try_get::<Option<String>, _>("root").unwrap_or(None),
-                lemma: r.try_get::<Option<String>, _>("lemma").unwrap_or(None),
-                pattern: r.try_get::<Option<String>, _>("pattern").unwrap_or(None),
-                pos: r.try_get::<Option<String>, _>("pos").unwrap_or(None),
-                verb_form: r.try_get::<Option<String>, _>("verb_form").unwrap_or(None),
-                voice: r.try_get::<Option<String>, _>("voice").unwrap_or(None),
-                mood: r.try_get::<Option<String>, _>("mood").unwrap_or(None),
-                aspect: r.try_get::<Option<String>, _>("aspect").unwrap_or(None),
-                person: r.try_get::<Option<String>, _>("person").unwrap_or(None),
-                number: r.try_get::<Option<String>, _>("number").unwrap_or(None),
-                gender: r.try_get::<Option<String>, _>("gender").unwrap_or(None),
-                case_: r.try_get::<Option<String>, _>("case_value").unwrap_or(None),
-                dependency_rel: r.try_get::<Option<String>, _>("dependency_rel").unwrap_or(None),
-                role: r.try_get::<Option<String>, _>("role").unwrap_or(None),
-                derived_noun_type: r.try_get::<Option<String>, _>("derived_noun_type").unwrap_or(None),
-                state: r.try_get::<Option<String>, _>("state").unwrap_or(None),
-            });
-        }
+        let segments: Vec<Segment> = seg_rows.iter().map(segment_from_row).collect();
 
         let verse_ref = format!("{}:{}", surah, ayah);
         Ok(Some(SegmentView {
@@ -830,6 +1293,35 @@ impl StorageBackend for SqliteStorage {
     }
 }
 
+/// Add `column` to `table` unless it already exists.
+async fn ensure_column(pool: &Pool<Sqlite>, table: &str, column: &str, decl: &str) -> EngineResult<()> {
+    let exists: Option<String> = sqlx::query_scalar(r#"SELECT name FROM pragma_table_info(?1) WHERE name = ?2"#)
+        .bind(table)
+        .bind(column)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    if exists.is_none() {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))
+            .execute(pool)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+    Ok(())
+}
+
+// Indexes over columns added by `ensure_column`; run after the upgrade step.
+const MIGRATION_INDEXES: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_annotations_deleted ON annotations(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_connections_deleted ON connections(deleted_at);
+CREATE INDEX IF NOT EXISTS idx_annotations_author ON annotations(author);
+CREATE INDEX IF NOT EXISTS idx_connections_author ON connections(author);
+CREATE INDEX IF NOT EXISTS idx_surahs_revelation ON surahs(revelation_place);
+CREATE INDEX IF NOT EXISTS idx_annotations_anchor ON annotations(anchor_start, anchor_end);
+CREATE INDEX IF NOT EXISTS idx_connections_from_span ON connections(from_start, from_end);
+CREATE INDEX IF NOT EXISTS idx_connections_to_span ON connections(to_start, to_end);
+"#;
+
 // Single-file migration to bootstrap SQLite schema.
 const MIGRATION_INIT: &str = r#"
 CREATE TABLE IF NOT EXISTS surahs (
@@ -837,6 +1329,27 @@ CREATE TABLE IF NOT EXISTS surahs (
     name TEXT
 );
 
+-- Canonical divisions (juz, hizb, rub, manzil) by start verse.
+CREATE TABLE IF NOT EXISTS divisions (
+    kind TEXT NOT NULL,
+    number INTEGER NOT NULL,
+    start_surah INTEGER NOT NULL,
+    start_ayah INTEGER NOT NULL,
+    PRIMARY KEY (kind, number)
+);
+CREATE INDEX IF NOT EXISTS idx_divisions_start ON divisions(kind, start_surah, start_ayah);
+
+-- Version of each research record last agreed on with a peer through a
+-- research bundle; the base of the three-way merge on import.
+CREATE TABLE IF NOT EXISTS sync_base (
+    record_type TEXT NOT NULL,
+    record_key TEXT NOT NULL,
+    value JSON,
+    bundle_exported_at TEXT,
+    synced_at TEXT,
+    PRIMARY KEY (record_type, record_key)
+);
+
 CREATE TABLE IF NOT EXISTS verses (
     surah_number INTEGER NOT NULL,
     ayah_number INTEGER NOT NULL,
@@ -850,6 +1363,36 @@ CREATE TABLE IF NOT EXISTS verse_texts (
     PRIMARY KEY (surah_number, ayah_number)
 );
 
+-- Text editions; the analysed edition itself lives in verse_texts/tokens.
+CREATE TABLE IF NOT EXISTS editions (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    riwayah TEXT,
+    script TEXT,
+    source TEXT
+);
+INSERT OR IGNORE INTO editions (id, name, riwayah, script, source)
+VALUES ('hafs-uthmani', 'Hafs (Uthmani)', 'hafs', 'uthmani', 'Quranic Arabic Corpus');
+
+CREATE TABLE IF NOT EXISTS edition_texts (
+    edition_id TEXT NOT NULL,
+    surah_number INTEGER NOT NULL,
+    ayah_number INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    PRIMARY KEY (edition_id, surah_number, ayah_number)
+);
+
+-- Words of each edition aligned to the analysed edition's tokens (NULL when unmatched).
+CREATE TABLE IF NOT EXISTS edition_words (
+    edition_id TEXT NOT NULL,
+    surah_number INTEGER NOT NULL,
+    ayah_number INTEGER NOT NULL,
+    word_index INTEGER NOT NULL,
+    form TEXT NOT NULL,
+    token_index INTEGER,
+    PRIMARY KEY (edition_id, surah_number, ayah_number, word_index)
+);
+
 CREATE TABLE IF NOT EXISTS tokens (
     id TEXT PRIMARY KEY,
     verse_surah INTEGER NOT NULL,
@@ -881,12 +1424,82 @@ CREATE TABLE IF NOT EXISTS segments (
     state TEXT
 );
 
+CREATE INDEX IF NOT EXISTS idx_tokens_verse ON tokens(verse_surah, verse_ayah, token_index);
 CREATE INDEX IF NOT EXISTS idx_segments_token ON segments(token_id);
 CREATE INDEX IF NOT EXISTS idx_segments_root ON segments(root);
 CREATE INDEX IF NOT EXISTS idx_segments_lemma ON segments(lemma);
 CREATE INDEX IF NOT EXISTS idx_segments_pos ON segments(pos);
 CREATE INDEX IF NOT EXISTS idx_segments_pattern ON segments(pattern);
 
+-- Root, lemma and POS tables of the Quranic Arabic Corpus; `size` is the
+-- occurrence count the table was published with.
+CREATE TABLE IF NOT EXISTS lexicon_roots (
+    root TEXT PRIMARY KEY,
+    root_ar TEXT NOT NULL,
+    size INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS lexicon_lemmas (
+    lemma TEXT PRIMARY KEY,
+    lemma_ar TEXT NOT NULL,
+    size INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS lexicon_pos (
+    tag TEXT PRIMARY KEY,
+    name_ar TEXT,
+    name_en TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_lexicon_roots_ar ON lexicon_roots(root_ar);
+CREATE INDEX IF NOT EXISTS idx_lexicon_lemmas_ar ON lexicon_lemmas(lemma_ar);
+
+-- Treebank: syntax nodes (segments, elided words, phrases) and head -> dependent edges.
+CREATE TABLE IF NOT EXISTS treebank_nodes (
+    surah INTEGER NOT NULL,
+    ayah INTEGER NOT NULL,
+    node_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    form TEXT,
+    token_index INTEGER,
+    segment_index INTEGER,
+    span_start TEXT,
+    span_end TEXT,
+    PRIMARY KEY (surah, ayah, node_id)
+);
+
+CREATE TABLE IF NOT EXISTS treebank_edges (
+    surah INTEGER NOT NULL,
+    ayah INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    head TEXT NOT NULL,
+    dependent TEXT NOT NULL,
+    relation TEXT NOT NULL,
+    PRIMARY KEY (surah, ayah, position)
+);
+
+CREATE TABLE IF NOT EXISTS glosses (
+    surah INTEGER NOT NULL,
+    ayah INTEGER NOT NULL,
+    token_index INTEGER NOT NULL,
+    segment_index INTEGER NOT NULL DEFAULT -1,
+    source TEXT NOT NULL,
+    lang TEXT,
+    text TEXT NOT NULL,
+    PRIMARY KEY (surah, ayah, token_index, segment_index, source)
+);
+
+CREATE TABLE IF NOT EXISTS translation_alignments (
+    surah INTEGER NOT NULL,
+    ayah INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    text TEXT NOT NULL,
+    links JSON NOT NULL,
+    PRIMARY KEY (surah, ayah, source)
+);
+
 CREATE TABLE IF NOT EXISTS segment_payload (
     id TEXT PRIMARY KEY,
     verse_ref TEXT NOT NULL,
@@ -908,6 +1521,15 @@ CREATE TABLE IF NOT EXISTS annotations (
 
 CREATE INDEX IF NOT EXISTS idx_annotations_target ON annotations(target_id);
 
+CREATE TABLE IF NOT EXISTS annotation_layers (
+    name TEXT PRIMARY KEY,
+    description TEXT,
+    target TEXT NOT NULL DEFAULT 'any',
+    schema JSON NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
 CREATE TABLE IF NOT EXISTS connections (
     id TEXT PRIMARY KEY,
     from_token TEXT NOT NULL,
@@ -931,4 +1553,76 @@ CREATE TABLE IF NOT EXISTS research_data (
     value JSON NOT NULL,
     updated_at TEXT DEFAULT CURRENT_TIMESTAMP
 );
+
+CREATE TABLE IF NOT EXISTS tags (
+    name TEXT PRIMARY KEY,
+    parent TEXT,
+    description TEXT,
+    meta JSON NOT NULL DEFAULT '{}',
+    author TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_tags_parent ON tags(parent);
+
+CREATE TABLE IF NOT EXISTS tag_assignments (
+    id TEXT PRIMARY KEY,
+    tag TEXT NOT NULL,
+    target TEXT NOT NULL,
+    span_start INTEGER NOT NULL,
+    span_end INTEGER NOT NULL,
+    author TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_assignments_tag ON tag_assignments(tag);
+CREATE INDEX IF NOT EXISTS idx_tag_assignments_span ON tag_assignments(span_start, span_end);
+
+CREATE TABLE IF NOT EXISTS notes (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    author TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS note_links (
+    note_id TEXT NOT NULL,
+    target TEXT NOT NULL,
+    context TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    span_start INTEGER NOT NULL,
+    span_end INTEGER NOT NULL,
+    PRIMARY KEY (note_id, target)
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_links_span ON note_links(span_start, span_end);
+
+CREATE TABLE IF NOT EXISTS change_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    record_type TEXT NOT NULL,
+    record_id TEXT NOT NULL,
+    verse_ref TEXT,
+    action TEXT NOT NULL,
+    actor TEXT,
+    before_value JSON,
+    after_value JSON,
+    changed_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_change_log_record ON change_log(record_type, record_id);
+CREATE INDEX IF NOT EXISTS idx_change_log_verse ON change_log(verse_ref);
+
+CREATE TRIGGER IF NOT EXISTS change_log_no_update BEFORE UPDATE ON change_log
+BEGIN
+    SELECT RAISE(ABORT, 'change_log is append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS change_log_no_delete BEFORE DELETE ON change_log
+BEGIN
+    SELECT RAISE(ABORT, 'change_log is append-only');
+END;
 "#;
@@ -0,0 +1,286 @@
+//! Surah metadata and canonical divisions of the mushaf.
+//!
+//! Names, revelation place/order and ayah counts live on the `surahs` table;
+//! juz, hizb, rub' and manzil boundaries live in `divisions` as start verses,
+//! with each division ending just before the next one starts.
+
+use common::{Division, EngineError, EngineResult};
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::collections::BTreeMap;
+
+use crate::SqliteStorage;
+
+/// Metadata shipped with the repository (`datasets/quran-metadata.json`).
+const BUNDLED_METADATA: &str = include_str!("../../../datasets/quran-metadata.json");
+
+/// Division kinds understood by the API, in the order they are listed.
+pub const DIVISION_KINDS: [&str; 4] = ["juz", "hizb", "rub", "manzil"];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuranMetadata {
+    #[serde(default)]
+    pub source: Option<String>,
+    pub surahs: Vec<SurahMetadata>,
+    /// Division kind -> ordered list of `[surah, ayah]` start verses.
+    #[serde(default)]
+    pub divisions: BTreeMap<String, Vec<(i64, i64)>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurahMetadata {
+    pub number: i64,
+    pub name_ar: String,
+    pub name_translit: String,
+    #[serde(default)]
+    pub name_en: Option<String>,
+    pub ayah_count: i64,
+    /// `meccan` or `medinan`.
+    pub revelation_place: String,
+    pub revelation_order: i64,
+}
+
+impl QuranMetadata {
+    /// Parse metadata from JSON, validating division kinds and verse numbers.
+    pub fn from_json(json: &str) -> EngineResult<Self> {
+        let meta: QuranMetadata =
+            serde_json::from_str(json).map_err(|e| EngineError::Invalid(e.to_string()))?;
+        let ayahs: BTreeMap<i64, i64> = meta.surahs.iter().map(|s| (s.number, s.ayah_count)).collect();
+        for (kind, starts) in &meta.divisions {
+            if !DIVISION_KINDS.contains(&kind.as_str()) {
+                return Err(EngineError::Invalid(format!("Unknown division kind: {}", kind)));
+            }
+            for (surah, ayah) in starts {
+                let max = ayahs.get(surah).copied().unwrap_or(0);
+                if *ayah < 1 || *ayah > max {
+                    return Err(EngineError::Invalid(format!(
+                        "{} boundary {}:{} is outside the text",
+                        kind, surah, ayah
+                    )));
+                }
+            }
+            if starts.windows(2).any(|w| w[0] >= w[1]) {
+                return Err(EngineError::Invalid(format!("{} boundaries are not ascending", kind)));
+            }
+        }
+        Ok(meta)
+    }
+
+    /// The metadata file bundled with the engine.
+    pub fn bundled() -> EngineResult<Self> {
+        Self::from_json(BUNDLED_METADATA)
+    }
+}
+
+impl SqliteStorage {
+    /// Write surah metadata and replace all division boundaries in one transaction.
+    pub async fn load_metadata(&self, meta: &QuranMetadata) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        for s in &meta.surahs {
+            sqlx::query(
+                r#"
+                INSERT INTO surahs (number, name, name_translit, name_en, revelation_place, revelation_order, ayah_count)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(number) DO UPDATE SET
+                    name=excluded.name,
+                    name_translit=excluded.name_translit,
+                    name_en=excluded.name_en,
+                    revelation_place=excluded.revelation_place,
+                    revelation_order=excluded.revelation_order,
+                    ayah_count=excluded.ayah_count;
+                "#,
+            )
+            .bind(s.number)
+            .bind(&s.name_ar)
+            .bind(&s.name_translit)
+            .bind(s.name_en.as_ref())
+            .bind(&s.revelation_place)
+            .bind(s.revelation_order)
+            .bind(s.ayah_count)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        }
+
+        sqlx::query(r#"DELETE FROM divisions"#)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        for (kind, starts) in &meta.divisions {
+            for (i, (surah, ayah)) in starts.iter().enumerate() {
+                sqlx::query(
+                    r#"INSERT INTO divisions (kind, number, start_surah, start_ayah) VALUES (?1, ?2, ?3, ?4)"#,
+                )
+                .bind(kind)
+                .bind(i as i64 + 1)
+                .bind(surah)
+                .bind(ayah)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+            }
+        }
+        Self::commit_tx(tx).await
+    }
+
+    /// Load the bundled metadata unless surah metadata is already present.
+    /// Returns `true` when the metadata was loaded.
+    pub async fn ensure_metadata(&self) -> EngineResult<bool> {
+        let loaded: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM surahs WHERE revelation_order IS NOT NULL"#)
+            .fetch_one(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if loaded > 0 {
+            return Ok(false);
+        }
+        self.load_metadata(&QuranMetadata::bundled()?).await?;
+        Ok(true)
+    }
+
+    pub async fn list_surah_metadata(&self) -> EngineResult<Vec<SurahMetadata>> {
+        let rows = sqlx::query(
+            r#"SELECT number, name, name_translit, name_en, ayah_count, revelation_place, revelation_order
+               FROM surahs WHERE revelation_order IS NOT NULL ORDER BY number"#,
+        )
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| SurahMetadata {
+                number: r.try_get("number").unwrap_or(0),
+                name_ar: r.try_get::<Option<String>, _>("name").unwrap_or(None).unwrap_or_default(),
+                name_translit: r.try_get::<Option<String>, _>("name_translit").unwrap_or(None).unwrap_or_default(),
+                name_en: r.try_get("name_en").unwrap_or(None),
+                ayah_count: r.try_get::<Option<i64>, _>("ayah_count").unwrap_or(None).unwrap_or(0),
+                revelation_place: r.try_get::<Option<String>, _>("revelation_place").unwrap_or(None).unwrap_or_default(),
+                revelation_order: r.try_get::<Option<i64>, _>("revelation_order").unwrap_or(None).unwrap_or(0),
+            })
+            .collect())
+    }
+
+    /// Number of divisions of each kind that have boundaries loaded.
+    pub async fn count_divisions(&self) -> EngineResult<Vec<(String, i64)>> {
+        let rows = sqlx::query(r#"SELECT kind, COUNT(*) AS n FROM divisions GROUP BY kind"#)
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        let counts: BTreeMap<String, i64> = rows
+            .into_iter()
+            .map(|r| (r.try_get("kind").unwrap_or_default(), r.try_get("n").unwrap_or(0)))
+            .collect();
+        Ok(DIVISION_KINDS
+            .iter()
+            .map(|k| (k.to_string(), counts.get(*k).copied().unwrap_or(0)))
+            .collect())
+    }
+
+    /// All divisions of a kind with their inclusive end verses resolved.
+    pub async fn list_divisions(&self, kind: &str) -> EngineResult<Vec<Division>> {
+        if !DIVISION_KINDS.contains(&kind) {
+            return Err(EngineError::Invalid(format!("Unknown division kind: {}", kind)));
+        }
+        let rows = sqlx::query(
+            r#"SELECT number, start_surah, start_ayah FROM divisions WHERE kind = ?1 ORDER BY number"#,
+        )
+        .bind(kind)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        let starts: Vec<(i64, i64, i64)> = rows
+            .into_iter()
+            .map(|r| {
+                (
+                    r.try_get("number").unwrap_or(0),
+                    r.try_get("start_surah").unwrap_or(0),
+                    r.try_get("start_ayah").unwrap_or(0),
+                )
+            })
+            .collect();
+        if starts.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ayah_counts: BTreeMap<i64, i64> = self
+            .list_surah_metadata()
+            .await?
+            .into_iter()
+            .map(|s| (s.number, s.ayah_count))
+            .collect();
+        let last_surah = ayah_counts.keys().next_back().copied().unwrap_or(114);
+        let last_verse = (last_surah, ayah_counts.get(&last_surah).copied().unwrap_or(0));
+        // The verse immediately before a division start.
+        let previous = |surah: i64, ayah: i64| -> (i64, i64) {
+            if ayah > 1 {
+                (surah, ayah - 1)
+            } else {
+                (surah - 1, ayah_counts.get(&(surah - 1)).copied().unwrap_or(0))
+            }
+        };
+
+        Ok(starts
+            .iter()
+            .enumerate()
+            .map(|(i, &(number, start_surah, start_ayah))| {
+                let (end_surah, end_ayah) = starts
+                    .get(i + 1)
+                    .map(|&(_, s, a)| previous(s, a))
+                    .unwrap_or(last_verse);
+                Division {
+                    kind: kind.to_string(),
+                    number,
+                    start_surah,
+                    start_ayah,
+                    end_surah,
+                    end_ayah,
+                }
+            })
+            .collect())
+    }
+
+    pub async fn get_division(&self, kind: &str, number: i64) -> EngineResult<Option<Division>> {
+        Ok(self
+            .list_divisions(kind)
+            .await?
+            .into_iter()
+            .find(|d| d.number == number))
+    }
+
+    /// The division of every loaded kind that contains a verse.
+    pub async fn locate_verse(&self, surah: i64, ayah: i64) -> EngineResult<Vec<Division>> {
+        let mut out = Vec::new();
+        for kind in DIVISION_KINDS {
+            if let Some(d) = self
+                .list_divisions(kind)
+                .await?
+                .into_iter()
+                .rev()
+                .find(|d| (d.start_surah, d.start_ayah) <= (surah, ayah))
+            {
+                out.push(d);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Verse count per revelation place (`meccan` / `medinan`).
+    pub async fn count_verses_by_revelation_place(&self) -> EngineResult<Vec<(String, i64)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT s.revelation_place AS place, COUNT(*) AS n
+            FROM verses v
+            JOIN surahs s ON s.number = v.surah_number
+            WHERE s.revelation_place IS NOT NULL
+            GROUP BY s.revelation_place
+            ORDER BY s.revelation_place
+            "#,
+        )
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .into_iter()
+            .map(|r| (r.try_get("place").unwrap_or_default(), r.try_get("n").unwrap_or(0)))
+            .collect())
+    }
+}
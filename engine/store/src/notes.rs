@@ -0,0 +1,320 @@
+//! Markdown research notes and the verse backlinks they create.
+//!
+//! A note cites the corpus with wiki-style references: `[[2:255]]`,
+//! `[[2:255-257]]`, `[[2:255:3]]`, `[[al-Baqarah:255]]` or, with a label,
+//! `[[2:255|the Throne verse]]`. Anything an [`Anchor`] parses is accepted,
+//! as is a single verse or range in any form [`VerseRef`] reads. Each save
+//! re-parses the body into `note_links`, which carry the span of the cited
+//! anchor so "which notes cite this verse" is a range query like the other
+//! overlap lookups. References that do not parse, or name verses missing
+//! from the corpus, are not linked; [`SqliteStorage::unresolved_note_links`]
+//! lists them.
+
+use common::{Anchor, EngineError, EngineResult, Note, NoteBacklink, NoteLink, VerseRef};
+use sqlx::{Row, Sqlite, SqliteConnection};
+
+use crate::history::{self, RECORD_NOTE};
+use crate::SqliteStorage;
+
+const NOTE_COLUMNS: &str = "id, title, body, author, created_at, updated_at";
+/// Longest context line kept for a link, in characters.
+const CONTEXT_CHARS: usize = 200;
+
+/// A `[[...]]` reference as an anchor: anything [`Anchor`] parses, else a
+/// single verse, range or surah named the ways [`VerseRef`] accepts.
+fn reference_anchor(reference: &str) -> Option<Anchor> {
+    reference.parse::<Anchor>().ok().or_else(|| match reference.parse::<VerseRef>() {
+        Ok(verses) if verses.ranges().len() == 1 => Some(verses.ranges()[0].anchor()),
+        _ => None,
+    })
+}
+
+/// Every `[[...]]` reference in a note body, in order of first citation,
+/// with the anchor it names (if it names one) and the line it first
+/// appears on. References naming the same anchor count once.
+fn note_references(body: &str) -> Vec<(String, Option<Anchor>, String)> {
+    let mut references: Vec<(String, Option<Anchor>, String)> = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    for line in body.lines() {
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let Some(len) = rest[start + 2..].find("]]") else {
+                break;
+            };
+            let inner = &rest[start + 2..start + 2 + len];
+            rest = &rest[start + 2 + len + 2..];
+            let reference = inner.split('|').next().unwrap_or_default().trim();
+            let anchor = reference_anchor(reference);
+            let key = anchor.map(|a| a.to_string()).unwrap_or_else(|| reference.to_string());
+            if seen.contains(&key) {
+                continue;
+            }
+            seen.push(key);
+            references.push((reference.to_string(), anchor, line.trim().chars().take(CONTEXT_CHARS).collect()));
+        }
+    }
+    references
+}
+
+/// The anchors a note body cites, in order of first citation, each with the
+/// line it first appears on.
+pub fn parse_note_links(body: &str) -> Vec<NoteLink> {
+    note_references(body)
+        .into_iter()
+        .filter_map(|(_, anchor, context)| anchor.map(|a| NoteLink { target: a.to_string(), context }))
+        .collect()
+}
+
+/// Whether the verses an anchor starts and ends in are in the corpus.
+async fn in_corpus(conn: &mut SqliteConnection, anchor: &Anchor) -> EngineResult<bool> {
+    let verses = match *anchor {
+        Anchor::Surah(surah) => vec![(surah, None)],
+        Anchor::VerseRange { start, end } => vec![(start.0, Some(start.1)), (end.0, Some(end.1))],
+        _ => anchor.first_verse().map(|(s, a)| (s, Some(a))).into_iter().collect(),
+    };
+    for (surah, ayah) in verses {
+        let found: Option<i64> = sqlx::query_scalar(
+            "SELECT 1 FROM verses WHERE surah_number = ?1 AND (?2 IS NULL OR ayah_number = ?2) LIMIT 1",
+        )
+        .bind(surah)
+        .bind(ayah)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if found.is_none() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Split a body's references into links to the corpus and the references
+/// that do not resolve, as written.
+async fn resolve_links(conn: &mut SqliteConnection, body: &str) -> EngineResult<(Vec<(NoteLink, Anchor)>, Vec<String>)> {
+    let mut links = Vec::new();
+    let mut unresolved = Vec::new();
+    for (reference, anchor, context) in note_references(body) {
+        match anchor {
+            Some(anchor) if in_corpus(conn, &anchor).await? => {
+                links.push((NoteLink { target: anchor.to_string(), context }, anchor))
+            }
+            _ => unresolved.push(reference),
+        }
+    }
+    Ok((links, unresolved))
+}
+
+/// Title for a note that was saved without one: its first heading, else its
+/// first non-empty line.
+pub fn note_title(body: &str) -> String {
+    let lines = || body.lines().map(str::trim).filter(|l| !l.is_empty());
+    lines()
+        .find(|l| l.starts_with('#'))
+        .map(|l| l.trim_start_matches('#').trim())
+        .or_else(|| lines().next())
+        .unwrap_or_default()
+        .chars()
+        .take(CONTEXT_CHARS)
+        .collect()
+}
+
+fn note_from_row(row: &sqlx::sqlite::SqliteRow) -> Note {
+    Note {
+        id: row.try_get("id").unwrap_or_default(),
+        title: row.try_get("title").unwrap_or_default(),
+        body: row.try_get("body").unwrap_or_default(),
+        author: row.try_get("author").unwrap_or(None),
+        created_at: row.try_get("created_at").unwrap_or(None),
+        updated_at: row.try_get("updated_at").unwrap_or(None),
+    }
+}
+
+async fn fetch_note<'e, E>(executor: E, id: &str) -> EngineResult<Option<Note>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(&format!("SELECT {} FROM notes WHERE id = ?1", NOTE_COLUMNS))
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(row.as_ref().map(note_from_row))
+}
+
+async fn replace_links(conn: &mut SqliteConnection, id: &str, body: &str) -> EngineResult<()> {
+    sqlx::query("DELETE FROM note_links WHERE note_id = ?1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    let (links, _) = resolve_links(conn, body).await?;
+    for (position, (link, anchor)) in links.into_iter().enumerate() {
+        let (start, end) = anchor.span();
+        sqlx::query(
+            r#"INSERT INTO note_links (note_id, target, context, position, span_start, span_end)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        )
+        .bind(id)
+        .bind(&link.target)
+        .bind(&link.context)
+        .bind(position as i64)
+        .bind(start)
+        .bind(end)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn write_note(conn: &mut SqliteConnection, note: &Note, actor: Option<&str>) -> EngineResult<()> {
+    let id = note.id.trim();
+    if id.is_empty() {
+        return Err(EngineError::Invalid("Note id must not be empty".into()));
+    }
+    let title = match note.title.trim() {
+        "" => note_title(&note.body),
+        title => title.to_string(),
+    };
+    if title.is_empty() {
+        return Err(EngineError::Invalid("A note needs a title or some text".into()));
+    }
+
+    let before = fetch_note(&mut *conn, id).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO notes (id, title, body, author, created_at, updated_at)
+        VALUES (?1, ?2, ?3, COALESCE(?4, ?5), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT(id) DO UPDATE SET
+          updated_at=CASE
+            WHEN notes.title IS NOT excluded.title OR notes.body IS NOT excluded.body
+            THEN CURRENT_TIMESTAMP ELSE notes.updated_at END,
+          title=excluded.title,
+          body=excluded.body,
+          author=COALESCE(?4, notes.author, ?5)
+        "#,
+    )
+    .bind(id)
+    .bind(&title)
+    .bind(&note.body)
+    .bind(note.author.as_deref())
+    .bind(actor)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    replace_links(conn, id, &note.body).await?;
+
+    let before = before.map(|n| history::snapshot(&n));
+    let after = fetch_note(&mut *conn, id).await?.map(|n| history::snapshot(&n));
+    history::record_change(conn, RECORD_NOTE, id, None, actor, before.as_ref(), after.as_ref()).await
+}
+
+pub(crate) async fn remove_note(conn: &mut SqliteConnection, id: &str, actor: Option<&str>) -> EngineResult<()> {
+    let before = fetch_note(&mut *conn, id).await?.ok_or(EngineError::NotFound)?;
+    for table in ["DELETE FROM note_links WHERE note_id = ?1", "DELETE FROM notes WHERE id = ?1"] {
+        sqlx::query(table)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+    history::record_change(conn, RECORD_NOTE, id, None, actor, Some(&history::snapshot(&before)), None)
+        .await
+}
+
+impl SqliteStorage {
+    /// Notes by title, optionally only those written by `author`.
+    pub async fn list_notes(&self, author: Option<&str>) -> EngineResult<Vec<Note>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM notes WHERE (?1 IS NULL OR author = ?1) ORDER BY title, id",
+            NOTE_COLUMNS
+        ))
+        .bind(author)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows.iter().map(note_from_row).collect())
+    }
+
+    pub async fn get_note(&self, id: &str) -> EngineResult<Option<Note>> {
+        fetch_note(self.pool(), id).await
+    }
+
+    /// Create or update a note, recording the change under `actor`, and
+    /// re-index the references in its body. A note without a title takes
+    /// its first heading.
+    pub async fn upsert_note(&self, note: &Note, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        write_note(&mut tx, note, actor).await?;
+        Self::commit_tx(tx).await
+    }
+
+    pub async fn delete_note(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        remove_note(&mut tx, id, actor).await?;
+        Self::commit_tx(tx).await
+    }
+
+    /// Notes whose title or body contains `query`, ignoring case.
+    pub async fn search_notes(&self, query: &str) -> EngineResult<Vec<Note>> {
+        let rows = sqlx::query(&format!(
+            r#"SELECT {} FROM notes
+               WHERE instr(lower(title), lower(?1)) > 0 OR instr(lower(body), lower(?1)) > 0
+               ORDER BY title, id"#,
+            NOTE_COLUMNS
+        ))
+        .bind(query.trim())
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows.iter().map(note_from_row).collect())
+    }
+
+    /// The anchors a note cites, in the order it first cites them.
+    pub async fn note_links(&self, id: &str) -> EngineResult<Vec<NoteLink>> {
+        let rows = sqlx::query("SELECT target, context FROM note_links WHERE note_id = ?1 ORDER BY position")
+            .bind(id)
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| NoteLink {
+                target: r.try_get("target").unwrap_or_default(),
+                context: r.try_get("context").unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// References in `body` that do not link to the corpus, as written: text
+    /// that is not a reference, or verses the corpus does not have.
+    pub async fn unresolved_note_links(&self, body: &str) -> EngineResult<Vec<String>> {
+        let mut conn = self.pool().acquire().await.map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(resolve_links(&mut conn, body).await?.1)
+    }
+
+    /// Citations overlapping `anchor`, in text order.
+    pub async fn note_backlinks(&self, anchor: &Anchor) -> EngineResult<Vec<NoteBacklink>> {
+        let (start, end) = anchor.span();
+        let rows = sqlx::query(
+            r#"SELECT l.note_id, n.title, l.target, l.context FROM note_links l
+               JOIN notes n ON n.id = l.note_id
+               WHERE l.span_start <= ?2 AND l.span_end >= ?1
+               ORDER BY l.span_start, l.span_end, n.title, l.note_id"#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| NoteBacklink {
+                note_id: r.try_get("note_id").unwrap_or_default(),
+                title: r.try_get("title").unwrap_or_default(),
+                target: r.try_get("target").unwrap_or_default(),
+                context: r.try_get("context").unwrap_or_default(),
+            })
+            .collect())
+    }
+}
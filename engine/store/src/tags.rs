@@ -0,0 +1,453 @@
+//! Research tags: a hierarchy of named tags and their assignments to anchors.
+//!
+//! Assignments carry the span of their target (see [`Anchor::span`]) in
+//! `span_start`/`span_end`, so "which verses carry tag X" is a range join
+//! against `verses`. Hierarchy queries walk `parent` with a recursive CTE;
+//! a verse tagged with a child tag also counts for all of its ancestors.
+//!
+//! Tags used to live in a single `research_data["tags"]` blob. Databases
+//! that still have one are migrated into the tables on start.
+
+use common::{Anchor, EngineError, EngineResult, Tag, TagAssignment, TagCount};
+use serde_json::Value;
+use sqlx::{Pool, Row, Sqlite, SqliteConnection};
+
+use crate::history::{self, RECORD_TAG, RECORD_TAG_ASSIGNMENT};
+use crate::SqliteStorage;
+
+const TAG_COLUMNS: &str = "name, parent, description, meta, author, created_at, updated_at";
+const ASSIGNMENT_COLUMNS: &str = "id, tag, target, author, created_at, updated_at";
+
+/// Names of `?1` and, when `?2` is true, all of its descendants.
+const SUBTREE: &str = r#"WITH RECURSIVE subtree(name) AS (
+    SELECT ?1
+    UNION SELECT t.name FROM tags t JOIN subtree s ON t.parent = s.name WHERE ?2
+) SELECT name FROM subtree"#;
+
+/// Build a tag from its JSON form as kept in the legacy blob or sent by
+/// clients: `parent`, `description` and `author` are taken out, every other
+/// field ends up in `meta`.
+pub fn tag_from_value(name: &str, value: &Value) -> Tag {
+    let mut meta = match value {
+        Value::Object(obj) => obj.clone(),
+        _ => serde_json::Map::new(),
+    };
+    let mut take = |key: &str| match meta.remove(key) {
+        Some(Value::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        _ => None,
+    };
+    let parent = take("parent");
+    let description = take("description");
+    let author = take("author");
+    meta.remove("name");
+    meta.remove("created_at");
+    meta.remove("updated_at");
+    if let Some(Value::Object(inner)) = meta.remove("meta") {
+        meta.extend(inner);
+    }
+    Tag {
+        name: name.trim().to_string(),
+        parent,
+        description,
+        meta: Value::Object(meta),
+        author,
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+fn tag_from_row(row: &sqlx::sqlite::SqliteRow) -> Tag {
+    Tag {
+        name: row.try_get("name").unwrap_or_default(),
+        parent: row.try_get("parent").unwrap_or(None),
+        description: row.try_get("description").unwrap_or(None),
+        meta: row.try_get("meta").unwrap_or(serde_json::json!({})),
+        author: row.try_get("author").unwrap_or(None),
+        created_at: row.try_get("created_at").unwrap_or(None),
+        updated_at: row.try_get("updated_at").unwrap_or(None),
+    }
+}
+
+fn assignment_from_row(row: &sqlx::sqlite::SqliteRow) -> TagAssignment {
+    TagAssignment {
+        id: row.try_get("id").unwrap_or_default(),
+        tag: row.try_get("tag").unwrap_or_default(),
+        target: row.try_get("target").unwrap_or_default(),
+        author: row.try_get("author").unwrap_or(None),
+        created_at: row.try_get("created_at").unwrap_or(None),
+        updated_at: row.try_get("updated_at").unwrap_or(None),
+    }
+}
+
+async fn fetch_tag<'e, E>(executor: E, name: &str) -> EngineResult<Option<Tag>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(&format!("SELECT {} FROM tags WHERE name = ?1", TAG_COLUMNS))
+        .bind(name)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(row.as_ref().map(tag_from_row))
+}
+
+async fn fetch_assignment<'e, E>(executor: E, id: &str) -> EngineResult<Option<TagAssignment>>
+where
+    E: sqlx::Executor<'e, Database = Sqlite>,
+{
+    let row = sqlx::query(&format!("SELECT {} FROM tag_assignments WHERE id = ?1", ASSIGNMENT_COLUMNS))
+        .bind(id)
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    Ok(row.as_ref().map(assignment_from_row))
+}
+
+pub(crate) async fn delete_assignment(conn: &mut SqliteConnection, id: &str, actor: Option<&str>) -> EngineResult<()> {
+    let Some(before) = fetch_assignment(&mut *conn, id).await? else {
+        return Ok(());
+    };
+    sqlx::query("DELETE FROM tag_assignments WHERE id = ?1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    history::record_change(
+        conn,
+        RECORD_TAG_ASSIGNMENT,
+        id,
+        history::verse_of(&before.target).as_deref(),
+        actor,
+        Some(&history::snapshot(&before)),
+        None,
+    )
+    .await
+}
+
+pub(crate) async fn write_tag(conn: &mut SqliteConnection, tag: &Tag, actor: Option<&str>) -> EngineResult<()> {
+    let name = tag.name.trim();
+    if name.is_empty() {
+        return Err(EngineError::Invalid("Tag name must not be empty".into()));
+    }
+    let parent = tag.parent.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    let meta = if tag.meta.is_null() { serde_json::json!({}) } else { tag.meta.clone() };
+
+    if let Some(parent) = parent {
+        if fetch_tag(&mut *conn, parent).await?.is_none() {
+            return Err(EngineError::Invalid(format!("Unknown parent tag: {}", parent)));
+        }
+        let cycle: bool = sqlx::query_scalar(&format!("SELECT ?3 IN ({})", SUBTREE))
+            .bind(name)
+            .bind(true)
+            .bind(parent)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if cycle {
+            return Err(EngineError::Invalid(format!(
+                "Tag {} cannot be placed under itself or its descendant {}",
+                name, parent
+            )));
+        }
+    }
+
+    let before = fetch_tag(&mut *conn, name).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO tags (name, parent, description, meta, author, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, COALESCE(?5, ?6), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT(name) DO UPDATE SET
+          updated_at=CASE
+            WHEN tags.parent IS NOT excluded.parent
+              OR tags.description IS NOT excluded.description
+              OR tags.meta IS NOT excluded.meta
+            THEN CURRENT_TIMESTAMP ELSE tags.updated_at END,
+          parent=excluded.parent,
+          description=excluded.description,
+          meta=excluded.meta,
+          author=COALESCE(?5, tags.author, ?6)
+        "#,
+    )
+    .bind(name)
+    .bind(parent)
+    .bind(&tag.description)
+    .bind(&meta)
+    .bind(tag.author.as_deref())
+    .bind(actor)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    let before = before.map(|t| history::snapshot(&t));
+    let after = fetch_tag(&mut *conn, name).await?.map(|t| history::snapshot(&t));
+    history::record_change(conn, RECORD_TAG, name, None, actor, before.as_ref(), after.as_ref()).await
+}
+
+pub(crate) async fn remove_tag(conn: &mut SqliteConnection, name: &str, actor: Option<&str>) -> EngineResult<()> {
+    let before = fetch_tag(&mut *conn, name).await?.ok_or(EngineError::NotFound)?;
+    let children: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tags WHERE parent = ?1")
+        .bind(name)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    if children > 0 {
+        return Err(EngineError::Conflict(format!("Tag {} still has {} child tag(s)", name, children)));
+    }
+
+    let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM tag_assignments WHERE tag = ?1 ORDER BY id")
+        .bind(name)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    for id in ids {
+        delete_assignment(conn, &id, actor).await?;
+    }
+    sqlx::query("DELETE FROM tags WHERE name = ?1")
+        .bind(name)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    history::record_change(conn, RECORD_TAG, name, None, actor, Some(&history::snapshot(&before)), None)
+        .await
+}
+
+pub(crate) async fn write_assignment(
+    conn: &mut SqliteConnection,
+    assignment: &TagAssignment,
+    actor: Option<&str>,
+) -> EngineResult<()> {
+    if assignment.id.trim().is_empty() {
+        return Err(EngineError::Invalid("Tag assignment id must not be empty".into()));
+    }
+    let anchor = Anchor::parse_target(assignment.target.trim())?
+        .ok_or_else(|| EngineError::Invalid(format!("Not an anchor: {}", assignment.target)))?;
+    let target = anchor.to_string();
+    let (start, end) = anchor.span();
+
+    if fetch_tag(&mut *conn, &assignment.tag).await?.is_none() {
+        return Err(EngineError::Invalid(format!("Unknown tag: {}", assignment.tag)));
+    }
+    let before = fetch_assignment(&mut *conn, &assignment.id).await?;
+    sqlx::query(
+        r#"
+        INSERT INTO tag_assignments (id, tag, target, span_start, span_end, author, created_at, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, COALESCE(?6, ?7), CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+        ON CONFLICT(id) DO UPDATE SET
+          updated_at=CASE
+            WHEN tag_assignments.tag IS NOT excluded.tag
+              OR tag_assignments.target IS NOT excluded.target
+            THEN CURRENT_TIMESTAMP ELSE tag_assignments.updated_at END,
+          tag=excluded.tag,
+          target=excluded.target,
+          span_start=excluded.span_start,
+          span_end=excluded.span_end,
+          author=COALESCE(?6, tag_assignments.author, ?7)
+        "#,
+    )
+    .bind(&assignment.id)
+    .bind(&assignment.tag)
+    .bind(&target)
+    .bind(start)
+    .bind(end)
+    .bind(assignment.author.as_deref())
+    .bind(actor)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+    let before = before.map(|a| history::snapshot(&a));
+    let after = fetch_assignment(&mut *conn, &assignment.id).await?.map(|a| history::snapshot(&a));
+    history::record_change(
+        conn,
+        RECORD_TAG_ASSIGNMENT,
+        &assignment.id,
+        history::verse_of(&target).as_deref(),
+        actor,
+        before.as_ref(),
+        after.as_ref(),
+    )
+    .await
+}
+
+/// Move tags out of the legacy `research_data["tags"]` blob into the tag
+/// tables. Parents that were never defined are dropped; the blob is removed
+/// once its tags are in.
+pub(crate) async fn migrate_legacy_tags(pool: &Pool<Sqlite>) -> EngineResult<()> {
+    let blob: Option<Value> = sqlx::query_scalar("SELECT value FROM research_data WHERE key = 'tags'")
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    let Some(blob) = blob else {
+        return Ok(());
+    };
+
+    let mut tx = pool.begin().await.map_err(|e| EngineError::Storage(e.to_string()))?;
+    for (name, value) in blob.get("tags").and_then(|t| t.as_object()).into_iter().flatten() {
+        let tag = tag_from_value(name, value);
+        if tag.name.is_empty() {
+            continue;
+        }
+        sqlx::query(
+            r#"INSERT OR IGNORE INTO tags (name, parent, description, meta, author)
+               VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        )
+        .bind(&tag.name)
+        .bind(&tag.parent)
+        .bind(&tag.description)
+        .bind(&tag.meta)
+        .bind(&tag.author)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    }
+    sqlx::query(
+        r#"UPDATE tags SET parent = NULL
+           WHERE parent IS NOT NULL AND (parent = name OR parent NOT IN (SELECT name FROM tags))"#,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| EngineError::Storage(e.to_string()))?;
+    sqlx::query("DELETE FROM research_data WHERE key = 'tags'")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+    tx.commit().await.map_err(|e| EngineError::Storage(e.to_string()))
+}
+
+impl SqliteStorage {
+    pub async fn list_tags(&self) -> EngineResult<Vec<Tag>> {
+        let rows = sqlx::query(&format!("SELECT {} FROM tags ORDER BY name", TAG_COLUMNS))
+            .fetch_all(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows.iter().map(tag_from_row).collect())
+    }
+
+    pub async fn get_tag(&self, name: &str) -> EngineResult<Option<Tag>> {
+        fetch_tag(self.pool(), name).await
+    }
+
+    /// Create or update a tag, recording the change under `actor`.
+    ///
+    /// The parent has to exist already and must not be the tag itself or one
+    /// of its descendants.
+    pub async fn upsert_tag(&self, tag: &Tag, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        write_tag(&mut tx, tag, actor).await?;
+        Self::commit_tx(tx).await
+    }
+
+    /// Delete a tag together with its assignments. Tags that still have
+    /// children are refused; move or delete the children first.
+    pub async fn delete_tag(&self, name: &str, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        remove_tag(&mut tx, name, actor).await?;
+        Self::commit_tx(tx).await
+    }
+
+    /// Apply a tag to an anchor, or update an existing assignment.
+    ///
+    /// The target has to parse as an [`Anchor`]; it is stored in canonical form.
+    pub async fn assign_tag(&self, assignment: &TagAssignment, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        write_assignment(&mut tx, assignment, actor).await?;
+        Self::commit_tx(tx).await
+    }
+
+    pub async fn get_tag_assignment(&self, id: &str) -> EngineResult<Option<TagAssignment>> {
+        fetch_assignment(self.pool(), id).await
+    }
+
+    pub async fn unassign_tag(&self, id: &str, actor: Option<&str>) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        delete_assignment(&mut tx, id, actor).await?;
+        Self::commit_tx(tx).await
+    }
+
+    /// Assignments of `tag` (and, with `descendants`, of the tags below it),
+    /// or of every tag when `tag` is `None`, in text order. `anchor` keeps
+    /// only assignments overlapping it.
+    pub async fn list_tag_assignments(
+        &self,
+        tag: Option<&str>,
+        descendants: bool,
+        anchor: Option<&Anchor>,
+        author: Option<&str>,
+    ) -> EngineResult<Vec<TagAssignment>> {
+        let span = anchor.map(|a| a.span());
+        let rows = sqlx::query(&format!(
+            r#"SELECT {} FROM tag_assignments
+               WHERE (?1 IS NULL OR tag IN ({}))
+                 AND (?3 IS NULL OR (span_start <= ?4 AND span_end >= ?3))
+                 AND (?5 IS NULL OR author = ?5)
+               ORDER BY span_start, span_end, tag, id"#,
+            ASSIGNMENT_COLUMNS, SUBTREE
+        ))
+        .bind(tag)
+        .bind(descendants)
+        .bind(span.map(|s| s.0))
+        .bind(span.map(|s| s.1))
+        .bind(author)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows.iter().map(assignment_from_row).collect())
+    }
+
+    /// Verses of the corpus overlapped by an assignment of `tag` (or, with
+    /// `descendants`, of any tag below it), in mushaf order.
+    pub async fn tagged_verses(&self, tag: &str, descendants: bool) -> EngineResult<Vec<(i64, i64)>> {
+        let rows = sqlx::query(&format!(
+            r#"SELECT DISTINCT v.surah_number, v.ayah_number FROM verses v
+               JOIN tag_assignments a
+                 ON a.span_start <= (v.surah_number * 1000 + v.ayah_number) * 1000000 + 999999
+                AND a.span_end >= (v.surah_number * 1000 + v.ayah_number) * 1000000
+               WHERE a.tag IN ({})
+               ORDER BY v.surah_number, v.ayah_number"#,
+            SUBTREE
+        ))
+        .bind(tag)
+        .bind(descendants)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        Ok(rows
+            .iter()
+            .map(|r| (r.try_get("surah_number").unwrap_or_default(), r.try_get("ayah_number").unwrap_or_default()))
+            .collect())
+    }
+
+    /// Usage of every tag, counting assignments of descendants towards
+    /// `total` and `verses`.
+    pub async fn tag_counts(&self) -> EngineResult<Vec<TagCount>> {
+        let mut counts = Vec::new();
+        for tag in self.list_tags().await? {
+            let row = sqlx::query(&format!(
+                r#"WITH sub(name) AS ({subtree})
+                   SELECT
+                     (SELECT COUNT(*) FROM tag_assignments WHERE tag = ?1) AS assignments,
+                     (SELECT COUNT(*) FROM tag_assignments WHERE tag IN (SELECT name FROM sub)) AS total,
+                     (SELECT COUNT(*) FROM (
+                        SELECT DISTINCT v.surah_number, v.ayah_number FROM verses v
+                        JOIN tag_assignments a
+                          ON a.span_start <= (v.surah_number * 1000 + v.ayah_number) * 1000000 + 999999
+                         AND a.span_end >= (v.surah_number * 1000 + v.ayah_number) * 1000000
+                        WHERE a.tag IN (SELECT name FROM sub))) AS verses"#,
+                subtree = SUBTREE
+            ))
+            .bind(&tag.name)
+            .bind(true)
+            .fetch_one(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+            counts.push(TagCount {
+                tag: tag.name,
+                parent: tag.parent,
+                assignments: row.try_get("assignments").unwrap_or_default(),
+                total: row.try_get("total").unwrap_or_default(),
+                verses: row.try_get("verses").unwrap_or_default(),
+            });
+        }
+        Ok(counts)
+    }
+}
@@ -0,0 +1,142 @@
+//! Trash for soft-deleted annotations and connections.
+//!
+//! Deleting either record only stamps `deleted_at`; rows stay restorable until
+//! they are purged once older than the retention period.
+
+use common::{EngineError, EngineResult, TrashItem};
+use sqlx::Row;
+
+use crate::history::{self, RECORD_ANNOTATION, RECORD_CONNECTION};
+use crate::{annotation_from_row, connection_from_row, SqliteStorage, ANNOTATION_COLUMNS, CONNECTION_COLUMNS};
+
+/// Days a trashed record is kept before `purge_trash` removes it for good.
+pub const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+impl SqliteStorage {
+    /// All trashed annotations and connections, most recently deleted first.
+    pub async fn list_trash(&self) -> EngineResult<Vec<TrashItem>> {
+        let mut items = Vec::new();
+
+        let rows = sqlx::query(&format!(
+            r#"SELECT {}, deleted_at FROM annotations WHERE deleted_at IS NOT NULL"#,
+            ANNOTATION_COLUMNS
+        ))
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        for r in rows {
+            let ann = annotation_from_row(&r)?;
+            items.push(TrashItem {
+                record_type: RECORD_ANNOTATION.to_string(),
+                id: ann.id.clone(),
+                verse_ref: history::verse_of(&ann.target_id),
+                deleted_at: r.try_get("deleted_at").unwrap_or_default(),
+                record: history::snapshot(&ann),
+            });
+        }
+
+        let rows = sqlx::query(&format!(
+            r#"SELECT {}, deleted_at FROM connections WHERE deleted_at IS NOT NULL"#,
+            CONNECTION_COLUMNS
+        ))
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        for r in rows {
+            let conn = connection_from_row(&r);
+            items.push(TrashItem {
+                record_type: RECORD_CONNECTION.to_string(),
+                id: conn.id.clone(),
+                verse_ref: history::verse_of(&conn.from_token),
+                deleted_at: r.try_get("deleted_at").unwrap_or_default(),
+                record: history::snapshot(&conn),
+            });
+        }
+
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        Ok(items)
+    }
+
+    /// Take a record back out of the trash.
+    pub async fn restore_from_trash(
+        &self,
+        record_type: &str,
+        id: &str,
+        actor: Option<&str>,
+    ) -> EngineResult<()> {
+        let mut tx = self.begin_tx().await?;
+        // Look the row up inside the transaction so a concurrent purge or
+        // restore cannot slip in between the lookup and the update.
+        let (table, verse_ref, record) = match record_type {
+            RECORD_ANNOTATION => {
+                let row = sqlx::query(&format!(
+                    r#"SELECT {} FROM annotations WHERE id = ?1 AND deleted_at IS NOT NULL"#,
+                    ANNOTATION_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?
+                .ok_or(EngineError::NotFound)?;
+                let ann = annotation_from_row(&row)?;
+                ("annotations", history::verse_of(&ann.target_id), history::snapshot(&ann))
+            }
+            RECORD_CONNECTION => {
+                let row = sqlx::query(&format!(
+                    r#"SELECT {} FROM connections WHERE id = ?1 AND deleted_at IS NOT NULL"#,
+                    CONNECTION_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?
+                .ok_or(EngineError::NotFound)?;
+                let conn = connection_from_row(&row);
+                ("connections", history::verse_of(&conn.from_token), history::snapshot(&conn))
+            }
+            other => return Err(EngineError::Invalid(format!("Unknown record type: {}", other))),
+        };
+
+        let res = sqlx::query(&format!(
+            "UPDATE {} SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            table
+        ))
+        .bind(id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        if res.rows_affected() == 0 {
+            return Err(EngineError::NotFound);
+        }
+        history::insert_change(
+            &mut tx,
+            "restore",
+            record_type,
+            id,
+            verse_ref.as_deref(),
+            actor,
+            None,
+            Some(&record),
+        )
+        .await?;
+        Self::commit_tx(tx).await
+    }
+
+    /// Permanently remove trashed records deleted more than `older_than_days` ago.
+    /// Returns the number of rows removed.
+    pub async fn purge_trash(&self, older_than_days: i64) -> EngineResult<u64> {
+        let mut removed = 0;
+        for table in ["annotations", "connections"] {
+            let res = sqlx::query(&format!(
+                "DELETE FROM {} WHERE deleted_at IS NOT NULL AND deleted_at <= datetime('now', ?1)",
+                table
+            ))
+            .bind(format!("-{} days", older_than_days.max(0)))
+            .execute(self.pool())
+            .await
+            .map_err(|e| EngineError::Storage(e.to_string()))?;
+            removed += res.rows_affected();
+        }
+        Ok(removed)
+    }
+}
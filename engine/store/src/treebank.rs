@@ -0,0 +1,288 @@
+//! Dependency treebank: syntax nodes and labelled head → dependent edges per
+//! verse, in the style of the Quranic Arabic Corpus.
+//!
+//! Nodes are morphological segments, elided words the grammar implies, and
+//! phrases spanning other nodes. Relation and constituent tags come from the
+//! tag sets shipped in `datasets/Quranic` (`RelLabels.csv` and
+//! `ConstituentsTags.csv`).
+//!
+//! The import format is tab separated, one node per line:
+//!
+//! ```text
+//! # ID            KIND     TAG  FORM                    HEAD         REL
+//! (1:1:1:1)       segment  P    bi                      -            -
+//! (1:1:1:2)       segment  N    somi                    (1:1:1:1)    gen
+//! (1:1:*1)        elided   V    >abotadi}u              -            -
+//! (1:1:P1)        phrase   PP   (1:1:1:1)-(1:1:2:1)     (1:1:*1)     link
+//! ```
+//!
+//! Segment locations are `(surah:ayah:word:morpheme)` with words and
+//! morphemes counted from 1 as in the corpus; elided words (`*n`) and phrases
+//! (`Pn`) are numbered within their verse. `-` or `_` leaves a column empty.
+//! A node listed again adds another head. Lines starting with `#` are
+//! comments.
+
+use common::{Anchor, DependencyEdge, DependencyTree, EngineError, EngineResult, SyntaxNode, TagLabels};
+use sqlx::Row;
+use std::collections::BTreeMap;
+
+use crate::SqliteStorage;
+
+pub const NODE_SEGMENT: &str = "segment";
+pub const NODE_ELIDED: &str = "elided";
+pub const NODE_PHRASE: &str = "phrase";
+
+/// Fill in relation and phrase names from `labels`.
+fn label_tree(labels: &TagLabels, tree: &mut DependencyTree) {
+    for node in tree.nodes.iter_mut().filter(|n| n.kind == NODE_PHRASE) {
+        if let Some(label) = labels.constituent(&node.tag) {
+            node.label_en = Some(label.en.clone());
+            node.label_ar = Some(label.ar.clone());
+        }
+    }
+    for edge in tree.edges.iter_mut() {
+        edge.relation_ar = labels.relation(&edge.relation).map(|l| l.ar.clone());
+    }
+}
+
+/// A node location from the import format, as (surah, ayah, node id).
+fn location(s: &str, line: usize) -> EngineResult<(i64, i64, String)> {
+    let invalid = || EngineError::Invalid(format!("line {}: invalid location '{}'", line, s));
+    let inner = s.trim().trim_start_matches('(').trim_end_matches(')');
+    let parts: Vec<&str> = inner.split(':').collect();
+    let num = |p: &str| p.parse::<i64>().ok().filter(|n| *n >= 1).ok_or_else(invalid);
+    match parts.as_slice() {
+        [surah, ayah, word, morpheme] => {
+            let (surah, ayah) = (num(surah)?, num(ayah)?);
+            let anchor = Anchor::Segment { surah, ayah, token: num(word)? - 1, segment: num(morpheme)? - 1 };
+            Ok((surah, ayah, anchor.to_string()))
+        }
+        [surah, ayah, local]
+            if (local.starts_with('*') || local.starts_with('P')) && local[1..].parse::<u32>().is_ok() =>
+        {
+            Ok((num(surah)?, num(ayah)?, local.to_string()))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// A reference to another node of the same verse.
+fn reference(s: &str, verse: (i64, i64), line: usize) -> EngineResult<String> {
+    let (surah, ayah, id) = location(s, line)?;
+    if (surah, ayah) != verse {
+        return Err(EngineError::Invalid(format!(
+            "line {}: {} is outside verse {}:{}",
+            line, s, verse.0, verse.1
+        )));
+    }
+    Ok(id)
+}
+
+fn column(s: Option<&&str>) -> Option<String> {
+    s.map(|c| c.trim()).filter(|c| !c.is_empty() && *c != "-" && *c != "_").map(str::to_string)
+}
+
+/// Parse a treebank in the import format, checking tags against `tags` and
+/// node references within each verse.
+pub fn parse_treebank(text: &str, tags: &TagLabels) -> EngineResult<Vec<DependencyTree>> {
+    let mut verses: BTreeMap<(i64, i64), DependencyTree> = BTreeMap::new();
+    // Node references and the line they were made on, checked at the end.
+    let mut references: Vec<((i64, i64), String, usize)> = Vec::new();
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = i + 1;
+        let raw = raw.trim_start_matches('\u{feff}').trim_end_matches('\r');
+        if raw.trim().is_empty() || raw.trim_start().starts_with('#') {
+            continue;
+        }
+        let cols: Vec<&str> = raw.split('\t').collect();
+        let (surah, ayah, id) = location(cols[0], line)?;
+        let tree = verses.entry((surah, ayah)).or_insert_with(|| DependencyTree {
+            surah,
+            ayah,
+            ..Default::default()
+        });
+
+        if tree.node(&id).is_none() {
+            let kind = column(cols.get(1))
+                .ok_or_else(|| EngineError::Invalid(format!("line {}: missing node kind", line)))?;
+            let tag = column(cols.get(2))
+                .ok_or_else(|| EngineError::Invalid(format!("line {}: missing tag", line)))?;
+            let form = column(cols.get(3));
+            let mut node = SyntaxNode { id: id.clone(), kind: kind.clone(), tag, ..Default::default() };
+            match kind.as_str() {
+                NODE_SEGMENT => {
+                    if let Ok(Anchor::Segment { token, segment, .. }) = id.parse::<Anchor>() {
+                        node.token_index = Some(token);
+                        node.segment_index = Some(segment);
+                    } else {
+                        return Err(EngineError::Invalid(format!("line {}: {} is not a segment location", line, cols[0])));
+                    }
+                    node.form = form;
+                }
+                NODE_ELIDED if id.starts_with('*') => node.form = form,
+                NODE_PHRASE if id.starts_with('P') => {
+                    if tags.constituent(&node.tag).is_none() {
+                        return Err(EngineError::Invalid(format!("line {}: unknown constituent tag '{}'", line, node.tag)));
+                    }
+                    let span = form
+                        .ok_or_else(|| EngineError::Invalid(format!("line {}: phrase {} has no span", line, id)))?;
+                    let (start, end) = span
+                        .split_once(")-(")
+                        .map(|(a, b)| (format!("{})", a), format!("({}", b)))
+                        .or_else(|| span.split_once('-').map(|(a, b)| (a.to_string(), b.to_string())))
+                        .ok_or_else(|| EngineError::Invalid(format!("line {}: invalid span '{}'", line, span)))?;
+                    let (start, end) = (reference(&start, (surah, ayah), line)?, reference(&end, (surah, ayah), line)?);
+                    references.push(((surah, ayah), start.clone(), line));
+                    references.push(((surah, ayah), end.clone(), line));
+                    node.start = Some(start);
+                    node.end = Some(end);
+                }
+                _ => {
+                    return Err(EngineError::Invalid(format!(
+                        "line {}: node {} cannot be a {} (expected segment, elided or phrase)",
+                        line, cols[0], kind
+                    )))
+                }
+            }
+            tree.nodes.push(node);
+        }
+
+        match (column(cols.get(4)), column(cols.get(5))) {
+            (None, None) => {}
+            (Some(head), Some(relation)) => {
+                let relation = &tags
+                    .relation(&relation)
+                    .ok_or_else(|| EngineError::Invalid(format!("line {}: unknown relation '{}'", line, relation)))?
+                    .code;
+                let head = reference(&head, (surah, ayah), line)?;
+                references.push(((surah, ayah), head.clone(), line));
+                let edge = DependencyEdge { head, dependent: id, relation: relation.clone(), relation_ar: None };
+                if !tree.edges.contains(&edge) {
+                    tree.edges.push(edge);
+                }
+            }
+            _ => return Err(EngineError::Invalid(format!("line {}: a head needs a relation and vice versa", line))),
+        }
+    }
+
+    // References may point forward, so check them once every node is known.
+    for (key, id, line) in &references {
+        if verses.get(key).and_then(|tree| tree.node(id)).is_none() {
+            return Err(EngineError::Invalid(format!(
+                "line {}: {} is not a node of verse {}:{}",
+                line, id, key.0, key.1
+            )));
+        }
+    }
+    Ok(verses.into_values().collect())
+}
+
+impl SqliteStorage {
+    /// Replace the treebank analysis of every verse in `trees`. Returns the
+    /// number of edges written.
+    pub async fn import_treebank(&self, trees: &[DependencyTree]) -> EngineResult<usize> {
+        let mut tx = self.begin_tx().await?;
+        let mut edges = 0;
+        for tree in trees {
+            for table in ["treebank_nodes", "treebank_edges"] {
+                sqlx::query(&format!("DELETE FROM {} WHERE surah = ?1 AND ayah = ?2", table))
+                    .bind(tree.surah)
+                    .bind(tree.ayah)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| EngineError::Storage(e.to_string()))?;
+            }
+            for (position, node) in tree.nodes.iter().enumerate() {
+                sqlx::query(
+                    r#"INSERT INTO treebank_nodes
+                       (surah, ayah, node_id, position, kind, tag, form, token_index, segment_index, span_start, span_end)
+                       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
+                )
+                .bind(tree.surah)
+                .bind(tree.ayah)
+                .bind(&node.id)
+                .bind(position as i64)
+                .bind(&node.kind)
+                .bind(&node.tag)
+                .bind(node.form.as_ref())
+                .bind(node.token_index)
+                .bind(node.segment_index)
+                .bind(node.start.as_ref())
+                .bind(node.end.as_ref())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+            }
+            for (position, edge) in tree.edges.iter().enumerate() {
+                sqlx::query(
+                    r#"INSERT INTO treebank_edges (surah, ayah, position, head, dependent, relation)
+                       VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                )
+                .bind(tree.surah)
+                .bind(tree.ayah)
+                .bind(position as i64)
+                .bind(&edge.head)
+                .bind(&edge.dependent)
+                .bind(&edge.relation)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| EngineError::Storage(e.to_string()))?;
+                edges += 1;
+            }
+        }
+        Self::commit_tx(tx).await?;
+        Ok(edges)
+    }
+
+    pub async fn get_dependency_tree(&self, surah: i64, ayah: i64) -> EngineResult<DependencyTree> {
+        let nodes = sqlx::query(
+            r#"SELECT node_id, kind, tag, form, token_index, segment_index, span_start, span_end
+               FROM treebank_nodes WHERE surah = ?1 AND ayah = ?2 ORDER BY position"#,
+        )
+        .bind(surah)
+        .bind(ayah)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+        let edges = sqlx::query(
+            r#"SELECT head, dependent, relation FROM treebank_edges
+               WHERE surah = ?1 AND ayah = ?2 ORDER BY position"#,
+        )
+        .bind(surah)
+        .bind(ayah)
+        .fetch_all(self.pool())
+        .await
+        .map_err(|e| EngineError::Storage(e.to_string()))?;
+
+        let mut tree = DependencyTree {
+            surah,
+            ayah,
+            nodes: nodes
+                .iter()
+                .map(|r| SyntaxNode {
+                    id: r.try_get("node_id").unwrap_or_default(),
+                    kind: r.try_get("kind").unwrap_or_default(),
+                    tag: r.try_get("tag").unwrap_or_default(),
+                    form: r.try_get("form").unwrap_or(None),
+                    token_index: r.try_get("token_index").unwrap_or(None),
+                    segment_index: r.try_get("segment_index").unwrap_or(None),
+                    start: r.try_get("span_start").unwrap_or(None),
+                    end: r.try_get("span_end").unwrap_or(None),
+                    ..Default::default()
+                })
+                .collect(),
+            edges: edges
+                .iter()
+                .map(|r| DependencyEdge {
+                    head: r.try_get("head").unwrap_or_default(),
+                    dependent: r.try_get("dependent").unwrap_or_default(),
+                    relation: r.try_get("relation").unwrap_or_default(),
+                    relation_ar: None,
+                })
+                .collect(),
+        };
+        label_tree(TagLabels::bundled(), &mut tree);
+        Ok(tree)
+    }
+}